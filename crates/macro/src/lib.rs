@@ -186,7 +186,7 @@ fn create_types(attrs: &[syn::Attribute], variants: &[WalrusVariant]) -> impl qu
             });
             quote! {
                 #( #attrs )*
-                #[derive(Clone, Debug)]
+                #[derive(Clone, Debug, PartialEq)]
                 pub struct #name {
                     #( #fields )*
                 }
@@ -580,6 +580,34 @@ fn create_visit(variants: &[WalrusVariant]) -> impl quote::ToTokens {
                 // ...
             }
 
+            /// Visit `ValType`.
+            ///
+            /// By default this visits the reference type of reference values.
+            #[inline]
+            fn visit_val_type(&mut self, ty: &crate::ValType) {
+                if let crate::ValType::Ref(r) = ty {
+                    self.visit_ref_type(r);
+                }
+            }
+
+            /// Visit `RefType`.
+            ///
+            /// By default this visits the heap type of the reference.
+            #[inline]
+            fn visit_ref_type(&mut self, ty: &crate::RefType) {
+                self.visit_heap_type(&ty.heap_type);
+            }
+
+            /// Visit `HeapType`.
+            ///
+            /// By default this visits the `TypeId` of concrete heap types.
+            #[inline]
+            fn visit_heap_type(&mut self, ty: &crate::HeapType) {
+                if let crate::HeapType::Concrete(id) = ty {
+                    self.visit_type_id(id);
+                }
+            }
+
             #( #visitor_trait_methods )*
         }
 
@@ -676,6 +704,28 @@ fn create_visit(variants: &[WalrusVariant]) -> impl quote::ToTokens {
                 // ...
             }
 
+            /// Visit `ValType`.
+            #[inline]
+            fn visit_val_type_mut(&mut self, ty: &mut crate::ValType) {
+                if let crate::ValType::Ref(r) = ty {
+                    self.visit_ref_type_mut(r);
+                }
+            }
+
+            /// Visit `RefType`.
+            #[inline]
+            fn visit_ref_type_mut(&mut self, ty: &mut crate::RefType) {
+                self.visit_heap_type_mut(&mut ty.heap_type);
+            }
+
+            /// Visit `HeapType`.
+            #[inline]
+            fn visit_heap_type_mut(&mut self, ty: &mut crate::HeapType) {
+                if let crate::HeapType::Concrete(id) = ty {
+                    self.visit_type_id_mut(id);
+                }
+            }
+
             #( #visitor_mut_trait_methods )*
         }
 
@@ -6,13 +6,12 @@ use walkdir::WalkDir;
 
 fn is_known_failing(name: &str) -> bool {
     match name {
-        // Tests that require the "gc" feature (struct/array types not yet supported).
+        // Tests that require the "gc" feature.
         "tests_spec_tests_array_fill_wast"
         | "tests_spec_tests_array_new_elem_wast"
         | "tests_spec_tests_array_new_data_wast"
         | "tests_spec_tests_array_init_elem_wast"
         | "tests_spec_tests_array_copy_wast"
-        | "tests_spec_tests_array_wast"
         | "tests_spec_tests_data_wast"
         | "tests_spec_tests_extern_wast"
         | "tests_spec_tests_array_init_data_wast"
@@ -20,9 +19,6 @@ fn is_known_failing(name: &str) -> bool {
         | "tests_spec_tests_ref_null_wast"
         | "tests_spec_tests_ref_test_wast"
         | "tests_spec_tests_ref_eq_wast"
-        | "tests_spec_tests_struct_wast"
-        | "tests_spec_tests_type_canon_wast"
-        | "tests_spec_tests_type_subtyping_wast"
         // Tests that require typed references (ref $typeidx) from function-references proposal
         | "tests_spec_tests_br_on_non_null_wast"
         | "tests_spec_tests_br_on_null_wast"
@@ -39,7 +35,6 @@ fn is_known_failing(name: &str) -> bool {
         | "tests_spec_tests_table_sub_wast"
         | "tests_spec_tests_table_wast"
         | "tests_spec_tests_type_equivalence_wast"
-        | "tests_spec_tests_type_rec_wast"
         | "tests_spec_tests_unreached_valid_wast"
         // Tests that use legacy syntax that is not supported by wasm-tools.
         | "tests_spec_tests_legacy_rethrow_wast"
@@ -66,6 +66,8 @@ use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+pub mod support;
+
 pub enum FileCheck {
     Exhaustive(Vec<String>, PathBuf),
     Patterns(Vec<Vec<String>>),
@@ -0,0 +1,35 @@
+//! Helpers shared by the integration tests.
+
+use walrus::ir::Instr;
+use walrus::{LocalFunction, Module};
+
+/// Parse `wat`, run `pass` over it, and `check` the result.
+pub fn run_pass(wat: &str, pass: fn(&mut Module)) -> Module {
+    let mut module = Module::from_wat(wat).unwrap();
+    pass(&mut module);
+    check(&mut module);
+    module
+}
+
+/// Check that the module is still valid, and still is once emitted.
+pub fn check(module: &mut Module) {
+    module.validate().unwrap();
+    let wasm = module.emit_wasm();
+    Module::from_buffer(&wasm).unwrap();
+}
+
+/// The local function named `name`.
+pub fn func<'a>(module: &'a Module, name: &str) -> &'a LocalFunction {
+    let id = module.funcs.by_name(name).unwrap();
+    module.funcs.get(id).kind.unwrap_local()
+}
+
+/// The instructions of the entry block of the local function named `name`.
+pub fn body(module: &Module, name: &str) -> Vec<Instr> {
+    let func = func(module, name);
+    func.block(func.entry_block())
+        .instrs
+        .iter()
+        .map(|(instr, _)| instr.clone())
+        .collect()
+}
@@ -0,0 +1,197 @@
+use walrus::analysis::{DominanceFrontiers, DominatorTree, Liveness, LoopNest};
+use walrus::ir::{BasicBlockId, Cfg};
+use walrus::{LocalId, Module};
+use walrus_tests::support::func;
+
+const NESTED_LOOPS: &str = r#"
+    (module
+      (func $f (param $n i32) (result i32)
+        (local $i i32) (local $acc i32)
+        loop $outer
+          loop $inner
+            local.get $i
+            i32.const 1
+            i32.add
+            local.tee $i
+            local.get $n
+            i32.lt_u
+            br_if $inner
+          end
+          local.get $acc
+          local.get $i
+          i32.add
+          local.set $acc
+          local.get $acc
+          local.get $n
+          i32.lt_u
+          br_if $outer
+        end
+        local.get $acc))
+"#;
+
+// The basic blocks of `NESTED_LOOPS`:
+//
+// 0: `loop $outer`
+// 1: `local.get $acc` at the end of the function
+// 2: `loop $inner`
+// 3: the rest of `$outer`'s body
+// 4: the end of `$outer`'s body, after its `br_if`
+// 5: `$inner`'s body
+// 6: the end of `$inner`'s body, after its `br_if`
+// 7: exit
+
+fn block(cfg: &Cfg, index: usize) -> BasicBlockId {
+    cfg.blocks().nth(index).unwrap().0
+}
+
+fn indices(blocks: &[BasicBlockId]) -> Vec<usize> {
+    blocks.iter().map(|b| b.index()).collect()
+}
+
+fn local(module: &Module, name: &str) -> LocalId {
+    module
+        .locals
+        .iter()
+        .find(|l| l.name.as_deref() == Some(name))
+        .unwrap()
+        .id()
+}
+
+#[test]
+fn dominators() {
+    let module = Module::from_wat(NESTED_LOOPS).unwrap();
+    let cfg = Cfg::new(func(&module, "f"));
+    let dominators = DominatorTree::new(&cfg);
+    let b = |i| block(&cfg, i);
+
+    let idoms = (0..cfg.num_blocks())
+        .map(|i| dominators.idom(b(i)).map(|d| d.index()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        idoms,
+        [
+            None,
+            Some(4),
+            Some(0),
+            Some(6),
+            Some(3),
+            Some(2),
+            Some(5),
+            Some(1)
+        ]
+    );
+    assert!(dominators.dominates(b(0), b(7)));
+    assert!(dominators.dominates(b(5), b(5)));
+    assert!(!dominators.strictly_dominates(b(5), b(5)));
+    assert!(dominators.dominates(b(2), b(3)));
+    assert!(!dominators.dominates(b(3), b(5)));
+    assert_eq!(indices(dominators.children(b(2))), [5]);
+
+    let frontiers = DominanceFrontiers::new(&cfg, &dominators);
+    assert_eq!(indices(frontiers.frontier(b(0))), [] as [usize; 0]);
+    assert_eq!(indices(frontiers.frontier(b(2))), [2]);
+    assert_eq!(indices(frontiers.frontier(b(3))), [2]);
+    assert_eq!(indices(frontiers.frontier(b(5))), [2, 5]);
+}
+
+#[test]
+fn unreachable_blocks_are_not_dominated() {
+    let module = Module::from_wat(
+        r#"
+        (module
+          (func $f
+            block $a
+              return
+            end))
+        "#,
+    )
+    .unwrap();
+    let cfg = Cfg::new(func(&module, "f"));
+    let dominators = DominatorTree::new(&cfg);
+
+    // 0: `block`, 1: after the block, 2: `return`, 3: exit.
+    let after = block(&cfg, 1);
+    assert!(!dominators.is_reachable(after));
+    assert_eq!(dominators.idom(after), None);
+    assert!(!dominators.dominates(cfg.entry(), after));
+    assert_eq!(dominators.idom(cfg.exit()), Some(block(&cfg, 2)));
+}
+
+#[test]
+fn loops() {
+    let module = Module::from_wat(NESTED_LOOPS).unwrap();
+    let cfg = Cfg::new(func(&module, "f"));
+    let dominators = DominatorTree::new(&cfg);
+    let loops = LoopNest::new(&cfg, &dominators);
+    let b = |i| block(&cfg, i);
+
+    let all = loops.loops().collect::<Vec<_>>();
+    assert_eq!(all.len(), 2);
+    let (outer, outer_loop) = all[0];
+    let (inner, inner_loop) = all[1];
+
+    assert_eq!(outer_loop.header(), b(2));
+    assert_eq!(indices(outer_loop.blocks()), [2, 3, 5, 6]);
+    assert_eq!(outer_loop.parent(), None);
+    assert_eq!(outer_loop.depth(), 1);
+
+    assert_eq!(inner_loop.header(), b(5));
+    assert_eq!(indices(inner_loop.blocks()), [5]);
+    assert_eq!(inner_loop.parent(), Some(outer));
+    assert_eq!(inner_loop.depth(), 2);
+
+    assert_eq!(loops.innermost_loop(b(5)), Some(inner));
+    assert_eq!(loops.innermost_loop(b(3)), Some(outer));
+    assert_eq!(loops.innermost_loop(b(4)), None);
+    assert_eq!(loops.depth(b(5)), 2);
+    assert_eq!(loops.depth(b(6)), 1);
+    assert_eq!(loops.depth(b(1)), 0);
+    assert!(loops.is_header(b(2)));
+    assert!(!loops.is_header(b(3)));
+}
+
+#[test]
+fn liveness() {
+    let module = Module::from_wat(NESTED_LOOPS).unwrap();
+    let cfg = Cfg::new(func(&module, "f"));
+    let liveness = Liveness::new(&cfg, func(&module, "f"));
+    let b = |i| block(&cfg, i);
+    let (n, i, acc) = (
+        local(&module, "n"),
+        local(&module, "i"),
+        local(&module, "acc"),
+    );
+
+    assert!(liveness.live_out(cfg.exit()).is_empty());
+    assert!(liveness.live_in(cfg.exit()).is_empty());
+    assert!(liveness.live_out(b(1)).is_empty());
+    assert_eq!(liveness.live_in(b(1)).len(), 1);
+    assert!(liveness.live_in(b(1)).contains(&acc));
+
+    // Everything is live around the loops, since `$i` and `$acc` are read
+    // before they are set.
+    for l in [n, i, acc] {
+        assert!(liveness.live_in(cfg.entry()).contains(&l));
+        assert!(liveness.live_out(b(3)).contains(&l));
+    }
+
+    // Once the outer loop is done, only `$acc` is needed.
+    assert_eq!(liveness.live_out(b(4)).len(), 1);
+}
+
+#[test]
+fn writes_kill_liveness() {
+    let module = Module::from_wat(
+        r#"
+        (module
+          (func $f (param $x i32) (result i32)
+            i32.const 1
+            local.set $x
+            local.get $x))
+        "#,
+    )
+    .unwrap();
+    let cfg = Cfg::new(func(&module, "f"));
+    let liveness = Liveness::new(&cfg, func(&module, "f"));
+    assert!(liveness.live_in(cfg.entry()).is_empty());
+}
@@ -0,0 +1,180 @@
+use walrus::analysis::{CallGraph, CallKind};
+use walrus::{FunctionId, Module};
+
+fn ids(module: &Module, names: &[&str]) -> Vec<FunctionId> {
+    names
+        .iter()
+        .map(|n| module.funcs.by_name(n).unwrap())
+        .collect()
+}
+
+fn named(module: &Module, calls: &[(FunctionId, CallKind)]) -> Vec<(String, CallKind)> {
+    calls
+        .iter()
+        .map(|(f, kind)| (module.funcs.get(*f).name.clone().unwrap(), *kind))
+        .collect()
+}
+
+#[test]
+fn direct_calls_and_sccs() {
+    let module = Module::from_wat(
+        r#"
+        (module
+          (import "env" "log" (func $log))
+          (func $main
+            i32.const 1
+            call $even
+            drop
+            call $log)
+          (func $even (param i32) (result i32)
+            local.get 0
+            return_call $odd)
+          (func $odd (param i32) (result i32)
+            local.get 0
+            call $even
+            drop
+            call $leaf)
+          (func $leaf (result i32)
+            i32.const 0)
+          (func $self
+            call $self))
+        "#,
+    )
+    .unwrap();
+    let graph = CallGraph::new(&module);
+    let [log, main, even, odd, leaf, self_] =
+        ids(&module, &["log", "main", "even", "odd", "leaf", "self"])[..]
+    else {
+        unreachable!()
+    };
+
+    assert_eq!(
+        named(&module, graph.callees(main)),
+        [
+            ("even".to_string(), CallKind::Direct),
+            ("log".to_string(), CallKind::Direct)
+        ]
+    );
+    assert!(graph.callees(log).is_empty());
+    assert_eq!(
+        named(&module, graph.callers(even)),
+        [
+            ("main".to_string(), CallKind::Direct),
+            ("odd".to_string(), CallKind::Direct)
+        ]
+    );
+
+    // Callees come before their callers.
+    let sccs = graph.sccs().map(|scc| scc.to_vec()).collect::<Vec<_>>();
+    let position = |f| sccs.iter().position(|scc| scc.contains(&f)).unwrap();
+    assert_eq!(sccs.len(), 5);
+    assert!(position(leaf) < position(odd));
+    assert!(position(log) < position(main));
+    assert!(position(even) < position(main));
+    assert_eq!(position(even), position(odd));
+
+    assert!(graph.is_recursive(even));
+    assert!(graph.is_recursive(self_));
+    assert!(!graph.is_recursive(main));
+    assert_eq!(graph.scc(leaf), [leaf]);
+}
+
+#[test]
+fn call_indirect_through_element_segments() {
+    let module = Module::from_wat(
+        r#"
+        (module
+          (type $unary (func (param i32) (result i32)))
+          (table $t 2 funcref)
+          (table $other 1 funcref)
+          (elem (table $t) (i32.const 0) func $double $zero)
+          (elem (table $other) (i32.const 0) func $triple)
+          (func $double (param i32) (result i32)
+            local.get 0
+            i32.const 2
+            i32.mul)
+          (func $triple (param i32) (result i32)
+            local.get 0
+            i32.const 3
+            i32.mul)
+          (func $zero (result i32)
+            i32.const 0)
+          (func $apply (param i32 i32) (result i32)
+            local.get 0
+            local.get 1
+            call_indirect $t (type $unary)))
+        "#,
+    )
+    .unwrap();
+    let graph = CallGraph::new(&module);
+    let apply = module.funcs.by_name("apply").unwrap();
+
+    // `$zero` has the wrong type and `$triple` is in another table.
+    assert_eq!(
+        named(&module, graph.callees(apply)),
+        [("double".to_string(), CallKind::Indirect)]
+    );
+}
+
+#[test]
+fn call_ref_reaches_address_taken_functions() {
+    let module = Module::from_wat(
+        r#"
+        (module
+          (type $thunk (func))
+          (table $t 1 funcref)
+          (elem declare func $a)
+          (func $a)
+          (func $b (export "b"))
+          (func $c (export "c") (param i32))
+          (func $not_taken)
+          (func $call_ref
+            ref.func $b
+            call_ref $thunk)
+          (func $call_indirect (param i32)
+            i32.const 0
+            ref.func $c
+            table.set $t
+            local.get 0
+            call_indirect $t (type $thunk)))
+        "#,
+    )
+    .unwrap();
+    let graph = CallGraph::new(&module);
+    let expected = [
+        ("a".to_string(), CallKind::Indirect),
+        ("b".to_string(), CallKind::Indirect),
+    ];
+
+    let call_ref = module.funcs.by_name("call_ref").unwrap();
+    assert_eq!(named(&module, graph.callees(call_ref)), expected);
+
+    // `table.set` makes the table's contents unknown.
+    let call_indirect = module.funcs.by_name("call_indirect").unwrap();
+    assert_eq!(named(&module, graph.callees(call_indirect)), expected);
+}
+
+#[test]
+fn dot_output() {
+    let module = Module::from_wat(
+        r#"
+        (module
+          (type $thunk (func))
+          (table 1 funcref)
+          (elem (i32.const 0) func $a)
+          (func $a)
+          (func $b (param i32)
+            call $a
+            local.get 0
+            call_indirect (type $thunk)))
+        "#,
+    )
+    .unwrap();
+    let graph = CallGraph::new(&module);
+    let path = std::env::temp_dir().join("walrus-call-graph.dot");
+    graph.write_graphviz_dot(&module, &path).unwrap();
+    let dot = std::fs::read_to_string(&path).unwrap();
+    assert!(dot.starts_with("digraph {"));
+    assert_eq!(dot.matches(" -> ").count(), 2);
+    assert_eq!(dot.matches("[style=\"dashed\"]").count(), 1);
+}
@@ -0,0 +1,193 @@
+use walrus::ir::{Cfg, Instr};
+use walrus::{FunctionBuilder, Module};
+use walrus_tests::support::func;
+
+/// The indices of the successors of a basic block.
+fn succs(cfg: &Cfg, block: usize) -> Vec<usize> {
+    let block = cfg.blocks().nth(block).unwrap().1;
+    block.succs().iter().map(|b| b.index()).collect()
+}
+
+#[test]
+fn if_else_diamond() {
+    let module = Module::from_wat(
+        r#"
+        (module
+          (func $f (param i32) (result i32)
+            local.get 0
+            if (result i32)
+              i32.const 1
+            else
+              i32.const 2
+            end
+            i32.eqz))
+        "#,
+    )
+    .unwrap();
+    let f = func(&module, "f");
+    let cfg = Cfg::new(f);
+
+    // 0: `local.get 0; if`, 1: `i32.eqz`, 2: consequent, 3: alternative, 4:
+    // exit.
+    assert_eq!(cfg.num_blocks(), 5);
+    assert_eq!(cfg.exit().index(), 4);
+    assert_eq!(succs(&cfg, 0), [2, 3]);
+    assert_eq!(succs(&cfg, 2), [1]);
+    assert_eq!(succs(&cfg, 3), [1]);
+    assert_eq!(succs(&cfg, 1), [4]);
+    let join = cfg.blocks().nth(1).unwrap().1;
+    assert_eq!(join.preds().len(), 2);
+
+    let entry = cfg.block(cfg.entry());
+    assert_eq!(entry.seq(), f.entry_block());
+    assert_eq!(entry.range(), 0..2);
+    assert!(matches!(entry.instrs(f)[1].0, Instr::IfElse(_)));
+    assert_eq!(cfg.block_of(f.entry_block(), 2).unwrap().index(), 1);
+    assert_eq!(cfg.block_of(f.entry_block(), 3).unwrap().index(), 1);
+    assert_eq!(cfg.block_of(f.entry_block(), 4), None);
+
+    let rpo = cfg
+        .reverse_postorder()
+        .map(|b| b.index())
+        .collect::<Vec<_>>();
+    assert_eq!(rpo.len(), 5);
+    assert_eq!(rpo[0], 0);
+    assert_eq!(rpo[3], 1);
+    assert_eq!(rpo[4], 4);
+}
+
+#[test]
+fn loop_back_edge() {
+    let module = Module::from_wat(
+        r#"
+        (module
+          (func $f (param i32)
+            loop $l
+              local.get 0
+              br_if $l
+            end))
+        "#,
+    )
+    .unwrap();
+    let cfg = Cfg::new(func(&module, "f"));
+
+    // 0: `loop`, 1: after the loop, 2: `local.get 0; br_if`, 3: after the
+    // `br_if`, 4: exit.
+    assert_eq!(succs(&cfg, 0), [2]);
+    assert_eq!(succs(&cfg, 2), [2, 3]);
+    assert_eq!(succs(&cfg, 3), [1]);
+    assert_eq!(succs(&cfg, 1), [4]);
+}
+
+#[test]
+fn br_table_targets_are_deduplicated() {
+    let module = Module::from_wat(
+        r#"
+        (module
+          (func $f (param i32)
+            block $a
+              block $b
+                local.get 0
+                br_table $a $b $a $b
+              end
+            end))
+        "#,
+    )
+    .unwrap();
+    let cfg = Cfg::new(func(&module, "f"));
+
+    // 0: `block $a`, 1: after `$a`, 2: `block $b`, 3: after `$b`, 4: the
+    // `br_table`, 5: exit.
+    assert_eq!(succs(&cfg, 4), [1, 3]);
+    assert_eq!(cfg.num_blocks(), 6);
+}
+
+#[test]
+fn dead_code_is_not_reachable() {
+    let mut module = Module::default();
+    let mut builder = FunctionBuilder::new(&mut module.types, &[], &[]);
+    builder.func_body().block(None, |block| {
+        let id = block.id();
+        block.br(id).i32_const(1).drop();
+    });
+    let f = builder.finish(vec![], &mut module.funcs);
+    let cfg = Cfg::new(module.funcs.get(f).kind.unwrap_local());
+
+    // 0: `block`, 1: after the block, 2: `br`, 3: `i32.const 1; drop`, 4:
+    // exit.
+    assert_eq!(succs(&cfg, 2), [1]);
+    assert!(cfg.blocks().nth(3).unwrap().1.preds().is_empty());
+    assert!(cfg.reverse_postorder().all(|b| b.index() != 3));
+}
+
+#[test]
+fn try_table_catch_edges() {
+    let module = Module::from_wat(
+        r#"
+        (module
+          (tag $e)
+          (func $g)
+          (func $f
+            block $h
+              try_table (catch $e $h)
+                call $g
+                i32.const 0
+                drop
+              end
+            end
+            throw $e))
+        "#,
+    )
+    .unwrap();
+    let cfg = Cfg::new(func(&module, "f"));
+
+    // 0: `block`, 1: `throw`, 2: `try_table`, 3: after the `try_table`, 4:
+    // `call`, 5: `i32.const 0; drop`, 6: exit.
+    assert_eq!(succs(&cfg, 4), [5, 1]);
+    assert_eq!(succs(&cfg, 5), [3]);
+    // Uncaught exceptions go to the exit block.
+    assert_eq!(succs(&cfg, 1), [6]);
+}
+
+#[test]
+fn legacy_try_edges() {
+    let module = Module::from_wat(
+        r#"
+        (module
+          (tag $e)
+          (func $g)
+          (func $f
+            try
+              call $g
+              throw $e
+            catch $e
+              rethrow 0
+            catch_all
+            end))
+        "#,
+    )
+    .unwrap();
+    let cfg = Cfg::new(func(&module, "f"));
+
+    // 0: `try`, 1: after the `try`, 2: `call`, 3: `throw`, 4: `rethrow`, 5:
+    // `catch_all`, 6: exit.
+    assert_eq!(succs(&cfg, 2), [3, 4, 5]);
+    assert_eq!(succs(&cfg, 3), [4, 5]);
+    assert_eq!(succs(&cfg, 4), [6]);
+    assert_eq!(succs(&cfg, 5), [1]);
+}
+
+#[test]
+fn dot() {
+    let module = Module::from_wat("(module (func $f (param i32) local.get 0 br_if 0))").unwrap();
+    let f = func(&module, "f");
+    let cfg = Cfg::new(f);
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("f.dot");
+    cfg.write_graphviz_dot(f, &path).unwrap();
+    let dot = std::fs::read_to_string(path).unwrap();
+    assert!(dot.starts_with("digraph {"), "{}", dot);
+    assert!(dot.contains("BasicBlock_0 -> BasicBlock_2;"), "{}", dot);
+    assert!(dot.contains("BasicBlock_0 -> BasicBlock_1;"), "{}", dot);
+    assert!(dot.contains("(exit)"), "{}", dot);
+}
@@ -0,0 +1,140 @@
+use std::collections::HashSet;
+use walrus::ir::{dfs_in_order, Instr, Visitor};
+use walrus::{LocalId, Module};
+use walrus_tests::support::{func, run_pass};
+
+fn coalesced(wat: &str) -> Module {
+    run_pass(wat, walrus::passes::coalesce_locals::run)
+}
+
+/// The distinct locals that a function uses.
+fn locals(module: &Module, name: &str) -> HashSet<LocalId> {
+    struct Collect(HashSet<LocalId>);
+
+    impl<'instr> Visitor<'instr> for Collect {
+        fn visit_local_id(&mut self, local: &LocalId) {
+            self.0.insert(*local);
+        }
+    }
+
+    let func = func(module, name);
+    let mut visitor = Collect(HashSet::new());
+    dfs_in_order(&mut visitor, func, func.entry_block());
+    visitor.0
+}
+
+#[test]
+fn sequential_temporaries() {
+    let module = coalesced(
+        r#"
+        (module
+          (func $temps (param i32) (result i32) (local i32 i32 i32)
+            local.get 0
+            i32.const 1
+            i32.add
+            local.set 1
+            local.get 1
+            i32.const 2
+            i32.mul
+            local.set 2
+            local.get 2
+            i32.const 3
+            i32.sub
+            local.set 3
+            local.get 3)
+          (func $types (result i64) (local i32 i64)
+            i32.const 1
+            local.set 0
+            local.get 0
+            i64.extend_i32_u
+            local.set 1
+            local.get 1))
+        "#,
+    );
+    let func = module.funcs.by_name("temps").unwrap();
+    let args = &module.funcs.get(func).kind.unwrap_local().args;
+    assert_eq!(locals(&module, "temps"), args.iter().cloned().collect());
+    assert_eq!(locals(&module, "types").len(), 2);
+}
+
+#[test]
+fn interfering_locals() {
+    let module = coalesced(
+        r#"
+        (module
+          (func $both_live (param i32 i32) (result i32) (local i32 i32)
+            local.get 0
+            local.set 2
+            local.get 1
+            local.set 3
+            local.get 2
+            local.get 3
+            i32.add)
+          (func $default (result i32) (local i32 i32)
+            i32.const 5
+            local.set 0
+            local.get 0
+            drop
+            local.get 1))
+        "#,
+    );
+    // `2` and `3` may reuse the arguments, but not each other.
+    assert_eq!(locals(&module, "both_live").len(), 2);
+    // `1` is still zero when `0` is written.
+    assert_eq!(locals(&module, "default").len(), 2);
+}
+
+#[test]
+fn loops() {
+    let module = coalesced(
+        r#"
+        (module
+          (func $sum (param i32) (result i32) (local $acc i32) (local $tmp i32)
+            loop
+              local.get 0
+              i32.const 1
+              i32.sub
+              local.set $tmp
+              local.get $acc
+              local.get $tmp
+              i32.add
+              local.set $acc
+              local.get $tmp
+              local.tee 0
+              br_if 0
+            end
+            local.get $acc))
+        "#,
+    );
+    // `$tmp` takes over from the argument, but `$acc` is live throughout.
+    assert_eq!(locals(&module, "sum").len(), 2);
+}
+
+#[test]
+fn unread_locals_are_removed() {
+    let module = coalesced(
+        r#"
+        (module
+          (import "env" "f" (func $f (result i32)))
+          (func $unread (result i32) (local i32 i32)
+            call $f
+            local.set 0
+            i32.const 1
+            local.tee 1))
+        "#,
+    );
+    assert!(locals(&module, "unread").is_empty());
+
+    let func = module.funcs.by_name("unread").unwrap();
+    let func = module.funcs.get(func).kind.unwrap_local();
+    let instrs = func
+        .block(func.entry_block())
+        .instrs
+        .iter()
+        .map(|(instr, _)| instr.clone())
+        .collect::<Vec<_>>();
+    assert!(matches!(
+        &instrs[..],
+        [Instr::Call(_), Instr::Drop(_), Instr::Const(_)]
+    ));
+}
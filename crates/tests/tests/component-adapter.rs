@@ -0,0 +1,215 @@
+use walrus::component::{
+    Adapter, CanonicalFunction, CanonicalOption, ComponentDefinedType, ComponentFuncType,
+    ComponentSection, ComponentValType, PrimitiveValType, VariantCase,
+};
+use walrus::{Component, FunctionBuilder, Module, ValType};
+
+fn func(
+    params: &[(&str, ComponentValType)],
+    result: Option<ComponentValType>,
+) -> ComponentFuncType {
+    ComponentFuncType {
+        async_: false,
+        params: params
+            .iter()
+            .map(|(name, ty)| (name.to_string(), *ty))
+            .collect(),
+        result,
+    }
+}
+
+fn canonical(component: &Component) -> Vec<&CanonicalFunction> {
+    component
+        .sections
+        .iter()
+        .filter_map(|s| match s {
+            ComponentSection::Canonical(funcs) => Some(funcs),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+fn validate(component: &mut Component) -> Component {
+    let wasm = component.emit_wasm();
+    // Parsing validates the emitted component.
+    Component::from_buffer(&wasm).unwrap()
+}
+
+#[test]
+fn lift_export() {
+    let module = Module::from_wat(
+        r#"
+            (module
+              (memory 1)
+              (func (export "area") (param i32 i32) (result i32)
+                i32.const 8))
+        "#,
+    )
+    .unwrap();
+    let area = module.exports.get_func("area").unwrap();
+
+    let mut adapter = Adapter::new(module);
+    let u32 = ComponentValType::from(PrimitiveValType::U32);
+    let size = adapter.define_named(
+        "size",
+        ComponentDefinedType::Record(vec![("w".to_string(), u32), ("h".to_string(), u32)]),
+    );
+    let ty = func(&[("name", PrimitiveValType::String.into())], Some(size));
+    assert_eq!(
+        adapter.lift_signature(&ty).unwrap(),
+        (vec![ValType::I32, ValType::I32], vec![ValType::I32])
+    );
+    adapter.export("area", area, ty).unwrap();
+
+    let mut component = validate(&mut adapter.finish().unwrap());
+    let exports = component
+        .exports()
+        .map(|e| e.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(exports, ["size", "area"]);
+    let main = component.modules_mut().next().unwrap();
+    assert!(main.exports.get_func("cabi_realloc").is_ok());
+    assert!(main.exports.iter().any(|e| e.name == "memory"));
+}
+
+#[test]
+fn lower_import() {
+    let module = Module::from_wat(r#"(module (memory 1))"#).unwrap();
+    let mut adapter = Adapter::new(module);
+    let names = adapter.define(ComponentDefinedType::List(PrimitiveValType::String.into()));
+    let ty = func(&[("names", names)], Some(PrimitiveValType::String.into()));
+    assert_eq!(
+        adapter.lower_signature(&ty).unwrap(),
+        (vec![ValType::I32; 3], vec![])
+    );
+    let join = adapter.import("join", ty).unwrap();
+    let log = adapter
+        .import("log", func(&[("level", PrimitiveValType::U8.into())], None))
+        .unwrap();
+
+    let module = adapter.module_mut();
+    let mut builder = FunctionBuilder::new(&mut module.types, &[], &[]);
+    builder
+        .func_body()
+        .i32_const(0)
+        .i32_const(0)
+        .i32_const(0)
+        .call(join)
+        .i32_const(1)
+        .call(log);
+    let run = builder.finish(vec![], &mut module.funcs);
+    adapter.export("run", run, func(&[], None)).unwrap();
+
+    let mut component = validate(&mut adapter.finish().unwrap());
+    let imports = component
+        .imports()
+        .map(|i| i.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(imports, ["join", "log"]);
+    // The lowered functions are patched into the main module through the
+    // shim and fixup modules.
+    assert_eq!(component.modules_mut().count(), 3);
+    let lowered = canonical(&component)
+        .into_iter()
+        .filter_map(|f| match f {
+            CanonicalFunction::Lower { options, .. } => Some(options.len()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(lowered, [2, 0]);
+}
+
+#[test]
+fn post_return() {
+    let module = Module::from_wat(
+        r#"
+            (module
+              (memory 1)
+              (func (export "name") (result i32)
+                i32.const 0)
+              (func (export "cabi_post_name") (param i32)))
+        "#,
+    )
+    .unwrap();
+    let name = module.exports.get_func("name").unwrap();
+    let mut adapter = Adapter::new(module);
+    let ty = func(&[], Some(PrimitiveValType::String.into()));
+    adapter.export("name", name, ty).unwrap();
+
+    let component = validate(&mut adapter.finish().unwrap());
+    assert!(matches!(
+        canonical(&component)[0],
+        CanonicalFunction::Lift { options, .. }
+            if options.iter().any(|o| matches!(o, CanonicalOption::PostReturn(_)))
+    ));
+}
+
+#[test]
+fn signatures() {
+    let module = Module::from_wat(r#"(module (func (export "f") (param i64)))"#).unwrap();
+    let f = module.exports.get_func("f").unwrap();
+    let mut adapter = Adapter::new(module);
+    let number = adapter.define(ComponentDefinedType::Variant(vec![
+        VariantCase {
+            name: "int".to_string(),
+            ty: Some(PrimitiveValType::U32.into()),
+        },
+        VariantCase {
+            name: "float".to_string(),
+            ty: Some(PrimitiveValType::F32.into()),
+        },
+        VariantCase {
+            name: "big".to_string(),
+            ty: Some(PrimitiveValType::U64.into()),
+        },
+    ]));
+    let ty = func(&[("n", number)], None);
+    assert_eq!(
+        adapter.lift_signature(&ty).unwrap().0,
+        [ValType::I32, ValType::I64]
+    );
+
+    let err = adapter.export("f", f, ty).unwrap_err();
+    assert!(err.to_string().contains("cannot lift"));
+}
+
+#[test]
+fn realloc_only_uses_memory_it_grows() {
+    use walrus::ir::{dfs_in_order, Instr, InstrLocId, Visitor};
+
+    struct Collect(Vec<Instr>);
+
+    impl<'instr> Visitor<'instr> for Collect {
+        fn visit_instr(&mut self, instr: &'instr Instr, _: &'instr InstrLocId) {
+            self.0.push(instr.clone());
+        }
+    }
+
+    let module = Module::from_wat(
+        r#"
+            (module
+              (memory 1)
+              (func (export "grow") (result i32)
+                (memory.grow (i32.const 1)))
+              (func (export "f") (param i32 i32)))
+        "#,
+    )
+    .unwrap();
+    let f = module.exports.get_func("f").unwrap();
+    let mut adapter = Adapter::new(module);
+    let ty = func(&[("s", PrimitiveValType::String.into())], None);
+    adapter.export("f", f, ty).unwrap();
+
+    let mut component = validate(&mut adapter.finish().unwrap());
+    let main = component.modules_mut().next().unwrap();
+    let realloc = main.exports.get_func("cabi_realloc").unwrap();
+    let realloc = main.funcs.get(realloc).kind.unwrap_local();
+    let mut instrs = Collect(Vec::new());
+    dfs_in_order(&mut instrs, realloc, realloc.entry_block());
+    // Allocations come from the memory that `memory.grow` returns, rather
+    // than from where the memory ends, which the module's own `memory.grow`
+    // may have already handed out.
+    assert!(instrs.0.iter().any(|i| matches!(i, Instr::MemoryGrow(_))));
+    assert!(!instrs.0.iter().any(|i| matches!(i, Instr::MemorySize(_))));
+}
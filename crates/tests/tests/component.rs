@@ -0,0 +1,177 @@
+use walrus::component::{
+    CanonicalFunction, CanonicalOption, ComponentDefinedType, ComponentExternalKind,
+    ComponentSection, ComponentType, ComponentTypeRef, ComponentValType, PrimitiveValType,
+};
+use walrus::{Component, Module, ModuleConfig};
+
+const WAT: &str = r#"
+    (component
+      (import "wasi:cli/stdout@0.2.0" (instance $stdout
+        (export "output-stream" (type $stream (sub resource)))
+        (export "get-stdout" (func (result (own $stream))))))
+      (import "log" (func $log (param "msg" string)))
+
+      (core module $libc
+        (memory (export "memory") 1)
+        (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+          i32.const 0))
+      (core instance $libc (instantiate $libc))
+      (core func $log (canon lower (func $log)
+        (memory (core memory $libc "memory"))
+          (realloc (core func $libc "realloc"))))
+
+      (core module $main
+        (import "host" "log" (func $log (param i32 i32)))
+        (func $unused (result i32)
+          i32.const 7)
+        (func (export "greet") (param i32 i32) (result i32)
+          local.get 0
+          local.get 1
+          call $log
+          i32.const 0))
+      (core instance $host (export "log" (func $log)))
+      (core instance $main (instantiate $main (with "host" (instance $host))))
+
+      (type $point (record (field "x" u32) (field "y" u32)))
+      (type $shape (variant (case "dot" $point) (case "none")))
+      (type $names (list string))
+      (func (export "greet") (param "name" string) (result u32)
+        (canon lift (core func $main "greet")
+          (memory (core memory $libc "memory"))
+          (realloc (core func $libc "realloc"))))
+      (export "point" (type $point))
+
+      (component $inner
+        (import "f" (func $f (param "msg" string)))
+        (export "g" (func $f)))
+      (instance (instantiate $inner (with "f" (func $log))))
+      (@custom "note" "hello"))
+"#;
+
+fn round_trip(component: &mut Component) -> Component {
+    let wasm = component.emit_wasm();
+    // Parsing validates the emitted component.
+    let mut reparsed = Component::from_buffer(&wasm).unwrap();
+    assert_eq!(reparsed.emit_wasm(), wasm);
+    reparsed
+}
+
+#[test]
+fn parse_component() {
+    let component = Component::from_wat(WAT).unwrap();
+    assert_eq!(component.modules().count(), 2);
+
+    let imports = component
+        .imports()
+        .map(|i| i.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(imports, ["wasi:cli/stdout@0.2.0", "log"]);
+    assert!(matches!(
+        component.imports().next().unwrap().ty,
+        ComponentTypeRef::Instance(_)
+    ));
+    let mut exports = component
+        .exports()
+        .map(|e| (e.name.as_str(), e.kind))
+        .collect::<Vec<_>>();
+    exports.sort_by_key(|(name, _)| *name);
+    assert_eq!(
+        exports,
+        [
+            ("greet", ComponentExternalKind::Func),
+            ("point", ComponentExternalKind::Type)
+        ]
+    );
+
+    let types = component
+        .sections
+        .iter()
+        .filter_map(|s| match s {
+            ComponentSection::Types(types) => Some(types),
+            _ => None,
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+    assert!(matches!(types[0], ComponentType::Raw(_)));
+    assert!(types.iter().any(|ty| matches!(
+        ty,
+        ComponentType::Defined(ComponentDefinedType::List(ComponentValType::Primitive(
+            PrimitiveValType::String
+        )))
+    )));
+
+    let canonical = component
+        .sections
+        .iter()
+        .filter_map(|s| match s {
+            ComponentSection::Canonical(funcs) => Some(funcs),
+            _ => None,
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+    assert!(matches!(
+        canonical[0],
+        CanonicalFunction::Lower { options, .. }
+            if options.iter().any(|o| matches!(o, CanonicalOption::Realloc(_)))
+    ));
+    assert!(canonical
+        .iter()
+        .any(|f| matches!(f, CanonicalFunction::Lift { .. })));
+
+    assert!(component.sections.iter().any(|s| matches!(
+        s,
+        ComponentSection::Custom(custom) if custom.name == "note" && custom.data == b"hello"
+    )));
+}
+
+#[test]
+fn round_trip_component() {
+    let mut component = Component::from_wat(WAT).unwrap();
+    let reparsed = round_trip(&mut component);
+    assert_eq!(reparsed.sections.len(), component.sections.len());
+    let nested = reparsed
+        .sections
+        .iter()
+        .find_map(|s| match s {
+            ComponentSection::Component(nested) => Some(nested),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(nested.exports().next().unwrap().name, "g");
+}
+
+#[test]
+fn transform_core_modules() {
+    let mut component = Component::from_wat(WAT).unwrap();
+    for module in component.modules_mut() {
+        walrus::passes::gc::run(module);
+    }
+    let component = round_trip(&mut component);
+    let main = component.modules().nth(1).unwrap();
+    assert_eq!(main.funcs.iter().count(), 2);
+}
+
+#[test]
+fn modules_and_components_are_distinct() {
+    let wasm = wat::parse_str("(component)").unwrap();
+    assert!(Module::from_buffer(&wasm).is_err());
+    let mut component = Component::from_buffer(&wasm).unwrap();
+    assert!(component.sections.is_empty());
+    assert_eq!(component.emit_wasm(), wasm);
+
+    let wasm = wat::parse_str("(module)").unwrap();
+    assert!(Component::from_buffer(&wasm).is_err());
+}
+
+#[test]
+fn strict_validate_can_be_disabled() {
+    // The exported function doesn't exist.
+    let wasm = wat::parse_str(r#"(component (export "f" (func 0)))"#).unwrap();
+    assert!(Component::from_buffer(&wasm).is_err());
+
+    let mut config = ModuleConfig::new();
+    config.strict_validate(false);
+    let mut component = Component::from_buffer_with_config(&wasm, &config).unwrap();
+    assert_eq!(component.exports().next().unwrap().name, "f");
+    assert_eq!(component.emit_wasm(), wasm);
+}
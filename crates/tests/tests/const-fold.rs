@@ -0,0 +1,281 @@
+use walrus::ir::{BinaryOp, Instr, UnaryOp, Value};
+use walrus::Module;
+use walrus_tests::support::{body, func, run_pass};
+
+fn folded(wat: &str) -> Module {
+    run_pass(wat, walrus::passes::const_fold::run)
+}
+
+/// The value of a function whose body was folded into a single constant.
+fn value(module: &Module, name: &str) -> Value {
+    match &body(module, name)[..] {
+        [Instr::Const(c)] => c.value,
+        other => panic!("expected a single constant, found {:?}", other),
+    }
+}
+
+#[test]
+fn arithmetic() {
+    let module = folded(
+        r#"
+        (module
+          (func $chain (result i32)
+            i32.const 2
+            i32.const 3
+            i32.mul
+            i32.const -4
+            i32.add
+            i32.eqz
+            i32.eqz)
+          (func $wrapping (result i64)
+            i64.const 0x7fffffffffffffff
+            i64.const 1
+            i64.add)
+          (func $unsigned (result i32)
+            i32.const -1
+            i32.const 2
+            i32.div_u)
+          (func $rem (result i32)
+            i32.const 0x80000000
+            i32.const -1
+            i32.rem_s)
+          (func $shift (result i32)
+            i32.const 1
+            i32.const 33
+            i32.shl)
+          (func $convert (result f64)
+            i32.const -1
+            f64.convert_i32_u)
+          (func $trunc (result i32)
+            f32.const -1.5
+            i32.trunc_f32_s)
+          (func $saturate (result i32)
+            f64.const 1e100
+            i32.trunc_sat_f64_u)
+          (func $min (result f32)
+            f32.const 0
+            f32.const -0
+            f32.min))
+        "#,
+    );
+    assert!(matches!(value(&module, "chain"), Value::I32(1)));
+    assert!(matches!(value(&module, "wrapping"), Value::I64(i64::MIN)));
+    assert!(matches!(value(&module, "unsigned"), Value::I32(0x7fffffff)));
+    assert!(matches!(value(&module, "rem"), Value::I32(0)));
+    assert!(matches!(value(&module, "shift"), Value::I32(2)));
+    assert!(matches!(value(&module, "convert"), Value::F64(x) if x == 4294967295.0));
+    assert!(matches!(value(&module, "trunc"), Value::I32(-1)));
+    assert!(matches!(value(&module, "saturate"), Value::I32(-1)));
+    assert!(matches!(value(&module, "min"), Value::F32(x) if x.to_bits() == 0x8000_0000));
+}
+
+#[test]
+fn traps_are_not_folded() {
+    let module = folded(
+        r#"
+        (module
+          (func $div_zero (result i32)
+            i32.const 1
+            i32.const 0
+            i32.div_s)
+          (func $div_overflow (result i64)
+            i64.const 0x8000000000000000
+            i64.const -1
+            i64.div_s)
+          (func $rem_zero (result i32)
+            i32.const 1
+            i32.const 0
+            i32.rem_u)
+          (func $trunc_nan (result i32)
+            f32.const nan
+            i32.trunc_f32_s)
+          (func $trunc_overflow (result i32)
+            f64.const 4294967296
+            i32.trunc_f64_u))
+        "#,
+    );
+    for name in [
+        "div_zero",
+        "div_overflow",
+        "rem_zero",
+        "trunc_nan",
+        "trunc_overflow",
+    ] {
+        assert!(
+            !matches!(&body(&module, name)[..], [Instr::Const(_)]),
+            "{} was folded",
+            name
+        );
+    }
+}
+
+#[test]
+fn nan_bits_are_preserved() {
+    let module = folded(
+        r#"
+        (module
+          (func $neg (result f32)
+            f32.const nan:0x123
+            f32.neg)
+          (func $copysign (result f64)
+            f64.const nan:0x4567
+            f64.const -1
+            f64.copysign)
+          (func $reinterpret (result f32)
+            i32.const 0x7fa00001
+            f32.reinterpret_i32)
+          (func $add (result f32)
+            f32.const nan:0x123
+            f32.const 1
+            f32.add)
+          (func $zero_div_zero (result f64)
+            f64.const 0
+            f64.const 0
+            f64.div))
+        "#,
+    );
+    assert!(matches!(value(&module, "neg"), Value::F32(x) if x.to_bits() == 0xff80_0123));
+    assert!(
+        matches!(value(&module, "copysign"), Value::F64(x) if x.to_bits() == 0xfff0_0000_0000_4567)
+    );
+    assert!(matches!(value(&module, "reinterpret"), Value::F32(x) if x.to_bits() == 0x7fa0_0001));
+    assert!(matches!(
+        &body(&module, "add")[..],
+        [_, _, Instr::Binop(b)] if matches!(b.op, BinaryOp::F32Add)
+    ));
+    assert_eq!(body(&module, "zero_div_zero").len(), 3);
+}
+
+#[test]
+fn identities() {
+    let module = folded(
+        r#"
+        (module
+          (func $f (param i32 i64) (result i32)
+            local.get 0
+            i32.const 0
+            i32.add
+            i32.const 1
+            i32.mul
+            i32.const -1
+            i32.and
+            local.get 1
+            i64.const 0
+            i64.shr_u
+            i32.wrap_i64
+            i32.add))
+        "#,
+    );
+    let body = body(&module, "f");
+    assert_eq!(body.len(), 4);
+    assert!(matches!(&body[2], Instr::Unop(u) if matches!(u.op, UnaryOp::I32WrapI64)));
+}
+
+#[test]
+fn constant_conditions() {
+    let module = folded(
+        r#"
+        (module
+          (func $if (result i32)
+            i32.const 1
+            i32.const 2
+            i32.lt_s
+            if (result i32)
+              i32.const 3
+            else
+              i32.const 4
+            end)
+          (func $br_if_taken (result i32)
+            block (result i32)
+              i32.const 5
+              i32.const 1
+              br_if 0
+              drop
+              i32.const 6
+            end)
+          (func $br_if_not_taken (result i32)
+            block (result i32)
+              i32.const 5
+              i32.const 0
+              br_if 0
+            end)
+          (func $select_first (param i32 i32) (result i32)
+            local.get 0
+            local.get 1
+            i32.const 7
+            select)
+          (func $select_second (param i32 i32) (result i32)
+            local.get 0
+            local.get 1
+            i32.const 0
+            select))
+        "#,
+    );
+
+    let if_ = func(&module, "if");
+    let if_body = body(&module, "if");
+    let seq = match &if_body[..] {
+        [Instr::Block(b)] => b.seq,
+        other => panic!("expected a block, found {:?}", other),
+    };
+    assert!(matches!(
+        &if_.block(seq).instrs[..],
+        [(Instr::Const(c), _)] if matches!(c.value, Value::I32(3))
+    ));
+
+    let taken = func(&module, "br_if_taken");
+    let seq = match &body(&module, "br_if_taken")[..] {
+        [Instr::Block(b)] => b.seq,
+        other => panic!("expected a block, found {:?}", other),
+    };
+    assert!(matches!(taken.block(seq).instrs[1].0, Instr::Br(_)));
+
+    let not_taken = func(&module, "br_if_not_taken");
+    let seq = match &body(&module, "br_if_not_taken")[..] {
+        [Instr::Block(b)] => b.seq,
+        other => panic!("expected a block, found {:?}", other),
+    };
+    assert_eq!(not_taken.block(seq).instrs.len(), 1);
+
+    assert!(matches!(
+        &body(&module, "select_first")[..],
+        [Instr::LocalGet(_), Instr::LocalGet(_), Instr::Drop(_)]
+    ));
+    let second = body(&module, "select_second");
+    assert!(matches!(
+        &second[..],
+        [Instr::LocalGet(a), Instr::Drop(_), Instr::LocalGet(b)]
+            if a.local != b.local
+    ));
+}
+
+#[test]
+fn immutable_globals() {
+    let module = folded(
+        r#"
+        (module
+          (import "env" "imported" (global $imported i32))
+          (global $constant i32 (i32.const 40))
+          (global $mutable (mut i32) (i32.const 1))
+          (global $derived i32 (global.get $imported))
+          (func $constant (result i32)
+            global.get $constant
+            i32.const 2
+            i32.add)
+          (func $others (result i32)
+            global.get $mutable
+            global.get $imported
+            i32.add
+            global.get $derived
+            i32.add))
+        "#,
+    );
+    assert!(matches!(value(&module, "constant"), Value::I32(42)));
+    assert_eq!(
+        body(&module, "others")
+            .iter()
+            .filter(|i| matches!(i, Instr::GlobalGet(_)))
+            .count(),
+        3
+    );
+}
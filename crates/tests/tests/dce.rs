@@ -0,0 +1,220 @@
+use walrus::ir::{Instr, Value};
+use walrus::{FunctionBuilder, Module, ValType};
+use walrus_tests::support::{body, check, func, run_pass};
+
+fn eliminated(wat: &str) -> Module {
+    run_pass(wat, walrus::passes::dce::run)
+}
+
+#[test]
+fn code_after_terminators() {
+    let mut module = Module::default();
+    let mut builder = FunctionBuilder::new(&mut module.types, &[], &[ValType::I32]);
+    builder.name("f".to_string());
+    builder
+        .func_body()
+        .block(None, |block| {
+            let id = block.id();
+            block.br(id).i32_const(1).drop();
+        })
+        .i32_const(2)
+        .return_()
+        .i32_const(3)
+        .unreachable();
+    builder.finish(vec![], &mut module.funcs);
+    walrus::passes::dce::run(&mut module);
+    check(&mut module);
+
+    let func = func(&module, "f");
+    let body = &func.block(func.entry_block()).instrs;
+    assert_eq!(body.len(), 3);
+    let seq = match &body[0].0 {
+        Instr::Block(b) => b.seq,
+        other => panic!("expected a block, found {:?}", other),
+    };
+    assert!(matches!(&func.block(seq).instrs[..], [(Instr::Br(_), _)]));
+    assert!(matches!(body[2].0, Instr::Return(_)));
+}
+
+#[test]
+fn dropped_pure_expressions() {
+    let module = eliminated(
+        r#"
+        (module
+          (global $g (mut i32) (i32.const 0))
+          (func $pure (param i32)
+            local.get 0
+            i32.const 1
+            i32.add
+            global.get $g
+            i32.mul
+            i32.eqz
+            drop
+            f64.const 1
+            f64.const 2
+            local.get 0
+            select
+            drop)
+          (func $effects (param i32) (result i32)
+            call $effects_callee
+            drop
+            local.get 0
+            i32.const 0
+            i32.div_u
+            drop
+            local.get 0
+            i32.load
+            i32.const 1
+            i32.add
+            drop
+            i32.const 0)
+          (func $effects_callee (result i32)
+            i32.const 0)
+          (memory 1))
+        "#,
+    );
+    assert!(body(&module, "pure").is_empty());
+
+    // The call, the division and the load stay, but the addition of the
+    // loaded value goes.
+    let effects = body(&module, "effects");
+    assert_eq!(effects.len(), 10);
+    assert!(matches!(effects[4], Instr::Binop(_)));
+    assert!(matches!(
+        &effects[7..],
+        [Instr::Load(_), Instr::Drop(_), Instr::Const(c)] if matches!(c.value, Value::I32(0))
+    ));
+}
+
+#[test]
+fn empty_blocks_and_ifs() {
+    let module = eliminated(
+        r#"
+        (module
+          (import "env" "f" (func $f))
+          (func $empty (param i32)
+            block
+              loop
+                i32.const 1
+                drop
+              end
+            end
+            local.get 0
+            if
+              nop
+            else
+              i32.const 2
+              drop
+            end)
+          (func $kept (param i32)
+            local.get 0
+            if
+              call $f
+            end
+            local.get 0
+            i32.load
+            if
+            end)
+          (memory 1))
+        "#,
+    );
+    assert!(body(&module, "empty").is_empty());
+
+    let kept = body(&module, "kept");
+    assert!(matches!(
+        &kept[..],
+        [
+            Instr::LocalGet(_),
+            Instr::IfElse(_),
+            Instr::LocalGet(_),
+            Instr::Load(_),
+            Instr::Drop(_),
+        ]
+    ));
+}
+
+#[test]
+fn ifs_with_pure_arms() {
+    let module = eliminated(
+        r#"
+        (module
+          (func $removed (param i32) (result i32)
+            local.get 0
+            if (result i32)
+              i32.const 1
+            else
+              local.get 0
+              i32.const 2
+              i32.add
+            end
+            drop
+            local.get 0
+            if
+              block (result i64)
+                i64.const 1
+              end
+              i64.const 2
+              local.get 0
+              select
+              drop
+            end
+            i32.const 0)
+          (func $kept (param i32) (param f32)
+            local.get 0
+            if (result i32)
+              local.get 1
+              i32.trunc_f32_s
+            else
+              i32.const 1
+            end
+            drop
+            block
+              local.get 0
+              if
+                br 1
+              end
+            end))
+        "#,
+    );
+    assert!(matches!(&body(&module, "removed")[..], [Instr::Const(_)]));
+
+    let kept = body(&module, "kept");
+    assert!(matches!(
+        &kept[..],
+        [
+            Instr::LocalGet(_),
+            Instr::IfElse(_),
+            Instr::Drop(_),
+            Instr::Block(_),
+        ]
+    ));
+}
+
+#[test]
+fn unread_locals() {
+    let module = eliminated(
+        r#"
+        (module
+          (import "env" "f" (func $f (result i32)))
+          (func $stores (param i32) (result i32) (local i32 i32)
+            call $f
+            local.set 1
+            local.get 0
+            local.tee 2
+            local.tee 0
+            drop
+            local.get 0))
+        "#,
+    );
+    let stores = body(&module, "stores");
+    assert!(matches!(
+        &stores[..],
+        [
+            Instr::Call(_),
+            Instr::Drop(_),
+            Instr::LocalGet(_),
+            Instr::LocalSet(_),
+            Instr::LocalGet(_),
+        ]
+    ));
+}
@@ -0,0 +1,160 @@
+use walrus::ir::{dfs_in_order, Instr, InstrLocId, Visitor};
+use walrus::{ElementItems, ExportItem, FunctionId, Module};
+use walrus_tests::support::run_pass;
+
+fn deduped(wat: &str) -> Module {
+    run_pass(wat, walrus::passes::dedup_funcs::run)
+}
+
+/// The functions that a function calls or takes a reference to.
+fn referenced(module: &Module, func: FunctionId) -> Vec<FunctionId> {
+    struct Collect(Vec<FunctionId>);
+
+    impl<'instr> Visitor<'instr> for Collect {
+        fn visit_instr(&mut self, instr: &'instr Instr, _: &'instr InstrLocId) {
+            match instr {
+                Instr::Call(c) => self.0.push(c.func),
+                Instr::RefFunc(r) => self.0.push(r.func),
+                _ => {}
+            }
+        }
+    }
+
+    let func = module.funcs.get(func).kind.unwrap_local();
+    let mut visitor = Collect(Vec::new());
+    dfs_in_order(&mut visitor, func, func.entry_block());
+    visitor.0
+}
+
+#[test]
+fn references_are_redirected() {
+    let module = deduped(
+        r#"
+        (module
+          (table 2 funcref)
+          (elem (i32.const 0) func $a $b)
+          (func $a (param i32) (result i32) (local i32 i64)
+            local.get 0
+            local.set 1
+            i64.const 1
+            local.set 2
+            local.get 1)
+          (func $b (export "b") (param i32) (result i32) (local i64 i32)
+            local.get 0
+            local.set 2
+            i64.const 1
+            local.set 1
+            local.get 2)
+          (func $main (export "main") (result i32)
+            ref.func $b
+            drop
+            i32.const 1
+            call $b)
+          (func $start
+            i32.const 2
+            call $a
+            drop)
+          (start $start))
+        "#,
+    );
+    let a = module.funcs.by_name("a").unwrap();
+    assert!(module.funcs.by_name("b").is_none());
+    assert_eq!(module.funcs.iter().count(), 3);
+
+    let main = module.funcs.by_name("main").unwrap();
+    assert_eq!(referenced(&module, main), [a, a]);
+    let export = module.exports.iter().find(|e| e.name == "b").unwrap();
+    assert!(matches!(export.item, ExportItem::Function(f) if f == a));
+    let elem = module.elements.iter().next().unwrap();
+    assert!(matches!(&elem.items, ElementItems::Functions(funcs) if funcs[..] == [a, a]));
+}
+
+#[test]
+fn different_functions_are_kept() {
+    let module = deduped(
+        r#"
+        (module
+          (func $one (result i32)
+            i32.const 1)
+          (func $two (result i32)
+            i32.const 2)
+          (func $nan1 (result f32)
+            f32.const nan:0x1)
+          (func $nan2 (result f32)
+            f32.const nan:0x2)
+          (func $zero (result f64)
+            f64.const 0)
+          (func $neg_zero (result f64)
+            f64.const -0)
+          (func $offset0 (result i32)
+            i32.const 0
+            i32.load offset=0)
+          (func $offset4 (result i32)
+            i32.const 0
+            i32.load offset=4)
+          (func $i32_local (local i32)
+            local.get 0
+            drop)
+          (func $i64_local (local i64)
+            local.get 0
+            drop)
+          (func $swapped (param i32 i32) (result i32)
+            local.get 1
+            local.get 0
+            i32.sub)
+          (func $in_order (param i32 i32) (result i32)
+            local.get 0
+            local.get 1
+            i32.sub)
+          (memory 1))
+        "#,
+    );
+    assert_eq!(module.funcs.iter().count(), 12);
+}
+
+#[test]
+fn identical_nans_are_merged() {
+    let module = deduped(
+        r#"
+        (module
+          (func $nan1 (export "nan1") (result f32)
+            f32.const nan:0x1)
+          (func $nan2 (export "nan2") (result f32)
+            f32.const nan:0x1))
+        "#,
+    );
+    assert_eq!(module.funcs.iter().count(), 1);
+}
+
+#[test]
+fn identical_callers_are_merged_too() {
+    let module = deduped(
+        r#"
+        (module
+          (import "env" "log" (func $log (param i32)))
+          (func $leaf1 (param i32)
+            local.get 0
+            call $log)
+          (func $leaf2 (param i32)
+            local.get 0
+            call $log)
+          (func $caller1 (export "caller1")
+            block
+              i32.const 3
+              call $leaf1
+            end)
+          (func $caller2 (export "caller2")
+            block
+              i32.const 3
+              call $leaf2
+            end))
+        "#,
+    );
+    assert!(module.funcs.by_name("leaf2").is_none());
+    assert!(module.funcs.by_name("caller2").is_none());
+    let caller1 = module.funcs.by_name("caller1").unwrap();
+    assert!(module
+        .exports
+        .iter()
+        .all(|e| matches!(e.item, ExportItem::Function(f) if f == caller1)));
+}
@@ -530,3 +530,185 @@ fn test_slab_allocator_builder() {
 
     let _ = round_trip(&mut module);
 }
+
+fn i32_field(mutable: bool) -> walrus::FieldType {
+    walrus::FieldType {
+        element_type: walrus::StorageType::Val(ValType::I32),
+        mutable,
+    }
+}
+
+fn non_null(ty: walrus::TypeId) -> ValType {
+    ValType::Ref(RefType {
+        nullable: false,
+        heap_type: HeapType::Concrete(ty),
+    })
+}
+
+#[test]
+fn test_struct_builders() {
+    let mut config = ModuleConfig::new();
+    config.generate_producers_section(false);
+    let mut module = Module::with_config(config);
+
+    let point = module
+        .types
+        .add_struct(&[i32_field(true), i32_field(false)]);
+
+    let mut builder = FunctionBuilder::new(&mut module.types, &[ValType::I32], &[ValType::I32]);
+    let arg = module.locals.add(ValType::I32);
+    let p = module.locals.add(non_null(point));
+    builder
+        .func_body()
+        .local_get(arg)
+        .i32_const(2)
+        .struct_new(point)
+        .local_set(p)
+        .local_get(p)
+        .i32_const(5)
+        .struct_set(point, 0)
+        .local_get(p)
+        .struct_get(point, 0)
+        .local_get(p)
+        .struct_get(point, 1)
+        .binop(BinaryOp::I32Add)
+        .struct_new_default(point)
+        .drop();
+    let func_id = builder.finish(vec![arg], &mut module.funcs);
+    module.exports.add("point", func_id);
+
+    let module = round_trip(&mut module);
+    let (_, local) = module.funcs.iter_local().next().unwrap();
+    let instrs = &local.block(local.entry_block()).instrs;
+    let point = module
+        .types
+        .find_struct(&[i32_field(true), i32_field(false)]);
+    assert!(point.is_some());
+    assert!(instrs.iter().any(|(i, _)| matches!(
+        i,
+        Instr::StructSet(StructSet { ty, field: 0 }) if Some(*ty) == point
+    )));
+    assert!(instrs
+        .iter()
+        .any(|(i, _)| matches!(i, Instr::StructGet(StructGet { field: 1, .. }))));
+}
+
+#[test]
+fn test_array_builders_keep_segments_alive() {
+    let mut config = ModuleConfig::new();
+    config.generate_producers_section(false);
+    let mut module = Module::with_config(config);
+
+    let bytes = module.types.add_array(walrus::FieldType {
+        element_type: walrus::StorageType::I8,
+        mutable: true,
+    });
+    let funcs = module.types.add_array(walrus::FieldType {
+        element_type: walrus::StorageType::Val(ValType::Ref(RefType::FUNCREF)),
+        mutable: true,
+    });
+
+    let mut builder = FunctionBuilder::new(&mut module.types, &[], &[ValType::I32]);
+    builder.func_body().i32_const(0);
+    let dummy = builder.finish(vec![], &mut module.funcs);
+
+    let data = module
+        .data
+        .add(walrus::DataKind::Passive, b"hello".to_vec());
+    let unused_data = module.data.add(walrus::DataKind::Passive, vec![1]);
+    let elem = module.elements.add(
+        walrus::ElementKind::Passive,
+        walrus::ElementItems::Functions(vec![dummy]),
+    );
+    let unused_elem = module.elements.add(
+        walrus::ElementKind::Passive,
+        walrus::ElementItems::Functions(vec![dummy]),
+    );
+
+    let mut builder = FunctionBuilder::new(&mut module.types, &[], &[ValType::I32]);
+    let arr = module.locals.add(non_null(bytes));
+    builder
+        .func_body()
+        .i32_const(0)
+        .i32_const(5)
+        .array_new_data(bytes, data)
+        .local_set(arr)
+        .i32_const(0)
+        .i32_const(1)
+        .array_new_elem(funcs, elem)
+        .i32_const(0)
+        .i32_const(0)
+        .i32_const(1)
+        .array_init_elem(funcs, elem)
+        .local_get(arr)
+        .i32_const(0)
+        .i32_const(0)
+        .i32_const(1)
+        .array_init_data(bytes, data)
+        .local_get(arr)
+        .i32_const(0)
+        .local_get(arr)
+        .i32_const(1)
+        .i32_const(2)
+        .array_copy(bytes, bytes)
+        .local_get(arr)
+        .i32_const(0)
+        .i32_const(7)
+        .i32_const(1)
+        .array_fill(bytes)
+        .local_get(arr)
+        .i32_const(1)
+        .i32_const(3)
+        .array_set(bytes)
+        .i32_const(4)
+        .i32_const(2)
+        .array_new_fixed(bytes, 2)
+        .array_len()
+        .local_get(arr)
+        .i32_const(0)
+        .array_get_u(bytes)
+        .binop(BinaryOp::I32Add)
+        .local_get(arr)
+        .i32_const(0)
+        .array_get_s(bytes)
+        .binop(BinaryOp::I32Add)
+        .i32_const(3)
+        .array_new_default(bytes)
+        .drop()
+        .i32_const(1)
+        .i32_const(3)
+        .array_new(bytes)
+        .drop();
+    let func_id = builder.finish(vec![], &mut module.funcs);
+    module.exports.add("arrays", func_id);
+
+    walrus::passes::gc::run(&mut module);
+    assert!(module.data.iter().any(|d| d.id() == data));
+    assert!(!module.data.iter().any(|d| d.id() == unused_data));
+    assert!(module.elements.iter().any(|e| e.id() == elem));
+    assert!(!module.elements.iter().any(|e| e.id() == unused_elem));
+
+    let module = round_trip(&mut module);
+    assert_eq!(module.data.iter().count(), 1);
+    assert_eq!(module.elements.iter().count(), 1);
+}
+
+#[test]
+fn test_ref_eq_builder() {
+    let mut config = ModuleConfig::new();
+    config.generate_producers_section(false);
+    let mut module = Module::with_config(config);
+
+    let eqref = ValType::Ref(RefType {
+        nullable: true,
+        heap_type: HeapType::Abstract(AbstractHeapType::Eq),
+    });
+    let mut builder = FunctionBuilder::new(&mut module.types, &[eqref, eqref], &[ValType::I32]);
+    let a = module.locals.add(eqref);
+    let b = module.locals.add(eqref);
+    builder.func_body().local_get(a).local_get(b).ref_eq();
+    let func_id = builder.finish(vec![a, b], &mut module.funcs);
+    module.exports.add("same", func_id);
+
+    let _ = round_trip(&mut module);
+}
@@ -0,0 +1,94 @@
+use std::borrow::Cow;
+use walrus::passes::gc::RootPolicy;
+use walrus::passes::Roots;
+use walrus::{CustomSection, FunctionId, IdsToIndices, Module};
+
+const WAT: &str = r#"
+    (module
+      (func $a (export "a"))
+      (func $b (export "b")
+        call $helper)
+      (func $helper)
+      (func $init
+        call $helper)
+      (memory (export "memory") 1)
+      (global (export "answer") i32 (i32.const 42))
+      (start $init))
+"#;
+
+fn exports(module: &Module) -> Vec<&str> {
+    let mut names = module
+        .exports
+        .iter()
+        .map(|e| e.name.as_str())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+#[test]
+fn default_policy_keeps_everything() {
+    let mut module = Module::from_wat(WAT).unwrap();
+    walrus::passes::gc::run_with_roots(&mut module, &RootPolicy::new());
+    assert_eq!(exports(&module), ["a", "answer", "b", "memory"]);
+    assert_eq!(module.funcs.iter().count(), 4);
+    assert!(module.start.is_some());
+}
+
+#[test]
+fn only_some_exports() {
+    let mut module = Module::from_wat(WAT).unwrap();
+    walrus::passes::gc::run_with_roots(&mut module, RootPolicy::new().keep_exports(["a"]));
+    assert_eq!(exports(&module), ["a"]);
+    assert!(module.funcs.by_name("b").is_none());
+    assert!(module.funcs.by_name("helper").is_some());
+    assert_eq!(module.memories.iter().count(), 0);
+    assert_eq!(module.globals.iter().count(), 0);
+    module.emit_wasm();
+
+    let mut module = Module::from_wat(WAT).unwrap();
+    walrus::passes::gc::run_with_roots(
+        &mut module,
+        RootPolicy::new()
+            .keep_exports_matching(|e| e.name.len() > 1)
+            .start(false),
+    );
+    assert_eq!(exports(&module), ["answer", "memory"]);
+    assert!(module.start.is_none());
+    assert_eq!(module.funcs.iter().count(), 0);
+    module.emit_wasm();
+}
+
+#[derive(Debug)]
+struct KeepFunc(FunctionId);
+
+impl CustomSection for KeepFunc {
+    fn name(&self) -> &str {
+        "keep-func"
+    }
+
+    fn data(&self, _: &IdsToIndices) -> Cow<'_, [u8]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn add_gc_roots(&self, roots: &mut Roots) {
+        roots.push_func(self.0);
+    }
+}
+
+#[test]
+fn custom_section_roots_are_kept() {
+    let mut module = Module::from_wat(WAT).unwrap();
+    let b = module.funcs.by_name("b").unwrap();
+    module.customs.add(KeepFunc(b));
+    walrus::passes::gc::run_with_roots(
+        &mut module,
+        RootPolicy::new()
+            .keep_exports(Vec::<String>::new())
+            .start(false),
+    );
+    assert!(exports(&module).is_empty());
+    assert!(module.funcs.by_name("b").is_some());
+    assert!(module.funcs.by_name("helper").is_some());
+    assert!(module.funcs.by_name("a").is_none());
+}
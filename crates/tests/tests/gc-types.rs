@@ -0,0 +1,759 @@
+//! Tests for GC struct and array type definitions.
+
+use walrus::{
+    FieldType, FunctionType, HeapType, Module, ModuleConfig, RefType, StorageType, StructType,
+    SubType, TypeKind, ValType,
+};
+
+fn round_trip(module: &mut Module) -> Module {
+    let wasm = module.emit_wasm();
+    Module::from_buffer(&wasm).expect("should parse emitted wasm")
+}
+
+fn new_module() -> Module {
+    let mut config = ModuleConfig::new();
+    config.generate_producers_section(false);
+    Module::with_config(config)
+}
+
+#[test]
+fn test_struct_type_round_trip() {
+    let mut module = new_module();
+
+    let fields = [
+        FieldType {
+            element_type: StorageType::Val(ValType::I32),
+            mutable: false,
+        },
+        FieldType {
+            element_type: StorageType::I8,
+            mutable: true,
+        },
+        FieldType {
+            element_type: StorageType::Val(ValType::Ref(RefType::ANYREF)),
+            mutable: true,
+        },
+    ];
+    let ty = module.types.add_struct(&fields);
+    assert_eq!(module.types.add_struct(&fields), ty);
+    assert_eq!(module.types.find_struct(&fields), Some(ty));
+    assert!(!module.types.get(ty).is_function());
+
+    let module = round_trip(&mut module);
+    let id = module.types.find_struct(&fields).expect("struct type kept");
+    match module.types.get(id).kind() {
+        TypeKind::Struct(s) => assert_eq!(&*s.fields, &fields),
+        other => panic!("expected struct type, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_array_type_round_trip() {
+    let mut module = new_module();
+
+    let field = FieldType {
+        element_type: StorageType::I16,
+        mutable: true,
+    };
+    let ty = module.types.add_array(field);
+    assert_eq!(module.types.find_array(field), Some(ty));
+    assert_eq!(module.types.find_struct(&[field]), None);
+
+    let module = round_trip(&mut module);
+    let id = module.types.find_array(field).expect("array type kept");
+    assert_eq!(module.types.get(id).as_array().unwrap().field, field);
+}
+
+#[test]
+fn test_parse_struct_and_array_types() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+          (type (struct))
+          (type (struct (field i32) (field (mut i64)) (field i8) (field (mut i16))))
+          (type (array (mut f32)))
+          (type (array i8))
+          (type (func (param i32) (result i32)))
+        )
+        "#,
+    )
+    .unwrap();
+    let mut module = Module::from_buffer(&wasm).unwrap();
+
+    let kinds = module
+        .types
+        .iter()
+        .map(|t| t.kind().clone())
+        .collect::<Vec<_>>();
+    assert_eq!(kinds.len(), 5);
+    assert_eq!(
+        kinds
+            .iter()
+            .filter(|k| matches!(k, TypeKind::Struct(_)))
+            .count(),
+        2
+    );
+    assert_eq!(
+        kinds
+            .iter()
+            .filter(|k| matches!(k, TypeKind::Array(_)))
+            .count(),
+        2
+    );
+
+    let packed = module
+        .types
+        .find_array(FieldType {
+            element_type: StorageType::I8,
+            mutable: false,
+        })
+        .unwrap();
+    assert_eq!(
+        module
+            .types
+            .get(packed)
+            .as_array()
+            .unwrap()
+            .field
+            .element_type
+            .unpacked(),
+        ValType::I32
+    );
+
+    let module = round_trip(&mut module);
+    assert_eq!(module.types.iter().count(), 5);
+}
+
+#[test]
+fn test_gc_removes_unused_struct_types() {
+    let mut module = new_module();
+    module.types.add_struct(&[FieldType {
+        element_type: StorageType::Val(ValType::I64),
+        mutable: false,
+    }]);
+    walrus::passes::gc::run(&mut module);
+    assert_eq!(module.types.iter().count(), 0);
+}
+
+fn i32_field() -> FieldType {
+    FieldType {
+        element_type: StorageType::Val(ValType::I32),
+        mutable: false,
+    }
+}
+
+#[test]
+fn test_rec_groups_keep_structure_and_order() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+          (type (struct (field i32)))
+          (rec
+            (type (struct (field i32)))
+            (type (struct (field i32))))
+          (rec
+            (type (struct (field i32)))
+            (type (struct (field i32))))
+          (type (func))
+        )
+        "#,
+    )
+    .unwrap();
+    let mut module = Module::from_buffer(&wasm).unwrap();
+
+    // Nothing may be merged: identical types in different recursion groups
+    // are distinct types.
+    assert_eq!(module.types.iter().count(), 6);
+    assert_eq!(module.types.rec_groups().count(), 2);
+    for group in module.types.rec_groups() {
+        assert_eq!(group.types().len(), 2);
+    }
+
+    let wasm2 = module.emit_wasm();
+    let module2 = Module::from_buffer(&wasm2).unwrap();
+    assert_eq!(module2.types.iter().count(), 6);
+    assert_eq!(module2.types.rec_groups().count(), 2);
+    let kinds = module2
+        .types
+        .iter()
+        .map(|t| (t.is_function(), t.rec_group().is_some()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        [
+            (false, false),
+            (false, true),
+            (false, true),
+            (false, true),
+            (false, true),
+            (true, false),
+        ]
+    );
+}
+
+#[test]
+fn test_sub_types_round_trip() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+          (type $base (sub (struct (field i32))))
+          (type $derived (sub final $base (struct (field i32) (field i64))))
+          (type $open (sub (func)))
+        )
+        "#,
+    )
+    .unwrap();
+    let mut module = Module::from_buffer(&wasm).unwrap();
+    let module = round_trip(&mut module);
+
+    let tys = module.types.iter().collect::<Vec<_>>();
+    assert_eq!(tys.len(), 3);
+    assert!(!tys[0].is_final());
+    assert_eq!(tys[0].supertype(), None);
+    assert!(tys[1].is_final());
+    assert_eq!(tys[1].supertype(), Some(tys[0].id()));
+    assert!(!tys[2].is_final());
+    assert!(tys[2].is_function());
+
+    // A non-final type is distinct from the final type of the same shape.
+    let mut module = module;
+    let base = module.types.iter().next().unwrap().id();
+    let final_base = module.types.add_struct(&[i32_field()]);
+    assert_ne!(final_base, base);
+}
+
+#[test]
+fn test_add_rec_group() {
+    let mut module = new_module();
+
+    let base = module.types.add_sub_type(SubType {
+        kind: TypeKind::Struct(StructType {
+            fields: Box::new([i32_field()]),
+        }),
+        supertype: None,
+        is_final: false,
+    });
+    let group = module.types.add_rec_group(2, |ids| {
+        assert_eq!(ids.len(), 2);
+        vec![
+            SubType {
+                kind: TypeKind::Struct(StructType {
+                    fields: Box::new([i32_field(), i32_field()]),
+                }),
+                supertype: Some(base),
+                is_final: true,
+            },
+            SubType {
+                kind: TypeKind::Function(FunctionType::new(Box::new([ValType::I32]), Box::new([]))),
+                supertype: None,
+                is_final: true,
+            },
+        ]
+    });
+    let members = module.types.rec_group(group).types().to_vec();
+    assert_eq!(module.types.get(members[0]).supertype(), Some(base));
+    assert_eq!(module.types.params(members[1]), &[ValType::I32]);
+    // The plain function type is not merged with the group member.
+    let plain = module.types.add(&[ValType::I32], &[]);
+    assert_ne!(plain, members[1]);
+
+    let module = round_trip(&mut module);
+    assert_eq!(module.types.iter().count(), 4);
+    let group = module.types.rec_groups().next().unwrap();
+    let member = module.types.get(group.types()[0]);
+    assert!(member.supertype().is_some());
+}
+
+#[test]
+fn test_gc_keeps_whole_rec_group_and_supertypes() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+          (type $base (sub (func)))
+          (rec
+            (type $f (sub $base (func)))
+            (type (struct (field i32))))
+          (type (struct (field i64)))
+          (func (export "f") (type $f))
+        )
+        "#,
+    )
+    .unwrap();
+    let mut module = Module::from_buffer(&wasm).unwrap();
+    walrus::passes::gc::run(&mut module);
+    let i64_field = FieldType {
+        element_type: StorageType::Val(ValType::I64),
+        mutable: false,
+    };
+    assert_eq!(module.types.find_struct(&[i64_field]), None);
+    let group = module.types.rec_groups().next().unwrap();
+    assert_eq!(group.types().len(), 2);
+    let f = module.types.get(group.types()[0]);
+    let base = f.supertype().expect("supertype kept");
+    assert!(!module.types.get(base).is_final());
+
+    let module = round_trip(&mut module);
+    assert_eq!(module.types.rec_groups().count(), 1);
+    let group = module.types.rec_groups().next().unwrap();
+    let s = module.types.get(group.types()[1]).as_struct().unwrap();
+    assert_eq!(&*s.fields, &[i32_field()]);
+}
+
+fn concrete(ty: walrus::TypeId) -> ValType {
+    ValType::Ref(RefType {
+        nullable: true,
+        heap_type: HeapType::Concrete(ty),
+    })
+}
+
+#[test]
+fn test_mutually_recursive_struct_types() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+          (rec
+            (type $a (struct (field (ref null $b))))
+            (type $b (struct (field (ref null $a)) (field i32))))
+          (global (export "g") (ref null $a) (ref.null $a))
+        )
+        "#,
+    )
+    .unwrap();
+    let mut module = Module::from_buffer(&wasm).unwrap();
+    walrus::passes::gc::run(&mut module);
+    let module = round_trip(&mut module);
+
+    let group = module.types.rec_groups().next().unwrap();
+    let (a, b) = (group.types()[0], group.types()[1]);
+    let a_fields = &module.types.get(a).as_struct().unwrap().fields;
+    let b_fields = &module.types.get(b).as_struct().unwrap().fields;
+    assert_eq!(a_fields[0].element_type, StorageType::Val(concrete(b)));
+    assert_eq!(b_fields[0].element_type, StorageType::Val(concrete(a)));
+
+    let global = module.globals.iter().next().unwrap();
+    assert_eq!(global.ty, concrete(a));
+}
+
+fn non_null(ty: walrus::TypeId) -> ValType {
+    ValType::Ref(RefType {
+        nullable: false,
+        heap_type: HeapType::Concrete(ty),
+    })
+}
+
+#[test]
+fn test_concrete_self_references_in_implicit_types() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+          (type $s (struct (field (ref null $s)) (field (mut (ref $s)))))
+          (type $f (func (param (ref null $f) (ref $f)) (result (ref $f))))
+        )
+        "#,
+    )
+    .unwrap();
+    let mut module = Module::from_buffer(&wasm).unwrap();
+    for module in [round_trip(&mut module), module] {
+        let ids = module.types.iter().map(|t| t.id()).collect::<Vec<_>>();
+        let (s, f) = (ids[0], ids[1]);
+
+        let fields = &module.types.get(s).as_struct().unwrap().fields;
+        assert_eq!(fields[0].element_type, StorageType::Val(concrete(s)));
+        assert_eq!(fields[1].element_type, StorageType::Val(non_null(s)));
+
+        assert_eq!(module.types.params(f), [concrete(f), non_null(f)]);
+        assert_eq!(module.types.results(f), [non_null(f)]);
+    }
+}
+
+#[test]
+fn test_build_self_referential_rec_group() {
+    let mut module = new_module();
+    let group = module.types.add_rec_group(1, |ids| {
+        vec![SubType {
+            kind: TypeKind::Struct(StructType {
+                fields: Box::new([FieldType {
+                    element_type: StorageType::Val(concrete(ids[0])),
+                    mutable: true,
+                }]),
+            }),
+            supertype: None,
+            is_final: true,
+        }]
+    });
+    let list = module.types.rec_group(group).types()[0];
+    let builder = walrus::FunctionBuilder::new(&mut module.types, &[concrete(list)], &[]);
+    let arg = module.locals.add(concrete(list));
+    let func = builder.finish(vec![arg], &mut module.funcs);
+    module.exports.add("f", func);
+
+    walrus::passes::gc::run(&mut module);
+    let module = round_trip(&mut module);
+    let group = module.types.rec_groups().next().unwrap();
+    let list = group.types()[0];
+    let field = module.types.get(list).as_struct().unwrap().fields[0];
+    assert_eq!(field.element_type, StorageType::Val(concrete(list)));
+}
+
+#[test]
+fn test_parse_self_referential_type() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+          (type $list (struct (field i32) (field (ref null $list))))
+          (type $other (struct (field i32) (field (ref null $list))))
+          (global (export "g") (ref null $other) (ref.null $other))
+        )
+        "#,
+    )
+    .unwrap();
+    let mut module = Module::from_buffer(&wasm).unwrap();
+
+    // `$other` has the same definition as `$list` but refers to `$list` from
+    // outside of its group, so it is a different type.
+    let ids = module.types.iter().map(|t| t.id()).collect::<Vec<_>>();
+    assert_eq!(ids.len(), 2);
+    let (list, other) = (ids[0], ids[1]);
+    assert!(!module.types.is_equivalent(list, other));
+    let group = module.types.get(list).rec_group().unwrap();
+    assert!(!module.types.rec_group(group).is_explicit());
+    assert!(module.types.get(other).rec_group().is_none());
+
+    let module = round_trip(&mut module);
+    let ids = module.types.iter().map(|t| t.id()).collect::<Vec<_>>();
+    assert_eq!(ids.len(), 2);
+    let field = module.types.get(ids[0]).as_struct().unwrap().fields[1];
+    assert_eq!(field.element_type, StorageType::Val(concrete(ids[0])));
+    let field = module.types.get(ids[1]).as_struct().unwrap().fields[1];
+    assert_eq!(field.element_type, StorageType::Val(concrete(ids[0])));
+    let global = module.globals.iter().next().unwrap();
+    assert_eq!(global.ty, concrete(ids[1]));
+
+    // The type is emitted outside of a `rec` again.
+    let group = module.types.get(ids[0]).rec_group().unwrap();
+    assert!(!module.types.rec_group(group).is_explicit());
+    assert!(!module.to_wat().contains("(rec"));
+}
+
+#[test]
+fn test_self_referential_types_round_trip() {
+    assert_stable_round_trip(
+        r#"
+        (module
+          (type $list (struct (field i32) (field (ref null $list))))
+          (type $tree (array (mut (ref null $tree))))
+          (func (export "cons") (param i32 (ref null $list)) (result (ref $list))
+            (struct.new $list (local.get 0) (local.get 1)))
+          (func (export "tail") (param (ref $list)) (result (ref null $list))
+            (struct.get $list 1 (local.get 0))))
+        "#,
+    );
+}
+
+/// Parses `wat` with only the stable features enabled, like the spec tests
+/// do, and checks that emitting it again is deterministic.
+fn assert_stable_round_trip(wat: &str) {
+    let mut config = ModuleConfig::new();
+    config.only_stable_features(true);
+    let mut module = config.parse_wat(wat).unwrap();
+    module.validate().unwrap();
+    let wasm = module.emit_wasm();
+    let mut module = config.parse(&wasm).unwrap();
+    assert_eq!(module.emit_wasm(), wasm);
+}
+
+#[test]
+fn test_struct_instructions_round_trip() {
+    assert_stable_round_trip(
+        r#"
+        (module
+          (type $vec (struct (field f32) (field $y (mut f32)) (field $z f32)))
+          (type $packed (struct (field i8) (field (mut i8)) (field i16) (field (mut i16))))
+          (global (ref $vec) (struct.new $vec (f32.const 1) (f32.const 2) (f32.const 3)))
+          (global (ref $vec) (struct.new_default $vec))
+          (global $p (ref $packed)
+            (struct.new $packed (i32.const 1) (i32.const 2) (i32.const 3) (i32.const 4)))
+          (func (export "new") (result anyref) (struct.new_default $vec))
+          (func (export "set_get_y") (param $v (ref null $vec)) (param $y f32) (result f32)
+            (struct.set $vec $y (local.get $v) (local.get $y))
+            (struct.get $vec $y (local.get $v)))
+          (func (export "get_packed") (result i32 i32)
+            (struct.get_s $packed 0 (global.get $p))
+            (struct.get_u $packed 1 (global.get $p))))
+        "#,
+    );
+}
+
+#[test]
+fn test_array_instructions_round_trip() {
+    assert_stable_round_trip(
+        r#"
+        (module
+          (type $vec (array f32))
+          (type $mvec (array (mut f32)))
+          (type $bvec (array i8))
+          (type $vecs (array (ref $bvec)))
+          (global (ref $vec) (array.new $vec (f32.const 1) (i32.const 3)))
+          (global (ref $vec) (array.new_default $vec (i32.const 3)))
+          (global (ref $vec) (array.new_fixed $vec 2 (f32.const 1) (f32.const 2)))
+          (data $d "\00\01\02\ff\04")
+          (elem $e (ref $bvec)
+            (array.new $bvec (i32.const 7) (i32.const 3))
+            (array.new_fixed $bvec 2 (i32.const 1) (i32.const 2)))
+          (func (export "set_get") (param $i i32) (param $y f32) (result f32)
+            (local $v (ref $mvec))
+            (local.set $v (array.new_default $mvec (i32.const 3)))
+            (array.set $mvec (local.get $v) (local.get $i) (local.get $y))
+            (array.get $mvec (local.get $v) (local.get $i)))
+          (func (export "get_s") (param $i i32) (result i32)
+            (array.get_s $bvec
+              (array.new_data $bvec $d (i32.const 1) (i32.const 3))
+              (local.get $i)))
+          (func (export "len") (result i32)
+            (array.len (array.new_elem $vecs $e (i32.const 0) (i32.const 2)))))
+        "#,
+    );
+}
+
+#[test]
+fn test_equivalent_rec_groups_round_trip() {
+    assert_stable_round_trip(
+        r#"
+        (module
+          (rec (type $f1 (func)) (type (struct (field (ref $f1)))))
+          (rec (type $f2 (func)) (type (struct (field (ref $f2)))))
+          (rec (type $g1 (func)) (type (struct)))
+          (rec (type $g2 (func)) (type (struct)))
+          (func $f (type $f2))
+          (func $g (type $g2))
+          (global (ref $f1) (ref.func $f))
+          (global (ref $g1) (ref.func $g))
+          (table funcref (elem $g))
+          (func (export "run") (call_indirect (type $g1) (i32.const 0))))
+        "#,
+    );
+    assert_stable_round_trip(
+        r#"
+        (module
+          (rec
+            (type $0 (func (param i32)))
+            (type $1 (func (param i32 (ref $0))))
+            (type $2 (func (param i32 (ref $0) (ref $1)))))
+          (rec (type $3 (func (param i32 (ref $0) (ref $1) (ref $2)))))
+          (rec (type $4 (func (param (ref $5)))) (type $5 (func (param (ref $4))))))
+        "#,
+    );
+
+    // The groups differ in the order of their types, so `$f2` isn't `$f1`.
+    let mut config = ModuleConfig::new();
+    config.only_stable_features(true);
+    assert!(config
+        .parse_wat(
+            r#"
+            (module
+              (rec (type $f1 (func)) (type (struct)))
+              (rec (type (struct)) (type $f2 (func)))
+              (func $f (type $f2))
+              (global (ref $f1) (ref.func $f)))
+            "#,
+        )
+        .is_err());
+}
+
+#[test]
+fn test_subtyping_round_trip() {
+    assert_stable_round_trip(
+        r#"
+        (module
+          (type $s (sub (struct)))
+          (type $s' (sub $s (struct)))
+          (type $f1 (sub (func (param (ref $s')) (result anyref))))
+          (type $f2 (sub $f1 (func (param (ref $s)) (result (ref any)))))
+          (type $f3 (sub final $f2 (func (param (ref null $s)) (result (ref $s)))))
+          (rec
+            (type $a1 (sub (struct (field i32 (ref $a2)))))
+            (type $a2 (sub (struct (field i64 (ref $a1))))))
+          (rec
+            (type $b1 (sub $a2 (struct (field i64 (ref $a1) i32))))
+            (type $b2 (sub $a1 (struct (field i32 (ref $a2) i32))))
+            (type $b3 (sub $a2 (struct (field i64 (ref $b2) i32)))))
+          (rec
+            (type $t1 (sub (func (param i32 (ref $t3)))))
+            (type $t2 (sub $t1 (func (param i32 (ref $t2)))))
+            (type $t3 (sub $t2 (func (param i32 (ref $t1))))))
+          (func $f1 (param $r (ref $t1)) (call $f1 (local.get $r)))
+          (func $f3 (param $r (ref $t3)) (call $f1 (local.get $r)) (call $f3 (local.get $r))))
+        "#,
+    );
+    assert_stable_round_trip(
+        r#"
+        (module
+          (type $t0 (sub (func (result (ref null func)))))
+          (rec (type $t1 (sub $t0 (func (result (ref null $t1))))))
+          (rec (type $t2 (sub $t1 (func (result (ref null $t2))))))
+          (func $f0 (type $t0) (ref.null func))
+          (func $f1 (type $t1) (ref.null $t1))
+          (func $f2 (type $t2) (ref.null $t2))
+          (table funcref (elem $f0 $f1 $f2))
+          (func (export "run")
+            (block (result (ref null func)) (call_indirect (type $t0) (i32.const 2)))
+            (block (result (ref null $t1)) (call_indirect (type $t1) (i32.const 2)))
+            (block (result (ref null $t1)) (ref.cast (ref $t1) (table.get (i32.const 2))))
+            (br 0)))
+        "#,
+    );
+
+    // The same, with the self-referential types outside of a `rec`.
+    assert_stable_round_trip(
+        r#"
+        (module
+          (type $t0 (sub (func (result (ref null func)))))
+          (type $t1 (sub $t0 (func (result (ref null $t1)))))
+          (type $t2 (sub $t1 (func (result (ref null $t2)))))
+          (type $s0 (sub (struct (field (ref null $s0)))))
+          (type $s1 (sub $s0 (struct (field (ref null $s1)) (field i32))))
+          (func $f1 (type $t1) (ref.null $t1))
+          (func $f2 (type $t2) (ref.null $t2))
+          (table funcref (elem $f1 $f2))
+          (func (export "run") (param (ref $s1)) (result (ref null $s0))
+            (block (result (ref null $t1)) (call_indirect (type $t1) (i32.const 1)))
+            (drop)
+            (struct.get $s0 0 (local.get 0))))
+        "#,
+    );
+
+    // Mutable fields are invariant, so a self-reference can't be refined.
+    let mut config = ModuleConfig::new();
+    config.only_stable_features(true);
+    assert!(config
+        .parse_wat(
+            r#"
+            (module
+              (type $s0 (sub (struct (field (mut (ref null $s0))))))
+              (type $s1 (sub $s0 (struct (field (mut (ref null $s1)))))))
+            "#,
+        )
+        .is_err());
+}
+
+#[test]
+fn test_find_ignores_subtypable_and_grouped_types() {
+    let mut module = Module::from_wat(
+        r#"
+        (module
+          (type $s (sub (struct (field i32))))
+          (type $a (sub (array i32)))
+          (type $a' (sub final $a (array i32)))
+          (rec (type $f (func (param i32))) (type (struct))))
+        "#,
+    )
+    .unwrap();
+    let field = i32_field();
+    assert_eq!(module.types.find_struct(&[field]), None);
+    assert_eq!(module.types.find_array(field), None);
+    assert_eq!(module.types.find(&[ValType::I32], &[]), None);
+
+    let s = module.types.add_struct(&[field]);
+    assert!(module.types.get(s).is_final());
+    assert_eq!(module.types.find_struct(&[field]), Some(s));
+    let f = module.types.add(&[ValType::I32], &[]);
+    assert!(module.types.get(f).rec_group().is_none());
+    assert_eq!(module.types.find(&[ValType::I32], &[]), Some(f));
+}
+
+#[test]
+fn test_block_types_can_be_subtypable_or_grouped() {
+    assert_stable_round_trip(
+        r#"
+        (module
+          (type $t (sub (func (param i32) (result i32 i32))))
+          (rec (type $u (func (param i64) (result i64 i64))) (type (struct)))
+          (func (param i32 i64) (result i32 i32 i64 i64)
+            (local.get 0)
+            (block (type $t) (i32.const 1) (i32.add) (local.get 0))
+            (local.get 1)
+            (if (type $u) (i32.const 1) (then (i64.const 2)) (else (i64.const 3)))
+            (drop)
+            (drop)
+            (local.get 1)
+            (loop (type $u) (i64.const 5))))
+        "#,
+    );
+}
+
+#[test]
+fn test_canonical_rec_groups_round_trip() {
+    // Modules in the style of the `type-canon.wast` and `type-rec.wast` spec
+    // tests.
+    assert_stable_round_trip(
+        r#"
+        (module
+          (rec
+            (type $t0 (func (param i32 (ref $t2) (ref $t3))))
+            (type $t1 (func (param i32 (ref $t0) i32 (ref $t3))))
+            (type $t2 (func (param i32 (ref $t2) (ref $t1))))
+            (type $t3 (func (param i32 (ref $t2) i32 (ref $t1)))))
+          (func (export "f0") (type $t0))
+          (func (export "f1") (type $t1)))
+        "#,
+    );
+    assert_stable_round_trip(
+        r#"
+        (module
+          (rec (type $s0 (struct (field (ref 0) (ref 1) (ref $s0) (ref $s1))))
+               (type $s1 (struct (field (ref 0) (ref 1) (ref $s0) (ref $s1)))))
+          (rec (type (func (param (ref null 2)))))
+          (rec (type (func (param (ref null 3)))))
+          (func (type 2))
+          (func (type 3)))
+        "#,
+    );
+    assert_stable_round_trip(
+        r#"
+        (module
+          (rec (type $f1 (func)) (type (struct (field (ref $f1)))))
+          (rec (type $f2 (func)) (type (struct (field (ref $f2)))))
+          (table funcref (elem $f1))
+          (func $f1 (type $f1))
+          (global (ref $f2) (ref.func $f1))
+          (func (export "run") (call_indirect (type $f2) (i32.const 0))))
+        "#,
+    );
+}
+
+#[test]
+fn test_subtyping_of_implicit_and_equivalent_types_round_trip() {
+    // Modules in the style of the `type-subtyping.wast` spec test.
+    assert_stable_round_trip(
+        r#"
+        (module
+          (type $t0 (sub (func (result (ref null func)))))
+          (type $t1 (sub $t0 (func (result (ref null $t1)))))
+          (type $t2 (sub $t1 (func (result (ref null $t2)))))
+          (type $final (sub final (func)))
+          (func $f0 (type $t0) (ref.null func))
+          (func $f1 (type $t1) (ref.null $t1))
+          (func $f2 (type $t2) (ref.null $t2))
+          (func $f3 (type $final))
+          (table funcref (elem $f0 $f1 $f2 $f3))
+          (func (export "run")
+            (drop (call_indirect (type $t0) (i32.const 1)))
+            (drop (call_indirect (type $t1) (i32.const 2)))
+            (call_indirect (type $final) (i32.const 3))))
+        "#,
+    );
+    assert_stable_round_trip(
+        r#"
+        (module
+          (rec (type $f1 (sub (func))) (type (struct (field (ref $f1)))))
+          (rec (type $f2 (sub (func))) (type (struct (field (ref $f2)))))
+          (rec (type $g1 (sub $f1 (func))) (type (struct)))
+          (rec (type $g2 (sub $f2 (func))) (type (struct)))
+          (func $g (type $g2))
+          (global (ref $g1) (ref.func $g))
+          (func (export "run") (result i32 i32)
+            (ref.test (ref $g1) (ref.func $g))
+            (ref.test (ref $f1) (ref.cast (ref $f2) (ref.func $g)))))
+        "#,
+    );
+}
@@ -0,0 +1,282 @@
+use walrus::ir::{dfs_in_order, Const, Instr, InstrLocId, Value, Visitor};
+use walrus::passes::inline::Inliner;
+use walrus::{FunctionId, Module};
+use walrus_tests::support::check;
+
+/// Every instruction of a function, in order.
+fn instrs(module: &Module, func: FunctionId) -> Vec<(Instr, InstrLocId)> {
+    struct Collect(Vec<(Instr, InstrLocId)>);
+
+    impl<'instr> Visitor<'instr> for Collect {
+        fn visit_instr(&mut self, instr: &'instr Instr, loc: &'instr InstrLocId) {
+            self.0.push((instr.clone(), *loc));
+        }
+    }
+
+    let func = module.funcs.get(func).kind.unwrap_local();
+    let mut visitor = Collect(Vec::new());
+    dfs_in_order(&mut visitor, func, func.entry_block());
+    visitor.0
+}
+
+fn calls(module: &Module, func: FunctionId) -> usize {
+    instrs(module, func)
+        .iter()
+        .filter(|(i, _)| matches!(i, Instr::Call(_) | Instr::ReturnCall(_)))
+        .count()
+}
+
+#[test]
+fn small_functions() {
+    let mut module = Module::from_wat(
+        r#"
+        (module
+          (func $add (param i32 i32) (result i32)
+            local.get 0
+            local.get 1
+            i32.add)
+          (func $big (param i32) (result i32)
+            local.get 0
+            local.get 0
+            i32.mul
+            local.get 0
+            i32.mul
+            local.get 0
+            i32.mul)
+          (func $main (export "main") (result i32)
+            i32.const 1
+            i32.const 2
+            call $add
+            call $big
+            i32.const 3
+            call $add))
+        "#,
+    )
+    .unwrap();
+    let main = module.funcs.by_name("main").unwrap();
+    Inliner::new().max_size(3).run(&mut module);
+    assert_eq!(calls(&module, main), 1);
+    check(&mut module);
+
+    walrus::passes::gc::run(&mut module);
+    assert!(module.funcs.by_name("add").is_none());
+    assert!(module.funcs.by_name("big").is_some());
+    check(&mut module);
+}
+
+#[test]
+fn returns_leave_the_inlined_body() {
+    let mut module = Module::from_wat(
+        r#"
+        (module
+          (func $pair (param i32) (result i32 i64)
+            local.get 0
+            if
+              i32.const 1
+              i64.const 2
+              return
+            end
+            local.get 0
+            i64.const 3)
+          (func $main (export "main") (param i32) (result i64) (local i64)
+            local.get 0
+            call $pair
+            local.set 1
+            drop
+            local.get 1))
+        "#,
+    )
+    .unwrap();
+    let main = module.funcs.by_name("main").unwrap();
+    Inliner::new().max_size(100).run(&mut module);
+
+    let instrs = instrs(&module, main);
+    assert!(!instrs.iter().any(|(i, _)| matches!(i, Instr::Call(_))));
+    assert!(!instrs.iter().any(|(i, _)| matches!(i, Instr::Return(_))));
+    assert_eq!(
+        instrs
+            .iter()
+            .filter(|(i, _)| matches!(i, Instr::Br(_)))
+            .count(),
+        1
+    );
+    check(&mut module);
+}
+
+#[test]
+fn single_callers() {
+    let mut module = Module::from_wat(
+        r#"
+        (module
+          (func $once (param i32) (result i32)
+            local.get 0
+            i32.const 1
+            i32.add)
+          (func $twice (param i32) (result i32)
+            local.get 0)
+          (func $exported (export "exported") (result i32)
+            i32.const 0)
+          (func $main (export "main") (result i32)
+            i32.const 1
+            call $once
+            call $twice
+            call $twice
+            call $exported
+            i32.add))
+        "#,
+    )
+    .unwrap();
+    let main = module.funcs.by_name("main").unwrap();
+    Inliner::new().inline_single_caller(true).run(&mut module);
+    let callees = instrs(&module, main)
+        .into_iter()
+        .filter_map(|(i, _)| match i {
+            Instr::Call(c) => module.funcs.get(c.func).name.clone(),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(callees, ["twice", "twice", "exported"]);
+    check(&mut module);
+}
+
+#[test]
+fn should_inline_callback() {
+    let mut module = Module::from_wat(
+        r#"
+        (module
+          (func $yes (result i32)
+            i32.const 1)
+          (func $no (result i32)
+            i32.const 2)
+          (func $main (export "main") (result i32)
+            call $yes
+            call $no
+            i32.add))
+        "#,
+    )
+    .unwrap();
+    let main = module.funcs.by_name("main").unwrap();
+    Inliner::new()
+        .should_inline(|module, _caller, callee| {
+            module.funcs.get(callee).name.as_deref() == Some("yes")
+        })
+        .run(&mut module);
+    assert_eq!(calls(&module, main), 1);
+    check(&mut module);
+}
+
+#[test]
+fn tail_calls() {
+    let mut module = Module::from_wat(
+        r#"
+        (module
+          (import "env" "f" (func $f (param i32) (result i32)))
+          (func $tail (param i32) (result i32)
+            local.get 0
+            return_call $f)
+          (func $call (export "call") (param i32) (result i32)
+            local.get 0
+            call $tail
+            i32.const 1
+            i32.add)
+          (func $return_call (export "return_call") (param i32) (result i32)
+            local.get 0
+            return_call $tail))
+        "#,
+    )
+    .unwrap();
+    Inliner::new().max_size(10).run(&mut module);
+
+    // The tail call of `$f` can't return from `$call`...
+    let call = module.funcs.by_name("call").unwrap();
+    let call_instrs = instrs(&module, call);
+    assert!(call_instrs
+        .iter()
+        .any(|(i, _)| matches!(i, Instr::Call(c) if Some(c.func) == module.funcs.by_name("f"))));
+    assert!(!call_instrs
+        .iter()
+        .any(|(i, _)| matches!(i, Instr::ReturnCall(_))));
+
+    // ...but it can from `$return_call`, which returns what `$tail` does.
+    let return_call = module.funcs.by_name("return_call").unwrap();
+    let return_call_instrs = instrs(&module, return_call);
+    assert!(return_call_instrs
+        .iter()
+        .any(|(i, _)| matches!(i, Instr::ReturnCall(_))));
+    assert!(matches!(
+        return_call_instrs.last().unwrap().0,
+        Instr::Return(_)
+    ));
+    check(&mut module);
+}
+
+#[test]
+fn locals_are_reset_in_loops() {
+    let mut module = Module::from_wat(
+        r#"
+        (module
+          (func $count (result i32) (local i32)
+            local.get 0
+            i32.const 1
+            i32.add
+            local.tee 0)
+          (func $main (export "main") (param i32)
+            loop
+              call $count
+              local.get 0
+              i32.lt_u
+              br_if 0
+            end))
+        "#,
+    )
+    .unwrap();
+    let main = module.funcs.by_name("main").unwrap();
+    Inliner::new().max_size(10).run(&mut module);
+
+    let instrs = instrs(&module, main);
+    // The callee's local is a new local of the caller, and set to zero at the
+    // start of every iteration.
+    let set = instrs
+        .iter()
+        .position(|(i, _)| matches!(i, Instr::LocalSet(_)))
+        .unwrap();
+    assert!(matches!(
+        &instrs[set - 1].0,
+        Instr::Const(Const {
+            value: Value::I32(0)
+        })
+    ));
+    check(&mut module);
+}
+
+#[test]
+fn keeps_instruction_locations() {
+    let mut module = Module::from_wat(
+        r#"
+        (module
+          (func $callee (param i32) (result i32)
+            local.get 0
+            i32.const 7
+            i32.mul)
+          (func $main (export "main") (result i32)
+            i32.const 6
+            call $callee))
+        "#,
+    )
+    .unwrap();
+    let main = module.funcs.by_name("main").unwrap();
+    let callee = module.funcs.by_name("callee").unwrap();
+    let callee_locs = instrs(&module, callee)
+        .into_iter()
+        .map(|(_, loc)| loc)
+        .collect::<Vec<_>>();
+    Inliner::new().max_size(10).run(&mut module);
+
+    let main_locs = instrs(&module, main)
+        .into_iter()
+        .map(|(_, loc)| loc)
+        .collect::<Vec<_>>();
+    for loc in callee_locs {
+        assert!(main_locs.contains(&loc));
+    }
+}
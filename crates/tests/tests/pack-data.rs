@@ -0,0 +1,105 @@
+use walrus::ir::Value;
+use walrus::{ConstExpr, DataKind, Module};
+use walrus_tests::support::run_pass;
+
+fn packed(wat: &str) -> Module {
+    run_pass(wat, walrus::passes::pack_data::run)
+}
+
+/// The constant offsets and contents of the module's active segments.
+fn segments(module: &Module) -> Vec<(i32, Vec<u8>)> {
+    let mut segments = module
+        .data
+        .iter()
+        .filter_map(|data| match &data.kind {
+            DataKind::Active {
+                offset: ConstExpr::Value(Value::I32(offset)),
+                ..
+            } => Some((*offset, data.value.clone())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    segments.sort();
+    segments
+}
+
+#[test]
+fn adjacent_and_overlapping_segments() {
+    let module = packed(
+        r#"
+        (module
+          (memory 1)
+          (data (i32.const 0) "ab")
+          (data (i32.const 2) "cd")
+          (data (i32.const 10) "wxyz")
+          (data (i32.const 12) "YZ")
+          (data (i32.const 1000) "far"))
+        "#,
+    );
+    assert_eq!(
+        segments(&module),
+        [
+            (0, b"abcd\0\0\0\0\0\0wxYZ".to_vec()),
+            (1000, b"far".to_vec())
+        ]
+    );
+    let memory = module.memories.iter().next().unwrap();
+    assert_eq!(memory.data_segments.len(), 2);
+}
+
+#[test]
+fn zeros_are_dropped() {
+    let module = packed(
+        r#"
+        (module
+          (memory 1)
+          (data (i32.const 100)
+            "\00\00\01\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\02\00")
+          (data (i32.const 200) "\00\00\00\00"))
+        "#,
+    );
+    assert_eq!(
+        segments(&module),
+        [(102, b"\x01".to_vec()), (122, b"\x02".to_vec())]
+    );
+}
+
+#[test]
+fn imported_memories_keep_zeros() {
+    let module = packed(
+        r#"
+        (module
+          (import "env" "memory" (memory 1))
+          (data (i32.const 0) "\00a")
+          (data (i32.const 2) "b\00")
+          (data (i32.const 8) "\00"))
+        "#,
+    );
+    assert_eq!(
+        segments(&module),
+        [(0, b"\0ab\0".to_vec()), (8, b"\0".to_vec())]
+    );
+}
+
+#[test]
+fn memories_that_are_left_alone() {
+    let module = packed(
+        r#"
+        (module
+          (import "env" "base" (global $base i32))
+          (memory $dynamic 1)
+          (memory $out_of_bounds 1)
+          (memory $used 1)
+          (data (memory $dynamic) (i32.const 0) "a")
+          (data (memory $dynamic) (global.get $base) "b")
+          (data (memory $dynamic) (i32.const 1) "c")
+          (data (memory $out_of_bounds) (i32.const 0) "d")
+          (data (memory $out_of_bounds) (i32.const 65535) "ef")
+          (data (memory $used) (i32.const 0) "g")
+          (data $used (memory $used) (i32.const 1) "h")
+          (func
+            data.drop $used))
+        "#,
+    );
+    assert_eq!(module.data.iter().count(), 7);
+}
@@ -0,0 +1,83 @@
+use walrus::{ExportItem, FunctionKind, Module, ModuleConfig};
+
+#[test]
+fn identifiers_become_names() {
+    let module = Module::from_wat(
+        r#"
+        (module $m
+          (type $sig (func (param i32) (result i32)))
+          (import "env" "mem" (memory $mem 1))
+          (table $table 1 funcref)
+          (global $counter (mut i32) (i32.const 0))
+          (tag $oops)
+          (func $double (export "double") (type $sig) (param $x i32) (result i32)
+            (local $tmp i32)
+            local.get $x
+            local.get $x
+            i32.add
+            local.tee $tmp)
+          (elem $funcs (table $table) (i32.const 0) func $double)
+          (data $bytes (memory $mem) (i32.const 0) "walrus"))
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(module.name.as_deref(), Some("m"));
+
+    let double = module.funcs.by_name("double").unwrap();
+    let func = match &module.funcs.get(double).kind {
+        FunctionKind::Local(func) => func,
+        _ => panic!("`$double` should be a local function"),
+    };
+    assert_eq!(module.types.get(func.ty()).name.as_deref(), Some("sig"));
+    assert_eq!(module.types.by_name("sig"), Some(func.ty()));
+    assert_eq!(module.locals.get(func.args[0]).name.as_deref(), Some("x"));
+    assert!(module
+        .locals
+        .iter()
+        .any(|local| local.name.as_deref() == Some("tmp")));
+
+    let export = module.exports.get_exported_func(double).unwrap();
+    assert_eq!(export.name, "double");
+    assert!(matches!(export.item, ExportItem::Function(f) if f == double));
+
+    let memory = module.memories.iter().next().unwrap();
+    assert_eq!(memory.name.as_deref(), Some("mem"));
+    let table = module.tables.iter().next().unwrap();
+    assert_eq!(table.name.as_deref(), Some("table"));
+    let global = module.globals.iter().next().unwrap();
+    assert_eq!(global.name.as_deref(), Some("counter"));
+    let tag = module.tags.iter().next().unwrap();
+    assert_eq!(tag.name.as_deref(), Some("oops"));
+    let element = module.elements.iter().next().unwrap();
+    assert_eq!(element.name.as_deref(), Some("funcs"));
+    let data = module.data.iter().next().unwrap();
+    assert_eq!(data.name.as_deref(), Some("bytes"));
+}
+
+#[test]
+fn uses_the_config() {
+    let mut config = ModuleConfig::new();
+    config.generate_synthetic_names_for_anonymous_items(true);
+    let module = config.parse_wat("(module (func (param i32)))").unwrap();
+    let func = module.funcs.iter().next().unwrap();
+    match &func.kind {
+        FunctionKind::Local(func) => {
+            assert_eq!(
+                module.locals.get(func.args[0]).name.as_deref(),
+                Some("arg0")
+            );
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn reports_text_errors() {
+    let err = Module::from_wat("(module (func $f (local.get $nope)))").unwrap_err();
+    assert!(
+        err.to_string().contains("$nope"),
+        "unexpected error: {}",
+        err
+    );
+}
@@ -0,0 +1,122 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use walrus::passes::{FunctionContext, FunctionPass, Pass, PassManager};
+use walrus::{FunctionId, LocalFunction, Module};
+
+const WAT: &str = r#"
+    (module
+      (func $unused (result i32)
+        i32.const 0)
+      (func $main (export "main") (result i32)
+        i32.const 2
+        i32.const 3
+        i32.add
+        i32.const 7
+        drop))
+"#;
+
+#[test]
+fn named_pipeline() {
+    let mut module = Module::from_wat(WAT).unwrap();
+    let mut manager = PassManager::new();
+    manager
+        .pipeline(&["const-fold", "dce", "gc"])
+        .unwrap()
+        .validate(true);
+    let stats = manager.run(&mut module).unwrap();
+
+    let names = stats.iter().map(|s| s.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["const-fold", "dce", "gc"]);
+    assert_eq!(stats[0].size_before, 6);
+    assert_eq!(stats[0].size_delta(), -2);
+    assert_eq!(stats[1].size_delta(), -2);
+    assert_eq!(stats[2].size_after, 1);
+    assert_eq!(module.funcs.iter().count(), 1);
+}
+
+#[test]
+fn inline_pipeline() {
+    let mut module = Module::from_wat(
+        r#"
+        (module
+          (func $double (param i32) (result i32)
+            local.get 0
+            local.get 0
+            i32.add)
+          (func $main (export "main") (param i32) (result i32)
+            local.get 0
+            call $double))
+        "#,
+    )
+    .unwrap();
+    let mut manager = PassManager::new();
+    manager.pipeline(&["inline", "gc"]).unwrap().validate(true);
+    let stats = manager.run(&mut module).unwrap();
+
+    assert_eq!(stats[0].name, "inline");
+    assert_eq!(module.funcs.iter().count(), 1);
+}
+
+#[test]
+fn unknown_names() {
+    let err = PassManager::new().add_named("nope").unwrap_err();
+    assert!(err.to_string().contains("nope"));
+}
+
+/// Counts the functions that it runs over.
+struct Count(Arc<AtomicUsize>);
+
+impl FunctionPass for Count {
+    fn name(&self) -> &str {
+        "count"
+    }
+
+    fn run_function(
+        &self,
+        _: &FunctionContext,
+        _: FunctionId,
+        _: &mut LocalFunction,
+    ) -> anyhow::Result<()> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// Breaks the module by deleting a function that is still exported.
+struct Break;
+
+impl Pass for Break {
+    fn name(&self) -> &str {
+        "break"
+    }
+
+    fn run(&self, module: &mut Module) -> anyhow::Result<()> {
+        let main = module.funcs.by_name("main").unwrap();
+        module.funcs.delete(main);
+        Ok(())
+    }
+}
+
+#[test]
+fn registered_passes() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let mut manager = PassManager::new();
+    let counter = count.clone();
+    manager
+        .register_function("count", move || Box::new(Count(counter.clone())))
+        .register("break", || Box::new(Break));
+    assert!(manager.registered().any(|name| name == "count"));
+
+    let mut module = Module::from_wat(WAT).unwrap();
+    manager.pipeline(&["count", "count"]).unwrap();
+    manager.run(&mut module).unwrap();
+    assert_eq!(count.load(Ordering::SeqCst), 4);
+
+    // Without validation, the broken module goes unnoticed.
+    manager.add_named("break").unwrap();
+    manager.run(&mut module).unwrap();
+
+    let mut module = Module::from_wat(WAT).unwrap();
+    let err = manager.validate(true).run(&mut module).unwrap_err();
+    assert!(format!("{:?}", err).contains("after pass `break`"));
+}
@@ -0,0 +1,200 @@
+use walrus::printer::Printer;
+use walrus::{FunctionBuilder, Module, ValType};
+
+fn parse(wat: &str) -> Module {
+    Module::from_wat(wat).unwrap()
+}
+
+/// Print `module`, check that the text parses back to a module that prints
+/// the same, and return it.
+fn print(module: &Module) -> String {
+    let printed = module.to_wat();
+    let reparsed = Module::from_wat(&printed).unwrap();
+    assert_eq!(printed, reparsed.to_wat());
+    printed
+}
+
+#[test]
+fn prints_module() {
+    let module = parse(
+        r#"
+        (module $m
+          (import "env" "log" (func $log (param i32)))
+          (memory $mem 1)
+          (global $count (mut i32) (i32.const 0))
+          (func $tick (export "tick") (param $by i32) (result i32)
+            (local $old i32)
+            global.get $count
+            local.tee $old
+            local.get $by
+            i32.add
+            global.set $count
+            block $done
+              loop $again
+                local.get $old
+                i32.eqz
+                br_if $done
+                local.get $old
+                call $log
+                br $again
+              end
+            end
+            local.get $old
+            if (result i32)
+              i32.const 1
+            else
+              i32.const 2
+            end
+            i32.load8_u offset=4)
+          (data (i32.const 8) "hi\00\ff"))
+        "#,
+    );
+
+    assert_eq!(
+        print(&module),
+        r#"(module $m
+  (type (;0;) (func (param i32)))
+  (type (;1;) (func (param i32) (result i32)))
+  (import "env" "log" (func $log (;0;) (type 0) (param i32)))
+  (func $tick (;1;) (type 1) (param $by (;0;) i32) (result i32)
+    (local $old (;1;) i32)
+    global.get $count
+    local.tee $old
+    local.get $by
+    i32.add
+    global.set $count
+    block $l1
+      loop $l2
+        local.get $old
+        i32.eqz
+        br_if $l1
+        local.get $old
+        call $log
+        br $l2
+      end
+    end
+    local.get $old
+    if $l1 (result i32)
+      i32.const 1
+    else
+      i32.const 2
+    end
+    i32.load8_u offset=4
+  )
+  (memory $mem (;0;) 1)
+  (global $count (;0;) (mut i32) (i32.const 0))
+  (export "tick" (func $tick))
+  (data (;0;) (memory $mem) (i32.const 8) "hi\00\ff")
+)
+"#
+    );
+}
+
+#[test]
+fn annotates_ids_and_locations() {
+    let module = parse(
+        r#"
+        (module
+          (func $f (result i32)
+            block (result i32)
+              i32.const 1
+            end))
+        "#,
+    );
+    let f = module.funcs.by_name("f").unwrap();
+    let wat = Printer::new(&module)
+        .annotate_ids(true)
+        .annotate_instr_locs(true)
+        .print_function(f);
+    let block = match &module.funcs.get(f).kind {
+        walrus::FunctionKind::Local(func) => {
+            let entry = func.block(func.entry_block());
+            match &entry.instrs[0].0 {
+                walrus::ir::Instr::Block(b) => b.seq,
+                other => panic!("unexpected instruction {:?}", other),
+            }
+        }
+        _ => unreachable!(),
+    };
+
+    assert_eq!(
+        wat,
+        format!(
+            "(func $f (;0;) (;id {};) (type 0) (result i32)
+  block $l1 (;id {};) (result i32) (;@18;)
+    i32.const 1 (;@1a;)
+  end (;@1c;)
+)
+",
+            f.index(),
+            block.index()
+        )
+    );
+
+    // Without annotations, only indices are printed.
+    assert_eq!(
+        Printer::new(&module).print_function(f),
+        "(func $f (;0;) (type 0) (result i32)
+  block $l1 (result i32)
+    i32.const 1
+  end
+)
+"
+    );
+}
+
+#[test]
+fn prints_legacy_exceptions() {
+    let module = parse(
+        r#"
+        (module
+          (tag $e)
+          (func (result i32)
+            try (result i32)
+              try
+                throw $e
+              delegate 0
+              i32.const 0
+            catch $e
+              i32.const 1
+              br 0
+            catch_all
+              i32.const 2
+            end))
+        "#,
+    );
+    let wat = print(&module);
+    assert!(wat.contains(
+        "    try $l1 (result i32)
+      try $l2
+        throw $e
+      delegate 0
+      i32.const 0
+    catch $e
+      i32.const 1
+      br $l1
+    catch_all
+      i32.const 2
+    end
+"
+    ));
+}
+
+#[test]
+fn disambiguates_and_quotes_names() {
+    let mut module = Module::default();
+    let mut funcs = Vec::new();
+    for name in ["same", "same", "with space"] {
+        let mut builder = FunctionBuilder::new(&mut module.types, &[], &[ValType::I32]);
+        builder.name(name.to_string()).func_body().i32_const(0);
+        let id = builder.finish(vec![], &mut module.funcs);
+        module.exports.add(&format!("f{}", funcs.len()), id);
+        funcs.push(id);
+    }
+
+    let wat = print(&module);
+    assert!(wat.contains("(func $same (;0;)"));
+    assert!(wat.contains("(func $same#1 (;1;)"));
+    assert!(wat.contains("(func $\"with space\" (;2;)"));
+    assert!(wat.contains("(export \"f2\" (func $\"with space\"))"));
+}
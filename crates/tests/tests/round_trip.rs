@@ -9,6 +9,13 @@ fn run(wat_path: &Path) -> Result<(), anyhow::Error> {
 
     let wasm = wat::parse_file(wat_path)?;
     let mut module = walrus::Module::from_buffer(&wasm)?;
+    module.validate()?;
+
+    // Printing the module as text and parsing it back should give a module
+    // that prints the same.
+    let printed = module.to_wat();
+    let reparsed = walrus::Module::from_buffer(&wat::parse_str(&printed)?)?;
+    assert_eq!(printed, reparsed.to_wat());
 
     if env::var("WALRUS_TESTS_DOT").is_ok() {
         module.write_graphviz_dot(wat_path.with_extension("dot"))?;
@@ -16,6 +23,7 @@ fn run(wat_path: &Path) -> Result<(), anyhow::Error> {
 
     let out_wasm_file = wat_path.with_extension("out.wasm");
     walrus::passes::gc::run(&mut module);
+    module.validate()?;
     module.emit_wasm_file(&out_wasm_file)?;
 
     let out_wat = wasmprinter::print_file(&out_wasm_file)?;
@@ -159,17 +159,17 @@
 
 (; CHECK-ALL:
   (module
-    (type (;0;) (func (result i32)))
-    (type (;1;) (func (result anyref)))
-    (type (;2;) (func (result eqref)))
+    (type (;0;) (func (param i32) (result i31ref)))
+    (type (;1;) (func (result i32)))
+    (type (;2;) (func (param anyref) (result i32)))
     (type (;3;) (func (result i31ref)))
-    (type (;4;) (func (param i32) (result externref)))
-    (type (;5;) (func (param i32) (result i31ref)))
-    (type (;6;) (func (param i32 externref)))
-    (type (;7;) (func (param externref) (result externref)))
-    (type (;8;) (func (param anyref) (result i32)))
-    (type (;9;) (func (param anyref) (result i31ref)))
-    (func (;0;) (type 8) (param anyref) (result i32)
+    (type (;4;) (func (param anyref) (result i31ref)))
+    (type (;5;) (func (result anyref)))
+    (type (;6;) (func (result eqref)))
+    (type (;7;) (func (param i32 externref)))
+    (type (;8;) (func (param i32) (result externref)))
+    (type (;9;) (func (param externref) (result externref)))
+    (func (;0;) (type 2) (param anyref) (result i32)
       block (result i31ref) ;; label = @1
         local.get 0
         br_on_cast 0 (;@1;) anyref i31ref
@@ -179,7 +179,7 @@
       end
       i31.get_u
     )
-    (func (;1;) (type 8) (param anyref) (result i32)
+    (func (;1;) (type 2) (param anyref) (result i32)
       block (result anyref) ;; label = @1
         local.get 0
         br_on_cast_fail 0 (;@1;) anyref i31ref
@@ -189,76 +189,76 @@
       drop
       i32.const -1
     )
-    (func (;2;) (type 0) (result i32)
+    (func (;2;) (type 1) (result i32)
       i32.const 42
       ref.i31
       ref.cast (ref i31)
       i31.get_u
     )
-    (func (;3;) (type 6) (param i32 externref)
+    (func (;3;) (type 7) (param i32 externref)
       local.get 0
       local.get 1
       any.convert_extern
       table.set $t
     )
-    (func (;4;) (type 0) (result i32)
+    (func (;4;) (type 1) (result i32)
       i32.const 42
       ref.i31
       i31.get_u
     )
-    (func (;5;) (type 0) (result i32)
+    (func (;5;) (type 1) (result i32)
       i32.const -1
       ref.i31
       i31.get_s
     )
-    (func (;6;) (type 0) (result i32)
+    (func (;6;) (type 1) (result i32)
       i32.const 100
       ref.i31
       i31.get_s
     )
-    (func (;7;) (type 0) (result i32)
+    (func (;7;) (type 1) (result i32)
       i32.const 1073741823
       ref.i31
       i31.get_s
     )
-    (func (;8;) (type 0) (result i32)
+    (func (;8;) (type 1) (result i32)
       i32.const 100
       ref.i31
       i31.get_u
     )
-    (func (;9;) (type 0) (result i32)
+    (func (;9;) (type 1) (result i32)
       i32.const 2147483647
       ref.i31
       i31.get_u
     )
-    (func (;10;) (type 0) (result i32)
+    (func (;10;) (type 1) (result i32)
       i32.const 42
       ref.i31
       ref.test i31ref
     )
-    (func (;11;) (type 4) (param i32) (result externref)
+    (func (;11;) (type 8) (param i32) (result externref)
       local.get 0
       table.get $t
       extern.convert_any
     )
-    (func (;12;) (type 7) (param externref) (result externref)
+    (func (;12;) (type 9) (param externref) (result externref)
       local.get 0
       any.convert_extern
       extern.convert_any
     )
-    (func (;13;) (type 5) (param i32) (result i31ref)
+    (func (;13;) (type 0) (param i32) (result i31ref)
       local.get 0
       ref.i31
     )
-    (func (;14;) (type 8) (param anyref) (result i32)
+    (func (;14;) (type 2) (param anyref) (result i32)
       local.get 0
       ref.test i31ref
     )
-    (func (;15;) (type 0) (result i32)
+    (func (;15;) (type 1) (result i32)
       ref.null any
       ref.test i31ref
     )
-    (func (;16;) (type 0) (result i32)
+    (func (;16;) (type 1) (result i32)
       ref.null any
       ref.test (ref i31)
     )
@@ -266,21 +266,21 @@
       ref.null any
       ref.cast i31ref
     )
-    (func (;18;) (type 9) (param anyref) (result i31ref)
+    (func (;18;) (type 4) (param anyref) (result i31ref)
       local.get 0
       ref.cast i31ref
     )
-    (func (;19;) (type 0) (result i32)
+    (func (;19;) (type 1) (result i32)
       ref.null any
       ref.is_null
     )
-    (func (;20;) (type 1) (result anyref)
+    (func (;20;) (type 5) (result anyref)
       ref.null any
     )
     (func (;21;) (type 3) (result i31ref)
       ref.null i31
     )
-    (func (;22;) (type 2) (result eqref)
+    (func (;22;) (type 6) (result eqref)
       ref.null eq
     )
     (table $t (;0;) 10 anyref)
@@ -0,0 +1,204 @@
+(module
+  (type $point (struct (field $x (mut i32)) (field $y i8)))
+  (type $bytes (array (mut i8)))
+  (type $funcs (array (mut funcref)))
+
+  (data $d "hello")
+  (elem $e func $new_point)
+
+  ;; struct instructions
+
+  (func $new_point (export "new_point") (param i32) (result (ref $point))
+    local.get 0
+    i32.const 7
+    struct.new $point
+  )
+
+  (func (export "default_point") (result (ref $point))
+    struct.new_default $point
+  )
+
+  (func (export "point_fields") (param (ref $point)) (result i32)
+    local.get 0
+    i32.const 3
+    struct.set $point $x
+    local.get 0
+    struct.get $point $x
+    local.get 0
+    struct.get_s $point $y
+    i32.add
+    local.get 0
+    struct.get_u $point $y
+    i32.add
+  )
+
+  ;; array instructions
+
+  (func (export "arrays") (param i32) (result i32)
+    (local (ref null $bytes))
+    i32.const 1
+    local.get 0
+    array.new $bytes
+    local.set 1
+    local.get 0
+    array.new_default $bytes
+    drop
+    i32.const 1
+    i32.const 2
+    array.new_fixed $bytes 2
+    drop
+    i32.const 0
+    i32.const 5
+    array.new_data $bytes $d
+    drop
+    i32.const 0
+    i32.const 1
+    array.new_elem $funcs $e
+    drop
+    local.get 1
+    i32.const 0
+    i32.const 9
+    array.set $bytes
+    local.get 1
+    i32.const 0
+    i32.const 0
+    local.get 0
+    array.fill $bytes
+    local.get 1
+    i32.const 0
+    local.get 1
+    i32.const 1
+    i32.const 1
+    array.copy $bytes $bytes
+    local.get 1
+    i32.const 0
+    i32.const 0
+    i32.const 1
+    array.init_data $bytes $d
+    local.get 0
+    array.new_default $funcs
+    i32.const 0
+    i32.const 0
+    i32.const 1
+    array.init_elem $funcs $e
+    local.get 1
+    i32.const 0
+    array.get_s $bytes
+    local.get 1
+    i32.const 0
+    array.get_u $bytes
+    i32.add
+    local.get 1
+    array.len
+    i32.add
+  )
+
+  (func (export "same") (param eqref eqref) (result i32)
+    local.get 0
+    local.get 1
+    ref.eq
+  )
+)
+
+(; CHECK-ALL:
+  (module
+    (type $point (;0;) (struct (field (mut i32)) (field i8)))
+    (type $bytes (;1;) (array (mut i8)))
+    (type $funcs (;2;) (array (mut funcref)))
+    (type (;3;) (func (param i32) (result (ref 0))))
+    (type (;4;) (func (result (ref 0))))
+    (type (;5;) (func (param (ref 0)) (result i32)))
+    (type (;6;) (func (param i32) (result i32)))
+    (type (;7;) (func (param eqref eqref) (result i32)))
+    (func (;0;) (type 6) (param i32) (result i32)
+      (local (ref null 1))
+      i32.const 1
+      local.get 0
+      array.new $bytes
+      local.set 1
+      local.get 0
+      array.new_default $bytes
+      drop
+      i32.const 1
+      i32.const 2
+      array.new_fixed $bytes 2
+      drop
+      i32.const 0
+      i32.const 5
+      array.new_data $bytes $d
+      drop
+      i32.const 0
+      i32.const 1
+      array.new_elem $funcs $e
+      drop
+      local.get 1
+      i32.const 0
+      i32.const 9
+      array.set $bytes
+      local.get 1
+      i32.const 0
+      i32.const 0
+      local.get 0
+      array.fill $bytes
+      local.get 1
+      i32.const 0
+      local.get 1
+      i32.const 1
+      i32.const 1
+      array.copy $bytes $bytes
+      local.get 1
+      i32.const 0
+      i32.const 0
+      i32.const 1
+      array.init_data $bytes $d
+      local.get 0
+      array.new_default $funcs
+      i32.const 0
+      i32.const 0
+      i32.const 1
+      array.init_elem $funcs $e
+      local.get 1
+      i32.const 0
+      array.get_s $bytes
+      local.get 1
+      i32.const 0
+      array.get_u $bytes
+      i32.add
+      local.get 1
+      array.len
+      i32.add
+    )
+    (func (;1;) (type 5) (param (ref 0)) (result i32)
+      local.get 0
+      i32.const 3
+      struct.set $point 0
+      local.get 0
+      struct.get $point 0
+      local.get 0
+      struct.get_s $point 1
+      i32.add
+      local.get 0
+      struct.get_u $point 1
+      i32.add
+    )
+    (func $new_point (;2;) (type 3) (param i32) (result (ref 0))
+      local.get 0
+      i32.const 7
+      struct.new $point
+    )
+    (func (;3;) (type 7) (param eqref eqref) (result i32)
+      local.get 0
+      local.get 1
+      ref.eq
+    )
+    (func (;4;) (type 4) (result (ref 0))
+      struct.new_default $point
+    )
+    (export "new_point" (func $new_point))
+    (export "default_point" (func 4))
+    (export "point_fields" (func 1))
+    (export "arrays" (func 0))
+    (export "same" (func 3))
+    (elem $e (;0;) func $new_point)
+    (data $d (;0;) "hello")
+;)
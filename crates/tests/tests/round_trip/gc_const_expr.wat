@@ -0,0 +1,58 @@
+;; Struct and array allocations and the any/extern conversions are allowed in
+;; constant expressions, and keep the types they allocate alive.
+
+(module
+  (type $point (struct (field i32) (field i32)))
+  (type $empty (struct (field (mut i64))))
+  (type $bytes (array (mut i8)))
+  (type $points (array (ref null $point)))
+  (type $unused (struct))
+
+  (global $origin (ref $point) (struct.new $point (i32.const 0) (i32.const 0)))
+  (global $empty (ref $empty) (struct.new_default $empty))
+  (global $zeros (ref $bytes) (array.new $bytes (i32.const 0) (i32.const 4)))
+  (global $blank (ref $bytes) (array.new_default $bytes (i32.const 8)))
+  (global $path (ref $points)
+    (array.new_fixed $points 2 (global.get $origin) (ref.null $point)))
+  (global $ext externref (extern.convert_any (ref.i31 (i32.const 7))))
+  (global $any anyref (any.convert_extern (global.get $ext)))
+
+  (table $t 1 (ref null $point))
+  (elem (table $t) (i32.const 0) (ref null $point)
+    (item (struct.new $point (i32.const 1) (i32.const 2))))
+
+  (func (export "f") (result anyref anyref anyref anyref anyref anyref)
+    global.get $empty
+    global.get $zeros
+    global.get $blank
+    global.get $path
+    global.get $any
+    (table.get $t (i32.const 0))))
+
+(; CHECK-ALL:
+  (module
+    (type $point (;0;) (struct (field i32) (field i32)))
+    (type $empty (;1;) (struct (field (mut i64))))
+    (type $bytes (;2;) (array (mut i8)))
+    (type $points (;3;) (array (ref null 0)))
+    (type (;4;) (func (result anyref anyref anyref anyref anyref anyref)))
+    (func (;0;) (type 4) (result anyref anyref anyref anyref anyref anyref)
+      global.get $empty
+      global.get $zeros
+      global.get $blank
+      global.get $path
+      global.get $any
+      i32.const 0
+      table.get $t
+    )
+    (table $t (;0;) 1 (ref null 0))
+    (global $origin (;0;) (ref 0) i32.const 0 i32.const 0 struct.new $point)
+    (global $empty (;1;) (ref 1) struct.new_default $empty)
+    (global $zeros (;2;) (ref 2) i32.const 0 i32.const 4 array.new $bytes)
+    (global $blank (;3;) (ref 2) i32.const 8 array.new_default $bytes)
+    (global $path (;4;) (ref 3) global.get $origin ref.null 0 array.new_fixed $points 2)
+    (global $ext (;5;) externref i32.const 7 ref.i31 extern.convert_any)
+    (global $any (;6;) anyref global.get $ext any.convert_extern)
+    (export "f" (func 0))
+    (elem (;0;) (i32.const 0) (ref null 0) (item i32.const 1 i32.const 2 struct.new $point))
+;)
@@ -21,11 +21,11 @@
 
 (; CHECK-ALL:
   (module
-    (type (;0;) (func))
-    (type (;1;) (func (result i32 i32)))
-    (type (;2;) (func (param i32 i32)))
-    (func (;0;) (type 0)
-      block (type 1) (result i32 i32) ;; label = @1
+    (type (;0;) (func (param i32 i32)))
+    (type (;1;) (func))
+    (type (;2;) (func (result i32 i32)))
+    (func (;0;) (type 1)
+      block (type 2) (result i32 i32) ;; label = @1
         try_table (catch $e-i32-i32 0 (;@1;)) ;; label = @2
           call $throw-1-2
         end
@@ -44,11 +44,11 @@
       else
       end
     )
-    (func $throw-1-2 (;1;) (type 0)
+    (func $throw-1-2 (;1;) (type 1)
       i32.const 1
       i32.const 2
       throw $e-i32-i32
     )
-    (tag $e-i32-i32 (;0;) (type 2) (param i32 i32))
+    (tag $e-i32-i32 (;0;) (type 0) (param i32 i32))
     (export "test-throw-1-2" (func 0))
 ;)
@@ -0,0 +1,59 @@
+(module
+  (type $sig (func (param i32) (result i32)))
+  (type $unused (func (param i64)))
+  (type $node (struct (field i32) (field (ref null $sig))))
+  (table $t 1 (ref null $sig))
+  (global $g (mut (ref null $sig)) (ref.null $sig))
+  (elem declare func $inc)
+
+  (func $inc (type $sig) (param i32) (result i32)
+    local.get 0
+    i32.const 1
+    i32.add)
+
+  (func (export "call") (param i32) (result i32)
+    (local $f (ref null $sig))
+    ref.func $inc
+    local.set $f
+    local.get 0
+    local.get $f
+    call_ref $sig)
+
+  (func (export "cast") (param anyref) (result i32)
+    local.get 0
+    ref.test (ref $node))
+
+  (export "t" (table $t))
+  (export "g" (global $g))
+)
+
+(; CHECK-ALL:
+  (module
+    (type $sig (;0;) (func (param i32) (result i32)))
+    (type $node (;1;) (struct (field i32) (field (ref null 0))))
+    (type (;2;) (func (param anyref) (result i32)))
+    (func (;0;) (type $sig) (param i32) (result i32)
+      (local $f (ref null 0))
+      ref.func $inc
+      local.set $f
+      local.get 0
+      local.get $f
+      call_ref $sig
+    )
+    (func $inc (;1;) (type $sig) (param i32) (result i32)
+      local.get 0
+      i32.const 1
+      i32.add
+    )
+    (func (;2;) (type 2) (param anyref) (result i32)
+      local.get 0
+      ref.test (ref 1)
+    )
+    (table $t (;0;) 1 (ref null 0))
+    (global $g (;0;) (mut (ref null 0)) ref.null 0)
+    (export "call" (func 0))
+    (export "cast" (func 2))
+    (export "t" (table $t))
+    (export "g" (global $g))
+    (elem (;0;) declare func $inc)
+;)
@@ -0,0 +1,210 @@
+//! Tests for continuation types and the stack switching instructions.
+
+use walrus::ir::*;
+use walrus::{FunctionBuilder, HeapType, Module, RefType, ValType};
+
+fn round_trip(module: &mut Module) -> Module {
+    let wasm = module.emit_wasm();
+    Module::from_buffer(&wasm).expect("should parse emitted wasm")
+}
+
+/// The instructions of an exported function, in order.
+fn instrs(module: &Module, name: &str) -> Vec<Instr> {
+    struct Collect(Vec<Instr>);
+
+    impl<'instr> Visitor<'instr> for Collect {
+        fn visit_instr(&mut self, instr: &'instr Instr, _: &'instr InstrLocId) {
+            self.0.push(instr.clone());
+        }
+    }
+
+    let f = module.exports.get_func(name).unwrap();
+    let func = module.funcs.get(f).kind.unwrap_local();
+    let mut visitor = Collect(Vec::new());
+    dfs_in_order(&mut visitor, func, func.entry_block());
+    visitor.0
+}
+
+const GENERATOR: &str = r#"
+    (module
+      (type $ft (func))
+      (type $ct (cont $ft))
+      (type $ft1 (func (param i32)))
+      (type $ct1 (cont $ft1))
+      (tag $yield (param i32))
+      (elem declare func $gen $take)
+
+      (func $gen
+        (suspend $yield (i32.const 1))
+        (suspend $yield (i32.const 2)))
+
+      (func $take (param i32))
+
+      (func (export "sum") (result i32)
+        (local $k (ref null $ct))
+        (local $sum i32)
+        (local.set $k (cont.new $ct (ref.func $gen)))
+        (loop $next
+          (block $on_yield (result i32 (ref $ct))
+            (resume $ct (on $yield $on_yield) (local.get $k))
+            (return (local.get $sum)))
+          (local.set $k)
+          (local.set $sum (i32.add (local.get $sum)))
+          (br $next))
+        unreachable)
+
+      (func (export "bind")
+        (resume $ct
+          (cont.bind $ct1 $ct (i32.const 3) (cont.new $ct1 (ref.func $take))))))
+"#;
+
+#[test]
+fn parse_and_round_trip() {
+    let mut module = Module::from_wat(GENERATOR).unwrap();
+    module.validate().unwrap();
+
+    let cont = module
+        .types
+        .iter()
+        .find(|ty| ty.as_cont().is_some())
+        .unwrap();
+    let func = cont.as_cont().unwrap().func_type;
+    assert!(module.types.params(func).is_empty());
+
+    let handler_label = |module: &Module| {
+        instrs(module, "sum")
+            .into_iter()
+            .find_map(|instr| match instr {
+                Instr::Resume(Resume { handlers, .. }) => match &handlers[..] {
+                    [ResumeHandler::OnLabel { label, .. }] => Some(*label),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .unwrap()
+    };
+    let label = handler_label(&module);
+    let f = module.exports.get_func("sum").unwrap();
+    let seq = module.funcs.get(f).kind.unwrap_local().block(label);
+    assert_eq!(seq.instrs.len(), 4);
+
+    let mut module = round_trip(&mut module);
+    module.validate().unwrap();
+    handler_label(&module);
+    assert!(instrs(&module, "bind")
+        .iter()
+        .any(|instr| matches!(instr, Instr::ContBind(_))));
+    round_trip(&mut module);
+}
+
+#[test]
+fn switch() {
+    let mut module = Module::from_wat(
+        r#"
+        (module
+          (rec
+            (type $fs (func (param (ref null $cs))))
+            (type $cs (cont $fs)))
+          (tag $sw)
+          (func (export "run") (param $k (ref null $cs))
+            (resume $cs (on $sw switch) (ref.null $cs) (local.get $k)))
+          (func (export "yield") (param $k (ref null $cs))
+            (switch $cs $sw (local.get $k))
+            drop))
+        "#,
+    )
+    .unwrap();
+    module.validate().unwrap();
+    assert!(matches!(
+        instrs(&module, "run")[..],
+        [.., Instr::Resume(Resume { ref handlers, .. })]
+            if matches!(handlers[..], [ResumeHandler::OnSwitch { .. }])
+    ));
+    round_trip(&mut module).validate().unwrap();
+}
+
+#[test]
+fn builder() {
+    let mut module = Module::default();
+    let ft = module.types.add(&[], &[]);
+    let ct = module.types.add_cont(ft);
+    assert_eq!(module.types.find_cont(ft), Some(ct));
+    let tag_ty = module.types.add(&[], &[]);
+    let tag = module.tags.add(tag_ty);
+
+    let func_ref = ValType::Ref(RefType {
+        nullable: true,
+        heap_type: HeapType::Concrete(ft),
+    });
+    let cont_ref = ValType::Ref(RefType {
+        nullable: false,
+        heap_type: HeapType::Concrete(ct),
+    });
+    let f = module.locals.add(func_ref);
+    let label_ty = InstrSeqType::new(&mut module.types, &[], &[cont_ref]);
+    let mut builder = FunctionBuilder::new(&mut module.types, &[func_ref], &[]);
+    builder.func_body().block(label_ty, |block| {
+        let label = block.id();
+        block
+            .local_get(f)
+            .cont_new(ct)
+            .resume(ct, vec![ResumeHandler::OnLabel { tag, label }])
+            .return_();
+    });
+    builder.func_body().drop();
+    let run = builder.finish(vec![f], &mut module.funcs);
+    module.exports.add("run", run);
+    module.validate().unwrap();
+
+    let module = round_trip(&mut module);
+    assert!(instrs(&module, "run")
+        .iter()
+        .any(|instr| matches!(instr, Instr::ContNew(_))));
+    module.validate().unwrap();
+}
+
+#[test]
+fn invalid_handler() {
+    let mut module = Module::from_wat(GENERATOR).unwrap();
+    // Make the `$yield` handler branch to the function's body, which doesn't
+    // take the tag's values and a continuation.
+    struct Redirect(InstrSeqId);
+
+    impl VisitorMut for Redirect {
+        fn visit_instr_mut(&mut self, instr: &mut Instr, _: &mut InstrLocId) {
+            if let Instr::Resume(Resume { handlers, .. }) = instr {
+                if let ResumeHandler::OnLabel { label, .. } = &mut handlers[0] {
+                    *label = self.0;
+                }
+            }
+        }
+    }
+
+    let f = module.exports.get_func("sum").unwrap();
+    let func = module.funcs.get_mut(f).kind.unwrap_local_mut();
+    let entry = func.entry_block();
+    dfs_pre_order_mut(&mut Redirect(entry), func, entry);
+    let err = module.validate().unwrap_err();
+    assert!(
+        format!("{:?}", err).contains("must take a reference"),
+        "unexpected error: {:?}",
+        err
+    );
+}
+
+#[test]
+fn find_cont_ignores_grouped_types() {
+    let mut module = Module::from_wat(
+        r#"
+        (module
+          (type $ft (func))
+          (rec (type $ct (cont $ft)) (type (struct))))
+        "#,
+    )
+    .unwrap();
+    let ft = module.types.find(&[], &[]).unwrap();
+    assert_eq!(module.types.find_cont(ft), None);
+    let ct = module.types.add_cont(ft);
+    assert!(module.types.get(ct).rec_group().is_none());
+    assert_eq!(module.types.find_cont(ft), Some(ct));
+}
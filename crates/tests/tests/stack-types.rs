@@ -0,0 +1,121 @@
+use walrus::analysis::StackTypes;
+use walrus::ir::UnaryOp;
+use walrus::{FunctionBuilder, Module, ValType};
+
+#[test]
+fn types_of_dropped_values() {
+    let module = Module::from_wat(
+        r#"
+        (module
+          (func $f (param i64) (result f32)
+            local.get 0
+            drop
+            f32.const 2
+            f32.const 3
+            f32.const 4
+            i32.const 0
+            select (result f32)
+            drop))
+        "#,
+    )
+    .unwrap();
+    let f = module.funcs.by_name("f").unwrap();
+    let entry = module.funcs.get(f).kind.unwrap_local().entry_block();
+    let types = StackTypes::new(&module, f).unwrap();
+
+    // `drop` discards an `i64`.
+    assert_eq!(types.before(entry, 1).values(), [Some(ValType::I64)]);
+    assert!(types.after(entry, 1).values().is_empty());
+
+    // The last `drop` discards the `f32` that `select` picked.
+    let last = module.funcs.get(f).kind.unwrap_local().block(entry).len() - 1;
+    assert_eq!(
+        types.before(entry, last).values(),
+        [Some(ValType::F32), Some(ValType::F32)]
+    );
+    assert_eq!(types.after(entry, last).values(), [Some(ValType::F32)]);
+    assert!(!types.after(entry, last).is_unreachable());
+}
+
+#[test]
+fn nested_blocks() {
+    let module = Module::from_wat(
+        r#"
+        (module
+          (func $f (result i32) (local i32)
+            i64.const 1
+            i32.const 2
+            block (param i32) (result i32 f32)
+              f32.const 3
+            end
+            drop
+            local.set 0
+            drop
+            i32.const 4))
+        "#,
+    )
+    .unwrap();
+    let f = module.funcs.by_name("f").unwrap();
+    let func = module.funcs.get(f).kind.unwrap_local();
+    let entry = func.entry_block();
+    let block = match &func.block(entry).instrs[2].0 {
+        walrus::ir::Instr::Block(b) => b.seq,
+        other => panic!("expected a block, found {:?}", other),
+    };
+    let types = StackTypes::new(&module, f).unwrap();
+
+    let start = types.before(block, 0);
+    assert_eq!(start.values(), [Some(ValType::I64), Some(ValType::I32)]);
+    assert_eq!(start.block_values(), [Some(ValType::I32)]);
+    let end = types.before(block, 1);
+    assert_eq!(end.block_values(), [Some(ValType::I32), Some(ValType::F32)]);
+
+    assert_eq!(
+        types.after(entry, 2).values(),
+        [Some(ValType::I64), Some(ValType::I32), Some(ValType::F32)]
+    );
+}
+
+#[test]
+fn unreachable_code_is_polymorphic() {
+    let mut module = Module::default();
+    let mut builder = FunctionBuilder::new(&mut module.types, &[], &[ValType::I32]);
+    builder
+        .func_body()
+        .i64_const(1)
+        .unreachable()
+        .select(None)
+        .unop(UnaryOp::I32Eqz);
+    let f = builder.finish(vec![], &mut module.funcs);
+    let entry = module.funcs.get(f).kind.unwrap_local().entry_block();
+    let types = StackTypes::new(&module, f).unwrap();
+
+    let after_unreachable = types.after(entry, 1);
+    assert!(after_unreachable.is_unreachable());
+    assert!(after_unreachable.values().is_empty());
+
+    // `select` pops an `i32` and two values of unknown type from the
+    // polymorphic stack, and pushes a value of unknown type.
+    assert_eq!(types.after(entry, 2).values(), [None]);
+    assert_eq!(types.after(entry, 3).values(), [Some(ValType::I32)]);
+}
+
+#[test]
+fn ill_typed_functions_are_errors() {
+    let mut module = Module::default();
+    let mut builder = FunctionBuilder::new(&mut module.types, &[], &[ValType::I32]);
+    builder.func_body().i64_const(1);
+    let f = builder.finish(vec![], &mut module.funcs);
+    assert!(StackTypes::new(&module, f).is_err());
+}
+
+#[test]
+fn functions_without_a_function_type_are_errors() {
+    let mut module = Module::default();
+    let builder = FunctionBuilder::new(&mut module.types, &[], &[]);
+    let f = builder.finish(vec![], &mut module.funcs);
+    let ty = module.funcs.get(f).ty();
+    module.types.delete(ty);
+    let err = StackTypes::new(&module, f).err().unwrap();
+    assert!(err.to_string().contains("not a function type"), "{}", err);
+}
@@ -0,0 +1,154 @@
+//! Tests that valid modules using proposals walrus can't represent fail to
+//! parse with a structured error instead of panicking.
+
+use walrus::{ErrorKind, Module, Proposal};
+
+fn unsupported(wat: &str) -> (Proposal, usize, Vec<u8>) {
+    let wasm = wat::parse_str(wat).unwrap();
+    let err = Module::from_buffer(&wasm).expect_err("should not parse");
+    match err.downcast_ref::<ErrorKind>() {
+        Some(ErrorKind::UnsupportedProposal { proposal, offset }) => {
+            assert!(*offset < wasm.len());
+            (*proposal, *offset, wasm)
+        }
+        _ => panic!("expected an unsupported proposal error, found: {:#}", err),
+    }
+}
+
+fn assert_unsupported(wat: &str, expected: Proposal) {
+    let (proposal, _, _) = unsupported(wat);
+    assert_eq!(proposal, expected, "{}", wat);
+}
+
+fn assert_unsupported_op(types: &str, body: &str, expected: Proposal) {
+    let wat = format!(
+        r#"
+        (module
+          (type $ft (func))
+          (type $s (struct (field (mut i32))))
+          (type $a (array (mut i32)))
+          {}
+          (memory 1)
+          (global $g (mut i32) (i32.const 0))
+          (tag $e)
+          (func $f
+            {}
+          )
+        )
+        "#,
+        types, body
+    );
+    assert_unsupported(&wat, expected);
+}
+
+#[test]
+fn operator_offset_points_at_operator() {
+    let (proposal, offset, wasm) = unsupported(
+        r#"
+        (module
+          (memory 1)
+          (func
+            i32.const 0
+            i32.const 0
+            memory.discard))
+        "#,
+    );
+    assert_eq!(proposal, Proposal::MemoryControl);
+    // `memory.discard` is encoded as `0xfc 0x12`.
+    assert_eq!(&wasm[offset..offset + 2], &[0xfc, 0x12]);
+}
+
+#[test]
+fn shared_everything_threads_operators() {
+    for body in [
+        "global.atomic.get seqcst $g drop",
+        "i32.const 1 global.atomic.set seqcst $g",
+        "i32.const 1 global.atomic.rmw.add seqcst $g drop",
+        "struct.new_default $s struct.atomic.get seqcst $s 0 drop",
+        "i32.const 1 array.new_default $a i32.const 0 array.atomic.get seqcst $a drop",
+        "i32.const 0 ref.i31_shared drop",
+    ] {
+        assert_unsupported_op("", body, Proposal::SharedEverythingThreads);
+    }
+}
+
+#[test]
+fn memory_control_operators() {
+    assert_unsupported_op(
+        "",
+        "i32.const 0 i32.const 0 memory.discard",
+        Proposal::MemoryControl,
+    );
+}
+
+#[test]
+fn custom_descriptors_operators() {
+    for body in [
+        "struct.new_default $desc struct.new_desc $d drop",
+        "struct.new_default $desc struct.new_default_desc $d drop",
+    ] {
+        assert_unsupported_op(
+            "(rec (type $d (descriptor $desc) (struct)) (type $desc (describes $d) (struct)))",
+            body,
+            Proposal::CustomDescriptors,
+        );
+    }
+}
+
+#[test]
+fn constant_expression_operators() {
+    let (proposal, offset, wasm) =
+        unsupported("(module (global (ref (shared i31)) (ref.i31_shared (i32.const 0))))");
+    assert_eq!(proposal, Proposal::SharedEverythingThreads);
+    // `ref.i31_shared` is encoded as `0xfe 0x72`.
+    assert_eq!(&wasm[offset..offset + 2], &[0xfe, 0x72]);
+}
+
+#[test]
+fn unsupported_types() {
+    assert_unsupported(
+        "(module (type (shared (struct))))",
+        Proposal::SharedEverythingThreads,
+    );
+    assert_unsupported(
+        "(module (global (mut (ref null (shared any))) (ref.null (shared any))))",
+        Proposal::SharedEverythingThreads,
+    );
+    assert_unsupported(
+        "(module (table shared 1 (ref null (shared any))))",
+        Proposal::SharedEverythingThreads,
+    );
+    assert_unsupported(
+        "(module (rec (type (descriptor 1) (struct)) (type (describes 0) (struct))))",
+        Proposal::CustomDescriptors,
+    );
+    assert_unsupported(
+        "(module (type $s (struct)) (func (param (ref (exact $s)))))",
+        Proposal::CustomDescriptors,
+    );
+}
+
+#[test]
+fn unsupported_heap_types_in_instructions() {
+    assert_unsupported(
+        "(module (func ref.null (shared any) drop))",
+        Proposal::SharedEverythingThreads,
+    );
+    assert_unsupported_op(
+        "",
+        "try_table (result (ref null (shared any))) ref.null (shared any) end drop",
+        Proposal::SharedEverythingThreads,
+    );
+    assert_unsupported_op(
+        "",
+        "try_table (result (ref null (exact $s))) ref.null $s end drop",
+        Proposal::CustomDescriptors,
+    );
+}
+
+#[test]
+fn components() {
+    let (proposal, offset, _) = unsupported("(component)");
+    assert_eq!(proposal, Proposal::ComponentModel);
+    assert_eq!(offset, 0);
+}
@@ -12,6 +12,7 @@ fn run(wat: &Path) -> Result<(), anyhow::Error> {
 
     // NB: reading the module will do the validation.
     let module = walrus::Module::from_buffer(&wasm)?;
+    module.validate()?;
 
     if env::var("WALRUS_TESTS_DOT").is_ok() {
         module.write_graphviz_dot(wat.with_extension("dot"))?;
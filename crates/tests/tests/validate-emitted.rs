@@ -0,0 +1,124 @@
+use walrus::{FunctionBuilder, InstrPosition, Module, ModuleConfig, ValType};
+
+/// A module with a function whose body adds an `i64` to an `i32`, along with
+/// the position of the `i32.add`.
+fn invalid_module(config: &ModuleConfig) -> (Module, InstrPosition) {
+    let mut module = Module::with_config(config.clone());
+    let mut builder = FunctionBuilder::new(&mut module.types, &[], &[ValType::I32]);
+    let mut seq = None;
+    builder
+        .func_body()
+        .i32_const(1)
+        .block(ValType::I32, |block| {
+            seq = Some(block.id());
+            block.i64_const(2).binop(walrus::ir::BinaryOp::I32Add);
+        })
+        .drop();
+    let f = builder.finish(vec![], &mut module.funcs);
+    module.exports.add("f", f);
+    let position = InstrPosition {
+        func: f,
+        seq: seq.unwrap(),
+        index: 1,
+    };
+    (module, position)
+}
+
+#[test]
+fn off_by_default() {
+    let (mut module, _) = invalid_module(&ModuleConfig::new());
+    assert!(module.try_emit_wasm().is_ok());
+}
+
+#[test]
+fn valid_modules_pass() {
+    let mut config = ModuleConfig::new();
+    config.validate_emitted_wasm(true);
+    let mut module = config
+        .parse_wat(
+            r#"
+            (module
+              (memory 1)
+              (func (export "f") (param i32) (result i32)
+                local.get 0
+                if (result i32)
+                  i32.const 1
+                else
+                  local.get 0
+                  i32.load
+                end))
+            "#,
+        )
+        .unwrap();
+    module.try_emit_wasm().unwrap();
+}
+
+#[test]
+fn reports_the_offending_instruction() {
+    let mut config = ModuleConfig::new();
+    config.validate_emitted_wasm(true);
+    let (mut module, expected) = invalid_module(&config);
+
+    let err = module.try_emit_wasm().unwrap_err();
+    assert_eq!(err.downcast_ref::<InstrPosition>(), Some(&expected));
+    assert!(
+        format!("{:?}", err).contains("type mismatch"),
+        "unexpected error: {:?}",
+        err
+    );
+}
+
+#[test]
+#[should_panic(expected = "module is invalid")]
+fn emit_wasm_panics() {
+    let mut config = ModuleConfig::new();
+    config.validate_emitted_wasm(true);
+    let (mut module, _) = invalid_module(&config);
+    module.emit_wasm();
+}
+
+#[test]
+fn dangling_ids_are_errors_without_validation() {
+    // A branch to a sibling block, which doesn't enclose it.
+    let mut module = Module::default();
+    let mut builder = FunctionBuilder::new(&mut module.types, &[], &[]);
+    let mut sibling = None;
+    builder.func_body().block(None, |block| {
+        sibling = Some(block.id());
+    });
+    let mut seq = None;
+    builder.func_body().block(None, |block| {
+        seq = Some(block.id());
+        block.br(sibling.unwrap());
+    });
+    let f = builder.finish(vec![], &mut module.funcs);
+    module.exports.add("f", f);
+    let err = module.try_emit_wasm().unwrap_err();
+    let expected = InstrPosition {
+        func: f,
+        seq: seq.unwrap(),
+        index: 0,
+    };
+    assert_eq!(err.downcast_ref::<InstrPosition>(), Some(&expected));
+    assert!(
+        format!("{:?}", err).contains("does not enclose it"),
+        "unexpected error: {:?}",
+        err
+    );
+
+    // A call to a function that was deleted.
+    let mut module = Module::default();
+    let callee =
+        FunctionBuilder::new(&mut module.types, &[], &[]).finish(vec![], &mut module.funcs);
+    let mut builder = FunctionBuilder::new(&mut module.types, &[], &[]);
+    builder.func_body().call(callee);
+    let f = builder.finish(vec![], &mut module.funcs);
+    module.exports.add("f", f);
+    module.funcs.delete(callee);
+    let err = module.try_emit_wasm().unwrap_err();
+    assert!(
+        format!("{:?}", err).contains("was deleted"),
+        "unexpected error: {:?}",
+        err
+    );
+}
@@ -0,0 +1,239 @@
+use walrus::ir::{BinaryOp, Br, Instr};
+use walrus::{ConstExpr, FunctionBuilder, FunctionId, InstrPosition, Module, RefType, ValType};
+
+/// Add an exported function with no parameters and the given results, whose
+/// body is built by `body`.
+fn add_func(
+    module: &mut Module,
+    results: &[ValType],
+    body: impl FnOnce(&mut Module, &mut walrus::InstrSeqBuilder),
+) -> FunctionId {
+    let mut builder = FunctionBuilder::new(&mut module.types, &[], results);
+    body(module, &mut builder.func_body());
+    let f = builder.finish(vec![], &mut module.funcs);
+    module.exports.add("f", f);
+    f
+}
+
+fn assert_invalid(module: &Module, message: &str) -> Option<InstrPosition> {
+    let err = module.validate().unwrap_err();
+    assert!(
+        format!("{:?}", err).contains(message),
+        "unexpected error: {:?}",
+        err
+    );
+    err.downcast_ref::<InstrPosition>().copied()
+}
+
+#[test]
+fn valid_modules_pass() {
+    let module = Module::from_wat(
+        r#"
+        (module
+          (type $point (struct (field $x (mut i32)) (field $y i64)))
+          (type $bytes (array (mut i8)))
+          (type $binop (func (param i32 i32) (result i32)))
+          (memory 1)
+          (table 1 funcref)
+          (global $g (mut i32) (i32.const 0))
+          (tag $e (param i32))
+          (func $add (type $binop) local.get 0 local.get 1 i32.add)
+          (func (export "f") (param $p i32) (result i32)
+            (local $r (ref null $point))
+            i32.const 1
+            i64.const 2
+            struct.new $point
+            local.set $r
+            local.get $r
+            i32.const 3
+            struct.set $point $x
+            block $out (result i32)
+              i32.const 7
+              local.get $p
+              br_if $out
+              drop
+              i32.const 4
+              i32.const 5
+              i32.const 0
+              call_indirect (type $binop)
+            end
+            try_table (result i32) (catch $e 0)
+              local.get $p
+              throw $e
+            end
+            i32.const 8
+            i32.const 1
+            array.new $bytes
+            i32.const 0
+            array.get_u $bytes
+            i32.add
+            i32.add
+            global.set $g
+            global.get $g
+            i32.const 2
+            ref.func $add
+            call_ref $binop)
+          (elem declare func $add))
+        "#,
+    )
+    .unwrap();
+    module.validate().unwrap();
+}
+
+#[test]
+fn unreachable_code_is_polymorphic() {
+    let module = Module::from_wat(
+        r#"
+        (module
+          (func (export "f") (result i32)
+            unreachable
+            i64.add
+            drop
+            i32.eqz))
+        "#,
+    )
+    .unwrap();
+    module.validate().unwrap();
+}
+
+#[test]
+fn stack_type_mismatch() {
+    let mut module = Module::default();
+    let mut seq = None;
+    let f = add_func(&mut module, &[ValType::I32], |_, body| {
+        body.i32_const(1).block(ValType::I32, |block| {
+            seq = Some(block.id());
+            block.i64_const(2).binop(BinaryOp::I32Add);
+        });
+    });
+    let position = assert_invalid(&module, "type mismatch: expected i32, found i64");
+    assert_eq!(
+        position,
+        Some(InstrPosition {
+            func: f,
+            seq: seq.unwrap(),
+            index: 1,
+        })
+    );
+}
+
+#[test]
+fn extra_values_at_the_end_of_a_block() {
+    let mut module = Module::default();
+    let f = add_func(&mut module, &[], |_, body| {
+        body.i32_const(1);
+    });
+    let position = assert_invalid(&module, "more value(s) than expected").unwrap();
+    assert_eq!(position.func, f);
+    assert_eq!(position.index, 1);
+}
+
+#[test]
+fn branch_to_a_block_that_does_not_enclose_it() {
+    let mut module = Module::default();
+    add_func(&mut module, &[], |_, body| {
+        let mut first = None;
+        body.block(None, |block| first = Some(block.id()));
+        body.block(None, |block| {
+            block.instr(Br {
+                block: first.unwrap(),
+            });
+        });
+    });
+    assert_invalid(&module, "does not enclose it");
+}
+
+#[test]
+fn local_of_another_function() {
+    let mut module = Module::default();
+    let mut builder = FunctionBuilder::new(&mut module.types, &[ValType::I32], &[]);
+    builder.func_body();
+    let arg = module.locals.add(ValType::I32);
+    builder.finish(vec![arg], &mut module.funcs);
+
+    add_func(&mut module, &[ValType::I32], |_, body| {
+        body.local_get(arg);
+    });
+    assert_invalid(&module, "is a parameter of function");
+}
+
+#[test]
+fn deleted_function() {
+    let mut module = Module::default();
+    let mut builder = FunctionBuilder::new(&mut module.types, &[], &[]);
+    builder.func_body();
+    let callee = builder.finish(vec![], &mut module.funcs);
+    add_func(&mut module, &[], |_, body| {
+        body.call(callee);
+    });
+    module.funcs.delete(callee);
+    assert_invalid(&module, "was deleted");
+}
+
+#[test]
+fn set_immutable_global() {
+    let mut module = Module::default();
+    let global = module.globals.add_local(
+        ValType::I32,
+        false,
+        false,
+        ConstExpr::Value(walrus::ir::Value::I32(0)),
+    );
+    add_func(&mut module, &[], |_, body| {
+        body.i32_const(1).global_set(global);
+    });
+    assert_invalid(&module, "is immutable");
+}
+
+#[test]
+fn call_indirect_through_an_externref_table() {
+    let mut module = Module::default();
+    let table = module.tables.add_local(false, 1, None, RefType::EXTERNREF);
+    let ty = module.types.add(&[], &[]);
+    add_func(&mut module, &[], |_, body| {
+        body.i32_const(0).call_indirect(ty, table);
+    });
+    assert_invalid(&module, "can't be used for indirect calls");
+}
+
+#[test]
+fn instruction_sequence_used_twice() {
+    let mut module = Module::default();
+    add_func(&mut module, &[], |_, body| {
+        let mut seq = None;
+        body.block(None, |block| seq = Some(block.id()));
+        body.instr(Instr::Block(walrus::ir::Block { seq: seq.unwrap() }));
+    });
+    assert_invalid(&module, "is used more than once");
+}
+
+#[test]
+fn equivalent_rec_groups_are_the_same_type() {
+    let module = Module::from_wat(
+        r#"
+        (module
+          (rec (type $a (sub (struct (field (ref null $a))))))
+          (rec (type $b (sub (struct (field (ref null $b))))))
+          (rec (type $c (sub (struct (field (ref null $a))))))
+          (type $sub (sub $a (struct (field (ref null $a)))))
+          (func $take (param (ref null $a)))
+          (func (param (ref null $b)) (param (ref null $sub))
+            (call $take (local.get 0))
+            (call $take (local.get 1))))
+        "#,
+    )
+    .unwrap();
+    module.validate().unwrap();
+
+    let ids = module
+        .types
+        .iter()
+        .filter(|t| t.as_struct().is_some())
+        .map(|t| t.id())
+        .collect::<Vec<_>>();
+    assert_eq!(ids.len(), 4);
+    let (a, b, c) = (ids[0], ids[1], ids[2]);
+    assert!(module.types.is_equivalent(a, b));
+    // `$c` refers to `$a` rather than to itself.
+    assert!(!module.types.is_equivalent(a, c));
+}
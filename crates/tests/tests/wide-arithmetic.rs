@@ -0,0 +1,120 @@
+//! Tests for the wide arithmetic instructions.
+
+use walrus::ir::*;
+use walrus::{ErrorKind, FunctionBuilder, Module, ModuleConfig, Proposal, ValType};
+use walrus_tests::support::body;
+
+const WAT: &str = r#"
+    (module
+      (func $add (export "add") (param i64 i64 i64 i64) (result i64 i64)
+        (i64.add128 (local.get 0) (local.get 1) (local.get 2) (local.get 3)))
+      (func $sub (export "sub") (param i64 i64 i64 i64) (result i64 i64)
+        (i64.sub128 (local.get 0) (local.get 1) (local.get 2) (local.get 3)))
+      (func $mul (export "mul") (param i64 i64) (result i64 i64 i64 i64)
+        (i64.mul_wide_s (local.get 0) (local.get 1))
+        (i64.mul_wide_u (local.get 0) (local.get 1))))
+"#;
+
+fn config() -> ModuleConfig {
+    let mut config = ModuleConfig::new();
+    config.wide_arithmetic(true);
+    config
+}
+
+#[test]
+fn disabled_by_default() {
+    let wasm = wat::parse_str(WAT).unwrap();
+    let err = Module::from_buffer(&wasm).unwrap_err();
+    match err.downcast_ref::<ErrorKind>() {
+        Some(ErrorKind::UnsupportedProposal { proposal, offset }) => {
+            assert_eq!(*proposal, Proposal::WideArithmetic);
+            // `i64.add128` is encoded as `0xfc 0x13`.
+            assert_eq!(&wasm[*offset..*offset + 2], &[0xfc, 0x13]);
+        }
+        _ => panic!("expected an unsupported proposal error, found: {:#}", err),
+    }
+}
+
+#[test]
+fn parse_and_round_trip() {
+    let mut module = config().parse_wat(WAT).unwrap();
+    module.validate().unwrap();
+    assert!(matches!(body(&module, "add")[4], Instr::I64Add128(_)));
+    assert!(matches!(body(&module, "sub")[4], Instr::I64Sub128(_)));
+    assert!(matches!(
+        body(&module, "mul")[..],
+        [_, _, Instr::I64MulWideS(_), _, _, Instr::I64MulWideU(_)]
+    ));
+
+    let wasm = module.emit_wasm();
+    let module = config().parse(&wasm).unwrap();
+    module.validate().unwrap();
+    assert!(matches!(body(&module, "add")[4], Instr::I64Add128(_)));
+}
+
+#[test]
+fn builder() {
+    let mut module = Module::with_config(config());
+    let a = module.locals.add(ValType::I64);
+    let b = module.locals.add(ValType::I64);
+    let mut builder =
+        FunctionBuilder::new(&mut module.types, &[ValType::I64; 2], &[ValType::I64; 2]);
+    // Add the unsigned and signed 128-bit products of `a` and `b`.
+    builder
+        .func_body()
+        .local_get(a)
+        .local_get(b)
+        .i64_mul_wide_u()
+        .local_get(a)
+        .local_get(b)
+        .i64_mul_wide_s()
+        .i64_add128();
+    let f = builder.finish(vec![a, b], &mut module.funcs);
+    module.exports.add("f", f);
+    module.validate().unwrap();
+
+    let wasm = module.emit_wasm();
+    config().parse(&wasm).unwrap();
+}
+
+#[test]
+fn stack_types_are_checked() {
+    let mut module = Module::with_config(config());
+    let a = module.locals.add(ValType::I64);
+    let mut builder = FunctionBuilder::new(&mut module.types, &[ValType::I64], &[ValType::I64]);
+    builder
+        .func_body()
+        .local_get(a)
+        .local_get(a)
+        .i64_mul_wide_s();
+    let f = builder.finish(vec![a], &mut module.funcs);
+    module.exports.add("f", f);
+
+    let err = module.validate().unwrap_err();
+    assert!(
+        format!("{:?}", err).contains("type mismatch"),
+        "unexpected error: {:?}",
+        err
+    );
+}
+
+#[test]
+fn emitted_wasm_is_validated_with_the_toggle() {
+    for enabled in [false, true] {
+        let mut config = ModuleConfig::new();
+        config.wide_arithmetic(enabled).validate_emitted_wasm(true);
+        let mut module = Module::with_config(config);
+        let a = module.locals.add(ValType::I64);
+        let mut builder =
+            FunctionBuilder::new(&mut module.types, &[ValType::I64], &[ValType::I64; 2]);
+        builder
+            .func_body()
+            .local_get(a)
+            .local_get(a)
+            .i64_mul_wide_u();
+        let f = builder.finish(vec![a], &mut module.funcs);
+        module.exports.add("f", f);
+
+        assert_eq!(module.try_emit_wasm().is_ok(), enabled);
+    }
+}
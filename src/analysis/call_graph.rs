@@ -0,0 +1,370 @@
+use crate::ir::{dfs_in_order, Instr, InstrLocId, Visitor};
+use crate::map::{IdHashMap, IdHashSet};
+use crate::{
+    ElementItems, ElementKind, ExportItem, Function, FunctionId, FunctionKind, GlobalKind,
+    HeapType, Module, TableId, TypeId,
+};
+use std::collections::HashSet;
+
+/// How a function calls another.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CallKind {
+    /// With `call` or `return_call`.
+    Direct,
+    /// With `call_indirect`, `return_call_indirect`, `call_ref` or
+    /// `return_call_ref`, which might call this function.
+    Indirect,
+}
+
+/// The call graph of a module: which functions each function calls.
+///
+/// Direct calls are exact. The targets of an indirect call are approximated
+/// by every function that it could reach and whose type matches the call's:
+///
+/// * `call_indirect` and `return_call_indirect` can reach the functions that
+///   active element segments or `table.init` put into their table, along with
+///   the table's initial value. When the table is imported, exported or
+///   written to with `table.set`, `table.fill`, `table.grow` or `table.copy`,
+///   they can also reach every function whose address is taken.
+///
+/// * `call_ref` and `return_call_ref` can reach every function whose address
+///   is taken.
+///
+/// A function's address is taken when it is in an element segment or is
+/// used by a `ref.func`.
+#[derive(Clone, Debug)]
+pub struct CallGraph {
+    callees: IdHashMap<Function, Vec<(FunctionId, CallKind)>>,
+    callers: IdHashMap<Function, Vec<(FunctionId, CallKind)>>,
+    sccs: Vec<Vec<FunctionId>>,
+    scc_of: IdHashMap<Function, usize>,
+}
+
+impl CallGraph {
+    /// Compute the call graph of the given module.
+    pub fn new(module: &Module) -> CallGraph {
+        let targets = IndirectTargets::new(module);
+
+        let mut callees = IdHashMap::default();
+        let mut callers: IdHashMap<Function, Vec<(FunctionId, CallKind)>> = IdHashMap::default();
+        for func in module.funcs.iter() {
+            callers.insert(func.id(), Vec::new());
+        }
+        for func in module.funcs.iter() {
+            let mut visitor = CallVisitor {
+                module,
+                targets: &targets,
+                calls: Vec::new(),
+                seen: Default::default(),
+            };
+            if let FunctionKind::Local(local) = &func.kind {
+                dfs_in_order(&mut visitor, local, local.entry_block());
+            }
+            for &(callee, kind) in visitor.calls.iter() {
+                callers.get_mut(&callee).unwrap().push((func.id(), kind));
+            }
+            callees.insert(func.id(), visitor.calls);
+        }
+
+        let (sccs, scc_of) = tarjan(module, &callees);
+        CallGraph {
+            callees,
+            callers,
+            sccs,
+            scc_of,
+        }
+    }
+
+    /// The functions that `func` calls, in the order of their first call,
+    /// along with how it calls them.
+    ///
+    /// A function that might be called both directly and indirectly shows up
+    /// once for each kind of call.
+    pub fn callees(&self, func: FunctionId) -> &[(FunctionId, CallKind)] {
+        &self.callees[&func]
+    }
+
+    /// The functions that call `func`, in the order they are defined in the
+    /// module, along with how they call it.
+    pub fn callers(&self, func: FunctionId) -> &[(FunctionId, CallKind)] {
+        &self.callers[&func]
+    }
+
+    /// Iterate over the strongly connected components of the call graph, in
+    /// bottom-up order: a component comes after every component that it
+    /// calls into.
+    ///
+    /// Each component is a set of functions that can all call each other,
+    /// possibly through other functions of the component.
+    pub fn sccs(&self) -> impl Iterator<Item = &[FunctionId]> {
+        self.sccs.iter().map(|scc| &scc[..])
+    }
+
+    /// The strongly connected component that a function is part of.
+    pub fn scc(&self, func: FunctionId) -> &[FunctionId] {
+        &self.sccs[self.scc_of[&func]]
+    }
+
+    /// Can this function end up calling itself?
+    pub fn is_recursive(&self, func: FunctionId) -> bool {
+        self.scc(func).len() > 1 || self.callees(func).iter().any(|(f, _)| *f == func)
+    }
+}
+
+/// The functions that indirect calls might reach, before filtering them by
+/// type.
+struct IndirectTargets {
+    address_taken: Vec<FunctionId>,
+    tables: IdHashMap<crate::Table, Vec<FunctionId>>,
+    /// Tables that might contain any function whose address is taken.
+    dynamic_tables: IdHashSet<crate::Table>,
+}
+
+impl IndirectTargets {
+    fn new(module: &Module) -> IndirectTargets {
+        let mut address_taken = IdHashSet::default();
+        let mut tables: IdHashMap<crate::Table, IdHashSet<Function>> = IdHashMap::default();
+        let mut dynamic_tables = IdHashSet::default();
+
+        for table in module.tables.iter() {
+            if table.import.is_some() {
+                dynamic_tables.insert(table.id());
+            }
+            if let Some(init) = &table.init {
+                tables.entry(table.id()).or_default().extend(init.funcs());
+            }
+        }
+        for export in module.exports.iter() {
+            if let ExportItem::Table(t) = export.item {
+                dynamic_tables.insert(t);
+            }
+        }
+        for global in module.globals.iter() {
+            if let GlobalKind::Local(init) = &global.kind {
+                address_taken.extend(init.funcs());
+            }
+        }
+
+        let elem_funcs = |items: &ElementItems| {
+            let mut funcs = Vec::new();
+            match items {
+                ElementItems::Functions(ids) => funcs.extend(ids.iter().cloned()),
+                ElementItems::Expressions(_, exprs) => {
+                    for expr in exprs {
+                        funcs.extend(expr.funcs());
+                    }
+                }
+            }
+            funcs
+        };
+        for elem in module.elements.iter() {
+            let funcs = elem_funcs(&elem.items);
+            if let ElementKind::Active { table, .. } = elem.kind {
+                tables
+                    .entry(table)
+                    .or_default()
+                    .extend(funcs.iter().cloned());
+            }
+            address_taken.extend(funcs);
+        }
+
+        for (_, func) in module.funcs.iter_local() {
+            let mut visitor = ForEachInstr(|instr: &Instr| match instr {
+                Instr::RefFunc(r) => {
+                    address_taken.insert(r.func);
+                }
+                Instr::TableSet(t) => {
+                    dynamic_tables.insert(t.table);
+                }
+                Instr::TableFill(t) => {
+                    dynamic_tables.insert(t.table);
+                }
+                Instr::TableGrow(t) => {
+                    dynamic_tables.insert(t.table);
+                }
+                Instr::TableCopy(t) => {
+                    dynamic_tables.insert(t.dst);
+                }
+                Instr::TableInit(t) => {
+                    let funcs = elem_funcs(&module.elements.get(t.elem).items);
+                    tables.entry(t.table).or_default().extend(funcs);
+                }
+                _ => {}
+            });
+            dfs_in_order(&mut visitor, func, func.entry_block());
+        }
+
+        // Keep the candidates in the order functions are defined in, so that
+        // the graph doesn't depend on hashing.
+        let address_taken = module
+            .funcs
+            .iter()
+            .map(|f| f.id())
+            .filter(|f| address_taken.contains(f))
+            .collect();
+        let tables = tables
+            .into_iter()
+            .map(|(table, funcs)| {
+                let funcs = module
+                    .funcs
+                    .iter()
+                    .map(|f| f.id())
+                    .filter(|f| funcs.contains(f))
+                    .collect();
+                (table, funcs)
+            })
+            .collect();
+        IndirectTargets {
+            address_taken,
+            tables,
+            dynamic_tables,
+        }
+    }
+
+    /// The functions that an indirect call through `table`, or through a
+    /// function reference if `table` is `None`, might reach, before
+    /// filtering them by type.
+    fn candidates(&self, table: Option<TableId>) -> &[FunctionId] {
+        match table {
+            // Everything in a dynamic table is address taken already.
+            Some(t) if !self.dynamic_tables.contains(&t) => {
+                self.tables.get(&t).map_or(&[], |f| &f[..])
+            }
+            _ => &self.address_taken,
+        }
+    }
+}
+
+struct ForEachInstr<F>(F);
+
+impl<'instr, F: FnMut(&'instr Instr)> Visitor<'instr> for ForEachInstr<F> {
+    fn visit_instr(&mut self, instr: &'instr Instr, _: &'instr InstrLocId) {
+        (self.0)(instr)
+    }
+}
+
+struct CallVisitor<'a> {
+    module: &'a Module,
+    targets: &'a IndirectTargets,
+    calls: Vec<(FunctionId, CallKind)>,
+    seen: HashSet<(FunctionId, CallKind)>,
+}
+
+impl CallVisitor<'_> {
+    fn push(&mut self, func: FunctionId, kind: CallKind) {
+        if self.seen.insert((func, kind)) {
+            self.calls.push((func, kind));
+        }
+    }
+
+    fn push_indirect(&mut self, table: Option<TableId>, ty: TypeId) {
+        let module = self.module;
+        let candidates = self
+            .targets
+            .candidates(table)
+            .iter()
+            .cloned()
+            .filter(|f| {
+                let sub = HeapType::Concrete(module.funcs.get(*f).ty());
+                module.types.is_heap_subtype(sub, HeapType::Concrete(ty))
+            })
+            .collect::<Vec<_>>();
+        for f in candidates {
+            self.push(f, CallKind::Indirect);
+        }
+    }
+}
+
+impl<'instr> Visitor<'instr> for CallVisitor<'_> {
+    fn visit_instr(&mut self, instr: &'instr Instr, _: &'instr InstrLocId) {
+        match instr {
+            Instr::Call(c) => self.push(c.func, CallKind::Direct),
+            Instr::ReturnCall(c) => self.push(c.func, CallKind::Direct),
+            Instr::CallIndirect(c) => self.push_indirect(Some(c.table), c.ty),
+            Instr::ReturnCallIndirect(c) => self.push_indirect(Some(c.table), c.ty),
+            Instr::CallRef(c) => self.push_indirect(None, c.ty),
+            Instr::ReturnCallRef(c) => self.push_indirect(None, c.ty),
+            _ => {}
+        }
+    }
+}
+
+/// Find the strongly connected components of the call graph with Tarjan's
+/// algorithm, which finds them in bottom-up order.
+fn tarjan(
+    module: &Module,
+    callees: &IdHashMap<Function, Vec<(FunctionId, CallKind)>>,
+) -> (Vec<Vec<FunctionId>>, IdHashMap<Function, usize>) {
+    #[derive(Copy, Clone)]
+    struct Node {
+        index: usize,
+        lowlink: usize,
+        on_stack: bool,
+    }
+
+    let mut nodes: IdHashMap<Function, Node> = IdHashMap::default();
+    let mut stack = Vec::new();
+    let mut sccs = Vec::new();
+    let mut scc_of = IdHashMap::default();
+    let mut next_index = 0;
+
+    for root in module.funcs.iter().map(|f| f.id()) {
+        if nodes.contains_key(&root) {
+            continue;
+        }
+        // Each frame is a function and how many of its callees were visited.
+        let mut work = vec![(root, 0)];
+        while let Some(&mut (func, ref mut next)) = work.last_mut() {
+            if *next == 0 && !nodes.contains_key(&func) {
+                nodes.insert(
+                    func,
+                    Node {
+                        index: next_index,
+                        lowlink: next_index,
+                        on_stack: true,
+                    },
+                );
+                next_index += 1;
+                stack.push(func);
+            }
+
+            let edges = &callees[&func];
+            if let Some(&(callee, _)) = edges.get(*next) {
+                *next += 1;
+                match nodes.get(&callee) {
+                    None => work.push((callee, 0)),
+                    Some(n) if n.on_stack => {
+                        let index = n.index;
+                        let node = nodes.get_mut(&func).unwrap();
+                        node.lowlink = node.lowlink.min(index);
+                    }
+                    Some(_) => {}
+                }
+                continue;
+            }
+
+            work.pop();
+            let node = nodes[&func];
+            if let Some(&(parent, _)) = work.last() {
+                let parent = nodes.get_mut(&parent).unwrap();
+                parent.lowlink = parent.lowlink.min(node.lowlink);
+            }
+            if node.lowlink == node.index {
+                let mut scc = Vec::new();
+                loop {
+                    let f = stack.pop().unwrap();
+                    nodes.get_mut(&f).unwrap().on_stack = false;
+                    scc_of.insert(f, sccs.len());
+                    scc.push(f);
+                    if f == func {
+                        break;
+                    }
+                }
+                scc.reverse();
+                sccs.push(scc);
+            }
+        }
+    }
+
+    (sccs, scc_of)
+}
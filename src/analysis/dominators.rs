@@ -0,0 +1,198 @@
+use crate::ir::{BasicBlockId, Cfg};
+
+/// The dominator tree of a control-flow graph.
+///
+/// A basic block `a` dominates a basic block `b` when every path from the
+/// entry block to `b` goes through `a`. Every basic block dominates itself.
+/// Basic blocks that aren't reachable from the entry block aren't part of the
+/// tree: they have no immediate dominator and neither dominate nor are
+/// dominated by any basic block.
+///
+/// This is computed with the algorithm from "A Simple, Fast Dominance
+/// Algorithm" by Cooper, Harvey and Kennedy.
+#[derive(Clone, Debug)]
+pub struct DominatorTree {
+    idom: Vec<Option<BasicBlockId>>,
+    children: Vec<Vec<BasicBlockId>>,
+    /// The position of each basic block in a preorder and a postorder walk of
+    /// the tree, or `usize::MAX` for unreachable basic blocks.
+    preorder: Vec<usize>,
+    postorder: Vec<usize>,
+}
+
+impl DominatorTree {
+    /// Compute the dominator tree of the given control-flow graph.
+    pub fn new(cfg: &Cfg) -> DominatorTree {
+        let n = cfg.num_blocks();
+        let mut rpo_index = vec![usize::MAX; n];
+        let rpo = cfg.reverse_postorder().collect::<Vec<_>>();
+        for (i, block) in rpo.iter().enumerate() {
+            rpo_index[block.index()] = i;
+        }
+
+        let entry = cfg.entry();
+        let mut idom = vec![None; n];
+        idom[entry.index()] = Some(entry);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in rpo.iter().skip(1) {
+                let mut new_idom = None;
+                for &pred in cfg.block(block).preds() {
+                    if idom[pred.index()].is_none() {
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        None => pred,
+                        Some(other) => intersect(&idom, &rpo_index, pred, other),
+                    });
+                }
+                if new_idom.is_some() && idom[block.index()] != new_idom {
+                    idom[block.index()] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+        // The entry block is its own dominator only for the sake of the
+        // algorithm above.
+        idom[entry.index()] = None;
+
+        let mut children = vec![Vec::new(); n];
+        for &block in rpo.iter() {
+            if let Some(parent) = idom[block.index()] {
+                children[parent.index()].push(block);
+            }
+        }
+
+        let mut preorder = vec![usize::MAX; n];
+        let mut postorder = vec![usize::MAX; n];
+        let (mut pre, mut post) = (0, 0);
+        let mut stack = vec![(entry, 0)];
+        preorder[entry.index()] = pre;
+        pre += 1;
+        while let Some((block, next)) = stack.last_mut() {
+            let block = *block;
+            match children[block.index()].get(*next) {
+                Some(&child) => {
+                    *next += 1;
+                    preorder[child.index()] = pre;
+                    pre += 1;
+                    stack.push((child, 0));
+                }
+                None => {
+                    postorder[block.index()] = post;
+                    post += 1;
+                    stack.pop();
+                }
+            }
+        }
+
+        DominatorTree {
+            idom,
+            children,
+            preorder,
+            postorder,
+        }
+    }
+
+    /// The immediate dominator of a basic block: the closest of the basic
+    /// blocks that strictly dominate it.
+    ///
+    /// Returns `None` for the entry block and for unreachable basic blocks.
+    pub fn idom(&self, block: BasicBlockId) -> Option<BasicBlockId> {
+        self.idom[block.index()]
+    }
+
+    /// The basic blocks that `block` is the immediate dominator of.
+    pub fn children(&self, block: BasicBlockId) -> &[BasicBlockId] {
+        &self.children[block.index()]
+    }
+
+    /// Is `block` reachable from the entry block?
+    pub fn is_reachable(&self, block: BasicBlockId) -> bool {
+        self.preorder[block.index()] != usize::MAX
+    }
+
+    /// Does `a` dominate `b`?
+    pub fn dominates(&self, a: BasicBlockId, b: BasicBlockId) -> bool {
+        self.is_reachable(a)
+            && self.is_reachable(b)
+            && self.preorder[a.index()] <= self.preorder[b.index()]
+            && self.postorder[b.index()] <= self.postorder[a.index()]
+    }
+
+    /// Does `a` dominate `b`, and is it a different basic block?
+    pub fn strictly_dominates(&self, a: BasicBlockId, b: BasicBlockId) -> bool {
+        a != b && self.dominates(a, b)
+    }
+}
+
+/// Find the closest common dominator of `a` and `b`, given the immediate
+/// dominators found so far.
+fn intersect(
+    idom: &[Option<BasicBlockId>],
+    rpo_index: &[usize],
+    mut a: BasicBlockId,
+    mut b: BasicBlockId,
+) -> BasicBlockId {
+    while a != b {
+        while rpo_index[a.index()] > rpo_index[b.index()] {
+            a = idom[a.index()].unwrap();
+        }
+        while rpo_index[b.index()] > rpo_index[a.index()] {
+            b = idom[b.index()].unwrap();
+        }
+    }
+    a
+}
+
+/// The dominance frontiers of the basic blocks of a control-flow graph.
+///
+/// The dominance frontier of a basic block `a` is the set of basic blocks `b`
+/// such that `a` dominates a predecessor of `b`, but doesn't strictly dominate
+/// `b` itself. These are the places where the values of a variable assigned
+/// in `a` merge with other values, and where an SSA construction would place
+/// phi nodes.
+#[derive(Clone, Debug)]
+pub struct DominanceFrontiers {
+    frontiers: Vec<Vec<BasicBlockId>>,
+}
+
+impl DominanceFrontiers {
+    /// Compute the dominance frontiers of the basic blocks of `cfg`, which
+    /// `dominators` is the dominator tree of.
+    pub fn new(cfg: &Cfg, dominators: &DominatorTree) -> DominanceFrontiers {
+        let mut frontiers = vec![Vec::new(); cfg.num_blocks()];
+        for (block, data) in cfg.blocks() {
+            if data.preds().len() < 2 || !dominators.is_reachable(block) {
+                continue;
+            }
+            let idom = dominators.idom(block);
+            for &pred in data.preds() {
+                if !dominators.is_reachable(pred) {
+                    continue;
+                }
+                let mut runner = Some(pred);
+                while let Some(r) = runner {
+                    if Some(r) == idom {
+                        break;
+                    }
+                    let frontier: &mut Vec<BasicBlockId> = &mut frontiers[r.index()];
+                    if !frontier.contains(&block) {
+                        frontier.push(block);
+                    }
+                    runner = dominators.idom(r);
+                }
+            }
+        }
+        for frontier in frontiers.iter_mut() {
+            frontier.sort();
+        }
+        DominanceFrontiers { frontiers }
+    }
+
+    /// The dominance frontier of a basic block, sorted by id.
+    pub fn frontier(&self, block: BasicBlockId) -> &[BasicBlockId] {
+        &self.frontiers[block.index()]
+    }
+}
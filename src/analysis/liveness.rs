@@ -0,0 +1,76 @@
+use crate::ir::{BasicBlockId, Cfg, Instr, Local, LocalGet, LocalSet, LocalTee};
+use crate::map::IdHashSet;
+use crate::LocalFunction;
+
+/// Which locals are live at the start and at the end of each basic block.
+///
+/// A local is live at a point of a function if there is a path from that
+/// point to a `local.get` of the local that doesn't go through a `local.set`
+/// or `local.tee` of it. Locals are never live in basic blocks that aren't
+/// reachable from the entry block.
+#[derive(Clone, Debug)]
+pub struct Liveness {
+    live_in: Vec<IdHashSet<Local>>,
+    live_out: Vec<IdHashSet<Local>>,
+}
+
+impl Liveness {
+    /// Compute the live locals of `func`, whose control-flow graph is `cfg`.
+    pub fn new(cfg: &Cfg, func: &LocalFunction) -> Liveness {
+        let n = cfg.num_blocks();
+
+        // The locals that each basic block reads before writing them, and the
+        // locals that it writes.
+        let mut uses = vec![IdHashSet::default(); n];
+        let mut defs = vec![IdHashSet::default(); n];
+        for block in cfg.reverse_postorder() {
+            let (uses, defs) = (&mut uses[block.index()], &mut defs[block.index()]);
+            for (instr, _) in cfg.block(block).instrs(func).iter().rev() {
+                match instr {
+                    Instr::LocalGet(LocalGet { local }) => {
+                        uses.insert(*local);
+                    }
+                    Instr::LocalSet(LocalSet { local }) | Instr::LocalTee(LocalTee { local }) => {
+                        uses.remove(local);
+                        defs.insert(*local);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut live_in: Vec<IdHashSet<Local>> = vec![IdHashSet::default(); n];
+        let mut live_out: Vec<IdHashSet<Local>> = vec![IdHashSet::default(); n];
+        let postorder = cfg.reverse_postorder().rev().collect::<Vec<_>>();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in postorder.iter() {
+                let i = block.index();
+                let mut out = IdHashSet::default();
+                for succ in cfg.block(block).succs() {
+                    out.extend(live_in[succ.index()].iter().cloned());
+                }
+                let mut in_ = uses[i].clone();
+                in_.extend(out.iter().filter(|l| !defs[i].contains(l)).cloned());
+                if in_.len() != live_in[i].len() {
+                    live_in[i] = in_;
+                    changed = true;
+                }
+                live_out[i] = out;
+            }
+        }
+
+        Liveness { live_in, live_out }
+    }
+
+    /// The locals that are live at the start of a basic block.
+    pub fn live_in(&self, block: BasicBlockId) -> &IdHashSet<Local> {
+        &self.live_in[block.index()]
+    }
+
+    /// The locals that are live at the end of a basic block.
+    pub fn live_out(&self, block: BasicBlockId) -> &IdHashSet<Local> {
+        &self.live_out[block.index()]
+    }
+}
@@ -0,0 +1,169 @@
+use crate::analysis::DominatorTree;
+use crate::ir::{BasicBlockId, Cfg};
+
+/// The id of a loop in a `LoopNest`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LoopId(u32);
+
+impl LoopId {
+    /// Get the index of this loop in its `LoopNest`.
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A natural loop of a control-flow graph.
+#[derive(Clone, Debug)]
+pub struct Loop {
+    header: BasicBlockId,
+    blocks: Vec<BasicBlockId>,
+    parent: Option<LoopId>,
+    depth: u32,
+}
+
+impl Loop {
+    /// The basic block that dominates the whole loop, which its back edges
+    /// branch to.
+    pub fn header(&self) -> BasicBlockId {
+        self.header
+    }
+
+    /// The basic blocks of this loop, including those of loops nested inside
+    /// it, sorted by id.
+    pub fn blocks(&self) -> &[BasicBlockId] {
+        &self.blocks
+    }
+
+    /// Does this loop contain the given basic block?
+    pub fn contains(&self, block: BasicBlockId) -> bool {
+        self.blocks.binary_search(&block).is_ok()
+    }
+
+    /// The innermost loop that this one is nested in, if any.
+    pub fn parent(&self) -> Option<LoopId> {
+        self.parent
+    }
+
+    /// How many loops this one is nested in, counting itself: outermost loops
+    /// have a depth of 1.
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+}
+
+/// The natural loops of a control-flow graph, and how they are nested.
+///
+/// A back edge is an edge whose target dominates its source, and the natural
+/// loop of a back edge is its target, the header, along with every basic
+/// block that can reach the source without going through the header. Back
+/// edges with the same header make up a single loop.
+///
+/// In wasm, every `loop` whose body can branch back to its start is such a
+/// loop.
+#[derive(Clone, Debug)]
+pub struct LoopNest {
+    loops: Vec<Loop>,
+    /// The innermost loop that each basic block is part of.
+    innermost: Vec<Option<LoopId>>,
+}
+
+impl LoopNest {
+    /// Find the loops of `cfg`, which `dominators` is the dominator tree of.
+    pub fn new(cfg: &Cfg, dominators: &DominatorTree) -> LoopNest {
+        let n = cfg.num_blocks();
+        let mut loops = Vec::new();
+        for header in cfg.reverse_postorder() {
+            let latches = cfg
+                .block(header)
+                .preds()
+                .iter()
+                .cloned()
+                .filter(|pred| dominators.dominates(header, *pred))
+                .collect::<Vec<_>>();
+            if latches.is_empty() {
+                continue;
+            }
+
+            let mut in_loop = vec![false; n];
+            in_loop[header.index()] = true;
+            let mut stack = Vec::new();
+            for latch in latches {
+                if !in_loop[latch.index()] {
+                    in_loop[latch.index()] = true;
+                    stack.push(latch);
+                }
+            }
+            while let Some(block) = stack.pop() {
+                for &pred in cfg.block(block).preds() {
+                    if !in_loop[pred.index()] && dominators.is_reachable(pred) {
+                        in_loop[pred.index()] = true;
+                        stack.push(pred);
+                    }
+                }
+            }
+
+            let blocks = (0..n)
+                .filter(|i| in_loop[*i])
+                .map(BasicBlockId::new)
+                .collect();
+            loops.push(Loop {
+                header,
+                blocks,
+                parent: None,
+                depth: 1,
+            });
+        }
+
+        // Loops are either disjoint or nested, and headers come in reverse
+        // postorder, so a loop's parent is the last loop before it that
+        // contains its header.
+        for i in 0..loops.len() {
+            let header = loops[i].header;
+            if let Some(parent) = (0..i).rev().find(|j| loops[*j].contains(header)) {
+                loops[i].parent = Some(LoopId(parent as u32));
+                loops[i].depth = loops[parent].depth + 1;
+            }
+        }
+
+        let mut innermost = vec![None; n];
+        for (i, l) in loops.iter().enumerate() {
+            for block in l.blocks.iter() {
+                // Nested loops come after their parents, so this ends up
+                // with the innermost one.
+                innermost[block.index()] = Some(LoopId(i as u32));
+            }
+        }
+
+        LoopNest { loops, innermost }
+    }
+
+    /// Iterate over all loops, outer loops before the loops nested in them.
+    pub fn loops(&self) -> impl Iterator<Item = (LoopId, &Loop)> {
+        self.loops
+            .iter()
+            .enumerate()
+            .map(|(i, l)| (LoopId(i as u32), l))
+    }
+
+    /// Get a loop.
+    pub fn get(&self, id: LoopId) -> &Loop {
+        &self.loops[id.index()]
+    }
+
+    /// The innermost loop that a basic block is part of, if any.
+    pub fn innermost_loop(&self, block: BasicBlockId) -> Option<LoopId> {
+        self.innermost[block.index()]
+    }
+
+    /// The number of loops that a basic block is part of.
+    pub fn depth(&self, block: BasicBlockId) -> u32 {
+        self.innermost_loop(block)
+            .map_or(0, |l| self.loops[l.index()].depth)
+    }
+
+    /// Is this basic block the header of a loop?
+    pub fn is_header(&self, block: BasicBlockId) -> bool {
+        self.innermost_loop(block)
+            .is_some_and(|l| self.loops[l.index()].header == block)
+    }
+}
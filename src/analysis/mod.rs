@@ -0,0 +1,30 @@
+//! Analyses of the control flow and data flow of local functions.
+//!
+//! `CallGraph` finds which functions of a module call each other, and
+//! `StackTypes` infers the types on the operand stack at every instruction.
+//! The other analyses work on the control-flow graph of a function, which is
+//! built with `walrus::ir::Cfg::new`:
+//!
+//! ```
+//! # fn foo(func: &walrus::LocalFunction) {
+//! use walrus::analysis::{DominatorTree, Liveness, LoopNest};
+//! use walrus::ir::Cfg;
+//!
+//! let cfg = Cfg::new(func);
+//! let dominators = DominatorTree::new(&cfg);
+//! let loops = LoopNest::new(&cfg, &dominators);
+//! let liveness = Liveness::new(&cfg, func);
+//! # }
+//! ```
+
+mod call_graph;
+mod dominators;
+mod liveness;
+mod loops;
+mod stack_types;
+
+pub use self::call_graph::{CallGraph, CallKind};
+pub use self::dominators::{DominanceFrontiers, DominatorTree};
+pub use self::liveness::Liveness;
+pub use self::loops::{Loop, LoopId, LoopNest};
+pub use self::stack_types::{OperandStack, StackTypes};
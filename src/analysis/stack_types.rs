@@ -0,0 +1,97 @@
+use crate::error::Result;
+use crate::ir::{InstrSeq, InstrSeqId};
+use crate::map::IdHashMap;
+use crate::{FunctionId, Module, ValType};
+
+/// The types of the values on the operand stack at some point of a function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OperandStack {
+    pub(crate) values: Vec<Option<ValType>>,
+    pub(crate) base: usize,
+    pub(crate) unreachable: bool,
+}
+
+impl OperandStack {
+    /// The types of all the values on the stack, from bottom to top.
+    ///
+    /// This includes the values that enclosing instruction sequences pushed
+    /// before entering the current one, which its instructions can't pop.
+    ///
+    /// The type of a value is `None` when it is unknown, which only happens
+    /// in unreachable code: an instruction such as `select` that pops a value
+    /// of any type from the polymorphic stack pushes a value of unknown type.
+    pub fn values(&self) -> &[Option<ValType>] {
+        &self.values
+    }
+
+    /// The types of the values that the current instruction sequence has on
+    /// the stack, including its parameters, from bottom to top.
+    pub fn block_values(&self) -> &[Option<ValType>] {
+        &self.values[self.base..]
+    }
+
+    /// Is this point unreachable, because it follows an instruction for which
+    /// `Instr::following_instructions_are_unreachable` is true?
+    ///
+    /// The stack is then polymorphic: below `block_values`, instructions can
+    /// pop any number of values of any type.
+    pub fn is_unreachable(&self) -> bool {
+        self.unreachable
+    }
+}
+
+/// The operand stack before and after every instruction of a local function.
+///
+/// This is what wasm validation infers about the types of the values that
+/// instructions pop and push, for example the type of the value that a
+/// `drop` discards, which the IR doesn't record.
+#[derive(Clone, Debug)]
+pub struct StackTypes {
+    stacks: IdHashMap<InstrSeq, Vec<OperandStack>>,
+}
+
+impl StackTypes {
+    /// Infer the operand stacks of the given local function.
+    ///
+    /// Returns an error when the function isn't well-typed, as
+    /// `Module::validate` would.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `func` isn't a local function.
+    pub fn new(module: &Module, func: FunctionId) -> Result<StackTypes> {
+        let local = module.funcs.get(func).kind.unwrap_local();
+        let stacks = crate::validate::operand_stacks(module, func, local)?;
+        Ok(StackTypes { stacks })
+    }
+
+    /// The operand stack before the instruction at `index` in the sequence
+    /// `seq`.
+    ///
+    /// An `index` that is the length of the sequence refers to its end, after
+    /// its last instruction but before the sequence's results are checked.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `seq` isn't an instruction sequence of the function, or if
+    /// `index` is out of bounds.
+    pub fn before(&self, seq: InstrSeqId, index: usize) -> &OperandStack {
+        &self.stacks[&seq][index]
+    }
+
+    /// The operand stack after the instruction at `index` in the sequence
+    /// `seq`.
+    ///
+    /// For instructions with nested sequences, such as `block`, this is after
+    /// control leaves them, with their results on the stack.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `seq` isn't an instruction sequence of the function, or if
+    /// `index` is out of bounds.
+    pub fn after(&self, seq: InstrSeqId, index: usize) -> &OperandStack {
+        let stacks = &self.stacks[&seq];
+        assert!(index + 1 < stacks.len(), "instruction index out of bounds");
+        &stacks[index + 1]
+    }
+}
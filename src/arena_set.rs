@@ -45,6 +45,12 @@ impl<T: Clone + Eq + Hash> ArenaSet<T> {
         self.arena.delete(id);
     }
 
+    /// Does this set contain an item with the given id, that hasn't been
+    /// removed?
+    pub fn contains(&self, id: Id<T>) -> bool {
+        self.arena.contains(id)
+    }
+
     /// Iterate over the items in this arena and their ids.
     pub fn iter(&self) -> impl Iterator<Item = (Id<T>, &T)> {
         self.arena.iter()
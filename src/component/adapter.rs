@@ -0,0 +1,934 @@
+//! Wrapping core modules in components with the canonical ABI.
+
+use super::{
+    CanonicalFunction, CanonicalOption, Component, ComponentDefinedType, ComponentExternalKind,
+    ComponentFuncType, ComponentImport, ComponentSection, ComponentType, ComponentTypeRef,
+    ComponentValType, CoreExport, CoreExternalKind, CoreInstance, PrimitiveValType, TypeBounds,
+};
+use crate::ir::{BinaryOp, Value};
+use crate::{
+    ConstExpr, ElementItems, ElementKind, FunctionBuilder, FunctionId, MemoryId, Module, RefType,
+    Result, ValType,
+};
+use anyhow::{anyhow, bail};
+use std::collections::HashMap;
+
+/// The most core values that are passed as parameters, rather than through
+/// memory.
+const MAX_FLAT_PARAMS: usize = 16;
+
+/// The most core values that are returned as results, rather than through
+/// memory.
+const MAX_FLAT_RESULTS: usize = 1;
+
+/// The core module name of the functions that `Adapter::import` adds.
+const IMPORT_MODULE: &str = "$root";
+
+/// Builds a component out of a core module, by lifting its exports to
+/// component functions and lowering component functions for it to import.
+///
+/// The component passes values to and from the module with the canonical
+/// ABI. When a value needs memory, the module's only memory is used, and
+/// exported as `memory` if it isn't exported yet. When a value needs to be
+/// allocated in that memory, the module's `cabi_realloc` export is used, or,
+/// if there is none, a simple bump allocator is added and exported as
+/// `cabi_realloc`. A lifted function whose core export is named `foo` uses
+/// the module's `cabi_post_foo` export, if it has one, to free its results.
+///
+/// ```
+/// # fn foo(module: walrus::Module) -> anyhow::Result<()> {
+/// use walrus::component::{Adapter, ComponentFuncType, PrimitiveValType};
+///
+/// let mut adapter = Adapter::new(module);
+/// let log = adapter.import(
+///     "log",
+///     ComponentFuncType {
+///         async_: false,
+///         params: vec![("msg".to_string(), PrimitiveValType::String.into())],
+///         result: None,
+///     },
+/// )?;
+/// // ... add calls to `log` to the module with `adapter.module_mut()` ...
+/// let mut component = adapter.finish()?;
+/// let wasm = component.emit_wasm();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Adapter {
+    module: Module,
+    types: Vec<(Option<String>, ComponentDefinedType)>,
+    imports: Vec<(String, ComponentFuncType)>,
+    exports: Vec<(String, FunctionId, ComponentFuncType)>,
+}
+
+/// The canonical options that a function needs.
+#[derive(Clone, Copy, Default)]
+struct Needs {
+    memory: bool,
+    realloc: bool,
+}
+
+impl Adapter {
+    /// Start building a component around the given core module.
+    pub fn new(module: Module) -> Adapter {
+        Adapter {
+            module,
+            types: Vec::new(),
+            imports: Vec::new(),
+            exports: Vec::new(),
+        }
+    }
+
+    /// The core module.
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    /// Mutable access to the core module, for example to add code that calls
+    /// the functions returned by `import`.
+    pub fn module_mut(&mut self) -> &mut Module {
+        &mut self.module
+    }
+
+    /// Define an anonymous type, such as a list or a tuple, and return a
+    /// reference to it for use in other types.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the type refers to a type that isn't defined yet.
+    pub fn define(&mut self, ty: ComponentDefinedType) -> ComponentValType {
+        self.push_type(None, ty)
+    }
+
+    /// Define a named type, such as a record or a variant, and return a
+    /// reference to it for use in other types.
+    ///
+    /// Named types are imported or exported under their name by the
+    /// component, next to the functions that use them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the type refers to a type that isn't defined yet.
+    pub fn define_named(&mut self, name: &str, ty: ComponentDefinedType) -> ComponentValType {
+        self.push_type(Some(name.to_string()), ty)
+    }
+
+    fn push_type(&mut self, name: Option<String>, ty: ComponentDefinedType) -> ComponentValType {
+        let index = self.types.len() as u32;
+        map_val_types(&ty, |ty| {
+            if let ComponentValType::Type(i) = ty {
+                assert!(i < index, "type {} is not defined yet", i);
+            }
+            Ok(ty)
+        })
+        .unwrap();
+        self.types.push((name, ty));
+        ComponentValType::Type(index)
+    }
+
+    /// Import a component function, and add a core import to the module for
+    /// the lowered function, which is returned.
+    pub fn import(&mut self, name: &str, ty: ComponentFuncType) -> Result<FunctionId> {
+        let (params, results) = self.lower_signature(&ty)?;
+        let core_ty = self.module.types.add(&params, &results);
+        let (func, _) = self.module.add_import_func(IMPORT_MODULE, name, core_ty);
+        self.imports.push((name.to_string(), ty));
+        Ok(func)
+    }
+
+    /// Lift a core function of the module, and export it from the component
+    /// under the given name.
+    ///
+    /// The core function must have the signature that `lift_signature`
+    /// returns for the component function type.
+    pub fn export(&mut self, name: &str, func: FunctionId, ty: ComponentFuncType) -> Result<()> {
+        let (params, results) = self.lift_signature(&ty)?;
+        let core_ty = self.module.types.get(self.module.funcs.get(func).ty());
+        if core_ty.params() != params.as_slice() || core_ty.results() != results.as_slice() {
+            bail!(
+                "cannot lift a function of type {:?} -> {:?} to `{}`, which needs {:?} -> {:?}",
+                core_ty.params(),
+                core_ty.results(),
+                name,
+                params,
+                results
+            );
+        }
+        self.exports.push((name.to_string(), func, ty));
+        Ok(())
+    }
+
+    /// The signature of a core function that is lifted to a component
+    /// function of the given type.
+    ///
+    /// When there are too many parameters, they are passed in memory, and the
+    /// function takes a pointer to them instead. Likewise, when there are
+    /// too many results, the function returns a pointer to them.
+    pub fn lift_signature(&self, ty: &ComponentFuncType) -> Result<(Vec<ValType>, Vec<ValType>)> {
+        let (mut params, mut results) = self.flatten_func(ty)?;
+        if params.len() > MAX_FLAT_PARAMS {
+            params = vec![ValType::I32];
+        }
+        if results.len() > MAX_FLAT_RESULTS {
+            results = vec![ValType::I32];
+        }
+        Ok((params, results))
+    }
+
+    /// The signature of the core function that a component function of the
+    /// given type is lowered to.
+    ///
+    /// When there are too many parameters, they are passed in memory, and the
+    /// function takes a pointer to them instead. When there are too many
+    /// results, the function takes a pointer to write them to as its last
+    /// parameter.
+    pub fn lower_signature(&self, ty: &ComponentFuncType) -> Result<(Vec<ValType>, Vec<ValType>)> {
+        let (mut params, mut results) = self.flatten_func(ty)?;
+        if params.len() > MAX_FLAT_PARAMS {
+            params = vec![ValType::I32];
+        }
+        if results.len() > MAX_FLAT_RESULTS {
+            params.push(ValType::I32);
+            results = Vec::new();
+        }
+        Ok((params, results))
+    }
+
+    fn flatten_func(&self, ty: &ComponentFuncType) -> Result<(Vec<ValType>, Vec<ValType>)> {
+        if ty.async_ {
+            bail!("async functions are not supported");
+        }
+        let mut params = Vec::new();
+        for (_, param) in ty.params.iter() {
+            self.flatten(*param, &mut params)?;
+        }
+        let mut results = Vec::new();
+        if let Some(result) = ty.result {
+            self.flatten(result, &mut results)?;
+        }
+        Ok((params, results))
+    }
+
+    /// Append the core values that a value of the given type is passed as.
+    fn flatten(&self, ty: ComponentValType, out: &mut Vec<ValType>) -> Result<()> {
+        let def = match ty {
+            ComponentValType::Primitive(ty) => {
+                out.extend(flatten_primitive(ty));
+                return Ok(());
+            }
+            ComponentValType::Type(index) => self.defined(index)?,
+        };
+        match def {
+            ComponentDefinedType::Primitive(ty) => out.extend(flatten_primitive(*ty)),
+            ComponentDefinedType::Record(fields) => {
+                for (_, ty) in fields {
+                    self.flatten(*ty, out)?;
+                }
+            }
+            ComponentDefinedType::Tuple(tys) => {
+                for ty in tys {
+                    self.flatten(*ty, out)?;
+                }
+            }
+            ComponentDefinedType::FixedLengthList(ty, len) => {
+                for _ in 0..*len {
+                    self.flatten(*ty, out)?;
+                }
+            }
+            ComponentDefinedType::List(_) | ComponentDefinedType::Map(..) => {
+                out.extend([ValType::I32, ValType::I32]);
+            }
+            ComponentDefinedType::Flags(names) => {
+                out.extend(std::iter::repeat_n(ValType::I32, names.len().div_ceil(32)));
+            }
+            ComponentDefinedType::Enum(_)
+            | ComponentDefinedType::Own(_)
+            | ComponentDefinedType::Borrow(_)
+            | ComponentDefinedType::Future(_)
+            | ComponentDefinedType::Stream(_) => out.push(ValType::I32),
+            ComponentDefinedType::Variant(cases) => {
+                self.flatten_variant(cases.iter().map(|case| case.ty), out)?
+            }
+            ComponentDefinedType::Option(ty) => self.flatten_variant([None, Some(*ty)], out)?,
+            ComponentDefinedType::Result { ok, err } => self.flatten_variant([*ok, *err], out)?,
+        }
+        Ok(())
+    }
+
+    /// Append the core values that a variant with cases of the given types is
+    /// passed as: its discriminant, followed by the payloads of all cases
+    /// laid on top of each other.
+    fn flatten_variant(
+        &self,
+        cases: impl IntoIterator<Item = Option<ComponentValType>>,
+        out: &mut Vec<ValType>,
+    ) -> Result<()> {
+        let mut payload: Vec<ValType> = Vec::new();
+        for ty in cases.into_iter().flatten() {
+            let mut case = Vec::new();
+            self.flatten(ty, &mut case)?;
+            for (i, ty) in case.into_iter().enumerate() {
+                match payload.get_mut(i) {
+                    Some(joined) => *joined = join(*joined, ty),
+                    None => payload.push(ty),
+                }
+            }
+        }
+        out.push(ValType::I32);
+        out.extend(payload);
+        Ok(())
+    }
+
+    fn defined(&self, index: u32) -> Result<&ComponentDefinedType> {
+        self.types
+            .get(index as usize)
+            .map(|(_, ty)| ty)
+            .ok_or_else(|| anyhow!("no type is defined at index {}", index))
+    }
+
+    /// Whether values of the given type point into memory.
+    fn has_pointers(&self, ty: ComponentValType) -> Result<bool> {
+        let def = match ty {
+            ComponentValType::Primitive(ty) => return Ok(ty == PrimitiveValType::String),
+            ComponentValType::Type(index) => self.defined(index)?,
+        };
+        let mut any = false;
+        match def {
+            ComponentDefinedType::Primitive(ty) => any = *ty == PrimitiveValType::String,
+            ComponentDefinedType::List(_) | ComponentDefinedType::Map(..) => any = true,
+            _ => {
+                map_val_types(def, |ty| {
+                    any |= self.has_pointers(ty)?;
+                    Ok(ty)
+                })?;
+            }
+        }
+        Ok(any)
+    }
+
+    /// The canonical options that lifting (or lowering) a function of the
+    /// given type needs.
+    fn needs(&self, ty: &ComponentFuncType, lift: bool) -> Result<Needs> {
+        let mut params = false;
+        for (_, ty) in ty.params.iter() {
+            params |= self.has_pointers(*ty)?;
+        }
+        let results = match ty.result {
+            Some(ty) => self.has_pointers(ty)?,
+            None => false,
+        };
+        let (flat_params, flat_results) = self.flatten_func(ty)?;
+        let spills_params = flat_params.len() > MAX_FLAT_PARAMS;
+        let spills_results = flat_results.len() > MAX_FLAT_RESULTS;
+        Ok(Needs {
+            memory: params || results || spills_params || spills_results,
+            // Whoever receives lists and strings allocates them in the
+            // module's memory, and so do the callers of lifted functions for
+            // parameters that don't fit.
+            realloc: if lift {
+                params || spills_params
+            } else {
+                results
+            },
+        })
+    }
+
+    /// Build the component.
+    ///
+    /// The component instantiates the module once. When the module imports
+    /// lowered functions, those can only be lowered once the module's memory
+    /// and `cabi_realloc` exist, so the module first imports shims that call
+    /// through a table, which is then filled in with the lowered functions.
+    pub fn finish(mut self) -> Result<Component> {
+        let mut needs = Needs::default();
+        let mut import_needs = Vec::new();
+        for (_, ty) in self.imports.iter() {
+            let n = self.needs(ty, false)?;
+            needs.memory |= n.memory;
+            needs.realloc |= n.realloc;
+            import_needs.push(n);
+        }
+        let mut export_needs = Vec::new();
+        for (_, _, ty) in self.exports.iter() {
+            let n = self.needs(ty, true)?;
+            needs.memory |= n.memory;
+            needs.realloc |= n.realloc;
+            export_needs.push(n);
+        }
+        let memory = match needs.memory || needs.realloc {
+            true => Some(self.export_memory()?),
+            false => None,
+        };
+        let realloc = match needs.realloc {
+            true => Some(self.export_realloc(memory.as_ref().unwrap().1)?),
+            false => None,
+        };
+        let mut lifts = Vec::new();
+        for (name, func, _) in self.exports.iter() {
+            let core_name = match self.module.exports.get_exported_func(*func) {
+                Some(export) => export.name.clone(),
+                None => {
+                    self.module.exports.add(name, *func);
+                    name.clone()
+                }
+            };
+            let post_return = format!("cabi_post_{}", core_name);
+            let post_return = self
+                .module
+                .exports
+                .get_func(&post_return)
+                .is_ok()
+                .then_some(post_return);
+            lifts.push((core_name, post_return));
+        }
+        for ((name, _, ty), (_, post_return)) in self.exports.iter().zip(lifts.iter()) {
+            if let Some(post_return) = post_return {
+                let (_, results) = self.lift_signature(ty)?;
+                let func = self.module.exports.get_func(post_return)?;
+                let core_ty = self.module.types.get(self.module.funcs.get(func).ty());
+                if core_ty.params() != results.as_slice() || !core_ty.results().is_empty() {
+                    bail!(
+                        "`{}` has the wrong type to be the post-return of `{}`",
+                        post_return,
+                        name
+                    );
+                }
+            }
+        }
+
+        let mut cx = Emitter::default();
+        let mut import_types = HashMap::new();
+        let mut import_funcs = Vec::new();
+        for (name, ty) in self.imports.iter() {
+            let ty = self.func_type(&mut cx, &mut import_types, true, ty)?;
+            import_funcs.push(cx.import(name, ComponentTypeRef::Func(ty)));
+        }
+
+        let signatures = self
+            .imports
+            .iter()
+            .map(|(_, ty)| self.lower_signature(ty))
+            .collect::<Result<Vec<_>>>()?;
+        let mut shims = None;
+        let mut args = Vec::new();
+        if !self.imports.is_empty() {
+            let module = cx.core_module(shim_module(&signatures));
+            let instance = cx.core_instance(CoreInstance::Instantiate {
+                module,
+                args: Vec::new(),
+            });
+            let exports = self
+                .imports
+                .iter()
+                .enumerate()
+                .map(|(i, (name, _))| CoreExport {
+                    name: name.clone(),
+                    kind: CoreExternalKind::Func,
+                    index: cx.alias_core(instance, CoreExternalKind::Func, &i.to_string()),
+                })
+                .collect();
+            let imports = cx.core_instance(CoreInstance::FromExports(exports));
+            args.push((IMPORT_MODULE.to_string(), imports));
+            shims = Some(instance);
+        }
+
+        let module = cx.core_module(std::mem::take(&mut self.module));
+        let instance = cx.core_instance(CoreInstance::Instantiate { module, args });
+        let memory =
+            memory.map(|(name, _)| cx.alias_core(instance, CoreExternalKind::Memory, &name));
+        let realloc = realloc.map(|name| cx.alias_core(instance, CoreExternalKind::Func, &name));
+        let options = |needs: Needs| {
+            let mut options = Vec::new();
+            if needs.memory || needs.realloc {
+                options.push(CanonicalOption::Memory(memory.unwrap()));
+            }
+            if needs.realloc {
+                options.push(CanonicalOption::Realloc(realloc.unwrap()));
+            }
+            options
+        };
+
+        if let Some(shims) = shims {
+            let lowered = import_funcs
+                .iter()
+                .zip(import_needs)
+                .map(|(func, needs)| {
+                    cx.canon(CanonicalFunction::Lower {
+                        func: *func,
+                        options: options(needs),
+                    })
+                })
+                .collect::<Vec<_>>();
+            let table = cx.alias_core(shims, CoreExternalKind::Table, "$imports");
+            let exports = std::iter::once(CoreExport {
+                name: "$imports".to_string(),
+                kind: CoreExternalKind::Table,
+                index: table,
+            })
+            .chain(lowered.iter().enumerate().map(|(i, func)| CoreExport {
+                name: i.to_string(),
+                kind: CoreExternalKind::Func,
+                index: *func,
+            }))
+            .collect();
+            let fixups = cx.core_instance(CoreInstance::FromExports(exports));
+            let module = cx.core_module(fixup_module(&signatures));
+            cx.core_instance(CoreInstance::Instantiate {
+                module,
+                args: vec![(String::new(), fixups)],
+            });
+        }
+
+        let mut export_types = HashMap::new();
+        for (((name, _, ty), needs), (core_name, post_return)) in
+            self.exports.iter().zip(export_needs).zip(lifts)
+        {
+            let core_func = cx.alias_core(instance, CoreExternalKind::Func, &core_name);
+            let mut options = options(needs);
+            if let Some(post_return) = post_return {
+                let func = cx.alias_core(instance, CoreExternalKind::Func, &post_return);
+                options.push(CanonicalOption::PostReturn(func));
+            }
+            let ty = self.func_type(&mut cx, &mut export_types, false, ty)?;
+            let func = cx.canon(CanonicalFunction::Lift {
+                core_func,
+                ty,
+                options,
+            });
+            cx.export(name, ComponentExternalKind::Func, func);
+        }
+
+        Ok(Component {
+            sections: cx.sections,
+            ..Default::default()
+        })
+    }
+
+    /// Export the module's memory, if it isn't already, and return the name
+    /// of its export.
+    fn export_memory(&mut self) -> Result<(String, MemoryId)> {
+        let mut memories = self.module.memories.iter();
+        let memory = match (memories.next(), memories.next()) {
+            (Some(memory), None) => memory,
+            (None, _) => bail!("the module needs a memory to pass these values"),
+            (Some(_), Some(_)) => bail!("the module has more than one memory"),
+        };
+        if memory.memory64 {
+            bail!("the module's memory is a 64-bit memory");
+        }
+        let id = memory.id();
+        let name = match self.module.exports.get_exported_memory(id) {
+            Some(export) => export.name.clone(),
+            None => {
+                self.module.exports.add("memory", id);
+                "memory".to_string()
+            }
+        };
+        Ok((name, id))
+    }
+
+    /// Find the module's `cabi_realloc` export, or add one.
+    fn export_realloc(&mut self, memory: MemoryId) -> Result<String> {
+        let name = "cabi_realloc";
+        match self.module.exports.get_func(name) {
+            Ok(func) => {
+                let ty = self.module.types.get(self.module.funcs.get(func).ty());
+                if ty.params() != [ValType::I32; 4] || ty.results() != [ValType::I32] {
+                    bail!("`{}` has the wrong type", name);
+                }
+            }
+            Err(_) => {
+                let func = add_realloc(&mut self.module, memory);
+                self.module.exports.add(name, func);
+            }
+        }
+        Ok(name.to_string())
+    }
+
+    /// Define the given function type in the import or export context of the
+    /// component, along with the types it refers to.
+    fn func_type(
+        &self,
+        cx: &mut Emitter,
+        types: &mut HashMap<u32, u32>,
+        import: bool,
+        ty: &ComponentFuncType,
+    ) -> Result<u32> {
+        let params = ty
+            .params
+            .iter()
+            .map(|(name, ty)| Ok((name.clone(), self.val_type(cx, types, import, *ty)?)))
+            .collect::<Result<_>>()?;
+        let result = ty
+            .result
+            .map(|ty| self.val_type(cx, types, import, ty))
+            .transpose()?;
+        Ok(cx.ty(ComponentType::Func(ComponentFuncType {
+            async_: false,
+            params,
+            result,
+        })))
+    }
+
+    /// Define the given value type in the import or export context of the
+    /// component.
+    ///
+    /// Functions can only be imported or exported if the types they use are
+    /// too, so each context gets its own copy of the types, where named types
+    /// are imported or exported right after being defined.
+    fn val_type(
+        &self,
+        cx: &mut Emitter,
+        types: &mut HashMap<u32, u32>,
+        import: bool,
+        ty: ComponentValType,
+    ) -> Result<ComponentValType> {
+        let index = match ty {
+            ComponentValType::Primitive(_) => return Ok(ty),
+            ComponentValType::Type(index) => index,
+        };
+        if let Some(index) = types.get(&index) {
+            return Ok(ComponentValType::Type(*index));
+        }
+        let def = map_val_types(self.defined(index)?, |ty| {
+            self.val_type(cx, types, import, ty)
+        })?;
+        let mut new_index = cx.ty(ComponentType::Defined(def));
+        if let Some(name) = &self.types[index as usize].0 {
+            new_index = match import {
+                true => cx.import(name, ComponentTypeRef::Type(TypeBounds::Eq(new_index))),
+                false => cx.export(name, ComponentExternalKind::Type, new_index),
+            };
+        }
+        types.insert(index, new_index);
+        Ok(ComponentValType::Type(new_index))
+    }
+}
+
+fn flatten_primitive(ty: PrimitiveValType) -> &'static [ValType] {
+    match ty {
+        PrimitiveValType::S64 | PrimitiveValType::U64 => &[ValType::I64],
+        PrimitiveValType::F32 => &[ValType::F32],
+        PrimitiveValType::F64 => &[ValType::F64],
+        PrimitiveValType::String => &[ValType::I32, ValType::I32],
+        _ => &[ValType::I32],
+    }
+}
+
+/// The core type that can hold values of both types.
+fn join(a: ValType, b: ValType) -> ValType {
+    match (a, b) {
+        _ if a == b => a,
+        (ValType::I32, ValType::F32) | (ValType::F32, ValType::I32) => ValType::I32,
+        _ => ValType::I64,
+    }
+}
+
+/// Rebuild a defined type with each of the value types it refers to mapped by
+/// `f`.
+fn map_val_types(
+    ty: &ComponentDefinedType,
+    mut f: impl FnMut(ComponentValType) -> Result<ComponentValType>,
+) -> Result<ComponentDefinedType> {
+    let mut opt = |ty: &Option<ComponentValType>| ty.map(&mut f).transpose();
+    Ok(match ty {
+        ComponentDefinedType::Primitive(ty) => ComponentDefinedType::Primitive(*ty),
+        ComponentDefinedType::Record(fields) => ComponentDefinedType::Record(
+            fields
+                .iter()
+                .map(|(name, ty)| Ok((name.clone(), f(*ty)?)))
+                .collect::<Result<_>>()?,
+        ),
+        ComponentDefinedType::Variant(cases) => ComponentDefinedType::Variant(
+            cases
+                .iter()
+                .map(|case| {
+                    Ok(super::VariantCase {
+                        name: case.name.clone(),
+                        ty: opt(&case.ty)?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+        ),
+        ComponentDefinedType::List(ty) => ComponentDefinedType::List(f(*ty)?),
+        ComponentDefinedType::Map(key, value) => ComponentDefinedType::Map(f(*key)?, f(*value)?),
+        ComponentDefinedType::FixedLengthList(ty, len) => {
+            ComponentDefinedType::FixedLengthList(f(*ty)?, *len)
+        }
+        ComponentDefinedType::Tuple(tys) => {
+            ComponentDefinedType::Tuple(tys.iter().map(|ty| f(*ty)).collect::<Result<_>>()?)
+        }
+        ComponentDefinedType::Flags(names) => ComponentDefinedType::Flags(names.clone()),
+        ComponentDefinedType::Enum(names) => ComponentDefinedType::Enum(names.clone()),
+        ComponentDefinedType::Option(ty) => ComponentDefinedType::Option(f(*ty)?),
+        ComponentDefinedType::Result { ok, err } => ComponentDefinedType::Result {
+            ok: opt(ok)?,
+            err: opt(err)?,
+        },
+        ComponentDefinedType::Own(index) => ComponentDefinedType::Own(*index),
+        ComponentDefinedType::Borrow(index) => ComponentDefinedType::Borrow(*index),
+        ComponentDefinedType::Future(ty) => ComponentDefinedType::Future(opt(ty)?),
+        ComponentDefinedType::Stream(ty) => ComponentDefinedType::Stream(opt(ty)?),
+    })
+}
+
+/// A module with a function for each of the given signatures, exported as
+/// `0`, `1`, ..., which calls the function at the same index of the table
+/// that it exports as `$imports`.
+fn shim_module(signatures: &[(Vec<ValType>, Vec<ValType>)]) -> Module {
+    let mut module = Module::default();
+    let len = signatures.len() as u64;
+    let table = module
+        .tables
+        .add_local(false, len, Some(len), RefType::FUNCREF);
+    module.exports.add("$imports", table);
+    for (i, (params, results)) in signatures.iter().enumerate() {
+        let ty = module.types.add(params, results);
+        let args = params
+            .iter()
+            .map(|ty| module.locals.add(*ty))
+            .collect::<Vec<_>>();
+        let mut builder = FunctionBuilder::new(&mut module.types, params, results);
+        let mut body = builder.func_body();
+        for arg in args.iter() {
+            body.local_get(*arg);
+        }
+        body.i32_const(i as i32).call_indirect(ty, table);
+        let func = builder.finish(args, &mut module.funcs);
+        module.exports.add(&i.to_string(), func);
+    }
+    module
+}
+
+/// A module that fills in the table of a `shim_module` with the functions
+/// that it imports.
+fn fixup_module(signatures: &[(Vec<ValType>, Vec<ValType>)]) -> Module {
+    let mut module = Module::default();
+    let len = signatures.len() as u64;
+    let (table, _) =
+        module.add_import_table("", "$imports", false, len, Some(len), RefType::FUNCREF);
+    let funcs = signatures
+        .iter()
+        .enumerate()
+        .map(|(i, (params, results))| {
+            let ty = module.types.add(params, results);
+            module.add_import_func("", &i.to_string(), ty).0
+        })
+        .collect();
+    let kind = ElementKind::Active {
+        table,
+        offset: ConstExpr::Value(Value::I32(0)),
+    };
+    let elem = module.elements.add(kind, ElementItems::Functions(funcs));
+    module.tables.get_mut(table).elem_segments.insert(elem);
+    module
+}
+
+/// Add a `cabi_realloc` function that allocates from chunks of memory that it
+/// grows the memory by, and never frees.
+///
+/// Each chunk starts where `memory.grow` says the memory ended, so the chunks
+/// never overlap memory that the module grows for itself.
+fn add_realloc(module: &mut Module, memory: MemoryId) -> FunctionId {
+    let page_bits = module.memories.get(memory).page_size_log2.unwrap_or(16) as i32;
+    // The start and end of the free space left in the current chunk.
+    let zero = ConstExpr::Value(Value::I32(0));
+    let next = module
+        .globals
+        .add_local(ValType::I32, true, false, zero.clone());
+    let end = module.globals.add_local(ValType::I32, true, false, zero);
+    let params = [ValType::I32; 4];
+    let [old_ptr, old_size, align, new_size] = params.map(|ty| module.locals.add(ty));
+    let ptr = module.locals.add(ValType::I32);
+    let pages = module.locals.add(ValType::I32);
+
+    let mut builder = FunctionBuilder::new(&mut module.types, &params, &[ValType::I32]);
+    builder.name("cabi_realloc".to_string());
+    let mut body = builder.func_body();
+    let align_up = |body: &mut crate::InstrSeqBuilder| {
+        body.local_get(align)
+            .binop(BinaryOp::I32Add)
+            .i32_const(1)
+            .binop(BinaryOp::I32Sub)
+            .i32_const(0)
+            .local_get(align)
+            .binop(BinaryOp::I32Sub)
+            .binop(BinaryOp::I32And);
+    };
+
+    // Try to fit the allocation in the rest of the current chunk.
+    body.global_get(next);
+    align_up(&mut body);
+    body.local_tee(ptr)
+        .local_get(new_size)
+        .binop(BinaryOp::I32Add)
+        .global_get(end)
+        .binop(BinaryOp::I32GtU)
+        .if_else(
+            None,
+            |then| {
+                // Otherwise grow the memory by a new chunk with enough pages
+                // to hold the aligned allocation, and allocate from its start.
+                then.local_get(new_size)
+                    .local_get(align)
+                    .binop(BinaryOp::I32Add)
+                    .i32_const((1 << page_bits) - 1)
+                    .binop(BinaryOp::I32Add)
+                    .i32_const(page_bits)
+                    .binop(BinaryOp::I32ShrU)
+                    .local_tee(pages)
+                    .memory_grow(memory)
+                    .local_tee(ptr)
+                    .i32_const(-1)
+                    .binop(BinaryOp::I32Eq)
+                    .if_else(
+                        None,
+                        |then| {
+                            then.unreachable();
+                        },
+                        |_| {},
+                    );
+                then.local_get(ptr)
+                    .i32_const(page_bits)
+                    .binop(BinaryOp::I32Shl)
+                    .local_tee(ptr)
+                    .local_get(pages)
+                    .i32_const(page_bits)
+                    .binop(BinaryOp::I32Shl)
+                    .binop(BinaryOp::I32Add)
+                    .global_set(end)
+                    .local_get(ptr);
+                align_up(then);
+                then.local_set(ptr);
+            },
+            |_| {},
+        );
+    body.local_get(ptr)
+        .local_get(new_size)
+        .binop(BinaryOp::I32Add)
+        .global_set(next);
+
+    // Copy over what fits of the old allocation.
+    body.local_get(ptr)
+        .local_get(old_ptr)
+        .local_get(old_size)
+        .local_get(new_size)
+        .local_get(old_size)
+        .local_get(new_size)
+        .binop(BinaryOp::I32LtU)
+        .select(None)
+        .memory_copy(memory, memory)
+        .local_get(ptr);
+
+    builder.finish(vec![old_ptr, old_size, align, new_size], &mut module.funcs)
+}
+
+/// Appends items to the sections of a component, while keeping track of the
+/// index spaces that they add to.
+#[derive(Default)]
+struct Emitter {
+    sections: Vec<ComponentSection>,
+    types: u32,
+    funcs: u32,
+    core_modules: u32,
+    core_instances: u32,
+    core_funcs: u32,
+    core_memories: u32,
+    core_tables: u32,
+}
+
+fn next(count: &mut u32) -> u32 {
+    *count += 1;
+    *count - 1
+}
+
+impl Emitter {
+    fn ty(&mut self, ty: ComponentType) -> u32 {
+        match self.sections.last_mut() {
+            Some(ComponentSection::Types(types)) => types.push(ty),
+            _ => self.sections.push(ComponentSection::Types(vec![ty])),
+        }
+        next(&mut self.types)
+    }
+
+    fn import(&mut self, name: &str, ty: ComponentTypeRef) -> u32 {
+        let import = ComponentImport {
+            name: name.to_string(),
+            ty,
+        };
+        match self.sections.last_mut() {
+            Some(ComponentSection::Imports(imports)) => imports.push(import),
+            _ => self.sections.push(ComponentSection::Imports(vec![import])),
+        }
+        match ty {
+            ComponentTypeRef::Func(_) => next(&mut self.funcs),
+            ComponentTypeRef::Type(_) => next(&mut self.types),
+            _ => unreachable!(),
+        }
+    }
+
+    fn export(&mut self, name: &str, kind: ComponentExternalKind, index: u32) -> u32 {
+        let export = super::ComponentExport {
+            name: name.to_string(),
+            kind,
+            index,
+            ty: None,
+        };
+        match self.sections.last_mut() {
+            Some(ComponentSection::Exports(exports)) => exports.push(export),
+            _ => self.sections.push(ComponentSection::Exports(vec![export])),
+        }
+        match kind {
+            ComponentExternalKind::Func => next(&mut self.funcs),
+            ComponentExternalKind::Type => next(&mut self.types),
+            _ => unreachable!(),
+        }
+    }
+
+    fn core_module(&mut self, module: Module) -> u32 {
+        self.sections
+            .push(ComponentSection::CoreModule(Box::new(module)));
+        next(&mut self.core_modules)
+    }
+
+    fn core_instance(&mut self, instance: CoreInstance) -> u32 {
+        match self.sections.last_mut() {
+            Some(ComponentSection::CoreInstances(instances)) => instances.push(instance),
+            _ => self
+                .sections
+                .push(ComponentSection::CoreInstances(vec![instance])),
+        }
+        next(&mut self.core_instances)
+    }
+
+    fn alias_core(&mut self, instance: u32, kind: CoreExternalKind, name: &str) -> u32 {
+        let alias = super::ComponentAlias::CoreInstanceExport {
+            kind,
+            instance,
+            name: name.to_string(),
+        };
+        match self.sections.last_mut() {
+            Some(ComponentSection::Aliases(aliases)) => aliases.push(alias),
+            _ => self.sections.push(ComponentSection::Aliases(vec![alias])),
+        }
+        match kind {
+            CoreExternalKind::Func => next(&mut self.core_funcs),
+            CoreExternalKind::Memory => next(&mut self.core_memories),
+            CoreExternalKind::Table => next(&mut self.core_tables),
+            _ => unreachable!(),
+        }
+    }
+
+    fn canon(&mut self, func: CanonicalFunction) -> u32 {
+        let lift = matches!(func, CanonicalFunction::Lift { .. });
+        match self.sections.last_mut() {
+            Some(ComponentSection::Canonical(funcs)) => funcs.push(func),
+            _ => self.sections.push(ComponentSection::Canonical(vec![func])),
+        }
+        match lift {
+            true => next(&mut self.funcs),
+            false => next(&mut self.core_funcs),
+        }
+    }
+}
@@ -0,0 +1,964 @@
+//! The items defined by the sections of a component.
+//!
+//! Unlike the items of a core module, these refer to each other by their
+//! index in the index spaces of the component, which are built up by the
+//! component's sections in order.
+
+use crate::error::{unsupported, Proposal};
+use crate::parse::IndicesToIds;
+use crate::{IdsToIndices, Result, ValType};
+
+/// A primitive component value type.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum PrimitiveValType {
+    Bool,
+    S8,
+    U8,
+    S16,
+    U16,
+    S32,
+    U32,
+    S64,
+    U64,
+    F32,
+    F64,
+    Char,
+    String,
+    ErrorContext,
+}
+
+impl PrimitiveValType {
+    fn parse(ty: wasmparser::PrimitiveValType) -> PrimitiveValType {
+        match ty {
+            wasmparser::PrimitiveValType::Bool => PrimitiveValType::Bool,
+            wasmparser::PrimitiveValType::S8 => PrimitiveValType::S8,
+            wasmparser::PrimitiveValType::U8 => PrimitiveValType::U8,
+            wasmparser::PrimitiveValType::S16 => PrimitiveValType::S16,
+            wasmparser::PrimitiveValType::U16 => PrimitiveValType::U16,
+            wasmparser::PrimitiveValType::S32 => PrimitiveValType::S32,
+            wasmparser::PrimitiveValType::U32 => PrimitiveValType::U32,
+            wasmparser::PrimitiveValType::S64 => PrimitiveValType::S64,
+            wasmparser::PrimitiveValType::U64 => PrimitiveValType::U64,
+            wasmparser::PrimitiveValType::F32 => PrimitiveValType::F32,
+            wasmparser::PrimitiveValType::F64 => PrimitiveValType::F64,
+            wasmparser::PrimitiveValType::Char => PrimitiveValType::Char,
+            wasmparser::PrimitiveValType::String => PrimitiveValType::String,
+            wasmparser::PrimitiveValType::ErrorContext => PrimitiveValType::ErrorContext,
+        }
+    }
+
+    fn to_wasmencoder_type(self) -> wasm_encoder::PrimitiveValType {
+        match self {
+            PrimitiveValType::Bool => wasm_encoder::PrimitiveValType::Bool,
+            PrimitiveValType::S8 => wasm_encoder::PrimitiveValType::S8,
+            PrimitiveValType::U8 => wasm_encoder::PrimitiveValType::U8,
+            PrimitiveValType::S16 => wasm_encoder::PrimitiveValType::S16,
+            PrimitiveValType::U16 => wasm_encoder::PrimitiveValType::U16,
+            PrimitiveValType::S32 => wasm_encoder::PrimitiveValType::S32,
+            PrimitiveValType::U32 => wasm_encoder::PrimitiveValType::U32,
+            PrimitiveValType::S64 => wasm_encoder::PrimitiveValType::S64,
+            PrimitiveValType::U64 => wasm_encoder::PrimitiveValType::U64,
+            PrimitiveValType::F32 => wasm_encoder::PrimitiveValType::F32,
+            PrimitiveValType::F64 => wasm_encoder::PrimitiveValType::F64,
+            PrimitiveValType::Char => wasm_encoder::PrimitiveValType::Char,
+            PrimitiveValType::String => wasm_encoder::PrimitiveValType::String,
+            PrimitiveValType::ErrorContext => wasm_encoder::PrimitiveValType::ErrorContext,
+        }
+    }
+}
+
+/// The type of a component value.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub enum ComponentValType {
+    /// A primitive type.
+    Primitive(PrimitiveValType),
+    /// The defined type at this index of the type index space.
+    Type(u32),
+}
+
+impl ComponentValType {
+    fn parse(ty: wasmparser::ComponentValType) -> ComponentValType {
+        match ty {
+            wasmparser::ComponentValType::Primitive(ty) => {
+                ComponentValType::Primitive(PrimitiveValType::parse(ty))
+            }
+            wasmparser::ComponentValType::Type(index) => ComponentValType::Type(index),
+        }
+    }
+
+    fn to_wasmencoder_type(self) -> wasm_encoder::ComponentValType {
+        match self {
+            ComponentValType::Primitive(ty) => {
+                wasm_encoder::ComponentValType::Primitive(ty.to_wasmencoder_type())
+            }
+            ComponentValType::Type(index) => wasm_encoder::ComponentValType::Type(index),
+        }
+    }
+}
+
+impl From<PrimitiveValType> for ComponentValType {
+    fn from(ty: PrimitiveValType) -> ComponentValType {
+        ComponentValType::Primitive(ty)
+    }
+}
+
+/// A case of a variant type.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct VariantCase {
+    /// The name of this case.
+    pub name: String,
+    /// The type of this case's payload, if it has one.
+    pub ty: Option<ComponentValType>,
+}
+
+/// A type defined in terms of other component value types.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum ComponentDefinedType {
+    Primitive(PrimitiveValType),
+    Record(Vec<(String, ComponentValType)>),
+    Variant(Vec<VariantCase>),
+    List(ComponentValType),
+    Map(ComponentValType, ComponentValType),
+    FixedLengthList(ComponentValType, u32),
+    Tuple(Vec<ComponentValType>),
+    Flags(Vec<String>),
+    Enum(Vec<String>),
+    Option(ComponentValType),
+    Result {
+        ok: Option<ComponentValType>,
+        err: Option<ComponentValType>,
+    },
+    /// An owned handle to the resource type at this index.
+    Own(u32),
+    /// A borrowed handle to the resource type at this index.
+    Borrow(u32),
+    Future(Option<ComponentValType>),
+    Stream(Option<ComponentValType>),
+}
+
+impl ComponentDefinedType {
+    fn parse(ty: wasmparser::ComponentDefinedType) -> ComponentDefinedType {
+        let val = ComponentValType::parse;
+        match ty {
+            wasmparser::ComponentDefinedType::Primitive(ty) => {
+                ComponentDefinedType::Primitive(PrimitiveValType::parse(ty))
+            }
+            wasmparser::ComponentDefinedType::Record(fields) => ComponentDefinedType::Record(
+                fields
+                    .iter()
+                    .map(|(name, ty)| (name.to_string(), val(*ty)))
+                    .collect(),
+            ),
+            wasmparser::ComponentDefinedType::Variant(cases) => ComponentDefinedType::Variant(
+                cases
+                    .iter()
+                    .map(|case| VariantCase {
+                        name: case.name.to_string(),
+                        ty: case.ty.map(val),
+                    })
+                    .collect(),
+            ),
+            wasmparser::ComponentDefinedType::List(ty) => ComponentDefinedType::List(val(ty)),
+            wasmparser::ComponentDefinedType::Map(key, value) => {
+                ComponentDefinedType::Map(val(key), val(value))
+            }
+            wasmparser::ComponentDefinedType::FixedLengthList(ty, len) => {
+                ComponentDefinedType::FixedLengthList(val(ty), len)
+            }
+            wasmparser::ComponentDefinedType::Tuple(tys) => {
+                ComponentDefinedType::Tuple(tys.iter().copied().map(val).collect())
+            }
+            wasmparser::ComponentDefinedType::Flags(names) => {
+                ComponentDefinedType::Flags(names.iter().map(|n| n.to_string()).collect())
+            }
+            wasmparser::ComponentDefinedType::Enum(names) => {
+                ComponentDefinedType::Enum(names.iter().map(|n| n.to_string()).collect())
+            }
+            wasmparser::ComponentDefinedType::Option(ty) => ComponentDefinedType::Option(val(ty)),
+            wasmparser::ComponentDefinedType::Result { ok, err } => ComponentDefinedType::Result {
+                ok: ok.map(val),
+                err: err.map(val),
+            },
+            wasmparser::ComponentDefinedType::Own(index) => ComponentDefinedType::Own(index),
+            wasmparser::ComponentDefinedType::Borrow(index) => ComponentDefinedType::Borrow(index),
+            wasmparser::ComponentDefinedType::Future(ty) => {
+                ComponentDefinedType::Future(ty.map(val))
+            }
+            wasmparser::ComponentDefinedType::Stream(ty) => {
+                ComponentDefinedType::Stream(ty.map(val))
+            }
+        }
+    }
+
+    fn encode(&self, encoder: wasm_encoder::ComponentDefinedTypeEncoder) {
+        let val = |ty: &ComponentValType| ty.to_wasmencoder_type();
+        match self {
+            ComponentDefinedType::Primitive(ty) => encoder.primitive(ty.to_wasmencoder_type()),
+            ComponentDefinedType::Record(fields) => {
+                encoder.record(fields.iter().map(|(name, ty)| (name.as_str(), val(ty))))
+            }
+            ComponentDefinedType::Variant(cases) => encoder.variant(
+                cases
+                    .iter()
+                    .map(|case| (case.name.as_str(), case.ty.as_ref().map(val))),
+            ),
+            ComponentDefinedType::List(ty) => encoder.list(val(ty)),
+            ComponentDefinedType::Map(key, value) => encoder.map(val(key), val(value)),
+            ComponentDefinedType::FixedLengthList(ty, len) => {
+                encoder.fixed_length_list(val(ty), *len)
+            }
+            ComponentDefinedType::Tuple(tys) => encoder.tuple(tys.iter().map(val)),
+            ComponentDefinedType::Flags(names) => encoder.flags(names.iter().map(|n| n.as_str())),
+            ComponentDefinedType::Enum(names) => {
+                encoder.enum_type(names.iter().map(|n| n.as_str()))
+            }
+            ComponentDefinedType::Option(ty) => encoder.option(val(ty)),
+            ComponentDefinedType::Result { ok, err } => {
+                encoder.result(ok.as_ref().map(val), err.as_ref().map(val))
+            }
+            ComponentDefinedType::Own(index) => encoder.own(*index),
+            ComponentDefinedType::Borrow(index) => encoder.borrow(*index),
+            ComponentDefinedType::Future(ty) => encoder.future(ty.as_ref().map(val)),
+            ComponentDefinedType::Stream(ty) => encoder.stream(ty.as_ref().map(val)),
+        }
+    }
+}
+
+/// The type of a component function.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct ComponentFuncType {
+    /// Whether this function uses the async ABI.
+    pub async_: bool,
+    /// The names and types of the parameters.
+    pub params: Vec<(String, ComponentValType)>,
+    /// The type of the result, if there is one.
+    pub result: Option<ComponentValType>,
+}
+
+/// A type defined by a component's type section.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub enum ComponentType {
+    /// A value type.
+    Defined(ComponentDefinedType),
+    /// A function type.
+    Func(ComponentFuncType),
+    /// A fresh resource type.
+    Resource {
+        /// The core type that the resource is represented by.
+        rep: ValType,
+        /// The core function that destroys a resource of this type.
+        dtor: Option<u32>,
+    },
+    /// A component or instance type, kept in its binary encoding.
+    Raw(Vec<u8>),
+}
+
+impl ComponentType {
+    pub(crate) fn parse(
+        ty: wasmparser::ComponentType,
+        raw: &[u8],
+        offset: usize,
+    ) -> Result<ComponentType> {
+        Ok(match ty {
+            wasmparser::ComponentType::Defined(ty) => {
+                ComponentType::Defined(ComponentDefinedType::parse(ty))
+            }
+            wasmparser::ComponentType::Func(ty) => ComponentType::Func(ComponentFuncType {
+                async_: ty.async_,
+                params: ty
+                    .params
+                    .iter()
+                    .map(|(name, ty)| (name.to_string(), ComponentValType::parse(*ty)))
+                    .collect(),
+                result: ty.result.map(ComponentValType::parse),
+            }),
+            wasmparser::ComponentType::Resource { rep, dtor } => ComponentType::Resource {
+                rep: ValType::parse(&rep, &IndicesToIds::default())
+                    .map_err(|_| unsupported(Proposal::ComponentModel, offset))?,
+                dtor,
+            },
+            wasmparser::ComponentType::Component(_) | wasmparser::ComponentType::Instance(_) => {
+                ComponentType::Raw(raw.to_vec())
+            }
+        })
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut section = wasm_encoder::ComponentTypeSection::new();
+        match self {
+            ComponentType::Defined(ty) => ty.encode(section.defined_type()),
+            ComponentType::Func(ty) => {
+                section
+                    .function()
+                    .async_(ty.async_)
+                    .params(
+                        ty.params
+                            .iter()
+                            .map(|(name, ty)| (name.as_str(), ty.to_wasmencoder_type())),
+                    )
+                    .result(ty.result.map(|ty| ty.to_wasmencoder_type()));
+            }
+            ComponentType::Resource { rep, dtor } => {
+                let rep = rep.to_wasmencoder_type(&IdsToIndices::default());
+                section.resource(rep, *dtor);
+            }
+            ComponentType::Raw(raw) => return raw.clone(),
+        }
+        single_item(&section)
+    }
+}
+
+/// The sort of item that a component imports, exports or instantiates with.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum ComponentExternalKind {
+    Module,
+    Func,
+    Value,
+    Type,
+    Instance,
+    Component,
+}
+
+impl ComponentExternalKind {
+    fn parse(kind: wasmparser::ComponentExternalKind) -> ComponentExternalKind {
+        match kind {
+            wasmparser::ComponentExternalKind::Module => ComponentExternalKind::Module,
+            wasmparser::ComponentExternalKind::Func => ComponentExternalKind::Func,
+            wasmparser::ComponentExternalKind::Value => ComponentExternalKind::Value,
+            wasmparser::ComponentExternalKind::Type => ComponentExternalKind::Type,
+            wasmparser::ComponentExternalKind::Instance => ComponentExternalKind::Instance,
+            wasmparser::ComponentExternalKind::Component => ComponentExternalKind::Component,
+        }
+    }
+
+    fn to_wasmencoder_kind(self) -> wasm_encoder::ComponentExportKind {
+        match self {
+            ComponentExternalKind::Module => wasm_encoder::ComponentExportKind::Module,
+            ComponentExternalKind::Func => wasm_encoder::ComponentExportKind::Func,
+            ComponentExternalKind::Value => wasm_encoder::ComponentExportKind::Value,
+            ComponentExternalKind::Type => wasm_encoder::ComponentExportKind::Type,
+            ComponentExternalKind::Instance => wasm_encoder::ComponentExportKind::Instance,
+            ComponentExternalKind::Component => wasm_encoder::ComponentExportKind::Component,
+        }
+    }
+}
+
+/// The sort of a core item that a core instance exports.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum CoreExternalKind {
+    Func,
+    Table,
+    Memory,
+    Global,
+    Tag,
+}
+
+impl CoreExternalKind {
+    fn parse(kind: wasmparser::ExternalKind, offset: usize) -> Result<CoreExternalKind> {
+        Ok(match kind {
+            wasmparser::ExternalKind::Func => CoreExternalKind::Func,
+            wasmparser::ExternalKind::Table => CoreExternalKind::Table,
+            wasmparser::ExternalKind::Memory => CoreExternalKind::Memory,
+            wasmparser::ExternalKind::Global => CoreExternalKind::Global,
+            wasmparser::ExternalKind::Tag => CoreExternalKind::Tag,
+            wasmparser::ExternalKind::FuncExact => {
+                return Err(unsupported(Proposal::CustomDescriptors, offset))
+            }
+        })
+    }
+
+    fn to_wasmencoder_kind(self) -> wasm_encoder::ExportKind {
+        match self {
+            CoreExternalKind::Func => wasm_encoder::ExportKind::Func,
+            CoreExternalKind::Table => wasm_encoder::ExportKind::Table,
+            CoreExternalKind::Memory => wasm_encoder::ExportKind::Memory,
+            CoreExternalKind::Global => wasm_encoder::ExportKind::Global,
+            CoreExternalKind::Tag => wasm_encoder::ExportKind::Tag,
+        }
+    }
+}
+
+/// The bounds of an imported or exported type.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub enum TypeBounds {
+    /// The type is equal to the type at this index.
+    Eq(u32),
+    /// The type is a fresh resource type.
+    SubResource,
+}
+
+/// The type of an imported or exported item.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum ComponentTypeRef {
+    /// A core module of the module type at this index of the core type index
+    /// space.
+    Module(u32),
+    Func(u32),
+    Value(ComponentValType),
+    Type(TypeBounds),
+    Instance(u32),
+    Component(u32),
+}
+
+impl ComponentTypeRef {
+    fn parse(ty: wasmparser::ComponentTypeRef) -> ComponentTypeRef {
+        match ty {
+            wasmparser::ComponentTypeRef::Module(index) => ComponentTypeRef::Module(index),
+            wasmparser::ComponentTypeRef::Func(index) => ComponentTypeRef::Func(index),
+            wasmparser::ComponentTypeRef::Value(ty) => {
+                ComponentTypeRef::Value(ComponentValType::parse(ty))
+            }
+            wasmparser::ComponentTypeRef::Type(wasmparser::TypeBounds::Eq(index)) => {
+                ComponentTypeRef::Type(TypeBounds::Eq(index))
+            }
+            wasmparser::ComponentTypeRef::Type(wasmparser::TypeBounds::SubResource) => {
+                ComponentTypeRef::Type(TypeBounds::SubResource)
+            }
+            wasmparser::ComponentTypeRef::Instance(index) => ComponentTypeRef::Instance(index),
+            wasmparser::ComponentTypeRef::Component(index) => ComponentTypeRef::Component(index),
+        }
+    }
+
+    fn to_wasmencoder_type(self) -> wasm_encoder::ComponentTypeRef {
+        match self {
+            ComponentTypeRef::Module(index) => wasm_encoder::ComponentTypeRef::Module(index),
+            ComponentTypeRef::Func(index) => wasm_encoder::ComponentTypeRef::Func(index),
+            ComponentTypeRef::Value(ty) => {
+                wasm_encoder::ComponentTypeRef::Value(ty.to_wasmencoder_type())
+            }
+            ComponentTypeRef::Type(TypeBounds::Eq(index)) => {
+                wasm_encoder::ComponentTypeRef::Type(wasm_encoder::TypeBounds::Eq(index))
+            }
+            ComponentTypeRef::Type(TypeBounds::SubResource) => {
+                wasm_encoder::ComponentTypeRef::Type(wasm_encoder::TypeBounds::SubResource)
+            }
+            ComponentTypeRef::Instance(index) => wasm_encoder::ComponentTypeRef::Instance(index),
+            ComponentTypeRef::Component(index) => wasm_encoder::ComponentTypeRef::Component(index),
+        }
+    }
+}
+
+/// An item imported into a component.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct ComponentImport {
+    /// The name of this import.
+    pub name: String,
+    /// The type of the imported item.
+    pub ty: ComponentTypeRef,
+}
+
+impl ComponentImport {
+    pub(crate) fn parse(import: wasmparser::ComponentImport) -> ComponentImport {
+        ComponentImport {
+            name: import.name.0.to_string(),
+            ty: ComponentTypeRef::parse(import.ty),
+        }
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut section = wasm_encoder::ComponentImportSection::new();
+        section.import(&self.name, self.ty.to_wasmencoder_type());
+        single_item(&section)
+    }
+}
+
+/// An item exported from a component, or from an instance that a component
+/// creates out of its own items.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct ComponentExport {
+    /// The name of this export.
+    pub name: String,
+    /// The sort of the exported item.
+    pub kind: ComponentExternalKind,
+    /// The index of the exported item in the index space of its sort.
+    pub index: u32,
+    /// The type that the item is exported as, if it is given explicitly.
+    ///
+    /// This is always `None` for the exports of an instance.
+    pub ty: Option<ComponentTypeRef>,
+}
+
+impl ComponentExport {
+    pub(crate) fn parse(export: &wasmparser::ComponentExport) -> ComponentExport {
+        ComponentExport {
+            name: export.name.0.to_string(),
+            kind: ComponentExternalKind::parse(export.kind),
+            index: export.index,
+            ty: export.ty.map(ComponentTypeRef::parse),
+        }
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut section = wasm_encoder::ComponentExportSection::new();
+        section.export(
+            &self.name,
+            self.kind.to_wasmencoder_kind(),
+            self.index,
+            self.ty.map(|ty| ty.to_wasmencoder_type()),
+        );
+        single_item(&section)
+    }
+}
+
+/// A named argument to the instantiation of a component.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct ComponentInstantiationArg {
+    /// The name of the import that this argument satisfies.
+    pub name: String,
+    /// The sort of the argument.
+    pub kind: ComponentExternalKind,
+    /// The index of the argument in the index space of its sort.
+    pub index: u32,
+}
+
+/// A component instance defined by a component.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub enum ComponentInstance {
+    /// An instance of the component at this index, instantiated with the
+    /// given arguments.
+    Instantiate {
+        /// The index of the component.
+        component: u32,
+        /// The arguments that the component is instantiated with.
+        args: Vec<ComponentInstantiationArg>,
+    },
+    /// An instance made of these items of the component.
+    FromExports(Vec<ComponentExport>),
+}
+
+impl ComponentInstance {
+    pub(crate) fn parse(instance: wasmparser::ComponentInstance) -> ComponentInstance {
+        match instance {
+            wasmparser::ComponentInstance::Instantiate {
+                component_index,
+                args,
+            } => ComponentInstance::Instantiate {
+                component: component_index,
+                args: args
+                    .iter()
+                    .map(|arg| ComponentInstantiationArg {
+                        name: arg.name.to_string(),
+                        kind: ComponentExternalKind::parse(arg.kind),
+                        index: arg.index,
+                    })
+                    .collect(),
+            },
+            wasmparser::ComponentInstance::FromExports(exports) => {
+                ComponentInstance::FromExports(exports.iter().map(ComponentExport::parse).collect())
+            }
+        }
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut section = wasm_encoder::ComponentInstanceSection::new();
+        match self {
+            ComponentInstance::Instantiate { component, args } => {
+                section.instantiate(
+                    *component,
+                    args.iter()
+                        .map(|arg| (&arg.name, arg.kind.to_wasmencoder_kind(), arg.index)),
+                );
+            }
+            ComponentInstance::FromExports(exports) => {
+                section.export_items(
+                    exports
+                        .iter()
+                        .map(|e| (e.name.as_str(), e.kind.to_wasmencoder_kind(), e.index)),
+                );
+            }
+        }
+        single_item(&section)
+    }
+}
+
+/// An item exported from a core instance.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct CoreExport {
+    /// The name of this export.
+    pub name: String,
+    /// The sort of the exported item.
+    pub kind: CoreExternalKind,
+    /// The index of the exported item in the core index space of its sort.
+    pub index: u32,
+}
+
+/// A core instance defined by a component.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub enum CoreInstance {
+    /// An instance of the core module at this index, instantiated with the
+    /// given core instances as arguments.
+    Instantiate {
+        /// The index of the core module.
+        module: u32,
+        /// The name of each import module and the index of the core instance
+        /// that satisfies it.
+        args: Vec<(String, u32)>,
+    },
+    /// An instance made of these core items of the component.
+    FromExports(Vec<CoreExport>),
+}
+
+impl CoreInstance {
+    pub(crate) fn parse(instance: wasmparser::Instance, offset: usize) -> Result<CoreInstance> {
+        Ok(match instance {
+            wasmparser::Instance::Instantiate { module_index, args } => CoreInstance::Instantiate {
+                module: module_index,
+                args: args
+                    .iter()
+                    .map(|arg| match arg.kind {
+                        wasmparser::InstantiationArgKind::Instance => {
+                            (arg.name.to_string(), arg.index)
+                        }
+                    })
+                    .collect(),
+            },
+            wasmparser::Instance::FromExports(exports) => CoreInstance::FromExports(
+                exports
+                    .iter()
+                    .map(|e| {
+                        Ok(CoreExport {
+                            name: e.name.to_string(),
+                            kind: CoreExternalKind::parse(e.kind, offset)?,
+                            index: e.index,
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+        })
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut section = wasm_encoder::InstanceSection::new();
+        match self {
+            CoreInstance::Instantiate { module, args } => {
+                section.instantiate(
+                    *module,
+                    args.iter()
+                        .map(|(name, index)| (name, wasm_encoder::ModuleArg::Instance(*index))),
+                );
+            }
+            CoreInstance::FromExports(exports) => {
+                section.export_items(
+                    exports
+                        .iter()
+                        .map(|e| (&e.name, e.kind.to_wasmencoder_kind(), e.index)),
+                );
+            }
+        }
+        single_item(&section)
+    }
+}
+
+/// The sort of an item that is aliased from an enclosing component.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum ComponentOuterAliasKind {
+    CoreModule,
+    CoreType,
+    Type,
+    Component,
+}
+
+/// An alias that brings an item defined elsewhere into an index space of a
+/// component.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub enum ComponentAlias {
+    /// An export of the component instance at this index.
+    InstanceExport {
+        /// The sort of the aliased item.
+        kind: ComponentExternalKind,
+        /// The index of the instance.
+        instance: u32,
+        /// The name of the export.
+        name: String,
+    },
+    /// An export of the core instance at this index.
+    CoreInstanceExport {
+        /// The sort of the aliased item.
+        kind: CoreExternalKind,
+        /// The index of the core instance.
+        instance: u32,
+        /// The name of the export.
+        name: String,
+    },
+    /// An item of an enclosing component.
+    Outer {
+        /// The sort of the aliased item.
+        kind: ComponentOuterAliasKind,
+        /// How many components out the item is, where zero is the component
+        /// that defines this alias.
+        count: u32,
+        /// The index of the item in that component.
+        index: u32,
+    },
+}
+
+impl ComponentAlias {
+    pub(crate) fn parse(
+        alias: wasmparser::ComponentAlias,
+        offset: usize,
+    ) -> Result<ComponentAlias> {
+        Ok(match alias {
+            wasmparser::ComponentAlias::InstanceExport {
+                kind,
+                instance_index,
+                name,
+            } => ComponentAlias::InstanceExport {
+                kind: ComponentExternalKind::parse(kind),
+                instance: instance_index,
+                name: name.to_string(),
+            },
+            wasmparser::ComponentAlias::CoreInstanceExport {
+                kind,
+                instance_index,
+                name,
+            } => ComponentAlias::CoreInstanceExport {
+                kind: CoreExternalKind::parse(kind, offset)?,
+                instance: instance_index,
+                name: name.to_string(),
+            },
+            wasmparser::ComponentAlias::Outer { kind, count, index } => ComponentAlias::Outer {
+                kind: match kind {
+                    wasmparser::ComponentOuterAliasKind::CoreModule => {
+                        ComponentOuterAliasKind::CoreModule
+                    }
+                    wasmparser::ComponentOuterAliasKind::CoreType => {
+                        ComponentOuterAliasKind::CoreType
+                    }
+                    wasmparser::ComponentOuterAliasKind::Type => ComponentOuterAliasKind::Type,
+                    wasmparser::ComponentOuterAliasKind::Component => {
+                        ComponentOuterAliasKind::Component
+                    }
+                },
+                count,
+                index,
+            },
+        })
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let alias = match self {
+            ComponentAlias::InstanceExport {
+                kind,
+                instance,
+                name,
+            } => wasm_encoder::Alias::InstanceExport {
+                instance: *instance,
+                kind: kind.to_wasmencoder_kind(),
+                name,
+            },
+            ComponentAlias::CoreInstanceExport {
+                kind,
+                instance,
+                name,
+            } => wasm_encoder::Alias::CoreInstanceExport {
+                instance: *instance,
+                kind: kind.to_wasmencoder_kind(),
+                name,
+            },
+            ComponentAlias::Outer { kind, count, index } => wasm_encoder::Alias::Outer {
+                kind: match kind {
+                    ComponentOuterAliasKind::CoreModule => {
+                        wasm_encoder::ComponentOuterAliasKind::CoreModule
+                    }
+                    ComponentOuterAliasKind::CoreType => {
+                        wasm_encoder::ComponentOuterAliasKind::CoreType
+                    }
+                    ComponentOuterAliasKind::Type => wasm_encoder::ComponentOuterAliasKind::Type,
+                    ComponentOuterAliasKind::Component => {
+                        wasm_encoder::ComponentOuterAliasKind::Component
+                    }
+                },
+                count: *count,
+                index: *index,
+            },
+        };
+        let mut section = wasm_encoder::ComponentAliasSection::new();
+        section.alias(alias);
+        single_item(&section)
+    }
+}
+
+/// An option of a canonical function, which says how values are passed
+/// between component functions and core functions.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub enum CanonicalOption {
+    /// Strings are encoded as UTF-8.
+    Utf8,
+    /// Strings are encoded as UTF-16.
+    Utf16,
+    /// Strings are encoded as Latin-1 or UTF-16.
+    CompactUtf16,
+    /// The core memory at this index holds the values that don't fit in core
+    /// values.
+    Memory(u32),
+    /// The core function at this index allocates memory for values.
+    Realloc(u32),
+    /// The core function at this index is called after a lifted function
+    /// returns, to free its results.
+    PostReturn(u32),
+    /// The function uses the async ABI.
+    Async,
+    /// The core function at this index is called with the events of an async
+    /// lifted function.
+    Callback(u32),
+    /// The core function type at this index is the type of the lowered
+    /// function.
+    CoreType(u32),
+    /// Values are passed as GC references rather than through memory.
+    Gc,
+}
+
+impl CanonicalOption {
+    fn parse(option: &wasmparser::CanonicalOption) -> CanonicalOption {
+        match *option {
+            wasmparser::CanonicalOption::UTF8 => CanonicalOption::Utf8,
+            wasmparser::CanonicalOption::UTF16 => CanonicalOption::Utf16,
+            wasmparser::CanonicalOption::CompactUTF16 => CanonicalOption::CompactUtf16,
+            wasmparser::CanonicalOption::Memory(index) => CanonicalOption::Memory(index),
+            wasmparser::CanonicalOption::Realloc(index) => CanonicalOption::Realloc(index),
+            wasmparser::CanonicalOption::PostReturn(index) => CanonicalOption::PostReturn(index),
+            wasmparser::CanonicalOption::Async => CanonicalOption::Async,
+            wasmparser::CanonicalOption::Callback(index) => CanonicalOption::Callback(index),
+            wasmparser::CanonicalOption::CoreType(index) => CanonicalOption::CoreType(index),
+            wasmparser::CanonicalOption::Gc => CanonicalOption::Gc,
+        }
+    }
+
+    fn to_wasmencoder_option(self) -> wasm_encoder::CanonicalOption {
+        match self {
+            CanonicalOption::Utf8 => wasm_encoder::CanonicalOption::UTF8,
+            CanonicalOption::Utf16 => wasm_encoder::CanonicalOption::UTF16,
+            CanonicalOption::CompactUtf16 => wasm_encoder::CanonicalOption::CompactUTF16,
+            CanonicalOption::Memory(index) => wasm_encoder::CanonicalOption::Memory(index),
+            CanonicalOption::Realloc(index) => wasm_encoder::CanonicalOption::Realloc(index),
+            CanonicalOption::PostReturn(index) => wasm_encoder::CanonicalOption::PostReturn(index),
+            CanonicalOption::Async => wasm_encoder::CanonicalOption::Async,
+            CanonicalOption::Callback(index) => wasm_encoder::CanonicalOption::Callback(index),
+            CanonicalOption::CoreType(index) => wasm_encoder::CanonicalOption::CoreType(index),
+            CanonicalOption::Gc => wasm_encoder::CanonicalOption::Gc,
+        }
+    }
+}
+
+/// A function defined by the canonical ABI.
+///
+/// `Lift` defines a component function, and everything else defines a core
+/// function.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub enum CanonicalFunction {
+    /// A component function that calls a core function.
+    Lift {
+        /// The index of the core function.
+        core_func: u32,
+        /// The index of the component function type.
+        ty: u32,
+        /// How values are passed to and from the core function.
+        options: Vec<CanonicalOption>,
+    },
+    /// A core function that calls a component function.
+    Lower {
+        /// The index of the component function.
+        func: u32,
+        /// How values are passed to and from the core function.
+        options: Vec<CanonicalOption>,
+    },
+    /// `resource.new` for the resource type at this index.
+    ResourceNew(u32),
+    /// `resource.drop` for the resource type at this index.
+    ResourceDrop(u32),
+    /// `resource.rep` for the resource type at this index.
+    ResourceRep(u32),
+    /// Any other built-in, kept in its binary encoding.
+    Raw(Vec<u8>),
+}
+
+impl CanonicalFunction {
+    pub(crate) fn parse(func: wasmparser::CanonicalFunction, raw: &[u8]) -> CanonicalFunction {
+        let options = |options: &[wasmparser::CanonicalOption]| {
+            options.iter().map(CanonicalOption::parse).collect()
+        };
+        match func {
+            wasmparser::CanonicalFunction::Lift {
+                core_func_index,
+                type_index,
+                options: opts,
+            } => CanonicalFunction::Lift {
+                core_func: core_func_index,
+                ty: type_index,
+                options: options(&opts),
+            },
+            wasmparser::CanonicalFunction::Lower {
+                func_index,
+                options: opts,
+            } => CanonicalFunction::Lower {
+                func: func_index,
+                options: options(&opts),
+            },
+            wasmparser::CanonicalFunction::ResourceNew { resource } => {
+                CanonicalFunction::ResourceNew(resource)
+            }
+            wasmparser::CanonicalFunction::ResourceDrop { resource } => {
+                CanonicalFunction::ResourceDrop(resource)
+            }
+            wasmparser::CanonicalFunction::ResourceRep { resource } => {
+                CanonicalFunction::ResourceRep(resource)
+            }
+            _ => CanonicalFunction::Raw(raw.to_vec()),
+        }
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let options = |options: &[CanonicalOption]| {
+            options
+                .iter()
+                .map(|o| o.to_wasmencoder_option())
+                .collect::<Vec<_>>()
+        };
+        let mut section = wasm_encoder::CanonicalFunctionSection::new();
+        match self {
+            CanonicalFunction::Lift {
+                core_func,
+                ty,
+                options: opts,
+            } => section.lift(*core_func, *ty, options(opts)),
+            CanonicalFunction::Lower {
+                func,
+                options: opts,
+            } => section.lower(*func, options(opts)),
+            CanonicalFunction::ResourceNew(ty) => section.resource_new(*ty),
+            CanonicalFunction::ResourceDrop(ty) => section.resource_drop(*ty),
+            CanonicalFunction::ResourceRep(ty) => section.resource_rep(*ty),
+            CanonicalFunction::Raw(raw) => return raw.clone(),
+        };
+        single_item(&section)
+    }
+}
+
+/// The start function of a component.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct ComponentStart {
+    /// The index of the component function.
+    pub func: u32,
+    /// The indices of the values that the function is called with.
+    pub args: Vec<u32>,
+    /// How many values the function returns.
+    pub results: u32,
+}
+
+/// The encoding of the only item of a section, without the section's size
+/// and item count.
+fn single_item(section: &impl wasm_encoder::Encode) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    section.encode(&mut bytes);
+    let mut reader = wasmparser::BinaryReader::new(&bytes, 0);
+    let _size = reader.read_var_u32().unwrap();
+    let count = reader.read_var_u32().unwrap();
+    debug_assert_eq!(count, 1);
+    bytes[reader.current_position()..].to_vec()
+}
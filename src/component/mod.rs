@@ -0,0 +1,419 @@
+//! WebAssembly components.
+//!
+//! A component is a sequence of sections, each of which defines items in
+//! the component's index spaces. The core modules that a component embeds
+//! are parsed into full walrus `Module`s, so that they can be transformed
+//! like any other module, while the rest of the component is kept as the
+//! items of its sections, which refer to each other by index.
+
+mod adapter;
+mod items;
+
+pub use self::adapter::Adapter;
+pub use self::items::*;
+
+use crate::error::Result;
+use crate::module::{Module, ModuleConfig, RawCustomSection};
+use anyhow::{bail, Context};
+use std::fs;
+use std::path::Path;
+use wasm_encoder::{ComponentSectionId, Encode};
+use wasmparser::{Chunk, Encoding, FromReader, Parser, Payload, SectionLimited, Validator};
+
+/// A section of a component.
+///
+/// The order of the sections matters: each section appends the items that it
+/// defines to the index spaces of their sorts.
+#[derive(Debug)]
+pub enum ComponentSection {
+    /// An embedded core module, which is appended to the core module index
+    /// space.
+    CoreModule(Box<Module>),
+    /// Core instances.
+    CoreInstances(Vec<CoreInstance>),
+    /// Core types, each kept in its binary encoding.
+    CoreTypes(Vec<Vec<u8>>),
+    /// A nested component.
+    Component(Box<Component>),
+    /// Component instances.
+    Instances(Vec<ComponentInstance>),
+    /// Aliases, which add to the index space of the sort of each alias.
+    Aliases(Vec<ComponentAlias>),
+    /// Component types.
+    Types(Vec<ComponentType>),
+    /// Functions defined by the canonical ABI.
+    Canonical(Vec<CanonicalFunction>),
+    /// The start function.
+    Start(ComponentStart),
+    /// Imports.
+    Imports(Vec<ComponentImport>),
+    /// Exports, which also add to the index space of the sort of each export.
+    Exports(Vec<ComponentExport>),
+    /// A custom section.
+    Custom(RawCustomSection),
+}
+
+/// A WebAssembly component.
+#[derive(Debug, Default)]
+pub struct Component {
+    /// The sections of this component, in order.
+    pub sections: Vec<ComponentSection>,
+    pub(crate) config: ModuleConfig,
+}
+
+impl Component {
+    /// Create a default, empty component that uses the given configuration.
+    pub fn with_config(config: ModuleConfig) -> Component {
+        Component {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Construct a new component from the given path with the default
+    /// configuration.
+    pub fn from_file<P>(path: P) -> Result<Component>
+    where
+        P: AsRef<Path>,
+    {
+        Component::from_buffer(&fs::read(path)?)
+    }
+
+    /// Construct a new component from the given path and configuration.
+    pub fn from_file_with_config<P>(path: P, config: &ModuleConfig) -> Result<Component>
+    where
+        P: AsRef<Path>,
+    {
+        Component::from_buffer_with_config(&fs::read(path)?, config)
+    }
+
+    /// Construct a new component from the in-memory wasm buffer with the
+    /// default configuration.
+    pub fn from_buffer(wasm: &[u8]) -> Result<Component> {
+        Component::from_buffer_with_config(wasm, &ModuleConfig::new())
+    }
+
+    /// Construct a new component from the in-memory wasm buffer and
+    /// configuration.
+    ///
+    /// The configuration is also used to parse the core modules of the
+    /// component, including those of nested components. The component as a
+    /// whole is only validated when `ModuleConfig::strict_validate` is
+    /// enabled, which is the default.
+    pub fn from_buffer_with_config(wasm: &[u8], config: &ModuleConfig) -> Result<Component> {
+        if !config.skip_strict_validate {
+            Validator::new_with_features(config.get_wasmparser_wasm_features())
+                .validate_all(wasm)
+                .context("failed to validate component")?;
+        }
+        Component::parse(wasm, config)
+    }
+
+    /// Construct a new component from a component in the WebAssembly text
+    /// format with the default configuration.
+    ///
+    /// This requires the `wat` feature of this crate.
+    #[cfg(feature = "wat")]
+    pub fn from_wat(wat: &str) -> Result<Component> {
+        Component::from_buffer(&wat::parse_str(wat)?)
+    }
+
+    /// Parses a component, which may not have been validated.
+    fn parse(wasm: &[u8], config: &ModuleConfig) -> Result<Component> {
+        let mut ret = Component::with_config(config.clone());
+        let mut parser = Parser::new(0);
+        parser.set_features(config.get_wasmparser_wasm_features());
+
+        let mut rest = wasm;
+        loop {
+            let payload = match parser.parse(rest, true)? {
+                Chunk::Parsed { consumed, payload } => {
+                    rest = &rest[consumed..];
+                    payload
+                }
+                // This can't happen with `eof = true`.
+                Chunk::NeedMoreData(_) => unreachable!(),
+            };
+            let section = match payload {
+                Payload::Version { encoding, .. } => {
+                    if encoding != Encoding::Component {
+                        bail!("expected a component, found a core module");
+                    }
+                    continue;
+                }
+                Payload::ModuleSection {
+                    unchecked_range: range,
+                    ..
+                } => {
+                    rest = &rest[range.len()..];
+                    let module = config
+                        .parse(&wasm[range])
+                        .context("failed to parse core module")?;
+                    ComponentSection::CoreModule(Box::new(module))
+                }
+                Payload::ComponentSection {
+                    unchecked_range: range,
+                    ..
+                } => {
+                    rest = &rest[range.len()..];
+                    let component = Component::parse(&wasm[range], config)
+                        .context("failed to parse nested component")?;
+                    ComponentSection::Component(Box::new(component))
+                }
+                Payload::InstanceSection(s) => {
+                    ComponentSection::CoreInstances(parse_items(wasm, s, |instance, _, offset| {
+                        CoreInstance::parse(instance, offset)
+                    })?)
+                }
+                Payload::CoreTypeSection(s) => {
+                    ComponentSection::CoreTypes(parse_items(wasm, s, |_, raw, _| Ok(raw.to_vec()))?)
+                }
+                Payload::ComponentInstanceSection(s) => {
+                    ComponentSection::Instances(parse_items(wasm, s, |instance, _, _| {
+                        Ok(ComponentInstance::parse(instance))
+                    })?)
+                }
+                Payload::ComponentAliasSection(s) => {
+                    ComponentSection::Aliases(parse_items(wasm, s, |alias, _, offset| {
+                        ComponentAlias::parse(alias, offset)
+                    })?)
+                }
+                Payload::ComponentTypeSection(s) => {
+                    ComponentSection::Types(parse_items(wasm, s, ComponentType::parse)?)
+                }
+                Payload::ComponentCanonicalSection(s) => {
+                    ComponentSection::Canonical(parse_items(wasm, s, |func, raw, _| {
+                        Ok(CanonicalFunction::parse(func, raw))
+                    })?)
+                }
+                Payload::ComponentStartSection { start, .. } => {
+                    ComponentSection::Start(ComponentStart {
+                        func: start.func_index,
+                        args: start.arguments.to_vec(),
+                        results: start.results,
+                    })
+                }
+                Payload::ComponentImportSection(s) => {
+                    ComponentSection::Imports(parse_items(wasm, s, |import, _, _| {
+                        Ok(ComponentImport::parse(import))
+                    })?)
+                }
+                Payload::ComponentExportSection(s) => {
+                    ComponentSection::Exports(parse_items(wasm, s, |export, _, _| {
+                        Ok(ComponentExport::parse(&export))
+                    })?)
+                }
+                Payload::CustomSection(s) => ComponentSection::Custom(RawCustomSection {
+                    name: s.name().to_string(),
+                    data: s.data().to_vec(),
+                }),
+                Payload::End(_) => break,
+                // Validation only lets the sections of core modules through
+                // inside of core modules, which are skipped above.
+                other => {
+                    let offset = other.as_section().map_or(0, |(_, range)| range.start);
+                    bail!("unexpected section in a component at offset {}", offset);
+                }
+            };
+            ret.sections.push(section);
+        }
+
+        log::debug!("parsed a component with {} sections", ret.sections.len());
+        Ok(ret)
+    }
+
+    /// The core modules embedded directly in this component, in order.
+    ///
+    /// This doesn't include the core modules of nested components.
+    pub fn modules(&self) -> impl Iterator<Item = &Module> {
+        self.sections.iter().filter_map(|section| match section {
+            ComponentSection::CoreModule(module) => Some(&**module),
+            _ => None,
+        })
+    }
+
+    /// Mutable access to the core modules embedded directly in this
+    /// component, in order.
+    pub fn modules_mut(&mut self) -> impl Iterator<Item = &mut Module> {
+        self.sections
+            .iter_mut()
+            .filter_map(|section| match section {
+                ComponentSection::CoreModule(module) => Some(&mut **module),
+                _ => None,
+            })
+    }
+
+    /// The imports of this component, in order.
+    pub fn imports(&self) -> impl Iterator<Item = &ComponentImport> {
+        self.sections.iter().flat_map(|section| match section {
+            ComponentSection::Imports(imports) => imports.as_slice(),
+            _ => &[],
+        })
+    }
+
+    /// The exports of this component, in order.
+    pub fn exports(&self) -> impl Iterator<Item = &ComponentExport> {
+        self.sections.iter().flat_map(|section| match section {
+            ComponentSection::Exports(exports) => exports.as_slice(),
+            _ => &[],
+        })
+    }
+
+    /// Emit this component into a `.wasm` file at the given path.
+    pub fn emit_wasm_file<P>(&mut self, path: P) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let buffer = self.try_emit_wasm()?;
+        fs::write(path, buffer).context("failed to write wasm component")?;
+        Ok(())
+    }
+
+    /// Emit this component into an in-memory wasm buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ModuleConfig::validate_emitted_wasm` is enabled and the
+    /// emitted wasm is invalid. Use `try_emit_wasm` to get an error instead.
+    pub fn emit_wasm(&mut self) -> Vec<u8> {
+        match self.try_emit_wasm() {
+            Ok(wasm) => wasm,
+            Err(e) => panic!("{:?}", e),
+        }
+    }
+
+    /// Emit this component into an in-memory wasm buffer, validating it if
+    /// `ModuleConfig::validate_emitted_wasm` is enabled.
+    ///
+    /// The core modules of the component are emitted, and validated, with
+    /// their own configuration.
+    pub fn try_emit_wasm(&mut self) -> Result<Vec<u8>> {
+        let wasm = self.emit()?;
+        if self.config.validate_emitted_wasm {
+            Validator::new_with_features(self.config.get_wasmparser_wasm_features())
+                .validate_all(&wasm)
+                .context("emitted an invalid component")?;
+        }
+        Ok(wasm)
+    }
+
+    fn emit(&mut self) -> Result<Vec<u8>> {
+        let mut component = wasm_encoder::Component::new();
+        for section in self.sections.iter_mut() {
+            match section {
+                ComponentSection::CoreModule(module) => {
+                    let wasm = module.try_emit_wasm()?;
+                    component.section(&wasm_encoder::RawSection {
+                        id: ComponentSectionId::CoreModule.into(),
+                        data: &wasm,
+                    });
+                }
+                ComponentSection::Component(nested) => {
+                    let wasm = nested.emit()?;
+                    component.section(&wasm_encoder::RawSection {
+                        id: ComponentSectionId::Component.into(),
+                        data: &wasm,
+                    });
+                }
+                ComponentSection::CoreInstances(instances) => emit_items(
+                    &mut component,
+                    ComponentSectionId::CoreInstance,
+                    instances.iter().map(|i| i.encode()),
+                ),
+                ComponentSection::CoreTypes(types) => emit_items(
+                    &mut component,
+                    ComponentSectionId::CoreType,
+                    types.iter().cloned(),
+                ),
+                ComponentSection::Instances(instances) => emit_items(
+                    &mut component,
+                    ComponentSectionId::Instance,
+                    instances.iter().map(|i| i.encode()),
+                ),
+                ComponentSection::Aliases(aliases) => emit_items(
+                    &mut component,
+                    ComponentSectionId::Alias,
+                    aliases.iter().map(|a| a.encode()),
+                ),
+                ComponentSection::Types(types) => emit_items(
+                    &mut component,
+                    ComponentSectionId::Type,
+                    types.iter().map(|t| t.encode()),
+                ),
+                ComponentSection::Canonical(funcs) => emit_items(
+                    &mut component,
+                    ComponentSectionId::CanonicalFunction,
+                    funcs.iter().map(|f| f.encode()),
+                ),
+                ComponentSection::Start(start) => {
+                    component.section(&wasm_encoder::ComponentStartSection {
+                        function_index: start.func,
+                        args: &start.args,
+                        results: start.results,
+                    });
+                }
+                ComponentSection::Imports(imports) => emit_items(
+                    &mut component,
+                    ComponentSectionId::Import,
+                    imports.iter().map(|i| i.encode()),
+                ),
+                ComponentSection::Exports(exports) => emit_items(
+                    &mut component,
+                    ComponentSectionId::Export,
+                    exports.iter().map(|e| e.encode()),
+                ),
+                ComponentSection::Custom(custom) => {
+                    component.section(&wasm_encoder::CustomSection {
+                        name: custom.name.as_str().into(),
+                        data: custom.data.as_slice().into(),
+                    });
+                }
+            }
+        }
+        Ok(component.finish())
+    }
+}
+
+/// Parse the items of a section with `f`, which is also given the binary
+/// encoding and offset of each item.
+fn parse_items<'a, T, U>(
+    wasm: &'a [u8],
+    section: SectionLimited<'a, T>,
+    mut f: impl FnMut(T, &'a [u8], usize) -> Result<U>,
+) -> Result<Vec<U>>
+where
+    T: FromReader<'a>,
+{
+    let end = section.range().end;
+    let items = section
+        .into_iter_with_offsets()
+        .collect::<wasmparser::Result<Vec<_>>>()?;
+    let ends = items
+        .iter()
+        .skip(1)
+        .map(|(offset, _)| *offset)
+        .chain(Some(end))
+        .collect::<Vec<_>>();
+    items
+        .into_iter()
+        .zip(ends)
+        .map(|((offset, item), end)| f(item, &wasm[offset..end], offset))
+        .collect()
+}
+
+/// Emit a section made of the given encoded items.
+fn emit_items(
+    component: &mut wasm_encoder::Component,
+    id: ComponentSectionId,
+    items: impl ExactSizeIterator<Item = Vec<u8>>,
+) {
+    let mut data = Vec::new();
+    items.len().encode(&mut data);
+    for item in items {
+        data.extend(item);
+    }
+    component.section(&wasm_encoder::RawSection {
+        id: id.into(),
+        data: &data,
+    });
+}
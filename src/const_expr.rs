@@ -1,11 +1,11 @@
 //! Handling wasm constant values
 
 use crate::emit::EmitContext;
+use crate::error::{locate, unsupported, Proposal};
 use crate::ir::Value;
 use crate::parse::IndicesToIds;
-use crate::{FunctionId, GlobalId, Result};
+use crate::{FunctionId, GlobalId, Result, TypeId};
 use crate::{HeapType, RefType};
-use anyhow::bail;
 
 /// A constant which is produced in WebAssembly, typically used in global
 /// initializers or element/data offsets.
@@ -56,15 +56,65 @@ pub enum ConstOp {
     I64Mul,
     /// Create i31ref from i32
     RefI31,
+    /// Allocate a struct, initializing its fields from the stack
+    StructNew(TypeId),
+    /// Allocate a struct with every field set to its default value
+    StructNewDefault(TypeId),
+    /// Allocate an array whose elements are all set to one value
+    ArrayNew(TypeId),
+    /// Allocate an array whose elements are all set to their default value
+    ArrayNewDefault(TypeId),
+    /// Allocate an array of a fixed length from values on the stack
+    ArrayNewFixed {
+        /// The array type
+        ty: TypeId,
+        /// The number of elements
+        len: u32,
+    },
+    /// Convert an externref into an anyref
+    AnyConvertExtern,
+    /// Convert an anyref into an externref
+    ExternConvertAny,
 }
 
 impl ConstExpr {
+    /// The functions that this expression takes a reference to.
+    pub(crate) fn funcs(&self) -> impl Iterator<Item = FunctionId> + '_ {
+        let (func, ops) = match self {
+            ConstExpr::RefFunc(f) => (Some(*f), &[][..]),
+            ConstExpr::Extended(ops) => (None, &ops[..]),
+            ConstExpr::Value(_) | ConstExpr::Global(_) | ConstExpr::RefNull(_) => (None, &[][..]),
+        };
+        func.into_iter().chain(ops.iter().filter_map(|op| match op {
+            ConstOp::RefFunc(f) => Some(*f),
+            _ => None,
+        }))
+    }
+
+    /// Mutable references to the functions that this expression takes a
+    /// reference to.
+    pub(crate) fn funcs_mut(&mut self) -> impl Iterator<Item = &mut FunctionId> + '_ {
+        let (func, ops) = match self {
+            ConstExpr::RefFunc(f) => (Some(f), &mut [][..]),
+            ConstExpr::Extended(ops) => (None, &mut ops[..]),
+            ConstExpr::Value(_) | ConstExpr::Global(_) | ConstExpr::RefNull(_) => {
+                (None, &mut [][..])
+            }
+        };
+        func.into_iter()
+            .chain(ops.iter_mut().filter_map(|op| match op {
+                ConstOp::RefFunc(f) => Some(f),
+                _ => None,
+            }))
+    }
+
     pub(crate) fn eval(init: &wasmparser::ConstExpr, ids: &IndicesToIds) -> Result<ConstExpr> {
         use wasmparser::Operator::*;
         let mut reader = init.get_operators_reader();
         let mut ops = Vec::new();
 
         loop {
+            let pos = reader.original_position();
             let op = reader.read()?;
             match op {
                 End => break,
@@ -77,7 +127,7 @@ impl ConstExpr {
                     ops.push(ConstOp::GlobalGet(ids.get_global(global_index)?))
                 }
                 RefNull { hty } => {
-                    let heap_type = HeapType::try_from(hty)?;
+                    let heap_type = HeapType::parse(hty, ids).map_err(|e| locate(e, pos))?;
                     let ref_type = RefType {
                         nullable: true,
                         heap_type,
@@ -94,7 +144,32 @@ impl ConstExpr {
                 I64Sub => ops.push(ConstOp::I64Sub),
                 I64Mul => ops.push(ConstOp::I64Mul),
                 RefI31 => ops.push(ConstOp::RefI31),
-                _ => bail!("unsupported operation in constant expression: {:?}", op),
+                StructNew { struct_type_index } => {
+                    ops.push(ConstOp::StructNew(ids.get_type(struct_type_index)?))
+                }
+                StructNewDefault { struct_type_index } => {
+                    ops.push(ConstOp::StructNewDefault(ids.get_type(struct_type_index)?))
+                }
+                ArrayNew { array_type_index } => {
+                    ops.push(ConstOp::ArrayNew(ids.get_type(array_type_index)?))
+                }
+                ArrayNewDefault { array_type_index } => {
+                    ops.push(ConstOp::ArrayNewDefault(ids.get_type(array_type_index)?))
+                }
+                ArrayNewFixed {
+                    array_type_index,
+                    array_size,
+                } => ops.push(ConstOp::ArrayNewFixed {
+                    ty: ids.get_type(array_type_index)?,
+                    len: array_size,
+                }),
+                AnyConvertExtern => ops.push(ConstOp::AnyConvertExtern),
+                ExternConvertAny => ops.push(ConstOp::ExternConvertAny),
+                RefI31Shared => return Err(unsupported(Proposal::SharedEverythingThreads, pos)),
+                StructNewDesc { .. } | StructNewDefaultDesc { .. } => {
+                    return Err(unsupported(Proposal::CustomDescriptors, pos))
+                }
+                _ => return Err(unsupported(Proposal::Unknown, pos)),
             }
         }
 
@@ -132,7 +207,7 @@ impl ConstExpr {
                 wasm_encoder::ConstExpr::global_get(cx.indices.get_global_index(*g))
             }
             ConstExpr::RefNull(ty) => {
-                wasm_encoder::ConstExpr::ref_null(ty.heap_type.to_wasmencoder_heap_type())
+                wasm_encoder::ConstExpr::ref_null(ty.heap_type.to_wasmencoder_heap_type(cx.indices))
             }
             ConstExpr::RefFunc(f) => {
                 wasm_encoder::ConstExpr::ref_func(cx.indices.get_func_index(*f))
@@ -157,7 +232,7 @@ impl ConstExpr {
                                 .encode(&mut bytes)
                         }
                         ConstOp::RefNull(ty) => {
-                            Instruction::RefNull(ty.heap_type.to_wasmencoder_heap_type())
+                            Instruction::RefNull(ty.heap_type.to_wasmencoder_heap_type(cx.indices))
                                 .encode(&mut bytes)
                         }
                         ConstOp::RefFunc(f) => {
@@ -170,6 +245,32 @@ impl ConstExpr {
                         ConstOp::I64Sub => Instruction::I64Sub.encode(&mut bytes),
                         ConstOp::I64Mul => Instruction::I64Mul.encode(&mut bytes),
                         ConstOp::RefI31 => Instruction::RefI31.encode(&mut bytes),
+                        ConstOp::StructNew(ty) => {
+                            Instruction::StructNew(cx.indices.get_type_index(*ty))
+                                .encode(&mut bytes)
+                        }
+                        ConstOp::StructNewDefault(ty) => {
+                            Instruction::StructNewDefault(cx.indices.get_type_index(*ty))
+                                .encode(&mut bytes)
+                        }
+                        ConstOp::ArrayNew(ty) => {
+                            Instruction::ArrayNew(cx.indices.get_type_index(*ty)).encode(&mut bytes)
+                        }
+                        ConstOp::ArrayNewDefault(ty) => {
+                            Instruction::ArrayNewDefault(cx.indices.get_type_index(*ty))
+                                .encode(&mut bytes)
+                        }
+                        ConstOp::ArrayNewFixed { ty, len } => Instruction::ArrayNewFixed {
+                            array_type_index: cx.indices.get_type_index(*ty),
+                            array_size: *len,
+                        }
+                        .encode(&mut bytes),
+                        ConstOp::AnyConvertExtern => {
+                            Instruction::AnyConvertExtern.encode(&mut bytes)
+                        }
+                        ConstOp::ExternConvertAny => {
+                            Instruction::ExternConvertAny.encode(&mut bytes)
+                        }
                     }
                 }
                 // Don't add End instruction - wasm_encoder::ConstExpr::raw adds it automatically
@@ -1,5 +1,6 @@
 //! Utilities for emitting GraphViz dot files.
 
+use crate::analysis::{CallGraph, CallKind};
 use crate::ir::*;
 use crate::*;
 use std::fs;
@@ -39,6 +40,39 @@ impl Module {
     }
 }
 
+impl Cfg {
+    /// Generate a [GraphViz Dot](https://graphviz.org/) file for this
+    /// control-flow graph of `func`, with a node listing the instructions of
+    /// each basic block and an edge from each basic block to its successors.
+    ///
+    /// See `Module::write_graphviz_dot` for how to render it.
+    pub fn write_graphviz_dot(&self, func: &LocalFunction, path: impl AsRef<Path>) -> Result<()> {
+        let mut dot_string = String::new();
+        CfgDot { cfg: self, func }.dot(&mut dot_string);
+        fs::write(path, dot_string)?;
+        Ok(())
+    }
+}
+
+impl CallGraph {
+    /// Generate a [GraphViz Dot](https://graphviz.org/) file for this call
+    /// graph of `module`, with a node for each function and an edge from each
+    /// function to the functions it calls. Edges of indirect calls are
+    /// dashed.
+    ///
+    /// See `Module::write_graphviz_dot` for how to render it.
+    pub fn write_graphviz_dot(&self, module: &Module, path: impl AsRef<Path>) -> Result<()> {
+        let mut dot_string = String::new();
+        CallGraphDot {
+            graph: self,
+            module,
+        }
+        .dot(&mut dot_string);
+        fs::write(path, dot_string)?;
+        Ok(())
+    }
+}
+
 trait Dot {
     /// Append a top-level graphviz dot form to the `out` string.
     fn dot(&self, out: &mut String);
@@ -63,6 +97,9 @@ trait EdgeAggregator {
 
     /// Add an outgoing edge form the current node at a specific port.
     fn add_edge_from_port(&mut self, port: &str, to: &impl DotName);
+
+    /// Add a dashed outgoing edge from the current node.
+    fn add_dashed_edge(&mut self, to: &impl DotName);
 }
 
 /// A trait for generating a top-level node with multiple fields and some number
@@ -148,6 +185,14 @@ impl<T: DotNode> Dot for T {
                 self.out.push_str(&to.dot_name());
                 self.out.push_str(";\n");
             }
+
+            fn add_dashed_edge(&mut self, to: &impl DotName) {
+                self.out.push_str("    ");
+                self.out.push_str(self.from);
+                self.out.push_str(" -> ");
+                self.out.push_str(&to.dot_name());
+                self.out.push_str(" [style=\"dashed\"];\n");
+            }
         }
     }
 }
@@ -298,8 +343,29 @@ impl DotNode for Table {
 impl DotNode for Type {
     fn fields(&self, fields: &mut impl FieldAggregator) {
         fields.add_field(&[&format!("<b>Type {:?}</b>", self.id())]);
-        fields.add_field(&["params", &format!("{:?}", self.params())]);
-        fields.add_field(&["results", &format!("{:?}", self.results())]);
+        match self.kind() {
+            TypeKind::Function(f) => {
+                fields.add_field(&["params", &format!("{:?}", f.params())]);
+                fields.add_field(&["results", &format!("{:?}", f.results())]);
+            }
+            TypeKind::Struct(s) => {
+                for (i, field) in s.fields.iter().enumerate() {
+                    fields.add_field(&[&format!("field {}", i), &field.to_string()]);
+                }
+            }
+            TypeKind::Array(a) => {
+                fields.add_field(&["element", &a.field.to_string()]);
+            }
+            TypeKind::Cont(c) => {
+                fields.add_field(&["func", &format!("{:?}", c.func_type)]);
+            }
+        }
+        if let Some(supertype) = self.supertype() {
+            fields.add_field(&["supertype", &format!("{:?}", supertype)]);
+        }
+        if !self.is_final() {
+            fields.add_field(&["final", "false"]);
+        }
     }
 
     fn edges(&self, _edges: &mut impl EdgeAggregator) {}
@@ -434,6 +500,10 @@ impl DotNode for InstrSeq {
                 self.edges.add_edge_from_port(&self.port, data);
             }
 
+            fn visit_element_id(&mut self, elem: &crate::ElementId) {
+                self.edges.add_edge_from_port(&self.port, elem);
+            }
+
             fn visit_type_id(&mut self, ty: &crate::TypeId) {
                 self.edges.add_edge_from_port(&self.port, ty);
             }
@@ -441,6 +511,122 @@ impl DotNode for InstrSeq {
     }
 }
 
+struct CfgDot<'a> {
+    cfg: &'a Cfg,
+    func: &'a LocalFunction,
+}
+
+impl Dot for CfgDot<'_> {
+    fn dot(&self, out: &mut String) {
+        out.push_str("digraph {\n");
+        for (id, _) in self.cfg.blocks() {
+            CfgBlock {
+                cfg: self.cfg,
+                func: self.func,
+                id,
+            }
+            .dot(out);
+        }
+        out.push('}');
+    }
+}
+
+struct CfgBlock<'a> {
+    cfg: &'a Cfg,
+    func: &'a LocalFunction,
+    id: BasicBlockId,
+}
+
+impl DotName for BasicBlockId {
+    fn dot_name(&self) -> String {
+        format!("BasicBlock_{}", self.index())
+    }
+}
+
+impl DotName for CfgBlock<'_> {
+    fn dot_name(&self) -> String {
+        self.id.dot_name()
+    }
+}
+
+impl DotNode for CfgBlock<'_> {
+    fn fields(&self, fields: &mut impl FieldAggregator) {
+        let title = if self.id == self.cfg.entry() {
+            "entry"
+        } else if self.id == self.cfg.exit() {
+            "exit"
+        } else {
+            "block"
+        };
+        fields.add_field(&[&format!(
+            "<b>BasicBlock {} ({})</b>",
+            self.id.index(),
+            title
+        )]);
+        let block = self.cfg.block(self.id);
+        for (instr, _) in block.instrs(self.func) {
+            fields.add_field(&[&format!("{:?}", instr)]);
+        }
+    }
+
+    fn edges(&self, edges: &mut impl EdgeAggregator) {
+        for succ in self.cfg.block(self.id).succs() {
+            edges.add_edge(succ);
+        }
+    }
+}
+
+struct CallGraphDot<'a> {
+    graph: &'a CallGraph,
+    module: &'a Module,
+}
+
+impl Dot for CallGraphDot<'_> {
+    fn dot(&self, out: &mut String) {
+        out.push_str("digraph {\n");
+        for func in self.module.funcs.iter() {
+            CallGraphNode {
+                graph: self.graph,
+                func,
+            }
+            .dot(out);
+        }
+        out.push('}');
+    }
+}
+
+struct CallGraphNode<'a> {
+    graph: &'a CallGraph,
+    func: &'a Function,
+}
+
+impl DotName for CallGraphNode<'_> {
+    fn dot_name(&self) -> String {
+        self.func.id().dot_name()
+    }
+}
+
+impl DotNode for CallGraphNode<'_> {
+    fn fields(&self, fields: &mut impl FieldAggregator) {
+        fields.add_field(&[&format!("<b>Function {:?}</b>", self.func.id())]);
+        if let Some(name) = self.func.name.as_ref() {
+            fields.add_field(&["name", name]);
+        }
+        if let FunctionKind::Import(_) = self.func.kind {
+            fields.add_field(&["imported"]);
+        }
+    }
+
+    fn edges(&self, edges: &mut impl EdgeAggregator) {
+        for (callee, kind) in self.graph.callees(self.func.id()) {
+            match kind {
+                CallKind::Direct => edges.add_edge(callee),
+                CallKind::Indirect => edges.add_dashed_edge(callee),
+            }
+        }
+    }
+}
+
 impl DotNode for Global {
     fn fields(&self, fields: &mut impl FieldAggregator) {
         fields.add_field(&[&format!("<b>Global {:?}</b>", self.id())]);
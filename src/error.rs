@@ -1,5 +1,7 @@
 //! Error types and utilities.
 
+use crate::ir::InstrSeqId;
+use crate::FunctionId;
 use std::fmt;
 
 /// Either `Ok(T)` or `Err(failure::Error)`.
@@ -13,14 +15,121 @@ pub use anyhow::Result;
 pub enum ErrorKind {
     /// Given invalid input wasm.
     InvalidWasm,
+
+    /// The input wasm is valid, but uses a proposal that walrus doesn't
+    /// support yet.
+    UnsupportedProposal {
+        /// The proposal that the input wasm is using.
+        proposal: Proposal,
+        /// The byte offset in the input wasm of the unsupported construct.
+        offset: usize,
+    },
 }
 
 impl fmt::Display for ErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             ErrorKind::InvalidWasm => "The input WebAssembly is invalid".fmt(f),
+            ErrorKind::UnsupportedProposal { proposal, offset } => write!(
+                f,
+                "the {} proposal is not supported (at offset {:#x})",
+                proposal, offset
+            ),
         }
     }
 }
 
 impl std::error::Error for ErrorKind {}
+
+/// A WebAssembly proposal that walrus can recognize but not represent.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[non_exhaustive]
+pub enum Proposal {
+    /// The [shared-everything
+    /// threads](https://github.com/WebAssembly/shared-everything-threads)
+    /// proposal.
+    SharedEverythingThreads,
+    /// The [wide arithmetic](https://github.com/WebAssembly/wide-arithmetic)
+    /// proposal, when `ModuleConfig::wide_arithmetic` is not enabled.
+    WideArithmetic,
+    /// The [memory control](https://github.com/WebAssembly/memory-control)
+    /// proposal.
+    MemoryControl,
+    /// The [custom descriptors](https://github.com/WebAssembly/custom-descriptors)
+    /// proposal.
+    CustomDescriptors,
+    /// The [component model](https://github.com/WebAssembly/component-model).
+    ComponentModel,
+    /// A construct that this version of walrus does not know the proposal of.
+    Unknown,
+}
+
+impl fmt::Display for Proposal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Proposal::SharedEverythingThreads => "shared-everything threads",
+            Proposal::WideArithmetic => "wide arithmetic",
+            Proposal::MemoryControl => "memory control",
+            Proposal::CustomDescriptors => "custom descriptors",
+            Proposal::ComponentModel => "component model",
+            Proposal::Unknown => "unknown",
+        }
+        .fmt(f)
+    }
+}
+
+/// The position of an instruction in the walrus IR.
+///
+/// This is attached as context to errors about a specific instruction, and
+/// can be recovered with `anyhow::Error::downcast_ref`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct InstrPosition {
+    /// The function that contains the instruction.
+    pub func: FunctionId,
+    /// The instruction sequence that contains the instruction.
+    pub seq: InstrSeqId,
+    /// The index of the instruction within `seq`, or the length of `seq` for
+    /// the `end` of the sequence itself.
+    pub index: usize,
+}
+
+impl fmt::Display for InstrPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "at instruction {} of instruction sequence {} in function {}",
+            self.index,
+            self.seq.index(),
+            self.func.index()
+        )
+    }
+}
+
+/// Creates an `ErrorKind::UnsupportedProposal` error.
+pub(crate) fn unsupported(proposal: Proposal, offset: usize) -> anyhow::Error {
+    ErrorKind::UnsupportedProposal { proposal, offset }.into()
+}
+
+/// An unsupported proposal found while parsing something that doesn't know
+/// its own position in the input, such as a value type.
+///
+/// This is turned into an `ErrorKind::UnsupportedProposal` by `locate` once
+/// the offset is known.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Unlocated(pub(crate) Proposal);
+
+impl fmt::Display for Unlocated {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the {} proposal is not supported", self.0)
+    }
+}
+
+impl std::error::Error for Unlocated {}
+
+/// Attaches `offset` to an `Unlocated` error, leaving other errors untouched.
+pub(crate) fn locate(err: anyhow::Error, offset: usize) -> anyhow::Error {
+    match err.downcast_ref::<Unlocated>() {
+        Some(Unlocated(proposal)) => unsupported(*proposal, offset),
+        None => err,
+    }
+}
@@ -0,0 +1,585 @@
+//! Control-flow graphs of local functions.
+
+use crate::ir::*;
+use crate::map::IdHashMap;
+use crate::LocalFunction;
+use std::ops::Range;
+
+/// The id of a basic block in a `Cfg`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BasicBlockId(u32);
+
+impl BasicBlockId {
+    pub(crate) fn new(index: usize) -> BasicBlockId {
+        BasicBlockId(index as u32)
+    }
+
+    /// Get the index of this basic block in its `Cfg`, which is in
+    /// `0..cfg.num_blocks()`.
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A basic block: a run of instructions of a single instruction sequence,
+/// which control only enters at the start and only leaves at the end.
+///
+/// Nested instruction sequences are not part of the basic block of the
+/// instruction that contains them. Instead, a `block`, `loop`, `if`, `try` or
+/// `try_table` ends its basic block, whose successors are the basic blocks
+/// that start the nested sequences. The instructions following it start a new
+/// basic block, which is where control goes at the end of the nested
+/// sequences, and where branches to them go.
+#[derive(Clone, Debug)]
+pub struct BasicBlock {
+    seq: InstrSeqId,
+    range: Range<usize>,
+    preds: Vec<BasicBlockId>,
+    succs: Vec<BasicBlockId>,
+}
+
+impl BasicBlock {
+    /// The instruction sequence that this basic block is a part of.
+    pub fn seq(&self) -> InstrSeqId {
+        self.seq
+    }
+
+    /// The indices of this basic block's instructions in its instruction
+    /// sequence.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// This basic block's instructions, in the function it was built from.
+    pub fn instrs<'a>(&self, func: &'a LocalFunction) -> &'a [(Instr, InstrLocId)] {
+        &func.block(self.seq).instrs[self.range.clone()]
+    }
+
+    /// The basic blocks that control may come from when entering this one.
+    pub fn preds(&self) -> &[BasicBlockId] {
+        &self.preds
+    }
+
+    /// The basic blocks that control may go to when leaving this one.
+    pub fn succs(&self) -> &[BasicBlockId] {
+        &self.succs
+    }
+}
+
+/// The control-flow graph of a `LocalFunction`.
+///
+/// Besides fallthrough and branches, the graph has edges for exceptions:
+///
+/// * A `throw`, `throw_ref` or `rethrow` has an edge to every handler that may
+///   catch its exception, and to the exit block if the exception may escape
+///   the function.
+///
+/// * Inside the body of a `try` or `try_table`, calls end their basic block,
+///   which has an edge to every handler that may catch an exception thrown by
+///   the callee. Exceptions that escape the function from a call are not
+///   represented. The same goes for `suspend` and `switch`, which may throw
+///   when the current continuation is resumed.
+///
+/// * A `resume`, `resume_throw` or `resume_throw_ref` always ends its basic
+///   block, which has an edge to the target of each of its handlers' labels,
+///   since the resumed continuation may suspend to them, and edges for the
+///   exceptions that the continuation may throw like a call.
+///
+/// The handlers of a legacy `try` are the basic blocks that start its `catch`
+/// and `catch_all` sequences, and the handlers of a `try_table` are the
+/// targets of its catch labels.
+///
+/// The entry block starts the function's body. The exit block has no
+/// instructions, and is where control goes on `return`, on a tail call, and
+/// at the end of the function's body. Basic blocks of dead code, such as the
+/// instructions that follow a `br` in the same sequence, are part of the graph
+/// but not reachable from the entry block.
+#[derive(Clone, Debug)]
+pub struct Cfg {
+    blocks: Vec<BasicBlock>,
+    /// The basic blocks of each instruction sequence, as indices in `blocks`.
+    seqs: IdHashMap<InstrSeq, Range<usize>>,
+    exit: BasicBlockId,
+    reverse_postorder: Vec<BasicBlockId>,
+}
+
+/// How an instruction sequence is nested in the function.
+struct SeqInfo {
+    /// The instruction sequence containing the instruction that this one is
+    /// part of, along with the instruction's index.
+    owner: Option<(InstrSeqId, usize)>,
+    is_loop: bool,
+    /// Where exceptions thrown in this sequence go, as an index into the
+    /// builder's `contexts`.
+    exn: usize,
+}
+
+/// Where exceptions thrown in an instruction sequence may go.
+struct ExnContext {
+    handlers: Vec<Handler>,
+    /// Whether an exception may escape the function.
+    escapes: bool,
+}
+
+#[derive(Copy, Clone)]
+enum Handler {
+    /// The start of a legacy `catch` or `catch_all` sequence.
+    Enter(InstrSeqId),
+    /// A branch to a `try_table` catch label.
+    Branch(InstrSeqId),
+}
+
+impl Cfg {
+    /// Build the control-flow graph of the given function.
+    pub fn new(func: &LocalFunction) -> Cfg {
+        let mut builder = CfgBuilder {
+            func,
+            blocks: Vec::new(),
+            seqs: IdHashMap::default(),
+            infos: IdHashMap::default(),
+            contexts: vec![ExnContext {
+                handlers: Vec::new(),
+                escapes: true,
+            }],
+            order: Vec::new(),
+        };
+        builder.split();
+        builder.connect()
+    }
+
+    /// The basic block where the function starts.
+    pub fn entry(&self) -> BasicBlockId {
+        BasicBlockId(0)
+    }
+
+    /// The basic block where the function returns.
+    pub fn exit(&self) -> BasicBlockId {
+        self.exit
+    }
+
+    /// The number of basic blocks in this graph, including the exit block.
+    pub fn num_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Get a basic block of this graph.
+    pub fn block(&self, id: BasicBlockId) -> &BasicBlock {
+        &self.blocks[id.index()]
+    }
+
+    /// Iterate over all the basic blocks of this graph, in order of their ids.
+    pub fn blocks(&self) -> impl Iterator<Item = (BasicBlockId, &BasicBlock)> {
+        self.blocks
+            .iter()
+            .enumerate()
+            .map(|(i, block)| (BasicBlockId(i as u32), block))
+    }
+
+    /// Get the basic block containing the instruction at `index` in the given
+    /// instruction sequence.
+    ///
+    /// An `index` that is the length of the sequence refers to its end, which
+    /// is in the sequence's last basic block. Returns `None` if the sequence
+    /// isn't part of the function or the index is out of bounds.
+    pub fn block_of(&self, seq: InstrSeqId, index: usize) -> Option<BasicBlockId> {
+        let range = self.seqs.get(&seq)?;
+        let blocks = &self.blocks[range.clone()];
+        let i = blocks.partition_point(|b| b.range.start <= index);
+        let block = i.checked_sub(1)?;
+        if index > blocks[block].range.end {
+            return None;
+        }
+        Some(BasicBlockId((range.start + block) as u32))
+    }
+
+    /// Iterate over the basic blocks that are reachable from the entry block,
+    /// in reverse postorder.
+    ///
+    /// In this order, every basic block comes before its successors, except
+    /// along the back edges of loops. This is the usual order for forward
+    /// dataflow analyses; iterate in reverse for backward ones.
+    pub fn reverse_postorder(&self) -> impl DoubleEndedIterator<Item = BasicBlockId> + '_ {
+        self.reverse_postorder.iter().cloned()
+    }
+}
+
+struct CfgBuilder<'a> {
+    func: &'a LocalFunction,
+    blocks: Vec<BasicBlock>,
+    seqs: IdHashMap<InstrSeq, Range<usize>>,
+    infos: IdHashMap<InstrSeq, SeqInfo>,
+    contexts: Vec<ExnContext>,
+    /// The instruction sequences, in the order they were split into basic
+    /// blocks.
+    order: Vec<InstrSeqId>,
+}
+
+impl CfgBuilder<'_> {
+    /// Split every instruction sequence into basic blocks, and find out how
+    /// they are nested.
+    fn split(&mut self) {
+        let entry = self.func.entry_block();
+        self.infos.insert(
+            entry,
+            SeqInfo {
+                owner: None,
+                is_loop: false,
+                exn: 0,
+            },
+        );
+
+        let mut stack = vec![entry];
+        while let Some(id) = stack.pop() {
+            self.order.push(id);
+            let seq = self.func.block(id);
+            let exn = self.infos[&id].exn;
+            let split_calls = !self.contexts[exn].handlers.is_empty();
+
+            let first = self.blocks.len();
+            let mut start = 0;
+            for (i, (instr, _)) in seq.instrs.iter().enumerate() {
+                if ends_block(instr, split_calls) {
+                    self.push_block(id, start..i + 1);
+                    start = i + 1;
+                }
+            }
+            let falls_through = match seq.instrs.last() {
+                Some((instr, _)) => !instr.following_instructions_are_unreachable(),
+                None => true,
+            };
+            if start < seq.len() || falls_through {
+                self.push_block(id, start..seq.len());
+            }
+            self.seqs.insert(id, first..self.blocks.len());
+
+            // Visit the nested sequences in order, so that their basic blocks
+            // are numbered in the order of the instructions.
+            let mut nested = Vec::new();
+            for (i, (instr, _)) in seq.instrs.iter().enumerate() {
+                let owner = Some((id, i));
+                let mut add = |seq: InstrSeqId, is_loop: bool, exn: usize| {
+                    nested.push((
+                        seq,
+                        SeqInfo {
+                            owner,
+                            is_loop,
+                            exn,
+                        },
+                    ));
+                };
+                match instr {
+                    Instr::Block(Block { seq }) => add(*seq, false, exn),
+                    Instr::Loop(Loop { seq }) => add(*seq, true, exn),
+                    Instr::IfElse(IfElse {
+                        consequent,
+                        alternative,
+                    }) => {
+                        add(*consequent, false, exn);
+                        add(*alternative, false, exn);
+                    }
+                    Instr::TryTable(TryTable { seq, catches }) => {
+                        let handlers = catches
+                            .iter()
+                            .map(|catch| match catch {
+                                TryTableCatch::Catch { label, .. }
+                                | TryTableCatch::CatchRef { label, .. }
+                                | TryTableCatch::CatchAll { label }
+                                | TryTableCatch::CatchAllRef { label } => Handler::Branch(*label),
+                            })
+                            .collect();
+                        let catches_all = catches.iter().any(|catch| {
+                            matches!(
+                                catch,
+                                TryTableCatch::CatchAll { .. } | TryTableCatch::CatchAllRef { .. }
+                            )
+                        });
+                        let body = self.push_context(handlers, catches_all, exn);
+                        add(*seq, false, body);
+                    }
+                    Instr::Try(Try { seq, catches }) => {
+                        let mut handlers = Vec::new();
+                        let mut catches_all = false;
+                        let mut delegate = None;
+                        for catch in catches {
+                            match catch {
+                                LegacyCatch::Catch { handler, .. } => {
+                                    handlers.push(Handler::Enter(*handler));
+                                }
+                                LegacyCatch::CatchAll { handler } => {
+                                    handlers.push(Handler::Enter(*handler));
+                                    catches_all = true;
+                                }
+                                LegacyCatch::Delegate { relative_depth } => {
+                                    delegate = Some(*relative_depth);
+                                }
+                            }
+                        }
+                        let body = match delegate {
+                            Some(depth) => self.label_context(id, depth),
+                            None => self.push_context(handlers, catches_all, exn),
+                        };
+                        add(*seq, false, body);
+                        for catch in catches {
+                            match catch {
+                                LegacyCatch::Catch { handler, .. }
+                                | LegacyCatch::CatchAll { handler } => add(*handler, false, exn),
+                                LegacyCatch::Delegate { .. } => {}
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            for (seq, info) in nested.into_iter().rev() {
+                self.infos.insert(seq, info);
+                stack.push(seq);
+            }
+        }
+    }
+
+    fn push_block(&mut self, seq: InstrSeqId, range: Range<usize>) {
+        self.blocks.push(BasicBlock {
+            seq,
+            range,
+            preds: Vec::new(),
+            succs: Vec::new(),
+        });
+    }
+
+    /// Add the context of a `try` body with the given handlers, inside
+    /// context `outer`.
+    fn push_context(
+        &mut self,
+        mut handlers: Vec<Handler>,
+        catches_all: bool,
+        outer: usize,
+    ) -> usize {
+        let mut escapes = false;
+        if !catches_all {
+            handlers.extend(self.contexts[outer].handlers.iter().cloned());
+            escapes = self.contexts[outer].escapes;
+        }
+        self.contexts.push(ExnContext { handlers, escapes });
+        self.contexts.len() - 1
+    }
+
+    /// The context of the label `depth` levels out from the sequence `seq`,
+    /// which is where `delegate` sends exceptions.
+    fn label_context(&self, mut seq: InstrSeqId, depth: u32) -> usize {
+        for _ in 0..depth {
+            match self.infos[&seq].owner {
+                Some((owner, _)) => seq = owner,
+                None => return 0,
+            }
+        }
+        self.infos[&seq].exn
+    }
+
+    fn first_block(&self, seq: InstrSeqId) -> BasicBlockId {
+        BasicBlockId(self.seqs[&seq].start as u32)
+    }
+
+    /// The basic block that starts at `index` in `seq`.
+    fn block_at(&self, seq: InstrSeqId, index: usize) -> BasicBlockId {
+        let range = self.seqs[&seq].clone();
+        let i = self.blocks[range.clone()]
+            .iter()
+            .position(|b| b.range.start == index)
+            .expect("basic block should start at this index");
+        BasicBlockId((range.start + i) as u32)
+    }
+
+    /// Where control goes at the end of the sequence `seq`.
+    fn continuation(&self, seq: InstrSeqId, exit: BasicBlockId) -> BasicBlockId {
+        match self.infos[&seq].owner {
+            Some((owner, index)) => self.block_at(owner, index + 1),
+            None => exit,
+        }
+    }
+
+    /// Where control goes on a branch to the label `seq`.
+    fn branch_target(&self, seq: InstrSeqId, exit: BasicBlockId) -> BasicBlockId {
+        let info = self
+            .infos
+            .get(&seq)
+            .expect("branch to an instruction sequence that is not in the function");
+        if info.is_loop {
+            self.first_block(seq)
+        } else {
+            self.continuation(seq, exit)
+        }
+    }
+
+    /// Add the edges between the basic blocks.
+    fn connect(mut self) -> Cfg {
+        let entry = self.func.entry_block();
+        let end = self.func.block(entry).len();
+        let exit = BasicBlockId(self.blocks.len() as u32);
+        self.push_block(entry, end..end);
+
+        for &id in self.order.iter() {
+            let seq = self.func.block(id);
+            let exn = &self.contexts[self.infos[&id].exn];
+            let split_calls = !exn.handlers.is_empty();
+            for b in self.seqs[&id].clone() {
+                let range = self.blocks[b].range.clone();
+                let mut succs = Vec::new();
+                let last = range.clone().last().map(|i| &seq.instrs[i].0);
+                let throw = |succs: &mut Vec<BasicBlockId>| {
+                    for handler in exn.handlers.iter() {
+                        succs.push(match handler {
+                            Handler::Enter(seq) => self.first_block(*seq),
+                            Handler::Branch(label) => self.branch_target(*label, exit),
+                        });
+                    }
+                };
+                match last.filter(|instr| ends_block(instr, split_calls)) {
+                    None => succs.push(self.continuation(id, exit)),
+                    Some(instr) => {
+                        let next = || self.block_at(id, range.end);
+                        match instr {
+                            Instr::Block(Block { seq })
+                            | Instr::Loop(Loop { seq })
+                            | Instr::TryTable(TryTable { seq, .. })
+                            | Instr::Try(Try { seq, .. }) => succs.push(self.first_block(*seq)),
+                            Instr::IfElse(IfElse {
+                                consequent,
+                                alternative,
+                            }) => {
+                                succs.push(self.first_block(*consequent));
+                                succs.push(self.first_block(*alternative));
+                            }
+                            Instr::Br(Br { block }) => succs.push(self.branch_target(*block, exit)),
+                            Instr::BrIf(BrIf { block })
+                            | Instr::BrOnNull(BrOnNull { block })
+                            | Instr::BrOnNonNull(BrOnNonNull { block })
+                            | Instr::BrOnCast(BrOnCast { block, .. })
+                            | Instr::BrOnCastFail(BrOnCastFail { block, .. }) => {
+                                succs.push(self.branch_target(*block, exit));
+                                succs.push(next());
+                            }
+                            Instr::BrTable(BrTable { blocks, default }) => {
+                                for block in blocks.iter().chain(Some(default)) {
+                                    succs.push(self.branch_target(*block, exit));
+                                }
+                            }
+                            Instr::Return(_)
+                            | Instr::ReturnCall(_)
+                            | Instr::ReturnCallIndirect(_)
+                            | Instr::ReturnCallRef(_) => succs.push(exit),
+                            Instr::Throw(_) | Instr::ThrowRef(_) | Instr::Rethrow(_) => {
+                                throw(&mut succs);
+                                if exn.escapes {
+                                    succs.push(exit);
+                                }
+                            }
+                            Instr::Call(_)
+                            | Instr::CallIndirect(_)
+                            | Instr::CallRef(_)
+                            | Instr::Suspend(_)
+                            | Instr::Switch(_) => {
+                                succs.push(next());
+                                throw(&mut succs);
+                            }
+                            Instr::Resume(Resume { handlers, .. })
+                            | Instr::ResumeThrow(ResumeThrow { handlers, .. })
+                            | Instr::ResumeThrowRef(ResumeThrowRef { handlers, .. }) => {
+                                succs.push(next());
+                                for handler in handlers {
+                                    if let ResumeHandler::OnLabel { label, .. } = handler {
+                                        succs.push(self.branch_target(*label, exit));
+                                    }
+                                }
+                                throw(&mut succs);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+
+                let mut deduped = Vec::with_capacity(succs.len());
+                for succ in succs {
+                    if !deduped.contains(&succ) {
+                        deduped.push(succ);
+                    }
+                }
+                self.blocks[b].succs = deduped;
+            }
+        }
+
+        for b in 0..self.blocks.len() {
+            for i in 0..self.blocks[b].succs.len() {
+                let succ = self.blocks[b].succs[i];
+                self.blocks[succ.index()].preds.push(BasicBlockId(b as u32));
+            }
+        }
+
+        let reverse_postorder = reverse_postorder(&self.blocks);
+        Cfg {
+            blocks: self.blocks,
+            seqs: self.seqs,
+            exit,
+            reverse_postorder,
+        }
+    }
+}
+
+/// Does `instr` end its basic block?
+fn ends_block(instr: &Instr, split_calls: bool) -> bool {
+    match instr {
+        Instr::Block(_)
+        | Instr::Loop(_)
+        | Instr::IfElse(_)
+        | Instr::Try(_)
+        | Instr::TryTable(_)
+        | Instr::Br(_)
+        | Instr::BrIf(_)
+        | Instr::BrTable(_)
+        | Instr::BrOnNull(_)
+        | Instr::BrOnNonNull(_)
+        | Instr::BrOnCast(_)
+        | Instr::BrOnCastFail(_)
+        | Instr::Return(_)
+        | Instr::ReturnCall(_)
+        | Instr::ReturnCallIndirect(_)
+        | Instr::ReturnCallRef(_)
+        | Instr::Unreachable(_)
+        | Instr::Throw(_)
+        | Instr::ThrowRef(_)
+        | Instr::Rethrow(_)
+        | Instr::Resume(_)
+        | Instr::ResumeThrow(_)
+        | Instr::ResumeThrowRef(_) => true,
+        Instr::Call(_)
+        | Instr::CallIndirect(_)
+        | Instr::CallRef(_)
+        | Instr::Suspend(_)
+        | Instr::Switch(_) => split_calls,
+        _ => false,
+    }
+}
+
+fn reverse_postorder(blocks: &[BasicBlock]) -> Vec<BasicBlockId> {
+    let mut visited = vec![false; blocks.len()];
+    let mut postorder = Vec::with_capacity(blocks.len());
+    let mut stack = vec![(BasicBlockId(0), 0)];
+    visited[0] = true;
+    while let Some((block, next)) = stack.last_mut() {
+        let block = *block;
+        match blocks[block.index()].succs.get(*next) {
+            Some(succ) => {
+                *next += 1;
+                if !visited[succ.index()] {
+                    visited[succ.index()] = true;
+                    stack.push((*succ, 0));
+                }
+            }
+            None => {
+                postorder.push(block);
+                stack.pop();
+            }
+        }
+    }
+    postorder.reverse();
+    postorder
+}
@@ -4,7 +4,9 @@
 //! the stack machine into an instruction tree. Additionally all control frames
 //! are representd as `Block`s.
 
+mod cfg;
 mod traversals;
+pub use self::cfg::*;
 pub use self::traversals::*;
 
 use crate::{
@@ -260,7 +262,7 @@ pub(crate) enum BlockKind {
 /// }
 /// ```
 #[walrus_instr]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Instr {
     /// `block ... end`
     #[walrus(skip_builder)]
@@ -351,7 +353,6 @@ pub enum Instr {
     Select {
         /// Optionally listed type that the `select` instruction is expected to
         /// produce, used in subtyping relations with the gc proposal.
-        #[walrus(skip_visit)]
         ty: Option<ValType>,
     },
 
@@ -550,7 +551,6 @@ pub enum Instr {
     /// `ref.null $ty`
     RefNull {
         /// The type of null that we're producing
-        #[walrus(skip_visit)]
         ty: RefType,
     },
 
@@ -614,7 +614,6 @@ pub enum Instr {
         #[walrus(skip_visit)]
         nullable: bool,
         /// The heap type to test against
-        #[walrus(skip_visit)]
         heap_type: HeapType,
     },
 
@@ -627,7 +626,6 @@ pub enum Instr {
         #[walrus(skip_visit)]
         nullable: bool,
         /// The heap type to cast to
-        #[walrus(skip_visit)]
         heap_type: HeapType,
     },
 
@@ -642,13 +640,11 @@ pub enum Instr {
         #[walrus(skip_visit)]
         from_nullable: bool,
         /// Input heap type
-        #[walrus(skip_visit)]
         from_heap_type: HeapType,
         /// Whether target type is nullable
         #[walrus(skip_visit)]
         to_nullable: bool,
         /// Target heap type
-        #[walrus(skip_visit)]
         to_heap_type: HeapType,
     },
 
@@ -663,13 +659,11 @@ pub enum Instr {
         #[walrus(skip_visit)]
         from_nullable: bool,
         /// Input heap type
-        #[walrus(skip_visit)]
         from_heap_type: HeapType,
         /// Whether target type is nullable
         #[walrus(skip_visit)]
         to_nullable: bool,
         /// Target heap type
-        #[walrus(skip_visit)]
         to_heap_type: HeapType,
     },
 
@@ -683,6 +677,153 @@ pub enum Instr {
     /// Externalizes an internal reference.
     ExternConvertAny {},
 
+    /// `ref.eq` - compare two `eqref`s for identity
+    RefEq {},
+
+    /// `struct.new` - allocate a struct, initializing its fields from the
+    /// operand stack
+    StructNew {
+        /// The struct type being allocated
+        ty: TypeId,
+    },
+
+    /// `struct.new_default` - allocate a struct with default-valued fields
+    StructNewDefault {
+        /// The struct type being allocated
+        ty: TypeId,
+    },
+
+    /// `struct.get` - read a struct field
+    StructGet {
+        /// The struct type being accessed
+        ty: TypeId,
+        /// The index of the field being read
+        #[walrus(skip_visit)]
+        field: u32,
+    },
+
+    /// `struct.get_s` - read a packed struct field, sign-extending it
+    StructGetS {
+        /// The struct type being accessed
+        ty: TypeId,
+        /// The index of the field being read
+        #[walrus(skip_visit)]
+        field: u32,
+    },
+
+    /// `struct.get_u` - read a packed struct field, zero-extending it
+    StructGetU {
+        /// The struct type being accessed
+        ty: TypeId,
+        /// The index of the field being read
+        #[walrus(skip_visit)]
+        field: u32,
+    },
+
+    /// `struct.set` - write a mutable struct field
+    StructSet {
+        /// The struct type being accessed
+        ty: TypeId,
+        /// The index of the field being written
+        #[walrus(skip_visit)]
+        field: u32,
+    },
+
+    /// `array.new` - allocate an array with every element set to one value
+    ArrayNew {
+        /// The array type being allocated
+        ty: TypeId,
+    },
+
+    /// `array.new_default` - allocate an array with default-valued elements
+    ArrayNewDefault {
+        /// The array type being allocated
+        ty: TypeId,
+    },
+
+    /// `array.new_fixed` - allocate an array from a fixed number of operands
+    ArrayNewFixed {
+        /// The array type being allocated
+        ty: TypeId,
+        /// The number of elements, all taken from the operand stack
+        #[walrus(skip_visit)]
+        len: u32,
+    },
+
+    /// `array.new_data` - allocate an array initialized from a data segment
+    ArrayNewData {
+        /// The array type being allocated
+        ty: TypeId,
+        /// The data segment the elements are copied from
+        data: DataId,
+    },
+
+    /// `array.new_elem` - allocate an array initialized from an element
+    /// segment
+    ArrayNewElem {
+        /// The array type being allocated
+        ty: TypeId,
+        /// The element segment the elements are copied from
+        elem: ElementId,
+    },
+
+    /// `array.get` - read an array element
+    ArrayGet {
+        /// The array type being accessed
+        ty: TypeId,
+    },
+
+    /// `array.get_s` - read a packed array element, sign-extending it
+    ArrayGetS {
+        /// The array type being accessed
+        ty: TypeId,
+    },
+
+    /// `array.get_u` - read a packed array element, zero-extending it
+    ArrayGetU {
+        /// The array type being accessed
+        ty: TypeId,
+    },
+
+    /// `array.set` - write a mutable array element
+    ArraySet {
+        /// The array type being accessed
+        ty: TypeId,
+    },
+
+    /// `array.len` - get the number of elements of any array
+    ArrayLen {},
+
+    /// `array.fill` - set a range of array elements to one value
+    ArrayFill {
+        /// The array type being filled
+        ty: TypeId,
+    },
+
+    /// `array.copy` - copy a range of elements between two arrays
+    ArrayCopy {
+        /// The type of the array being copied into
+        dst: TypeId,
+        /// The type of the array being copied from
+        src: TypeId,
+    },
+
+    /// `array.init_data` - copy a range of a data segment into an array
+    ArrayInitData {
+        /// The array type being initialized
+        ty: TypeId,
+        /// The data segment the elements are copied from
+        data: DataId,
+    },
+
+    /// `array.init_elem` - copy a range of an element segment into an array
+    ArrayInitElem {
+        /// The array type being initialized
+        ty: TypeId,
+        /// The element segment the elements are copied from
+        elem: ElementId,
+    },
+
     /// `v128.bitselect`
     V128Bitselect {},
 
@@ -782,13 +923,93 @@ pub enum Instr {
         #[walrus(skip_visit)]
         relative_depth: u32,
     },
+
+    // Stack switching instructions
+    /// `cont.new` - create a continuation from a function reference
+    ContNew {
+        /// The continuation type being created
+        ty: TypeId,
+    },
+
+    /// `cont.bind` - bind a continuation to some of its parameters, giving a
+    /// continuation that takes the rest of them
+    ContBind {
+        /// The type of the continuation being bound
+        from: TypeId,
+        /// The type of the resulting continuation
+        to: TypeId,
+    },
+
+    /// `suspend` - suspend the current continuation, passing control to the
+    /// handler of the innermost `resume` that handles the tag
+    Suspend {
+        /// The tag that the suspension is handled by
+        tag: TagId,
+    },
+
+    /// `resume` - run a continuation until it finishes or suspends
+    Resume {
+        /// The type of the continuation being resumed
+        ty: TypeId,
+        /// The handlers for the continuation's suspensions
+        #[walrus(skip_visit)]
+        handlers: Vec<ResumeHandler>,
+    },
+
+    /// `resume_throw` - resume a continuation by throwing an exception at the
+    /// point where it is suspended
+    ResumeThrow {
+        /// The type of the continuation being resumed
+        ty: TypeId,
+        /// The tag of the exception being thrown
+        tag: TagId,
+        /// The handlers for the continuation's suspensions
+        #[walrus(skip_visit)]
+        handlers: Vec<ResumeHandler>,
+    },
+
+    /// `resume_throw_ref` - resume a continuation by rethrowing an exception
+    /// reference at the point where it is suspended
+    ResumeThrowRef {
+        /// The type of the continuation being resumed
+        ty: TypeId,
+        /// The handlers for the continuation's suspensions
+        #[walrus(skip_visit)]
+        handlers: Vec<ResumeHandler>,
+    },
+
+    /// `switch` - suspend the current continuation and resume another one
+    /// directly, with the current one as its last argument
+    Switch {
+        /// The type of the continuation being switched to
+        ty: TypeId,
+        /// The tag of the `switch` handler that the switch goes through
+        tag: TagId,
+    },
+
+    // Wide arithmetic instructions
+    /// `i64.add128` - add two 128-bit integers, each given as its low and then
+    /// high 64 bits, giving the sum's low and high 64 bits
+    I64Add128 {},
+
+    /// `i64.sub128` - subtract two 128-bit integers, each given as its low and
+    /// then high 64 bits, giving the difference's low and high 64 bits
+    I64Sub128 {},
+
+    /// `i64.mul_wide_s` - multiply two signed i64s, giving the low and high
+    /// 64 bits of the 128-bit product
+    I64MulWideS {},
+
+    /// `i64.mul_wide_u` - multiply two unsigned i64s, giving the low and high
+    /// 64 bits of the 128-bit product
+    I64MulWideU {},
 }
 
 /// Argument in `V128Shuffle` of lane indices to select
 pub type ShuffleIndices = [u8; 16];
 
 /// A catch clause in a legacy `Try` instruction
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum LegacyCatch {
     /// `catch tag` - catches exception with specific tag
     Catch {
@@ -810,7 +1031,7 @@ pub enum LegacyCatch {
 }
 
 /// A catch clause in a `TryTable` instruction
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum TryTableCatch {
     /// `catch tag label` - catches exception with specific tag, binds payload values
     Catch {
@@ -838,8 +1059,26 @@ pub enum TryTableCatch {
     },
 }
 
+/// A handler in a `Resume`, `ResumeThrow` or `ResumeThrowRef` instruction
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResumeHandler {
+    /// `on tag label` - handles a suspension by branching with its payload
+    /// and the suspended continuation
+    OnLabel {
+        /// The tag to match
+        tag: TagId,
+        /// The block to branch to
+        label: InstrSeqId,
+    },
+    /// `on tag switch` - handles a `switch` through the tag
+    OnSwitch {
+        /// The tag to match
+        tag: TagId,
+    },
+}
+
 /// Constant values that can show up in WebAssembly
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Value {
     /// A constant 32-bit integer
     I32(i32),
@@ -867,7 +1106,7 @@ impl fmt::Display for Value {
 
 /// Possible ternary operations in wasm
 #[allow(missing_docs)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum TernaryOp {
     F32x4RelaxedMadd,
     F32x4RelaxedNmadd,
@@ -882,7 +1121,7 @@ pub enum TernaryOp {
 
 /// Possible binary operations in wasm
 #[allow(missing_docs)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum BinaryOp {
     I32Eq,
     I32Ne,
@@ -1130,7 +1369,7 @@ pub enum BinaryOp {
 
 /// Possible unary operations in wasm
 #[allow(missing_docs)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum UnaryOp {
     I32Eqz,
     I32Clz,
@@ -1288,7 +1527,7 @@ pub enum UnaryOp {
 }
 
 /// The different kinds of load instructions that are part of a `Load` IR node
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[allow(missing_docs)]
 pub enum LoadKind {
     // TODO: much of this is probably redundant with type information already
@@ -1308,7 +1547,7 @@ pub enum LoadKind {
 }
 
 /// The different kinds of load instructions that are part of a `LoadSimd` IR node
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[allow(missing_docs)]
 pub enum LoadSimdKind {
     Splat8,
@@ -1336,7 +1575,7 @@ pub enum LoadSimdKind {
 }
 
 /// The kinds of extended loads which can happen
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[allow(missing_docs)]
 pub enum ExtendedLoad {
     SignExtend,
@@ -1383,7 +1622,7 @@ impl ExtendedLoad {
 }
 
 /// The different kinds of store instructions that are part of a `Store` IR node
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[allow(missing_docs)]
 pub enum StoreKind {
     I32 { atomic: bool },
@@ -1430,7 +1669,7 @@ impl StoreKind {
 
 /// Arguments to memory operations, containing a constant offset from a dynamic
 /// address as well as a predicted alignment.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct MemArg {
     /// The alignment of the memory operation, must be a power of two
     pub align: u32,
@@ -1439,7 +1678,7 @@ pub struct MemArg {
 }
 
 /// The different kinds of atomic rmw operations
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[allow(missing_docs)]
 pub enum AtomicOp {
     Add,
@@ -1451,7 +1690,7 @@ pub enum AtomicOp {
 }
 
 /// The different kinds of atomic rmw operations
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[allow(missing_docs)]
 pub enum AtomicWidth {
     I32,
@@ -1557,7 +1796,177 @@ impl Instr {
             | Instr::BrOnCast(..)
             | Instr::BrOnCastFail(..)
             | Instr::AnyConvertExtern(..)
-            | Instr::ExternConvertAny(..) => false,
+            | Instr::ExternConvertAny(..)
+            | Instr::RefEq(..)
+            | Instr::StructNew(..)
+            | Instr::StructNewDefault(..)
+            | Instr::StructGet(..)
+            | Instr::StructGetS(..)
+            | Instr::StructGetU(..)
+            | Instr::StructSet(..)
+            | Instr::ArrayNew(..)
+            | Instr::ArrayNewDefault(..)
+            | Instr::ArrayNewFixed(..)
+            | Instr::ArrayNewData(..)
+            | Instr::ArrayNewElem(..)
+            | Instr::ArrayGet(..)
+            | Instr::ArrayGetS(..)
+            | Instr::ArrayGetU(..)
+            | Instr::ArraySet(..)
+            | Instr::ArrayLen(..)
+            | Instr::ArrayFill(..)
+            | Instr::ArrayCopy(..)
+            | Instr::ArrayInitData(..)
+            | Instr::ArrayInitElem(..)
+            | Instr::ContNew(..)
+            | Instr::ContBind(..)
+            | Instr::Suspend(..)
+            | Instr::Resume(..)
+            | Instr::ResumeThrow(..)
+            | Instr::ResumeThrowRef(..)
+            | Instr::Switch(..)
+            | Instr::I64Add128(..)
+            | Instr::I64Sub128(..)
+            | Instr::I64MulWideS(..)
+            | Instr::I64MulWideU(..) => false,
+        }
+    }
+
+    /// Can this instruction be removed when its results aren't used?
+    ///
+    /// Returns `true` for instructions that only compute their results from
+    /// their operands and the current state of the module (`i32.add`,
+    /// `local.get`, `memory.size`, etc...). Returns `false` for instructions
+    /// that change any state, branch, or may trap, like calls, stores, loads,
+    /// integer division and casts.
+    pub fn is_pure(&self) -> bool {
+        match self {
+            Instr::Binop(Binop { op }) => !matches!(
+                op,
+                BinaryOp::I32DivS
+                    | BinaryOp::I32DivU
+                    | BinaryOp::I32RemS
+                    | BinaryOp::I32RemU
+                    | BinaryOp::I64DivS
+                    | BinaryOp::I64DivU
+                    | BinaryOp::I64RemS
+                    | BinaryOp::I64RemU
+            ),
+            Instr::Unop(Unop { op }) => !matches!(
+                op,
+                UnaryOp::I32TruncSF32
+                    | UnaryOp::I32TruncUF32
+                    | UnaryOp::I32TruncSF64
+                    | UnaryOp::I32TruncUF64
+                    | UnaryOp::I64TruncSF32
+                    | UnaryOp::I64TruncUF32
+                    | UnaryOp::I64TruncSF64
+                    | UnaryOp::I64TruncUF64
+            ),
+
+            Instr::LocalGet(..)
+            | Instr::GlobalGet(..)
+            | Instr::Const(..)
+            | Instr::TernOp(..)
+            | Instr::Select(..)
+            | Instr::MemorySize(..)
+            | Instr::TableSize(..)
+            | Instr::RefNull(..)
+            | Instr::RefIsNull(..)
+            | Instr::RefFunc(..)
+            | Instr::V128Bitselect(..)
+            | Instr::I8x16Swizzle(..)
+            | Instr::I8x16Shuffle(..)
+            | Instr::RefI31(..)
+            | Instr::RefTest(..)
+            | Instr::AnyConvertExtern(..)
+            | Instr::ExternConvertAny(..)
+            | Instr::RefEq(..)
+            | Instr::StructNew(..)
+            | Instr::StructNewDefault(..)
+            | Instr::ArrayNewFixed(..)
+            | Instr::I64Add128(..)
+            | Instr::I64Sub128(..)
+            | Instr::I64MulWideS(..)
+            | Instr::I64MulWideU(..) => true,
+
+            // No `_` arm to make sure that we properly update this function as
+            // we add support for new instructions.
+            Instr::Block(..)
+            | Instr::Loop(..)
+            | Instr::Call(..)
+            | Instr::CallIndirect(..)
+            | Instr::LocalSet(..)
+            | Instr::LocalTee(..)
+            | Instr::GlobalSet(..)
+            | Instr::Unreachable(..)
+            | Instr::Br(..)
+            | Instr::BrIf(..)
+            | Instr::IfElse(..)
+            | Instr::BrTable(..)
+            | Instr::Drop(..)
+            | Instr::Return(..)
+            | Instr::MemoryGrow(..)
+            | Instr::MemoryInit(..)
+            | Instr::DataDrop(..)
+            | Instr::MemoryCopy(..)
+            | Instr::MemoryFill(..)
+            | Instr::Load(..)
+            | Instr::Store(..)
+            | Instr::AtomicRmw(..)
+            | Instr::Cmpxchg(..)
+            | Instr::AtomicNotify(..)
+            | Instr::AtomicWait(..)
+            | Instr::AtomicFence(..)
+            | Instr::TableGet(..)
+            | Instr::TableSet(..)
+            | Instr::TableGrow(..)
+            | Instr::TableFill(..)
+            | Instr::RefAsNonNull(..)
+            | Instr::BrOnNull(..)
+            | Instr::BrOnNonNull(..)
+            | Instr::CallRef(..)
+            | Instr::ReturnCallRef(..)
+            | Instr::I31GetS(..)
+            | Instr::I31GetU(..)
+            | Instr::RefCast(..)
+            | Instr::BrOnCast(..)
+            | Instr::BrOnCastFail(..)
+            | Instr::StructGet(..)
+            | Instr::StructGetS(..)
+            | Instr::StructGetU(..)
+            | Instr::StructSet(..)
+            | Instr::ArrayNew(..)
+            | Instr::ArrayNewDefault(..)
+            | Instr::ArrayNewData(..)
+            | Instr::ArrayNewElem(..)
+            | Instr::ArrayGet(..)
+            | Instr::ArrayGetS(..)
+            | Instr::ArrayGetU(..)
+            | Instr::ArraySet(..)
+            | Instr::ArrayLen(..)
+            | Instr::ArrayFill(..)
+            | Instr::ArrayCopy(..)
+            | Instr::ArrayInitData(..)
+            | Instr::ArrayInitElem(..)
+            | Instr::LoadSimd(..)
+            | Instr::TableInit(..)
+            | Instr::ElemDrop(..)
+            | Instr::TableCopy(..)
+            | Instr::ReturnCall(..)
+            | Instr::ReturnCallIndirect(..)
+            | Instr::TryTable(..)
+            | Instr::Throw(..)
+            | Instr::ThrowRef(..)
+            | Instr::Try(..)
+            | Instr::Rethrow(..)
+            | Instr::ContNew(..)
+            | Instr::ContBind(..)
+            | Instr::Suspend(..)
+            | Instr::Resume(..)
+            | Instr::ResumeThrow(..)
+            | Instr::ResumeThrowRef(..)
+            | Instr::Switch(..) => false,
         }
     }
 }
@@ -1583,8 +1992,10 @@ impl<'instr> Visit<'instr> for InstrSeq {
     where
         V: Visitor<'instr>,
     {
-        if let InstrSeqType::MultiValue(ref ty) = self.ty {
-            visitor.visit_type_id(ty);
+        match &self.ty {
+            InstrSeqType::Simple(Some(ty)) => visitor.visit_val_type(ty),
+            InstrSeqType::Simple(None) => {}
+            InstrSeqType::MultiValue(ty) => visitor.visit_type_id(ty),
         }
     }
 }
@@ -1594,8 +2005,10 @@ impl VisitMut for InstrSeq {
     where
         V: VisitorMut,
     {
-        if let InstrSeqType::MultiValue(ref mut ty) = self.ty {
-            visitor.visit_type_id_mut(ty);
+        match &mut self.ty {
+            InstrSeqType::Simple(Some(ty)) => visitor.visit_val_type_mut(ty),
+            InstrSeqType::Simple(None) => {}
+            InstrSeqType::MultiValue(ty) => visitor.visit_type_id_mut(ty),
         }
     }
 }
@@ -1628,6 +2041,32 @@ impl VisitMut for TryTableCatch {
     }
 }
 
+impl<'instr> Visit<'instr> for ResumeHandler {
+    fn visit<V>(&self, visitor: &mut V)
+    where
+        V: Visitor<'instr>,
+    {
+        match self {
+            ResumeHandler::OnLabel { tag, .. } | ResumeHandler::OnSwitch { tag } => {
+                visitor.visit_tag_id(tag);
+            }
+        }
+    }
+}
+
+impl VisitMut for ResumeHandler {
+    fn visit_mut<V>(&mut self, visitor: &mut V)
+    where
+        V: VisitorMut,
+    {
+        match self {
+            ResumeHandler::OnLabel { tag, .. } | ResumeHandler::OnSwitch { tag } => {
+                visitor.visit_tag_id_mut(tag);
+            }
+        }
+    }
+}
+
 impl<'instr> Visit<'instr> for LegacyCatch {
     fn visit<V>(&self, visitor: &mut V)
     where
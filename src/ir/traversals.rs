@@ -151,6 +151,19 @@ pub fn dfs_in_order<'instr>(
                     continue 'traversing_blocks;
                 }
 
+                // Visit the handlers to mark their tags as used. Like the
+                // labels of `try_table` catches, the handlers' labels are
+                // branch targets that are visited during the traversal.
+                Instr::Resume(Resume { handlers, .. })
+                | Instr::ResumeThrow(ResumeThrow { handlers, .. })
+                | Instr::ResumeThrowRef(ResumeThrowRef { handlers, .. }) => {
+                    for handler in handlers.iter() {
+                        log::trace!("dfs_in_order: ({:?}).visit(..)", handler);
+                        handler.visit(visitor);
+                    }
+                    continue 'traversing_instrs;
+                }
+
                 // No other instructions define new instruction sequences, so
                 // continue to the next instruction.
                 _ => continue 'traversing_instrs,
@@ -281,6 +294,14 @@ pub fn dfs_pre_order_mut(
                     stack.push(*seq);
                 }
 
+                Instr::Resume(Resume { handlers, .. })
+                | Instr::ResumeThrow(ResumeThrow { handlers, .. })
+                | Instr::ResumeThrowRef(ResumeThrowRef { handlers, .. }) => {
+                    for handler in handlers.iter_mut() {
+                        handler.visit_mut(visitor);
+                    }
+                }
+
                 _ => {}
             }
         }
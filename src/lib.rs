@@ -17,7 +17,9 @@ macro_rules! maybe_parallel {
     };
 }
 
+pub mod analysis;
 mod arena_set;
+pub mod component;
 mod const_expr;
 pub mod dot;
 mod emit;
@@ -28,14 +30,20 @@ mod map;
 mod module;
 mod parse;
 pub mod passes;
+pub mod printer;
 mod tombstone_arena;
 mod ty;
+mod validate;
 
+pub use crate::component::Component;
 pub use crate::const_expr::{ConstExpr, ConstOp};
 pub use crate::emit::IdsToIndices;
-pub use crate::error::{ErrorKind, Result};
+pub use crate::error::{ErrorKind, InstrPosition, Proposal, Result};
 pub use crate::function_builder::{FunctionBuilder, InstrSeqBuilder};
 pub use crate::ir::{Local, LocalId};
 pub use crate::module::*;
 pub use crate::parse::IndicesToIds;
-pub use crate::ty::{AbstractHeapType, HeapType, RefType, Type, TypeId, ValType};
+pub use crate::ty::{
+    AbstractHeapType, ArrayType, ContType, FieldType, FunctionType, HeapType, RecGroup, RecGroupId,
+    RefType, StorageType, StructType, SubType, Type, TypeId, TypeKind, ValType,
+};
@@ -19,9 +19,11 @@ pub struct ModuleConfig {
     pub(crate) generate_synthetic_names_for_anonymous_items: bool,
     pub(crate) only_stable_features: bool,
     pub(crate) skip_strict_validate: bool,
+    pub(crate) validate_emitted_wasm: bool,
     pub(crate) skip_producers_section: bool,
     pub(crate) skip_name_section: bool,
     pub(crate) preserve_code_transform: bool,
+    pub(crate) wide_arithmetic: bool,
     pub(crate) on_parse: Option<OnParseFn>,
     pub(crate) on_instr_loc: Option<OnInstrLocFn>,
 }
@@ -35,9 +37,11 @@ impl Clone for ModuleConfig {
                 .generate_synthetic_names_for_anonymous_items,
             only_stable_features: self.only_stable_features,
             skip_strict_validate: self.skip_strict_validate,
+            validate_emitted_wasm: self.validate_emitted_wasm,
             skip_producers_section: self.skip_producers_section,
             skip_name_section: self.skip_name_section,
             preserve_code_transform: self.preserve_code_transform,
+            wide_arithmetic: self.wide_arithmetic,
 
             // ... and this is left empty.
             on_parse: None,
@@ -55,9 +59,11 @@ impl fmt::Debug for ModuleConfig {
             ref generate_synthetic_names_for_anonymous_items,
             ref only_stable_features,
             ref skip_strict_validate,
+            ref validate_emitted_wasm,
             ref skip_producers_section,
             ref skip_name_section,
             ref preserve_code_transform,
+            ref wide_arithmetic,
             ref on_parse,
             ref on_instr_loc,
         } = self;
@@ -70,9 +76,11 @@ impl fmt::Debug for ModuleConfig {
             )
             .field("only_stable_features", only_stable_features)
             .field("skip_strict_validate", skip_strict_validate)
+            .field("validate_emitted_wasm", validate_emitted_wasm)
             .field("skip_producers_section", skip_producers_section)
             .field("skip_name_section", skip_name_section)
             .field("preserve_code_transform", preserve_code_transform)
+            .field("wide_arithmetic", wide_arithmetic)
             .field("on_parse", &on_parse.as_ref().map(|_| ".."))
             .field("on_instr_loc", &on_instr_loc.as_ref().map(|_| ".."))
             .finish()
@@ -134,12 +142,30 @@ impl ModuleConfig {
     /// create a `Module` from a wasm file. This includes checks such as "atomic
     /// instructions require a shared memory".
     ///
+    /// For a `Component`, this controls whether the component as a whole is
+    /// validated before it is parsed.
+    ///
     /// By default this flag is `true`
     pub fn strict_validate(&mut self, strict: bool) -> &mut ModuleConfig {
         self.skip_strict_validate = !strict;
         self
     }
 
+    /// Indicates whether the wasm emitted for the module is validated, with
+    /// the same WebAssembly features that are enabled for parsing.
+    ///
+    /// This catches transformations that leave the module in an invalid state,
+    /// such as a pass that leaves the wrong type on the operand stack. The IR
+    /// is checked with `Module::validate` before it is emitted. Invalid
+    /// output makes `Module::try_emit_wasm` return an error, and
+    /// `Module::emit_wasm` panic.
+    ///
+    /// By default this flag is `false`
+    pub fn validate_emitted_wasm(&mut self, validate: bool) -> &mut ModuleConfig {
+        self.validate_emitted_wasm = validate;
+        self
+    }
+
     /// Indicates whether the module will have the "producers" custom section
     /// which preserves the original producers and also includes `walrus`.
     ///
@@ -166,6 +192,19 @@ impl ModuleConfig {
         self
     }
 
+    /// Enables the [wide
+    /// arithmetic](https://github.com/WebAssembly/wide-arithmetic) proposal,
+    /// whose instructions, like `i64.add128`, are otherwise rejected with an
+    /// `ErrorKind::UnsupportedProposal` when parsing. The proposal is only
+    /// enabled in wasmparser, which also validates the emitted wasm when
+    /// `validate_emitted_wasm` is enabled, when this flag is set.
+    ///
+    /// By default this flag is `false`.
+    pub fn wide_arithmetic(&mut self, enable: bool) -> &mut ModuleConfig {
+        self.wide_arithmetic = enable;
+        self
+    }
+
     /// Returns a `wasmparser::WasmFeatures` based on the enabled proposals
     /// which should be used for `wasmparser::Parser`` and `wasmparser::Validator`.
     pub(crate) fn get_wasmparser_wasm_features(&self) -> WasmFeatures {
@@ -178,6 +217,24 @@ impl ModuleConfig {
         // Support this as long as browsers also support it
         features.insert(WasmFeatures::LEGACY_EXCEPTIONS);
 
+        // Continuation types and the stack switching instructions are
+        // represented in the IR.
+        features.insert(WasmFeatures::STACK_SWITCHING);
+
+        // The wide arithmetic instructions are represented in the IR, but
+        // only accepted when `wide_arithmetic` is enabled. Otherwise they are
+        // reported as an `ErrorKind::UnsupportedProposal` when parsing.
+        if self.wide_arithmetic {
+            features.insert(WasmFeatures::WIDE_ARITHMETIC);
+        }
+
+        // Let these proposals through the parser and validator even though
+        // walrus can't represent them, so that their use is reported as an
+        // `ErrorKind::UnsupportedProposal` rather than a validation failure.
+        features.insert(WasmFeatures::SHARED_EVERYTHING_THREADS);
+        features.insert(WasmFeatures::MEMORY_CONTROL);
+        features.insert(WasmFeatures::CUSTOM_DESCRIPTORS);
+
         features
     }
 
@@ -230,6 +287,18 @@ impl ModuleConfig {
         Module::parse(wasm, self)
     }
 
+    /// Parses a module in the WebAssembly text format into a `Module` using
+    /// this configuration.
+    ///
+    /// Identifiers in the text, such as `$foo`, become the `name` of the
+    /// function, local, global, type, etc... that they identify.
+    ///
+    /// This requires the `wat` feature of this crate.
+    #[cfg(feature = "wat")]
+    pub fn parse_wat(&self, wat: &str) -> Result<Module> {
+        self.parse(&wat::parse_str(wat)?)
+    }
+
     /// Parses a WebAssembly file into a `Module` using this configuration.
     pub fn parse_file<P>(&self, path: P) -> Result<Module>
     where
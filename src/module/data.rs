@@ -81,6 +81,12 @@ impl ModuleData {
         &self.arena[id]
     }
 
+    /// Does this module contain a data segment with the given id, that hasn't
+    /// been deleted?
+    pub(crate) fn contains(&self, id: DataId) -> bool {
+        self.arena.contains(id)
+    }
+
     /// Get an element associated with an ID
     pub fn get_mut(&mut self, id: DataId) -> &mut Data {
         &mut self.arena[id]
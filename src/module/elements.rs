@@ -1,6 +1,7 @@
 //! Table elements within a wasm module.
 
 use crate::emit::{Emit, EmitContext};
+use crate::error::locate;
 use crate::parse::IndicesToIds;
 use crate::tombstone_arena::{Id, Tombstone, TombstoneArena};
 use crate::{ir::Value, ConstExpr, FunctionId, Module, RefType, Result, TableId, ValType};
@@ -73,6 +74,12 @@ impl ModuleElements {
         &self.arena[id]
     }
 
+    /// Does this module contain a element segment with the given id, that hasn't
+    /// been deleted?
+    pub(crate) fn contains(&self, id: ElementId) -> bool {
+        self.arena.contains(id)
+    }
+
     /// Get an element associated with an ID
     pub fn get_mut(&mut self, id: ElementId) -> &mut Element {
         &mut self.arena[id]
@@ -133,7 +140,8 @@ impl Module {
                     ElementItems::Functions(function_ids)
                 }
                 wasmparser::ElementItems::Expressions(ref_type, items) => {
-                    let ty = RefType::try_from(ref_type)?;
+                    let ty = RefType::parse(ref_type, ids)
+                        .map_err(|e| locate(e, element.range.start))?;
                     let mut const_exprs = Vec::with_capacity(items.count() as usize);
                     for item in items {
                         let const_expr = item?;
@@ -227,7 +235,7 @@ impl Emit for ModuleElements {
                     emit_elem(cx, &mut wasm_element_section, &element.kind, els);
                 }
                 ElementItems::Expressions(ty, const_exprs) => {
-                    let ref_type = ty.to_wasmencoder_ref_type();
+                    let ref_type = ty.to_wasmencoder_ref_type(cx.indices);
                     let const_exprs = const_exprs
                         .iter()
                         .map(|expr| expr.to_wasmencoder_type(cx))
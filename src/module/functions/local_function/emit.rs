@@ -221,10 +221,10 @@ impl<'instr> Visitor<'instr> for Emit<'_, 'instr> {
                         // Check if this catch matches the current block we're ending
                         match catch {
                             LegacyCatch::Catch { handler, .. }
-                            | LegacyCatch::CatchAll { handler } => {
-                                if handler == seq.id() {
-                                    found_current = true;
-                                }
+                            | LegacyCatch::CatchAll { handler }
+                                if handler == seq.id() =>
+                            {
+                                found_current = true;
                             }
                             _ => {}
                         }
@@ -781,7 +781,7 @@ impl<'instr> Visitor<'instr> for Emit<'_, 'instr> {
             }
 
             Select(e) => match e.ty {
-                Some(ty) => Instruction::TypedSelect(ty.to_wasmencoder_type()),
+                Some(ty) => Instruction::TypedSelect(ty.to_wasmencoder_type(self.indices)),
                 None => Instruction::Select,
             },
 
@@ -965,7 +965,9 @@ impl<'instr> Visitor<'instr> for Emit<'_, 'instr> {
             TableGrow(e) => Instruction::TableGrow(self.indices.get_table_index(e.table)),
             TableSize(e) => Instruction::TableSize(self.indices.get_table_index(e.table)),
             TableFill(e) => Instruction::TableFill(self.indices.get_table_index(e.table)),
-            RefNull(e) => Instruction::RefNull(e.ty.heap_type.to_wasmencoder_heap_type()),
+            RefNull(e) => {
+                Instruction::RefNull(e.ty.heap_type.to_wasmencoder_heap_type(self.indices))
+            }
             RefIsNull(_) => Instruction::RefIsNull,
             RefFunc(e) => Instruction::RefFunc(self.indices.get_func_index(e.func)),
             RefAsNonNull(_) => Instruction::RefAsNonNull,
@@ -1057,7 +1059,7 @@ impl<'instr> Visitor<'instr> for Emit<'_, 'instr> {
             I31GetS(_) => Instruction::I31GetS,
             I31GetU(_) => Instruction::I31GetU,
             RefTest(e) => {
-                let heap_type = e.heap_type.to_wasmencoder_heap_type();
+                let heap_type = e.heap_type.to_wasmencoder_heap_type(self.indices);
                 if e.nullable {
                     Instruction::RefTestNullable(heap_type)
                 } else {
@@ -1065,7 +1067,7 @@ impl<'instr> Visitor<'instr> for Emit<'_, 'instr> {
                 }
             }
             RefCast(e) => {
-                let heap_type = e.heap_type.to_wasmencoder_heap_type();
+                let heap_type = e.heap_type.to_wasmencoder_heap_type(self.indices);
                 if e.nullable {
                     Instruction::RefCastNullable(heap_type)
                 } else {
@@ -1078,11 +1080,11 @@ impl<'instr> Visitor<'instr> for Emit<'_, 'instr> {
                     relative_depth,
                     from_ref_type: wasm_encoder::RefType {
                         nullable: e.from_nullable,
-                        heap_type: e.from_heap_type.to_wasmencoder_heap_type(),
+                        heap_type: e.from_heap_type.to_wasmencoder_heap_type(self.indices),
                     },
                     to_ref_type: wasm_encoder::RefType {
                         nullable: e.to_nullable,
-                        heap_type: e.to_heap_type.to_wasmencoder_heap_type(),
+                        heap_type: e.to_heap_type.to_wasmencoder_heap_type(self.indices),
                     },
                 }
             }
@@ -1092,21 +1094,119 @@ impl<'instr> Visitor<'instr> for Emit<'_, 'instr> {
                     relative_depth,
                     from_ref_type: wasm_encoder::RefType {
                         nullable: e.from_nullable,
-                        heap_type: e.from_heap_type.to_wasmencoder_heap_type(),
+                        heap_type: e.from_heap_type.to_wasmencoder_heap_type(self.indices),
                     },
                     to_ref_type: wasm_encoder::RefType {
                         nullable: e.to_nullable,
-                        heap_type: e.to_heap_type.to_wasmencoder_heap_type(),
+                        heap_type: e.to_heap_type.to_wasmencoder_heap_type(self.indices),
                     },
                 }
             }
             AnyConvertExtern(_) => Instruction::AnyConvertExtern,
             ExternConvertAny(_) => Instruction::ExternConvertAny,
+            RefEq(_) => Instruction::RefEq,
+
+            StructNew(e) => Instruction::StructNew(self.indices.get_type_index(e.ty)),
+            StructNewDefault(e) => Instruction::StructNewDefault(self.indices.get_type_index(e.ty)),
+            StructGet(e) => Instruction::StructGet {
+                struct_type_index: self.indices.get_type_index(e.ty),
+                field_index: e.field,
+            },
+            StructGetS(e) => Instruction::StructGetS {
+                struct_type_index: self.indices.get_type_index(e.ty),
+                field_index: e.field,
+            },
+            StructGetU(e) => Instruction::StructGetU {
+                struct_type_index: self.indices.get_type_index(e.ty),
+                field_index: e.field,
+            },
+            StructSet(e) => Instruction::StructSet {
+                struct_type_index: self.indices.get_type_index(e.ty),
+                field_index: e.field,
+            },
+
+            ArrayNew(e) => Instruction::ArrayNew(self.indices.get_type_index(e.ty)),
+            ArrayNewDefault(e) => Instruction::ArrayNewDefault(self.indices.get_type_index(e.ty)),
+            ArrayNewFixed(e) => Instruction::ArrayNewFixed {
+                array_type_index: self.indices.get_type_index(e.ty),
+                array_size: e.len,
+            },
+            ArrayNewData(e) => Instruction::ArrayNewData {
+                array_type_index: self.indices.get_type_index(e.ty),
+                array_data_index: self.indices.get_data_index(e.data),
+            },
+            ArrayNewElem(e) => Instruction::ArrayNewElem {
+                array_type_index: self.indices.get_type_index(e.ty),
+                array_elem_index: self.indices.get_element_index(e.elem),
+            },
+            ArrayGet(e) => Instruction::ArrayGet(self.indices.get_type_index(e.ty)),
+            ArrayGetS(e) => Instruction::ArrayGetS(self.indices.get_type_index(e.ty)),
+            ArrayGetU(e) => Instruction::ArrayGetU(self.indices.get_type_index(e.ty)),
+            ArraySet(e) => Instruction::ArraySet(self.indices.get_type_index(e.ty)),
+            ArrayLen(_) => Instruction::ArrayLen,
+            ArrayFill(e) => Instruction::ArrayFill(self.indices.get_type_index(e.ty)),
+            ArrayCopy(e) => Instruction::ArrayCopy {
+                array_type_index_dst: self.indices.get_type_index(e.dst),
+                array_type_index_src: self.indices.get_type_index(e.src),
+            },
+            ArrayInitData(e) => Instruction::ArrayInitData {
+                array_type_index: self.indices.get_type_index(e.ty),
+                array_data_index: self.indices.get_data_index(e.data),
+            },
+            ArrayInitElem(e) => Instruction::ArrayInitElem {
+                array_type_index: self.indices.get_type_index(e.ty),
+                array_elem_index: self.indices.get_element_index(e.elem),
+            },
+
+            // Stack switching instructions
+            ContNew(e) => Instruction::ContNew(self.indices.get_type_index(e.ty)),
+            ContBind(e) => Instruction::ContBind {
+                argument_index: self.indices.get_type_index(e.from),
+                result_index: self.indices.get_type_index(e.to),
+            },
+            Suspend(e) => Instruction::Suspend(self.indices.get_tag_index(e.tag)),
+            Resume(e) => Instruction::Resume {
+                cont_type_index: self.indices.get_type_index(e.ty),
+                resume_table: self.resume_table(&e.handlers).into(),
+            },
+            ResumeThrow(e) => Instruction::ResumeThrow {
+                cont_type_index: self.indices.get_type_index(e.ty),
+                tag_index: self.indices.get_tag_index(e.tag),
+                resume_table: self.resume_table(&e.handlers).into(),
+            },
+            ResumeThrowRef(e) => Instruction::ResumeThrowRef {
+                cont_type_index: self.indices.get_type_index(e.ty),
+                resume_table: self.resume_table(&e.handlers).into(),
+            },
+            Switch(e) => Instruction::Switch {
+                cont_type_index: self.indices.get_type_index(e.ty),
+                tag_index: self.indices.get_tag_index(e.tag),
+            },
+
+            I64Add128(_) => Instruction::I64Add128,
+            I64Sub128(_) => Instruction::I64Sub128,
+            I64MulWideS(_) => Instruction::I64MulWideS,
+            I64MulWideU(_) => Instruction::I64MulWideU,
         });
     }
 }
 
 impl Emit<'_, '_> {
+    fn resume_table(&self, handlers: &[ResumeHandler]) -> Vec<wasm_encoder::Handle> {
+        handlers
+            .iter()
+            .map(|handler| match handler {
+                ResumeHandler::OnLabel { tag, label } => wasm_encoder::Handle::OnLabel {
+                    tag: self.indices.get_tag_index(*tag),
+                    label: self.branch_target(*label),
+                },
+                ResumeHandler::OnSwitch { tag } => wasm_encoder::Handle::OnSwitch {
+                    tag: self.indices.get_tag_index(*tag),
+                },
+            })
+            .collect()
+    }
+
     fn branch_target(&self, block: InstrSeqId) -> u32 {
         self.blocks.iter().rev().position(|b| *b == block).expect(
             "attempt to branch to invalid block; bad transformation pass introduced bad branching?",
@@ -1117,7 +1217,7 @@ impl Emit<'_, '_> {
         match ty {
             InstrSeqType::Simple(None) => wasm_encoder::BlockType::Empty,
             InstrSeqType::Simple(Some(ty)) => {
-                wasm_encoder::BlockType::Result(ty.to_wasmencoder_type())
+                wasm_encoder::BlockType::Result(ty.to_wasmencoder_type(self.indices))
             }
             InstrSeqType::MultiValue(ty) => {
                 wasm_encoder::BlockType::FunctionType(self.indices.get_type_index(ty))
@@ -3,8 +3,9 @@
 mod context;
 mod emit;
 
-use self::context::ValidationContext;
+use self::context::{ControlFrame, ValidationContext};
 use crate::emit::IdsToIndices;
+use crate::error::{locate, Proposal, Unlocated};
 use crate::map::{IdHashMap, IdHashSet};
 use crate::parse::IndicesToIds;
 use crate::{ir::*, HeapType, RefType};
@@ -99,8 +100,21 @@ impl LocalFunction {
             } else {
                 InstrLocId::new(pos as u32)
             };
+            // The validator rejects the wide arithmetic instructions unless
+            // they are enabled, so report them as unsupported before that.
+            if !ctx.module.config.wide_arithmetic
+                && matches!(
+                    inst,
+                    Operator::I64Add128
+                        | Operator::I64Sub128
+                        | Operator::I64MulWideS
+                        | Operator::I64MulWideU
+                )
+            {
+                return Err(locate(Unlocated(Proposal::WideArithmetic).into(), pos));
+            }
             validator.op(pos, &inst)?;
-            append_instruction(&mut ctx, inst, loc);
+            append_instruction(&mut ctx, inst, loc).map_err(|e| locate(e, pos))?;
             instruction_mapping.insert(pos - code_address_offset, loc);
         }
         ctx.func.instruction_mapping = instruction_mapping.into_iter().collect();
@@ -197,7 +211,7 @@ impl LocalFunction {
         }
     }
 
-    fn used_locals(&self) -> IdHashSet<Local> {
+    pub(crate) fn used_locals(&self) -> IdHashSet<Local> {
         let mut locals = Used::default();
         dfs_in_order(&mut locals, self, self.entry_block());
         return locals.locals;
@@ -214,8 +228,41 @@ impl LocalFunction {
         }
     }
 
+    /// The position of every instruction and every instruction sequence's
+    /// `end`, in the order that they are emitted.
+    ///
+    /// This lines up with the map filled in by `emit_instructions`.
+    pub(crate) fn emitted_positions(&self) -> Vec<(InstrSeqId, usize)> {
+        let mut v = Positions::default();
+        dfs_in_order(&mut v, self, self.entry_block());
+        return v.positions;
+
+        #[derive(Default)]
+        struct Positions {
+            positions: Vec<(InstrSeqId, usize)>,
+            stack: Vec<(InstrSeqId, usize)>,
+        }
+
+        impl<'a> Visitor<'a> for Positions {
+            fn start_instr_seq(&mut self, seq: &'a InstrSeq) {
+                self.stack.push((seq.id(), 0));
+            }
+
+            fn visit_instr(&mut self, _: &'a Instr, _: &'a InstrLocId) {
+                let top = self.stack.last_mut().unwrap();
+                self.positions.push(*top);
+                top.1 += 1;
+            }
+
+            fn end_instr_seq(&mut self, seq: &'a InstrSeq) {
+                self.stack.pop();
+                self.positions.push((seq.id(), seq.len()));
+            }
+        }
+    }
+
     /// Emit this function's compact locals declarations.
-    pub(crate) fn emit_locals(&self, module: &Module) -> EmitLocalsResult {
+    pub(crate) fn emit_locals(&self, module: &Module, indices: &IdsToIndices) -> EmitLocalsResult {
         let used_set = self.used_locals();
         let mut used_locals = used_set.iter().cloned().collect::<Vec<_>>();
         // Sort to ensure we assign local indexes deterministically, and
@@ -260,7 +307,7 @@ impl LocalFunction {
         (
             ty_to_locals
                 .iter()
-                .map(|(ty, locals)| (locals.len() as u32, ty.to_wasmencoder_type()))
+                .map(|(ty, locals)| (locals.len() as u32, ty.to_wasmencoder_type(indices)))
                 .collect(),
             used_set,
             local_map,
@@ -281,7 +328,7 @@ impl LocalFunction {
 
 fn block_result_tys(ctx: &ValidationContext, ty: wasmparser::BlockType) -> Result<Box<[ValType]>> {
     match ty {
-        wasmparser::BlockType::Type(ty) => ValType::from_wasmparser_type(ty),
+        wasmparser::BlockType::Type(ty) => ValType::from_wasmparser_type(ty, ctx.indices),
         wasmparser::BlockType::FuncType(idx) => {
             let ty = ctx.indices.get_type(idx)?;
             Ok(ctx.module.types.results(ty).into())
@@ -301,10 +348,64 @@ fn block_param_tys(ctx: &ValidationContext, ty: wasmparser::BlockType) -> Result
     }
 }
 
-fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLocId) {
+/// Pushes the control frame of a block, loop, if or try with the given block
+/// type. Blocks that refer to a function type keep that exact type, even if
+/// it is in an explicit recursion group or can be subtyped.
+fn push_block_control(
+    ctx: &mut ValidationContext,
+    kind: BlockKind,
+    ty: wasmparser::BlockType,
+) -> Result<InstrSeqId> {
+    if let wasmparser::BlockType::FuncType(idx) = ty {
+        let ty = ctx.indices.get_type(idx)?;
+        return Ok(ctx.push_control_with_ty(kind, ty));
+    }
+    let param_tys = block_param_tys(ctx, ty)?;
+    let result_tys = block_result_tys(ctx, ty)?;
+    ctx.push_control(kind, param_tys, result_tys)
+}
+
+/// Pushes the control frame of the `else` arm of an if/else, which has the
+/// same type as its `consequent` arm.
+fn push_else_control(
+    ctx: &mut ValidationContext,
+    consequent: InstrSeqId,
+    frame: &ControlFrame,
+) -> Result<InstrSeqId> {
+    match ctx.func.block(consequent).ty {
+        InstrSeqType::MultiValue(ty) => Ok(ctx.push_control_with_ty(BlockKind::Else, ty)),
+        InstrSeqType::Simple(_) => ctx.push_control(
+            BlockKind::Else,
+            frame.start_types.clone(),
+            frame.end_types.clone(),
+        ),
+    }
+}
+
+fn resume_handlers(
+    ctx: &ValidationContext,
+    resume_table: &wasmparser::ResumeTable,
+) -> Vec<ResumeHandler> {
+    resume_table
+        .handlers
+        .iter()
+        .map(|handle| match *handle {
+            wasmparser::Handle::OnLabel { tag, label } => ResumeHandler::OnLabel {
+                tag: ctx.indices.get_tag(tag).unwrap(),
+                label: ctx.control(label as usize).unwrap().block,
+            },
+            wasmparser::Handle::OnSwitch { tag } => ResumeHandler::OnSwitch {
+                tag: ctx.indices.get_tag(tag).unwrap(),
+            },
+        })
+        .collect()
+}
+
+fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLocId) -> Result<()> {
     // NB. there's a lot of `unwrap()` here in this function, and that's because
     // the `Operator` was validated above to already be valid, so everything
-    // should succeed.
+    // should succeed. The exception is valid operators and types from
+    // proposals that walrus can't represent, which are reported as errors.
     use crate::ir::ExtendedLoad::*;
 
     log::trace!("validate instruction: {:?}", inst);
@@ -551,7 +652,7 @@ fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLoc
         Operator::Drop => ctx.alloc_instr(Drop {}, loc),
         Operator::Select => ctx.alloc_instr(Select { ty: None }, loc),
         Operator::TypedSelect { ty } => {
-            let ty = ValType::parse(&ty).unwrap();
+            let ty = ValType::parse(&ty, ctx.indices)?;
             ctx.alloc_instr(Select { ty: Some(ty) }, loc);
         }
         Operator::Return => {
@@ -563,28 +664,15 @@ fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLoc
             ctx.unreachable();
         }
         Operator::Block { blockty } => {
-            let param_tys = block_param_tys(ctx, blockty).unwrap();
-            let result_tys = block_result_tys(ctx, blockty).unwrap();
-            let seq = ctx
-                .push_control(BlockKind::Block, param_tys, result_tys)
-                .unwrap();
+            let seq = push_block_control(ctx, BlockKind::Block, blockty)?;
             ctx.alloc_instr_in_control(1, Block { seq }, loc).unwrap();
         }
         Operator::Loop { blockty } => {
-            let result_tys = block_result_tys(ctx, blockty).unwrap();
-            let param_tys = block_param_tys(ctx, blockty).unwrap();
-            let seq = ctx
-                .push_control(BlockKind::Loop, param_tys, result_tys)
-                .unwrap();
+            let seq = push_block_control(ctx, BlockKind::Loop, blockty)?;
             ctx.alloc_instr_in_control(1, Loop { seq }, loc).unwrap();
         }
         Operator::If { blockty } => {
-            let result_tys = block_result_tys(ctx, blockty).unwrap();
-            let param_tys = block_param_tys(ctx, blockty).unwrap();
-
-            let consequent = ctx
-                .push_control(BlockKind::If, param_tys, result_tys)
-                .unwrap();
+            let consequent = push_block_control(ctx, BlockKind::If, blockty)?;
             ctx.if_else.push(context::IfElseState {
                 start: loc,
                 consequent,
@@ -615,13 +703,7 @@ fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLoc
                         }
                         None => {
                             debug_assert_eq!(frame.kind, BlockKind::If);
-                            let alternative = ctx
-                                .push_control(
-                                    BlockKind::Else,
-                                    frame.start_types.clone(),
-                                    frame.end_types.clone(),
-                                )
-                                .unwrap();
+                            let alternative = push_else_control(ctx, consequent, &frame)?;
                             ctx.pop_control().unwrap();
                             alternative
                         }
@@ -651,9 +733,7 @@ fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLoc
 
             // But we still need to parse the alternative block, so allocate the
             // block here to parse.
-            let alternative = ctx
-                .push_control(BlockKind::Else, frame.start_types, frame.end_types)
-                .unwrap();
+            let alternative = push_else_control(ctx, consequent, &frame)?;
             let last = ctx.if_else.last_mut().unwrap();
             if last.alternative.is_some() {
                 panic!("`else` without a leading `if`")
@@ -997,7 +1077,7 @@ fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLoc
             ctx.alloc_instr(TableFill { table }, loc);
         }
         Operator::RefNull { hty } => {
-            let heap_type = HeapType::try_from(hty).expect("unsupported heap type for ref.null");
+            let heap_type = HeapType::parse(hty, ctx.indices)?;
             let ty = RefType {
                 nullable: true,
                 heap_type,
@@ -1044,7 +1124,7 @@ fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLoc
             ctx.alloc_instr(I31GetU {}, loc);
         }
         Operator::RefTestNonNull { hty } => {
-            let heap_type = HeapType::try_from(hty).expect("unsupported heap type for ref.test");
+            let heap_type = HeapType::parse(hty, ctx.indices)?;
             ctx.alloc_instr(
                 RefTest {
                     nullable: false,
@@ -1054,7 +1134,7 @@ fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLoc
             );
         }
         Operator::RefTestNullable { hty } => {
-            let heap_type = HeapType::try_from(hty).expect("unsupported heap type for ref.test");
+            let heap_type = HeapType::parse(hty, ctx.indices)?;
             ctx.alloc_instr(
                 RefTest {
                     nullable: true,
@@ -1064,7 +1144,7 @@ fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLoc
             );
         }
         Operator::RefCastNonNull { hty } => {
-            let heap_type = HeapType::try_from(hty).expect("unsupported heap type for ref.cast");
+            let heap_type = HeapType::parse(hty, ctx.indices)?;
             ctx.alloc_instr(
                 RefCast {
                     nullable: false,
@@ -1074,7 +1154,7 @@ fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLoc
             );
         }
         Operator::RefCastNullable { hty } => {
-            let heap_type = HeapType::try_from(hty).expect("unsupported heap type for ref.cast");
+            let heap_type = HeapType::parse(hty, ctx.indices)?;
             ctx.alloc_instr(
                 RefCast {
                     nullable: true,
@@ -1090,10 +1170,8 @@ fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLoc
         } => {
             let n = relative_depth as usize;
             let block = ctx.control(n).unwrap().block;
-            let from_heap_type =
-                HeapType::try_from(from_ref_type.heap_type()).expect("unsupported from heap type");
-            let to_heap_type =
-                HeapType::try_from(to_ref_type.heap_type()).expect("unsupported to heap type");
+            let from_heap_type = HeapType::parse(from_ref_type.heap_type(), ctx.indices)?;
+            let to_heap_type = HeapType::parse(to_ref_type.heap_type(), ctx.indices)?;
             ctx.alloc_instr(
                 BrOnCast {
                     block,
@@ -1112,10 +1190,8 @@ fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLoc
         } => {
             let n = relative_depth as usize;
             let block = ctx.control(n).unwrap().block;
-            let from_heap_type =
-                HeapType::try_from(from_ref_type.heap_type()).expect("unsupported from heap type");
-            let to_heap_type =
-                HeapType::try_from(to_ref_type.heap_type()).expect("unsupported to heap type");
+            let from_heap_type = HeapType::parse(from_ref_type.heap_type(), ctx.indices)?;
+            let to_heap_type = HeapType::parse(to_ref_type.heap_type(), ctx.indices)?;
             ctx.alloc_instr(
                 BrOnCastFail {
                     block,
@@ -1133,6 +1209,130 @@ fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLoc
         Operator::ExternConvertAny => {
             ctx.alloc_instr(ExternConvertAny {}, loc);
         }
+        Operator::RefEq => {
+            ctx.alloc_instr(RefEq {}, loc);
+        }
+
+        Operator::StructNew { struct_type_index } => {
+            let ty = ctx.indices.get_type(struct_type_index).unwrap();
+            ctx.alloc_instr(StructNew { ty }, loc);
+        }
+        Operator::StructNewDefault { struct_type_index } => {
+            let ty = ctx.indices.get_type(struct_type_index).unwrap();
+            ctx.alloc_instr(StructNewDefault { ty }, loc);
+        }
+        Operator::StructGet {
+            struct_type_index,
+            field_index,
+        } => {
+            let ty = ctx.indices.get_type(struct_type_index).unwrap();
+            let field = field_index;
+            ctx.alloc_instr(StructGet { ty, field }, loc);
+        }
+        Operator::StructGetS {
+            struct_type_index,
+            field_index,
+        } => {
+            let ty = ctx.indices.get_type(struct_type_index).unwrap();
+            let field = field_index;
+            ctx.alloc_instr(StructGetS { ty, field }, loc);
+        }
+        Operator::StructGetU {
+            struct_type_index,
+            field_index,
+        } => {
+            let ty = ctx.indices.get_type(struct_type_index).unwrap();
+            let field = field_index;
+            ctx.alloc_instr(StructGetU { ty, field }, loc);
+        }
+        Operator::StructSet {
+            struct_type_index,
+            field_index,
+        } => {
+            let ty = ctx.indices.get_type(struct_type_index).unwrap();
+            let field = field_index;
+            ctx.alloc_instr(StructSet { ty, field }, loc);
+        }
+
+        Operator::ArrayNew { array_type_index } => {
+            let ty = ctx.indices.get_type(array_type_index).unwrap();
+            ctx.alloc_instr(ArrayNew { ty }, loc);
+        }
+        Operator::ArrayNewDefault { array_type_index } => {
+            let ty = ctx.indices.get_type(array_type_index).unwrap();
+            ctx.alloc_instr(ArrayNewDefault { ty }, loc);
+        }
+        Operator::ArrayNewFixed {
+            array_type_index,
+            array_size,
+        } => {
+            let ty = ctx.indices.get_type(array_type_index).unwrap();
+            let len = array_size;
+            ctx.alloc_instr(ArrayNewFixed { ty, len }, loc);
+        }
+        Operator::ArrayNewData {
+            array_type_index,
+            array_data_index,
+        } => {
+            let ty = ctx.indices.get_type(array_type_index).unwrap();
+            let data = ctx.indices.get_data(array_data_index).unwrap();
+            ctx.alloc_instr(ArrayNewData { ty, data }, loc);
+        }
+        Operator::ArrayNewElem {
+            array_type_index,
+            array_elem_index,
+        } => {
+            let ty = ctx.indices.get_type(array_type_index).unwrap();
+            let elem = ctx.indices.get_element(array_elem_index).unwrap();
+            ctx.alloc_instr(ArrayNewElem { ty, elem }, loc);
+        }
+        Operator::ArrayGet { array_type_index } => {
+            let ty = ctx.indices.get_type(array_type_index).unwrap();
+            ctx.alloc_instr(ArrayGet { ty }, loc);
+        }
+        Operator::ArrayGetS { array_type_index } => {
+            let ty = ctx.indices.get_type(array_type_index).unwrap();
+            ctx.alloc_instr(ArrayGetS { ty }, loc);
+        }
+        Operator::ArrayGetU { array_type_index } => {
+            let ty = ctx.indices.get_type(array_type_index).unwrap();
+            ctx.alloc_instr(ArrayGetU { ty }, loc);
+        }
+        Operator::ArraySet { array_type_index } => {
+            let ty = ctx.indices.get_type(array_type_index).unwrap();
+            ctx.alloc_instr(ArraySet { ty }, loc);
+        }
+        Operator::ArrayLen => {
+            ctx.alloc_instr(ArrayLen {}, loc);
+        }
+        Operator::ArrayFill { array_type_index } => {
+            let ty = ctx.indices.get_type(array_type_index).unwrap();
+            ctx.alloc_instr(ArrayFill { ty }, loc);
+        }
+        Operator::ArrayCopy {
+            array_type_index_dst,
+            array_type_index_src,
+        } => {
+            let dst = ctx.indices.get_type(array_type_index_dst).unwrap();
+            let src = ctx.indices.get_type(array_type_index_src).unwrap();
+            ctx.alloc_instr(ArrayCopy { dst, src }, loc);
+        }
+        Operator::ArrayInitData {
+            array_type_index,
+            array_data_index,
+        } => {
+            let ty = ctx.indices.get_type(array_type_index).unwrap();
+            let data = ctx.indices.get_data(array_data_index).unwrap();
+            ctx.alloc_instr(ArrayInitData { ty, data }, loc);
+        }
+        Operator::ArrayInitElem {
+            array_type_index,
+            array_elem_index,
+        } => {
+            let ty = ctx.indices.get_type(array_type_index).unwrap();
+            let elem = ctx.indices.get_element(array_elem_index).unwrap();
+            ctx.alloc_instr(ArrayInitElem { ty, elem }, loc);
+        }
 
         Operator::I8x16Swizzle => {
             ctx.alloc_instr(I8x16Swizzle {}, loc);
@@ -1484,9 +1684,6 @@ fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLoc
             ctx.unreachable();
         }
         Operator::TryTable { try_table } => {
-            let param_tys = block_param_tys(ctx, try_table.ty).unwrap();
-            let result_tys = block_result_tys(ctx, try_table.ty).unwrap();
-
             // Parse the catch clauses
             let mut catches = Vec::new();
             for catch in try_table.catches.iter() {
@@ -1519,20 +1716,14 @@ fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLoc
                 catches.push(catch_clause);
             }
 
-            let seq = ctx
-                .push_control(BlockKind::TryTable, param_tys, result_tys)
-                .unwrap();
+            let seq = push_block_control(ctx, BlockKind::TryTable, try_table.ty)?;
             ctx.alloc_instr_in_control(1, TryTable { seq, catches }, loc)
                 .unwrap();
         }
 
         // Legacy exception handling (phase 1 proposal)
         Operator::Try { blockty } => {
-            let param_tys = block_param_tys(ctx, blockty).unwrap();
-            let result_tys = block_result_tys(ctx, blockty).unwrap();
-            let seq = ctx
-                .push_control(BlockKind::Try, param_tys, result_tys)
-                .unwrap();
+            let seq = push_block_control(ctx, BlockKind::Try, blockty)?;
             ctx.alloc_instr_in_control(
                 1,
                 Try {
@@ -1596,77 +1787,10 @@ fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLoc
 
         // List all unimplmented operators instead of have a catch-all arm.
         // So that future upgrades won't miss additions to this list that may be important to know.
-        Operator::RefEq
-        | Operator::StructNew {
-            struct_type_index: _,
-        }
-        | Operator::StructNewDefault {
-            struct_type_index: _,
-        }
-        | Operator::StructGet {
-            struct_type_index: _,
-            field_index: _,
-        }
-        | Operator::StructGetS {
-            struct_type_index: _,
-            field_index: _,
-        }
-        | Operator::StructGetU {
-            struct_type_index: _,
-            field_index: _,
-        }
-        | Operator::StructSet {
-            struct_type_index: _,
-            field_index: _,
-        }
-        | Operator::ArrayNew {
-            array_type_index: _,
-        }
-        | Operator::ArrayNewDefault {
-            array_type_index: _,
-        }
-        | Operator::ArrayNewFixed {
-            array_type_index: _,
-            array_size: _,
-        }
-        | Operator::ArrayNewData {
-            array_type_index: _,
-            array_data_index: _,
-        }
-        | Operator::ArrayNewElem {
-            array_type_index: _,
-            array_elem_index: _,
-        }
-        | Operator::ArrayGet {
-            array_type_index: _,
-        }
-        | Operator::ArrayGetS {
-            array_type_index: _,
-        }
-        | Operator::ArrayGetU {
-            array_type_index: _,
-        }
-        | Operator::ArraySet {
-            array_type_index: _,
+        Operator::MemoryDiscard { mem: _ } => {
+            return Err(Unlocated(Proposal::MemoryControl).into());
         }
-        | Operator::ArrayLen
-        | Operator::ArrayFill {
-            array_type_index: _,
-        }
-        | Operator::ArrayCopy {
-            array_type_index_dst: _,
-            array_type_index_src: _,
-        }
-        | Operator::ArrayInitData {
-            array_type_index: _,
-            array_data_index: _,
-        }
-        | Operator::ArrayInitElem {
-            array_type_index: _,
-            array_elem_index: _,
-        }
-        | Operator::MemoryDiscard { mem: _ }
-        | Operator::GlobalAtomicGet {
+        Operator::GlobalAtomicGet {
             ordering: _,
             global_index: _,
         }
@@ -1817,32 +1941,96 @@ fn append_instruction(ctx: &mut ValidationContext, inst: Operator, loc: InstrLoc
             ordering: _,
             array_type_index: _,
         }
-        | Operator::RefI31Shared
-        | Operator::ContNew { cont_type_index: _ }
-        | Operator::ContBind {
-            argument_index: _,
-            result_index: _,
-        }
-        | Operator::Suspend { tag_index: _ }
-        | Operator::Resume {
-            cont_type_index: _,
-            resume_table: _,
-        }
-        | Operator::ResumeThrow {
-            cont_type_index: _,
-            tag_index: _,
-            resume_table: _,
-        }
-        | Operator::Switch {
-            cont_type_index: _,
-            tag_index: _,
-        }
-        | Operator::I64Add128
-        | Operator::I64Sub128
-        | Operator::I64MulWideS
-        | Operator::I64MulWideU
-        | _ => {
-            unimplemented!("unsupported operator: {:?}", inst)
+        | Operator::RefI31Shared => {
+            return Err(Unlocated(Proposal::SharedEverythingThreads).into());
+        }
+        Operator::ContNew { cont_type_index } => {
+            let ty = ctx.indices.get_type(cont_type_index).unwrap();
+            ctx.alloc_instr(ContNew { ty }, loc);
+        }
+        Operator::ContBind {
+            argument_index,
+            result_index,
+        } => {
+            let from = ctx.indices.get_type(argument_index).unwrap();
+            let to = ctx.indices.get_type(result_index).unwrap();
+            ctx.alloc_instr(ContBind { from, to }, loc);
+        }
+        Operator::Suspend { tag_index } => {
+            let tag = ctx.indices.get_tag(tag_index).unwrap();
+            ctx.alloc_instr(Suspend { tag }, loc);
+        }
+        Operator::Resume {
+            cont_type_index,
+            resume_table,
+        } => {
+            let ty = ctx.indices.get_type(cont_type_index).unwrap();
+            let handlers = resume_handlers(ctx, &resume_table);
+            ctx.alloc_instr(Resume { ty, handlers }, loc);
+        }
+        Operator::ResumeThrow {
+            cont_type_index,
+            tag_index,
+            resume_table,
+        } => {
+            let ty = ctx.indices.get_type(cont_type_index).unwrap();
+            let tag = ctx.indices.get_tag(tag_index).unwrap();
+            let handlers = resume_handlers(ctx, &resume_table);
+            ctx.alloc_instr(ResumeThrow { ty, tag, handlers }, loc);
+        }
+        Operator::ResumeThrowRef {
+            cont_type_index,
+            resume_table,
+        } => {
+            let ty = ctx.indices.get_type(cont_type_index).unwrap();
+            let handlers = resume_handlers(ctx, &resume_table);
+            ctx.alloc_instr(ResumeThrowRef { ty, handlers }, loc);
+        }
+        Operator::Switch {
+            cont_type_index,
+            tag_index,
+        } => {
+            let ty = ctx.indices.get_type(cont_type_index).unwrap();
+            let tag = ctx.indices.get_tag(tag_index).unwrap();
+            ctx.alloc_instr(Switch { ty, tag }, loc);
+        }
+        Operator::I64Add128 => {
+            ctx.alloc_instr(I64Add128 {}, loc);
+        }
+        Operator::I64Sub128 => {
+            ctx.alloc_instr(I64Sub128 {}, loc);
+        }
+        Operator::I64MulWideS => {
+            ctx.alloc_instr(I64MulWideS {}, loc);
+        }
+        Operator::I64MulWideU => {
+            ctx.alloc_instr(I64MulWideU {}, loc);
+        }
+        Operator::StructNewDesc {
+            struct_type_index: _,
+        }
+        | Operator::StructNewDefaultDesc {
+            struct_type_index: _,
+        }
+        | Operator::RefGetDesc { type_index: _ }
+        | Operator::RefCastDescEqNullable { hty: _ }
+        | Operator::RefCastDescEqNonNull { hty: _ }
+        | Operator::BrOnCastDescEq {
+            relative_depth: _,
+            from_ref_type: _,
+            to_ref_type: _,
+        }
+        | Operator::BrOnCastDescEqFail {
+            relative_depth: _,
+            from_ref_type: _,
+            to_ref_type: _,
+        } => {
+            return Err(Unlocated(Proposal::CustomDescriptors).into());
+        }
+        _ => {
+            return Err(Unlocated(Proposal::Unknown).into());
         }
     }
+
+    Ok(())
 }
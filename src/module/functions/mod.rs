@@ -14,7 +14,7 @@ use rayon::prelude::*;
 mod local_function;
 
 use crate::emit::{Emit, EmitContext};
-use crate::error::Result;
+use crate::error::{locate, Result};
 use crate::ir::InstrLocId;
 use crate::module::imports::ImportId;
 use crate::module::Module;
@@ -186,6 +186,12 @@ impl ModuleFunctions {
         &self.arena[id]
     }
 
+    /// Does this module contain a function with the given id, that hasn't
+    /// been deleted?
+    pub(crate) fn contains(&self, id: FunctionId) -> bool {
+        self.arena.contains(id)
+    }
+
     /// Gets a reference to a function given its id
     pub fn get_mut(&mut self, id: FunctionId) -> &mut Function {
         &mut self.arena[id]
@@ -385,7 +391,7 @@ impl Module {
                 let pos = locals_reader.original_position();
                 let (count, ty) = locals_reader.read()?;
                 validator.define_locals(pos, count, ty)?;
-                let ty = ValType::parse(&ty)?;
+                let ty = ValType::parse(&ty, indices).map_err(|e| locate(e, pos))?;
                 for _ in 0..count {
                     let local_id = self.locals.add(ty);
                     let idx = indices.push_local(id, local_id);
@@ -620,7 +626,8 @@ impl Emit for ModuleFunctions {
                 let mut wasm = Vec::new();
                 let mut map = if generate_map { Some(Vec::new()) } else { None };
 
-                let (locals_types, used_locals, local_indices) = func.emit_locals(cx.module);
+                let (locals_types, used_locals, local_indices) =
+                    func.emit_locals(cx.module, cx.indices);
                 let mut wasm_function = wasm_encoder::Function::new(locals_types);
                 func.emit_instructions(
                     cx.indices,
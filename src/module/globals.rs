@@ -1,5 +1,6 @@
 //! Globals within a wasm module.
 use crate::emit::{Emit, EmitContext};
+use crate::error::locate;
 use crate::parse::IndicesToIds;
 use crate::tombstone_arena::{Id, Tombstone, TombstoneArena};
 use crate::{ConstExpr, ImportId, Module, Result, ValType};
@@ -94,6 +95,12 @@ impl ModuleGlobals {
         &self.arena[id]
     }
 
+    /// Does this module contain a global with the given id, that hasn't
+    /// been deleted?
+    pub(crate) fn contains(&self, id: GlobalId) -> bool {
+        self.arena.contains(id)
+    }
+
     /// Gets a reference to a global given its id
     pub fn get_mut(&mut self, id: GlobalId) -> &mut Global {
         &mut self.arena[id]
@@ -121,11 +128,11 @@ impl Module {
         ids: &mut IndicesToIds,
     ) -> Result<()> {
         log::debug!("parse global section");
-        for g in section {
-            let g = g?;
+        for g in section.into_iter_with_offsets() {
+            let (offset, g) = g?;
             let init_expr = ConstExpr::eval(&g.init_expr, ids)?;
             let id = self.globals.add_local(
-                ValType::parse(&g.ty.content_type)?,
+                ValType::parse(&g.ty.content_type, ids).map_err(|e| locate(e, offset))?,
                 g.ty.mutable,
                 g.ty.shared,
                 init_expr,
@@ -160,7 +167,7 @@ impl Emit for ModuleGlobals {
 
             wasm_global_section.global(
                 wasm_encoder::GlobalType {
-                    val_type: global.ty.to_wasmencoder_type(),
+                    val_type: global.ty.to_wasmencoder_type(cx.indices),
                     mutable: global.mutable,
                     shared: global.shared,
                 },
@@ -1,10 +1,9 @@
 //! A wasm module's imports.
 
-use std::convert::TryInto;
-
 use anyhow::Context;
 
 use crate::emit::{Emit, EmitContext};
+use crate::error::{locate, unsupported, Proposal};
 use crate::parse::IndicesToIds;
 use crate::tombstone_arena::{Id, Tombstone, TombstoneArena};
 use crate::{FunctionId, GlobalId, MemoryId, Result, TableId, TagId};
@@ -154,8 +153,8 @@ impl Module {
         ids: &mut IndicesToIds,
     ) -> Result<()> {
         log::debug!("parse import section");
-        for entry in section.into_imports() {
-            let entry = entry?;
+        for entry in section.into_imports_with_offsets() {
+            let (offset, entry) = entry?;
             match entry.ty {
                 wasmparser::TypeRef::Func(idx) | wasmparser::TypeRef::FuncExact(idx) => {
                     let ty = ids.get_type(idx)?;
@@ -163,13 +162,16 @@ impl Module {
                     ids.push_func(id.0);
                 }
                 wasmparser::TypeRef::Table(t) => {
+                    if t.shared {
+                        return Err(unsupported(Proposal::SharedEverythingThreads, offset));
+                    }
                     let id = self.add_import_table(
                         entry.module,
                         entry.name,
                         t.table64,
                         t.initial,
                         t.maximum,
-                        t.element_type.try_into()?,
+                        RefType::parse(t.element_type, ids).map_err(|e| locate(e, offset))?,
                     );
                     ids.push_table(id.0);
                 }
@@ -189,7 +191,7 @@ impl Module {
                     let id = self.add_import_global(
                         entry.module,
                         entry.name,
-                        ValType::parse(&g.content_type)?,
+                        ValType::parse(&g.content_type, ids).map_err(|e| locate(e, offset))?,
                         g.mutable,
                         g.shared,
                     );
@@ -307,7 +309,7 @@ impl Emit for ModuleImports {
                         cx.indices.push_table(id);
                         let table = cx.module.tables.get(id);
                         wasm_encoder::EntityType::Table(wasm_encoder::TableType {
-                            element_type: table.element_ty.to_wasmencoder_ref_type(),
+                            element_type: table.element_ty.to_wasmencoder_ref_type(cx.indices),
                             table64: table.table64,
                             minimum: table.initial,
                             maximum: table.maximum,
@@ -329,7 +331,7 @@ impl Emit for ModuleImports {
                         cx.indices.push_global(id);
                         let g = cx.module.globals.get(id);
                         wasm_encoder::EntityType::Global(wasm_encoder::GlobalType {
-                            val_type: g.ty.to_wasmencoder_type(),
+                            val_type: g.ty.to_wasmencoder_type(cx.indices),
                             mutable: g.mutable,
                             shared: g.shared,
                         })
@@ -25,6 +25,11 @@ impl ModuleLocals {
         &self.arena[id]
     }
 
+    /// Does this module contain a local with the given id?
+    pub(crate) fn contains(&self, id: LocalId) -> bool {
+        self.arena.get(id).is_some()
+    }
+
     /// Gets a reference to a local given its id
     pub fn get_mut(&mut self, id: LocalId) -> &mut Local {
         &mut self.arena[id]
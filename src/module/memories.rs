@@ -117,6 +117,12 @@ impl ModuleMemories {
         &self.arena[id]
     }
 
+    /// Does this module contain a memory with the given id, that hasn't
+    /// been deleted?
+    pub(crate) fn contains(&self, id: MemoryId) -> bool {
+        self.arena.contains(id)
+    }
+
     /// Gets a reference to a memory given its id
     pub fn get_mut(&mut self, id: MemoryId) -> &mut Memory {
         &mut self.arena[id]
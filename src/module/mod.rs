@@ -17,7 +17,7 @@ mod tags;
 mod types;
 
 use crate::emit::{Emit, EmitContext, IdsToIndices};
-use crate::error::Result;
+use crate::error::{unsupported, InstrPosition, Proposal, Result};
 pub use crate::ir::InstrLocId;
 pub use crate::module::custom::{
     CustomSection, CustomSectionId, ModuleCustomSections, RawCustomSection, TypedCustomSectionId,
@@ -40,7 +40,7 @@ pub use crate::module::tables::{ModuleTables, Table, TableId};
 pub use crate::module::tags::{ModuleTags, Tag, TagId, TagKind};
 pub use crate::module::types::ModuleTypes;
 use crate::parse::IndicesToIds;
-use anyhow::{bail, Context};
+use anyhow::Context;
 use id_arena::Id;
 use log::warn;
 use std::fs;
@@ -51,6 +51,10 @@ use wasmparser::{BinaryReader, Parser, Payload, Validator};
 
 pub use self::config::ModuleConfig;
 
+/// Return type for `Module::emit`: (wasm, index assignment, code ranges of
+/// local functions)
+type EmitResult = (Vec<u8>, IdsToIndices, Vec<(FunctionId, Range<usize>)>);
+
 /// A wasm module.
 #[derive(Debug, Default)]
 #[allow(missing_docs)]
@@ -138,6 +142,15 @@ impl Module {
         config.parse(wasm)
     }
 
+    /// Construct a new module from a module in the WebAssembly text format
+    /// with the default configuration.
+    ///
+    /// This requires the `wat` feature of this crate.
+    #[cfg(feature = "wat")]
+    pub fn from_wat(wat: &str) -> Result<Module> {
+        ModuleConfig::new().parse_wat(wat)
+    }
+
     fn parse(wasm: &[u8], config: &ModuleConfig) -> Result<Module> {
         let mut ret = Module {
             config: config.clone(),
@@ -154,6 +167,7 @@ impl Module {
 
         let mut local_functions = Vec::new();
         let mut debug_sections = Vec::new();
+        let mut name_section = None;
 
         let mut parser = Parser::new(0);
         parser.set_features(wasm_features);
@@ -166,6 +180,9 @@ impl Module {
                     range,
                 } => {
                     validator.version(num, encoding, &range)?;
+                    if encoding == wasmparser::Encoding::Component {
+                        return Err(unsupported(Proposal::ComponentModel, range.start));
+                    }
                 }
                 Payload::DataSection(s) => {
                     validator
@@ -246,14 +263,17 @@ impl Module {
                         )
                         .map_err(anyhow::Error::from)
                         .and_then(|s| ret.parse_producers_section(s)),
+                        // Locals are only created while parsing the code
+                        // section, so wait until then to apply their names.
                         "name" => {
-                            let name_section_reader =
-                                wasmparser::NameSectionReader::new(BinaryReader::new_features(
+                            name_section = Some(wasmparser::NameSectionReader::new(
+                                BinaryReader::new_features(
                                     s.data(),
                                     s.data_offset(),
                                     wasm_features,
-                                ));
-                            ret.parse_name_section(name_section_reader, &indices)
+                                ),
+                            ));
+                            continue;
                         }
                         name => {
                             log::debug!("parsing custom section `{}`", name);
@@ -291,10 +311,11 @@ impl Module {
                     ret.parse_tags(s, &mut indices)?;
                 }
 
-                // Among other things, the component module proposal is not
+                // Everything else belongs to the component model, which isn't
                 // implemented yet.
-                _ => {
-                    bail!("not supported yet");
+                other => {
+                    let offset = other.as_section().map_or(0, |(_, range)| range.start);
+                    return Err(unsupported(Proposal::ComponentModel, offset));
                 }
             }
         }
@@ -306,6 +327,12 @@ impl Module {
         )
         .context("failed to parse code section")?;
 
+        if let Some(name_section) = name_section {
+            if let Err(e) = ret.parse_name_section(name_section, &indices) {
+                log::warn!("failed to parse `name` custom section {}", e);
+            }
+        }
+
         ret.parse_debug_sections(debug_sections)
             .context("failed to parse debug data section")?;
 
@@ -325,13 +352,51 @@ impl Module {
     where
         P: AsRef<Path>,
     {
-        let buffer = self.emit_wasm();
+        let buffer = self.try_emit_wasm()?;
         fs::write(path, buffer).context("failed to write wasm module")?;
         Ok(())
     }
 
     /// Emit this module into an in-memory wasm buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the module refers to items that were deleted, or if
+    /// `ModuleConfig::validate_emitted_wasm` is enabled and the emitted wasm
+    /// is invalid. Use `try_emit_wasm` to get an error instead.
     pub fn emit_wasm(&mut self) -> Vec<u8> {
+        match self.try_emit_wasm() {
+            Ok(wasm) => wasm,
+            Err(e) => panic!("{:?}", e),
+        }
+    }
+
+    /// Emit this module into an in-memory wasm buffer, validating it if
+    /// `ModuleConfig::validate_emitted_wasm` is enabled.
+    ///
+    /// Validation first checks the IR with `Module::validate`, and then the
+    /// emitted wasm itself. When an instruction of a local function fails
+    /// validation, the returned error has an `InstrPosition` context pointing
+    /// at it.
+    ///
+    /// Without validation, this still returns an error rather than panicking
+    /// when the module refers to items or instruction sequences that were
+    /// deleted, or branches to an instruction sequence that doesn't enclose
+    /// the branch, since the module can't be emitted at all.
+    pub fn try_emit_wasm(&mut self) -> Result<Vec<u8>> {
+        if self.config.validate_emitted_wasm {
+            self.validate().context("module is invalid")?;
+        } else {
+            self.validate_ids().context("module is invalid")?;
+        }
+        let (out, indices, function_ranges) = self.emit();
+        if self.config.validate_emitted_wasm {
+            self.validate_emitted(&out, &indices, &function_ranges)?;
+        }
+        Ok(out)
+    }
+
+    fn emit(&mut self) -> EmitResult {
         log::debug!("start emit");
 
         let indices = &mut IdsToIndices::default();
@@ -399,13 +464,65 @@ impl Module {
         let out = cx.wasm_module.finish();
         log::debug!("emission finished");
 
-        // let mut validator = Validator::new();
-        // if let Err(err) = validator.validate_all(&out) {
-        //     eprintln!("{:?}", err);
-        //     panic!("Unable to validate serialized output");
-        // }
+        (out, indices, cx.code_transform.function_ranges)
+    }
 
-        out
+    /// Validate wasm that was just emitted for this module.
+    fn validate_emitted(
+        &self,
+        wasm: &[u8],
+        indices: &IdsToIndices,
+        function_ranges: &[(FunctionId, Range<usize>)],
+    ) -> Result<()> {
+        log::debug!("validating emitted wasm");
+        let mut validator =
+            Validator::new_with_features(self.config.get_wasmparser_wasm_features());
+        let err = match validator.validate_all(wasm) {
+            Ok(_) => return Ok(()),
+            Err(e) => e,
+        };
+
+        let offset = err.offset();
+        let mut err = anyhow::Error::from(err);
+        if let Some(position) = self.emitted_instr_position(offset, indices, function_ranges) {
+            err = err.context(position);
+        }
+        Err(err.context("emitted wasm is invalid"))
+    }
+
+    /// Find the instruction that was emitted at `offset`, if any.
+    fn emitted_instr_position(
+        &self,
+        offset: usize,
+        indices: &IdsToIndices,
+        function_ranges: &[(FunctionId, Range<usize>)],
+    ) -> Option<InstrPosition> {
+        let (id, range) = function_ranges
+            .iter()
+            .find(|(_, range)| range.contains(&offset))?;
+        let func = match &self.funcs.get(*id).kind {
+            FunctionKind::Local(func) => func,
+            _ => return None,
+        };
+
+        // Emit the function again, this time recording where each instruction
+        // ends up. Emission is deterministic, so this matches the function's
+        // bytes in `wasm`.
+        let (locals, _, local_indices) = func.emit_locals(self, indices);
+        let mut encoder = wasm_encoder::Function::new(locals);
+        let mut map = Vec::new();
+        func.emit_instructions(indices, &local_indices, &mut encoder, Some(&mut map));
+        let offset = offset.checked_sub(range.end - encoder.byte_len())?;
+
+        // `map` has an entry for every instruction and the end of every
+        // instruction sequence, in the same order as `emitted_positions`.
+        let i = map.iter().rposition(|(_, pos)| *pos <= offset)?;
+        let (seq, index) = func.emitted_positions()[i];
+        Some(InstrPosition {
+            func: *id,
+            seq,
+            index,
+        })
     }
 
     /// Returns an iterator over all functions in this module
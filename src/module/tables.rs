@@ -1,8 +1,7 @@
 //! Tables within a wasm module.
 
-use std::convert::TryInto;
-
 use crate::emit::{Emit, EmitContext};
+use crate::error::{locate, unsupported, Proposal};
 use crate::map::IdHashSet;
 use crate::parse::IndicesToIds;
 use crate::tombstone_arena::{Id, Tombstone, TombstoneArena};
@@ -120,6 +119,12 @@ impl ModuleTables {
         &self.arena[table]
     }
 
+    /// Does this module contain a table with the given id, that hasn't
+    /// been deleted?
+    pub(crate) fn contains(&self, id: TableId) -> bool {
+        self.arena.contains(id)
+    }
+
     /// Returns the actual table associated with an ID
     pub fn get_mut(&mut self, table: TableId) -> &mut Table {
         &mut self.arena[table]
@@ -174,8 +179,11 @@ impl Module {
         ids: &mut IndicesToIds,
     ) -> Result<()> {
         log::debug!("parse table section");
-        for t in section {
-            let t = t?;
+        for t in section.into_iter_with_offsets() {
+            let (offset, t) = t?;
+            if t.ty.shared {
+                return Err(unsupported(Proposal::SharedEverythingThreads, offset));
+            }
             let init = match t.init {
                 wasmparser::TableInit::RefNull => None,
                 wasmparser::TableInit::Expr(expr) => Some(ConstExpr::eval(&expr, ids)?),
@@ -184,7 +192,7 @@ impl Module {
                 t.ty.table64,
                 t.ty.initial,
                 t.ty.maximum,
-                t.ty.element_type.try_into()?,
+                RefType::parse(t.ty.element_type, ids).map_err(|e| locate(e, offset))?,
                 init,
             );
             ids.push_table(id);
@@ -212,7 +220,7 @@ impl Emit for ModuleTables {
                 table64: table.table64,
                 minimum: table.initial,
                 maximum: table.maximum,
-                element_type: table.element_ty.to_wasmencoder_ref_type(),
+                element_type: table.element_ty.to_wasmencoder_ref_type(cx.indices),
                 shared: false,
             };
 
@@ -126,6 +126,12 @@ impl ModuleTags {
         &self.arena[id]
     }
 
+    /// Does this module contain a tag with the given id, that hasn't
+    /// been deleted?
+    pub(crate) fn contains(&self, id: TagId) -> bool {
+        self.arena.contains(id)
+    }
+
     /// Get a mutable reference to a tag by id.
     pub fn get_mut(&mut self, id: TagId) -> &mut Tag {
         &mut self.arena[id]
@@ -1,16 +1,25 @@
 //! Types in a wasm module.
 
 use crate::arena_set::ArenaSet;
-use crate::emit::{Emit, EmitContext};
-use crate::error::Result;
+use crate::emit::{Emit, EmitContext, IdsToIndices};
+use crate::error::{locate, Proposal, Result, Unlocated};
+use crate::map::IdHashSet;
 use crate::module::Module;
 use crate::parse::IndicesToIds;
-use crate::ty::{Type, TypeId, ValType};
+use crate::ty::{AbstractHeapType, HeapType, RefType, Type, TypeId, TypeKind, ValType};
+use crate::ty::{
+    ArrayType, ContType, FieldType, FunctionType, RecGroup, RecGroupId, StorageType, StructType,
+    SubType,
+};
+use anyhow::bail;
+use id_arena::Arena;
+use std::collections::HashSet;
 
 /// The set of de-duplicated types within a module.
 #[derive(Debug, Default)]
 pub struct ModuleTypes {
     arena: ArenaSet<Type>,
+    rec_groups: Arena<RecGroup>,
 }
 
 impl ModuleTypes {
@@ -24,28 +33,152 @@ impl ModuleTypes {
         &mut self.arena[id]
     }
 
+    /// Does this module contain a type with the given id, that hasn't been
+    /// deleted?
+    pub(crate) fn contains(&self, id: TypeId) -> bool {
+        self.arena.contains(id)
+    }
+
     /// Get the parameters and results for the given type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the type is not a function type. Use `Type::as_function` to
+    /// handle struct, array and continuation types.
     pub fn params_results(&self, id: TypeId) -> (&[ValType], &[ValType]) {
         let ty = self.get(id);
         (ty.params(), ty.results())
     }
 
     /// Get the parameters for the given type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the type is not a function type.
     pub fn params(&self, id: TypeId) -> &[ValType] {
         self.get(id).params()
     }
 
     /// Get the results for the given type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the type is not a function type.
     pub fn results(&self, id: TypeId) -> &[ValType] {
         self.get(id).results()
     }
 
+    /// Is a value of type `sub` also a value of type `sup`?
+    ///
+    /// Concrete reference types are related through their declared
+    /// supertypes, and two different `TypeId`s are the same type if they are
+    /// equivalent, as decided by `is_equivalent`.
+    pub fn is_subtype(&self, sub: ValType, sup: ValType) -> bool {
+        match (sub, sup) {
+            (ValType::Ref(sub), ValType::Ref(sup)) => self.is_ref_subtype(sub, sup),
+            (sub, sup) => sub == sup,
+        }
+    }
+
+    /// Is a reference of type `sub` also a reference of type `sup`?
+    ///
+    /// See `is_subtype` for details.
+    pub fn is_ref_subtype(&self, sub: RefType, sup: RefType) -> bool {
+        (sup.nullable || !sub.nullable) && self.is_heap_subtype(sub.heap_type, sup.heap_type)
+    }
+
+    /// Is the heap type `sub` a subtype of `sup`?
+    ///
+    /// See `is_subtype` for details.
+    pub fn is_heap_subtype(&self, sub: HeapType, sup: HeapType) -> bool {
+        use AbstractHeapType::*;
+
+        if sub == sup {
+            return true;
+        }
+        match (sub, sup) {
+            (HeapType::Concrete(sub), HeapType::Concrete(sup)) => {
+                // Walk up the declared supertypes, watching out for cycles
+                // that a module under construction may contain.
+                let mut seen = IdHashSet::default();
+                let mut ty = sub;
+                while seen.insert(ty) && self.contains(ty) {
+                    match self.get(ty).supertype() {
+                        Some(parent) if self.is_equivalent(parent, sup) => return true,
+                        Some(parent) => ty = parent,
+                        _ => break,
+                    }
+                }
+                self.is_equivalent(sub, sup)
+            }
+            (HeapType::Concrete(sub), HeapType::Abstract(sup)) => {
+                if !self.contains(sub) {
+                    return false;
+                }
+                match self.get(sub).kind() {
+                    TypeKind::Function(_) => sup == Func,
+                    TypeKind::Struct(_) => matches!(sup, Struct | Eq | Any),
+                    TypeKind::Array(_) => matches!(sup, Array | Eq | Any),
+                    TypeKind::Cont(_) => sup == Cont,
+                }
+            }
+            (HeapType::Abstract(sub), HeapType::Concrete(sup)) => {
+                if !self.contains(sup) {
+                    return false;
+                }
+                match self.get(sup).kind() {
+                    TypeKind::Function(_) => sub == NoFunc,
+                    TypeKind::Struct(_) | TypeKind::Array(_) => sub == None,
+                    TypeKind::Cont(_) => sub == NoCont,
+                }
+            }
+            (HeapType::Abstract(sub), HeapType::Abstract(sup)) => matches!(
+                (sub, sup),
+                (None, Any | Eq | Struct | Array | I31)
+                    | (I31 | Struct | Array, Eq | Any)
+                    | (Eq, Any)
+                    | (NoFunc, Func)
+                    | (NoExtern, Extern)
+                    | (NoExn, Exn)
+                    | (NoCont, Cont)
+            ),
+        }
+    }
+
+    /// Are `a` and `b` the same type?
+    ///
+    /// Types are compared iso-recursively, like wasm does: two types are the
+    /// same if they are at the same position of recursive type groups with
+    /// the same definitions, where references to types of the same group are
+    /// compared by their position in the group. Types that are not in an
+    /// explicit group form an implicit group of their own.
+    pub fn is_equivalent(&self, a: TypeId, b: TypeId) -> bool {
+        Equivalence {
+            types: self,
+            assumed: HashSet::new(),
+        }
+        .types(a, b)
+    }
+
+    /// The types of the recursion group that `ty` is in, and its position in
+    /// the group.
+    fn group_of(&self, ty: TypeId) -> (Vec<TypeId>, usize) {
+        match self.get(ty).rec_group() {
+            Some(group) => {
+                let types = self.rec_groups[group].types.clone();
+                let index = types.iter().position(|t| *t == ty).unwrap();
+                (types, index)
+            }
+            None => (vec![ty], 0),
+        }
+    }
+
     /// Get a type ID by its name.
     ///
-    /// This is currently only intended for in-memory modifications, and by
-    /// default will always return `None` for a newly parsed module. A
-    /// hypothetical future WAT text format to `walrus::Module` parser could
-    /// preserve type names from the WAT.
+    /// Types are named by the `name` section of a parsed module, by their
+    /// identifiers when the module is parsed from the text format with
+    /// `ModuleConfig::parse_wat` or `Module::from_wat`, or by setting
+    /// `Type::name`.
     pub fn by_name(&self, name: &str) -> Option<TypeId> {
         self.arena.iter().find_map(|(id, ty)| {
             if ty.name.as_deref() == Some(name) {
@@ -67,9 +200,30 @@ impl ModuleTypes {
     /// type are also removed, eg `call_indirect` expressions, function types,
     /// etc.
     pub fn delete(&mut self, ty: TypeId) {
+        if let Some(group) = self.arena[ty].rec_group() {
+            self.rec_groups[group].types.retain(|t| *t != ty);
+        }
         self.arena.remove(ty);
     }
 
+    /// Get a recursive type group associated with an ID
+    pub fn rec_group(&self, id: RecGroupId) -> &RecGroup {
+        &self.rec_groups[id]
+    }
+
+    /// Get a shared reference to this module's recursive type groups.
+    ///
+    /// These are the explicit groups, along with the implicit groups of
+    /// parsed types that refer to themselves; see `RecGroup::is_explicit`.
+    ///
+    /// Groups whose types have all been deleted are skipped.
+    pub fn rec_groups(&self) -> impl Iterator<Item = &RecGroup> {
+        self.rec_groups
+            .iter()
+            .map(|(_, g)| g)
+            .filter(|g| !g.types.is_empty())
+    }
+
     /// Add a new type to this module, and return its `Id`
     pub fn add(&mut self, params: &[ValType], results: &[ValType]) -> TypeId {
         let id = self.arena.next_id();
@@ -88,17 +242,145 @@ impl ModuleTypes {
         ))
     }
 
+    /// Add a new struct type with the given fields to this module, and return
+    /// its `Id`
+    pub fn add_struct(&mut self, fields: &[FieldType]) -> TypeId {
+        let id = self.arena.next_id();
+        self.arena.insert(Type::with_kind(
+            id,
+            TypeKind::Struct(StructType {
+                fields: fields.to_vec().into_boxed_slice(),
+            }),
+        ))
+    }
+
+    /// Add a new array type with the given element type to this module, and
+    /// return its `Id`
+    pub fn add_array(&mut self, field: FieldType) -> TypeId {
+        let id = self.arena.next_id();
+        self.arena
+            .insert(Type::with_kind(id, TypeKind::Array(ArrayType { field })))
+    }
+
+    /// Add a new continuation type of the given function type to this module,
+    /// and return its `Id`
+    pub fn add_cont(&mut self, func_type: TypeId) -> TypeId {
+        let id = self.arena.next_id();
+        self.arena
+            .insert(Type::with_kind(id, TypeKind::Cont(ContType { func_type })))
+    }
+
+    /// Add a new type with declared subtyping information to this module, and
+    /// return its `Id`
+    ///
+    /// The type forms an implicit recursion group of its own, so it is merged
+    /// with any existing identical type.
+    pub fn add_sub_type(&mut self, sub: SubType) -> TypeId {
+        let id = self.arena.next_id();
+        self.arena.insert(Type::from_sub_type(id, sub, None))
+    }
+
+    /// Add a new explicit recursive type group of `len` types to this module.
+    ///
+    /// The `define` callback is given the ids of the group's types, so that
+    /// they can refer to each other, and must return exactly `len` type
+    /// definitions in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `define` doesn't return `len` definitions.
+    pub fn add_rec_group<F>(&mut self, len: usize, define: F) -> RecGroupId
+    where
+        F: FnOnce(&[TypeId]) -> Vec<SubType>,
+    {
+        let group = self.new_rec_group(len);
+        let ids = self.rec_groups[group].types.clone();
+        let subs = define(&ids);
+        assert_eq!(
+            subs.len(),
+            len,
+            "wrong number of types defined for a recursive type group"
+        );
+        for (id, sub) in ids.into_iter().zip(subs) {
+            self.arena[id].define_in_rec_group(sub);
+        }
+        group
+    }
+
+    /// Allocate a new explicit recursion group with `len` placeholder types,
+    /// which must be defined afterwards.
+    fn new_rec_group(&mut self, len: usize) -> RecGroupId {
+        let group = self.rec_groups.alloc_with_id(RecGroup::new);
+        for i in 0..len {
+            let id = self.arena.next_id();
+            let placeholder = SubType {
+                kind: TypeKind::Function(FunctionType::new(Box::new([]), Box::new([]))),
+                supertype: None,
+                is_final: true,
+            };
+            let id = self
+                .arena
+                .insert(Type::from_sub_type(id, placeholder, Some((group, i))));
+            self.rec_groups[group].types.push(id);
+        }
+        group
+    }
+
     /// Find the existing type for the given parameters and results.
+    ///
+    /// Like the `add*` methods, this only considers final types without a
+    /// supertype that are in a recursion group of their own.
     pub fn find(&self, params: &[ValType], results: &[ValType]) -> Option<TypeId> {
-        self.arena.iter().find_map(|(id, ty)| {
-            if !ty.is_for_function_entry() && ty.params() == params && ty.results() == results {
+        self.implicit().find_map(|(id, ty)| match ty.as_function() {
+            Some(f)
+                if !ty.is_for_function_entry()
+                    && f.params() == params
+                    && f.results() == results =>
+            {
                 Some(id)
-            } else {
-                None
             }
+            _ => None,
+        })
+    }
+
+    /// Find the existing struct type with the given fields.
+    ///
+    /// Like `find`, this ignores subtypable and explicitly grouped types.
+    pub fn find_struct(&self, fields: &[FieldType]) -> Option<TypeId> {
+        self.implicit().find_map(|(id, ty)| match ty.as_struct() {
+            Some(s) if &*s.fields == fields => Some(id),
+            _ => None,
+        })
+    }
+
+    /// Find the existing array type with the given element type.
+    ///
+    /// Like `find`, this ignores subtypable and explicitly grouped types.
+    pub fn find_array(&self, field: FieldType) -> Option<TypeId> {
+        self.implicit().find_map(|(id, ty)| match ty.as_array() {
+            Some(a) if a.field == field => Some(id),
+            _ => None,
+        })
+    }
+
+    /// Find the existing continuation type of the given function type.
+    ///
+    /// Like `find`, this ignores subtypable and explicitly grouped types.
+    pub fn find_cont(&self, func_type: TypeId) -> Option<TypeId> {
+        self.implicit().find_map(|(id, ty)| match ty.as_cont() {
+            Some(c) if c.func_type == func_type => Some(id),
+            _ => None,
         })
     }
 
+    /// The types that `add` and friends would create: final, without a
+    /// supertype, and in an implicit recursion group of their own.
+    fn implicit(&self) -> impl Iterator<Item = (TypeId, &Type)> + '_ {
+        self.arena
+            .iter()
+            .filter(|(_, ty)| ty.rec_group().is_none() && ty.is_final() && ty.supertype().is_none())
+    }
+
     pub(crate) fn find_for_function_entry(&self, results: &[ValType]) -> Option<TypeId> {
         self.arena.iter().find_map(|(id, ty)| {
             if ty.is_for_function_entry() && ty.params().is_empty() && ty.results() == results {
@@ -110,6 +392,109 @@ impl ModuleTypes {
     }
 }
 
+/// The state of comparing types with `ModuleTypes::is_equivalent`.
+struct Equivalence<'a> {
+    types: &'a ModuleTypes,
+    /// The pairs of recursion groups, by their first types, that are being
+    /// compared, which are assumed to be equivalent when they are compared
+    /// again. Only a module under construction can have such cycles between
+    /// groups.
+    assumed: HashSet<(TypeId, TypeId)>,
+}
+
+impl Equivalence<'_> {
+    fn types(&mut self, a: TypeId, b: TypeId) -> bool {
+        if a == b {
+            return true;
+        }
+        if !self.types.contains(a) || !self.types.contains(b) {
+            return false;
+        }
+        let (group_a, index_a) = self.types.group_of(a);
+        let (group_b, index_b) = self.types.group_of(b);
+        if index_a != index_b || group_a.len() != group_b.len() {
+            return false;
+        }
+        if !self.assumed.insert((group_a[0], group_b[0])) {
+            return true;
+        }
+        let groups = (&group_a[..], &group_b[..]);
+        group_a
+            .iter()
+            .zip(group_b.iter())
+            .all(|(a, b)| self.definitions(*a, *b, groups))
+    }
+
+    /// Do the types `a` and `b`, of the groups `groups`, have the same
+    /// definition?
+    fn definitions(&mut self, a: TypeId, b: TypeId, groups: (&[TypeId], &[TypeId])) -> bool {
+        let (a, b) = (self.types.get(a), self.types.get(b));
+        if a.is_final() != b.is_final() || a.is_for_function_entry() != b.is_for_function_entry() {
+            return false;
+        }
+        let supertypes = match (a.supertype(), b.supertype()) {
+            (None, None) => true,
+            (Some(a), Some(b)) => self.refs(a, b, groups),
+            _ => false,
+        };
+        supertypes
+            && match (a.kind(), b.kind()) {
+                (TypeKind::Function(a), TypeKind::Function(b)) => {
+                    self.vals(a.params(), b.params(), groups)
+                        && self.vals(a.results(), b.results(), groups)
+                }
+                (TypeKind::Struct(a), TypeKind::Struct(b)) => {
+                    a.fields.len() == b.fields.len()
+                        && a.fields
+                            .iter()
+                            .zip(b.fields.iter())
+                            .all(|(a, b)| self.fields(*a, *b, groups))
+                }
+                (TypeKind::Array(a), TypeKind::Array(b)) => self.fields(a.field, b.field, groups),
+                (TypeKind::Cont(a), TypeKind::Cont(b)) => {
+                    self.refs(a.func_type, b.func_type, groups)
+                }
+                _ => false,
+            }
+    }
+
+    /// Are the references to `a` and `b`, from types of the groups `groups`,
+    /// references to the same type?
+    fn refs(&mut self, a: TypeId, b: TypeId, groups: (&[TypeId], &[TypeId])) -> bool {
+        let index_a = groups.0.iter().position(|t| *t == a);
+        let index_b = groups.1.iter().position(|t| *t == b);
+        match (index_a, index_b) {
+            (Some(a), Some(b)) => a == b,
+            (None, None) => self.types(a, b),
+            _ => false,
+        }
+    }
+
+    fn fields(&mut self, a: FieldType, b: FieldType, groups: (&[TypeId], &[TypeId])) -> bool {
+        a.mutable == b.mutable
+            && match (a.element_type, b.element_type) {
+                (StorageType::Val(a), StorageType::Val(b)) => self.vals(&[a], &[b], groups),
+                (a, b) => a == b,
+            }
+    }
+
+    fn vals(&mut self, a: &[ValType], b: &[ValType], groups: (&[TypeId], &[TypeId])) -> bool {
+        a.len() == b.len()
+            && a.iter().zip(b).all(|(a, b)| match (a, b) {
+                (ValType::Ref(a), ValType::Ref(b)) => {
+                    a.nullable == b.nullable
+                        && match (a.heap_type, b.heap_type) {
+                            (HeapType::Concrete(a), HeapType::Concrete(b)) => {
+                                self.refs(a, b, groups)
+                            }
+                            (a, b) => a == b,
+                        }
+                }
+                (a, b) => a == b,
+            })
+    }
+}
+
 impl Module {
     /// Construct the set of types within a module.
     pub(crate) fn parse_types(
@@ -118,26 +503,159 @@ impl Module {
         ids: &mut IndicesToIds,
     ) -> Result<()> {
         log::debug!("parsing type section");
-        for ty in section.into_iter_err_on_gc_types() {
-            let fun_ty = ty?;
-            let id = self.types.arena.next_id();
+        for rec_group in section.into_iter_with_offsets() {
+            let (offset, rec_group) = rec_group?;
+            if rec_group.is_explicit_rec_group() {
+                // Allocate all of the group's types up front since they may
+                // refer to each other.
+                let group = self.types.new_rec_group(rec_group.types().len());
+                let members = self.types.rec_groups[group].types.clone();
+                for id in members.iter() {
+                    ids.push_type(*id);
+                }
+                for (id, sub_ty) in members.iter().zip(rec_group.into_types()) {
+                    let sub = parse_sub_type(sub_ty, ids).map_err(|e| locate(e, offset))?;
+                    self.types.arena[*id].define_in_rec_group(sub);
+                }
+            } else {
+                for sub_ty in rec_group.into_types() {
+                    // The type is a group of its own and may refer to itself,
+                    // so it needs an id before it is parsed.
+                    let id = self.types.arena.next_id();
+                    let index = ids.push_type(id);
+                    let sub = parse_sub_type(sub_ty, ids).map_err(|e| locate(e, offset))?;
+                    if refers_to(&sub, id) {
+                        // Record the group so that the type is identified by
+                        // its position in it, rather than merged with a type
+                        // that has the same definition but refers to `id`
+                        // from outside of its group.
+                        let group = self.types.new_rec_group(1);
+                        self.types.rec_groups[group].explicit = false;
+                        debug_assert_eq!(self.types.rec_groups[group].types, [id]);
+                        self.types.arena[id].define_in_rec_group(sub);
+                    } else {
+                        let type_id = self.types.arena.insert(Type::from_sub_type(id, sub, None));
+                        ids.set_type(index, type_id);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Does the definition `sub` refer to the type `id`?
+fn refers_to(sub: &SubType, id: TypeId) -> bool {
+    let val = |ty: &ValType| match ty {
+        ValType::Ref(RefType {
+            heap_type: HeapType::Concrete(ty),
+            ..
+        }) => *ty == id,
+        _ => false,
+    };
+    let field = |field: &FieldType| match &field.element_type {
+        StorageType::Val(ty) => val(ty),
+        _ => false,
+    };
+    sub.supertype == Some(id)
+        || match &sub.kind {
+            TypeKind::Function(f) => f.params().iter().chain(f.results()).any(val),
+            TypeKind::Struct(s) => s.fields.iter().any(field),
+            TypeKind::Array(a) => field(&a.field),
+            TypeKind::Cont(c) => c.func_type == id,
+        }
+}
+
+fn parse_sub_type(sub_ty: wasmparser::SubType, ids: &IndicesToIds) -> Result<SubType> {
+    if sub_ty.composite_type.shared {
+        return Err(Unlocated(Proposal::SharedEverythingThreads).into());
+    }
+    if sub_ty.composite_type.descriptor_idx.is_some()
+        || sub_ty.composite_type.describes_idx.is_some()
+    {
+        return Err(Unlocated(Proposal::CustomDescriptors).into());
+    }
+    let supertype = match sub_ty.supertype_idx {
+        Some(idx) => match idx.as_module_index() {
+            Some(idx) => Some(ids.get_type(idx)?),
+            None => bail!("unexpected rec-group-relative supertype index"),
+        },
+        None => None,
+    };
+    let kind = match &sub_ty.composite_type.inner {
+        wasmparser::CompositeInnerType::Func(fun_ty) => {
             let params = fun_ty
                 .params()
                 .iter()
-                .map(ValType::parse)
+                .map(|ty| ValType::parse(ty, ids))
                 .collect::<Result<Vec<_>>>()?
                 .into_boxed_slice();
             let results = fun_ty
                 .results()
                 .iter()
-                .map(ValType::parse)
+                .map(|ty| ValType::parse(ty, ids))
+                .collect::<Result<Vec<_>>>()?
+                .into_boxed_slice();
+            TypeKind::Function(FunctionType::new(params, results))
+        }
+        wasmparser::CompositeInnerType::Struct(struct_ty) => {
+            let fields = struct_ty
+                .fields
+                .iter()
+                .map(|field| FieldType::parse(field, ids))
                 .collect::<Result<Vec<_>>>()?
                 .into_boxed_slice();
-            let id = self.types.arena.insert(Type::new(id, params, results));
-            ids.push_type(id);
+            TypeKind::Struct(StructType { fields })
         }
+        wasmparser::CompositeInnerType::Array(array_ty) => TypeKind::Array(ArrayType {
+            field: FieldType::parse(&array_ty.0, ids)?,
+        }),
+        wasmparser::CompositeInnerType::Cont(cont_ty) => match cont_ty.0.as_module_index() {
+            Some(idx) => TypeKind::Cont(ContType {
+                func_type: ids.get_type(idx)?,
+            }),
+            None => bail!("unexpected rec-group-relative type index in continuation type"),
+        },
+    };
+    Ok(SubType {
+        kind,
+        supertype,
+        is_final: sub_ty.is_final,
+    })
+}
 
-        Ok(())
+fn to_wasmencoder_sub_type(ty: &Type, indices: &IdsToIndices) -> wasm_encoder::SubType {
+    let inner = match ty.kind() {
+        TypeKind::Function(f) => {
+            wasm_encoder::CompositeInnerType::Func(wasm_encoder::FuncType::new(
+                f.params().iter().map(|ty| ty.to_wasmencoder_type(indices)),
+                f.results().iter().map(|ty| ty.to_wasmencoder_type(indices)),
+            ))
+        }
+        TypeKind::Struct(s) => wasm_encoder::CompositeInnerType::Struct(wasm_encoder::StructType {
+            fields: s
+                .fields
+                .iter()
+                .map(|f| f.to_wasmencoder_field_type(indices))
+                .collect(),
+        }),
+        TypeKind::Array(a) => wasm_encoder::CompositeInnerType::Array(wasm_encoder::ArrayType(
+            a.field.to_wasmencoder_field_type(indices),
+        )),
+        TypeKind::Cont(c) => wasm_encoder::CompositeInnerType::Cont(wasm_encoder::ContType(
+            indices.get_type_index(c.func_type),
+        )),
+    };
+    wasm_encoder::SubType {
+        is_final: ty.is_final(),
+        supertype_idx: ty.supertype().map(|s| indices.get_type_index(s)),
+        composite_type: wasm_encoder::CompositeType {
+            inner,
+            shared: false,
+            descriptor: None,
+            describes: None,
+        },
     }
 }
 
@@ -147,7 +665,7 @@ impl Emit for ModuleTypes {
 
         let mut wasm_type_section = wasm_encoder::TypeSection::new();
 
-        let mut tys = self
+        let tys = self
             .arena
             .iter()
             .filter(|(_, ty)| !ty.is_for_function_entry())
@@ -157,15 +675,39 @@ impl Emit for ModuleTypes {
             return;
         }
 
-        // Sort for deterministic ordering.
-        tys.sort_by_key(|&(_, ty)| ty);
-
+        // Types are emitted in the order they were added, which for a parsed
+        // module is their original order. This keeps recursion groups intact
+        // and guarantees that supertypes are defined before their subtypes.
+        let mut emitted_groups = IdHashSet::default();
         for (id, ty) in tys {
-            cx.indices.push_type(id);
-            wasm_type_section.ty().function(
-                ty.params().iter().map(ValType::to_wasmencoder_type),
-                ty.results().iter().map(ValType::to_wasmencoder_type),
-            );
+            match ty.rec_group() {
+                None => {
+                    cx.indices.push_type(id);
+                    wasm_type_section
+                        .ty()
+                        .subtype(&to_wasmencoder_sub_type(ty, cx.indices));
+                }
+                Some(group) if !self.rec_groups[group].explicit => {
+                    cx.indices.push_type(id);
+                    wasm_type_section
+                        .ty()
+                        .subtype(&to_wasmencoder_sub_type(ty, cx.indices));
+                }
+                Some(group) => {
+                    if !emitted_groups.insert(group) {
+                        continue;
+                    }
+                    let members = &self.rec_groups[group].types;
+                    for member in members {
+                        cx.indices.push_type(*member);
+                    }
+                    let subs = members
+                        .iter()
+                        .map(|member| to_wasmencoder_sub_type(&self.arena[*member], cx.indices))
+                        .collect::<Vec<_>>();
+                    wasm_type_section.ty().rec(subs);
+                }
+            }
         }
 
         cx.wasm_module.section(&wasm_type_section);
@@ -66,6 +66,11 @@ define_push_get!(push_data, get_data, DataId, data);
 define_push_get!(push_tag, get_tag, TagId, tags);
 
 impl IndicesToIds {
+    /// Replaces the ID that the type at `index` maps to.
+    pub(crate) fn set_type(&mut self, index: u32, id: TypeId) {
+        self.types[index as usize] = id;
+    }
+
     /// Pushes a new local ID to map it to the next index internally
     pub(crate) fn push_local(&mut self, function: FunctionId, id: LocalId) -> u32 {
         let list = self.locals.entry(function).or_default();
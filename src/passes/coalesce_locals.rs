@@ -0,0 +1,175 @@
+//! Reuses locals whose values are no longer needed.
+//!
+//! When emitting a function, every local that it uses gets its own index.
+//! Transformations that introduce temporaries, like instrumentation or
+//! inlining, can therefore leave a function with many more locals than it
+//! needs at any one time. This pass merges locals of the same type whose live
+//! ranges, as computed by `analysis::Liveness`, don't interfere, so that one
+//! local holds each of their values in turn.
+//!
+//! Before merging, `local.set`s and `local.tee`s of locals that are never read
+//! are removed, keeping the values that they consume when needed.
+
+use crate::analysis::Liveness;
+use crate::ir::*;
+use crate::map::{IdHashMap, IdHashSet};
+use crate::{LocalFunction, Module, ModuleLocals};
+
+/// Coalesce the locals of every local function of the module.
+pub fn run(module: &mut Module) {
+    for (_, func) in module.funcs.iter_local_mut() {
+        run_func(&module.locals, func);
+    }
+}
+
+/// Coalesce the locals of one function.
+pub(crate) fn run_func(locals: &ModuleLocals, func: &mut LocalFunction) {
+    remove_dead_stores(func);
+    coalesce(locals, func);
+}
+
+/// All the sequences of a function, in order, and the locals it uses, in the
+/// order they first appear.
+#[derive(Default)]
+struct Collect {
+    seqs: Vec<InstrSeqId>,
+    read: IdHashSet<Local>,
+    locals: Vec<LocalId>,
+    seen: IdHashSet<Local>,
+}
+
+impl Collect {
+    fn new(func: &LocalFunction) -> Collect {
+        let mut visitor = Collect::default();
+        dfs_in_order(&mut visitor, func, func.entry_block());
+        visitor
+    }
+}
+
+impl<'instr> Visitor<'instr> for Collect {
+    fn start_instr_seq(&mut self, seq: &'instr InstrSeq) {
+        self.seqs.push(seq.id());
+    }
+
+    fn visit_local_get(&mut self, instr: &LocalGet) {
+        self.read.insert(instr.local);
+    }
+
+    fn visit_local_id(&mut self, local: &LocalId) {
+        if self.seen.insert(*local) {
+            self.locals.push(*local);
+        }
+    }
+}
+
+/// Turn each `local.set` of a local that is never read into a `drop`, and
+/// remove each `local.tee` of one.
+fn remove_dead_stores(func: &mut LocalFunction) {
+    let collect = Collect::new(func);
+    for seq in collect.seqs {
+        func.block_mut(seq)
+            .instrs
+            .retain_mut(|(instr, _)| match instr {
+                Instr::LocalSet(LocalSet { local }) if !collect.read.contains(local) => {
+                    *instr = Drop {}.into();
+                    true
+                }
+                Instr::LocalTee(LocalTee { local }) => collect.read.contains(local),
+                _ => true,
+            });
+    }
+}
+
+fn coalesce(locals: &ModuleLocals, func: &mut LocalFunction) {
+    let cfg = Cfg::new(func);
+    let liveness = Liveness::new(&cfg, func);
+
+    // Two locals interfere when one of them is written while the other is
+    // live, because merging them would overwrite the live value.
+    let mut interference: IdHashMap<Local, IdHashSet<Local>> = IdHashMap::default();
+    let mut interfere = |a: LocalId, b: LocalId| {
+        if a != b {
+            interference.entry(a).or_default().insert(b);
+            interference.entry(b).or_default().insert(a);
+        }
+    };
+    for block in cfg.reverse_postorder() {
+        let mut live = liveness.live_out(block).clone();
+        for (instr, _) in cfg.block(block).instrs(func).iter().rev() {
+            match instr {
+                Instr::LocalGet(LocalGet { local }) => {
+                    live.insert(*local);
+                }
+                Instr::LocalSet(LocalSet { local }) | Instr::LocalTee(LocalTee { local }) => {
+                    live.remove(local);
+                    for &other in live.iter() {
+                        interfere(*local, other);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    // The arguments are written on entry, and any other local that is live
+    // there still holds its default value.
+    let entry = liveness.live_in(cfg.entry());
+    for (i, &arg) in func.args.iter().enumerate() {
+        for &other in func.args[i + 1..].iter().chain(entry.iter()) {
+            interfere(arg, other);
+        }
+    }
+
+    // Greedily give each local the first class it fits in, starting with the
+    // arguments, which must keep their own indices.
+    let collect = Collect::new(func);
+    let args = func.args.iter().cloned().collect::<IdHashSet<_>>();
+    let order = func
+        .args
+        .iter()
+        .cloned()
+        .chain(collect.locals.into_iter().filter(|l| !args.contains(l)));
+    let no_interference = IdHashSet::default();
+    let mut classes: Vec<(LocalId, IdHashSet<Local>)> = Vec::new();
+    let mut renames = IdHashMap::default();
+    for local in order {
+        let ty = locals.get(local).ty();
+        let conflicts = interference.get(&local).unwrap_or(&no_interference);
+        let class = if args.contains(&local) {
+            None
+        } else {
+            classes.iter_mut().find(|(rep, members_conflicts)| {
+                locals.get(*rep).ty() == ty && !members_conflicts.contains(&local)
+            })
+        };
+        match class {
+            Some((rep, members_conflicts)) => {
+                members_conflicts.extend(conflicts.iter().cloned());
+                renames.insert(local, *rep);
+            }
+            None => classes.push((local, conflicts.clone())),
+        }
+    }
+    if renames.is_empty() {
+        return;
+    }
+
+    log::debug!(
+        "coalesced {} locals into {}",
+        renames.len() + classes.len(),
+        classes.len()
+    );
+    let entry = func.entry_block();
+    dfs_pre_order_mut(&mut Rename { renames: &renames }, func, entry);
+}
+
+struct Rename<'a> {
+    renames: &'a IdHashMap<Local, LocalId>,
+}
+
+impl VisitorMut for Rename<'_> {
+    fn visit_local_id_mut(&mut self, local: &mut LocalId) {
+        if let Some(rep) = self.renames.get(local) {
+            *local = *rep;
+        }
+    }
+}
@@ -0,0 +1,420 @@
+//! Folds instructions whose operands are constants into constants.
+//!
+//! This evaluates unary and binary operators of constants with the exact
+//! semantics of wasm, and removes operators that don't change their other
+//! operand, like adding zero. Operators that would trap are left alone, as are
+//! float operators that would produce a NaN, whose bits wasm leaves up to the
+//! engine.
+//!
+//! It also resolves control flow that depends on a constant condition: `if`
+//! turns into a `block` of the arm that is taken, `br_if` turns into a `br` or
+//! disappears, and `select` turns into a `drop` of the operand that isn't
+//! picked. Finally, `global.get`s of immutable globals that are initialized
+//! with a constant turn into that constant.
+
+use crate::ir::*;
+use crate::{ConstExpr, GlobalKind, LocalFunction, Module, ModuleGlobals};
+
+/// Run constant folding over every local function of the module.
+pub fn run(module: &mut Module) {
+    for (_, func) in module.funcs.iter_local_mut() {
+        run_func(&module.globals, func);
+    }
+}
+
+/// Run constant folding over one function.
+pub(crate) fn run_func(globals: &ModuleGlobals, func: &mut LocalFunction) {
+    let mut seqs = Vec::new();
+    let mut visitor = SeqsVisitor { seqs: &mut seqs };
+    dfs_in_order(&mut visitor, func, func.entry_block());
+    for seq in seqs {
+        fold_seq(globals, &mut func.block_mut(seq).instrs);
+    }
+}
+
+struct SeqsVisitor<'a> {
+    seqs: &'a mut Vec<InstrSeqId>,
+}
+
+impl<'instr> Visitor<'instr> for SeqsVisitor<'_> {
+    fn start_instr_seq(&mut self, seq: &'instr InstrSeq) {
+        self.seqs.push(seq.id());
+    }
+}
+
+fn fold_seq(globals: &ModuleGlobals, instrs: &mut Vec<(Instr, InstrLocId)>) {
+    let mut i = 0;
+    while i < instrs.len() {
+        match fold_at(globals, instrs, i) {
+            Some((start, replacement)) => {
+                let loc = instrs[i].1;
+                let len = replacement.len();
+                instrs.splice(
+                    start..i + 1,
+                    replacement.into_iter().map(|instr| (instr, loc)),
+                );
+                // The replacement may in turn be an operand of what follows.
+                i = start + len;
+            }
+            None => i += 1,
+        }
+    }
+}
+
+/// Try to fold the instruction at `i` along with the constants before it.
+///
+/// Returns the index of the first instruction that is folded, and what
+/// replaces the instructions from there up to and including `i`.
+fn fold_at(
+    globals: &ModuleGlobals,
+    instrs: &[(Instr, InstrLocId)],
+    i: usize,
+) -> Option<(usize, Vec<Instr>)> {
+    let constant = |back: usize| -> Option<Value> {
+        match instrs.get(i.checked_sub(back)?) {
+            Some((Instr::Const(Const { value }), _)) => Some(*value),
+            _ => None,
+        }
+    };
+    let condition = || match constant(1)? {
+        Value::I32(c) => Some(c != 0),
+        _ => None,
+    };
+
+    match &instrs[i].0 {
+        Instr::GlobalGet(GlobalGet { global }) => {
+            let global = globals.get(*global);
+            match &global.kind {
+                GlobalKind::Local(ConstExpr::Value(value)) if !global.mutable => {
+                    Some((i, vec![Const { value: *value }.into()]))
+                }
+                _ => None,
+            }
+        }
+
+        Instr::Unop(Unop { op }) => {
+            let value = eval_unop(*op, constant(1)?)?;
+            Some((i - 1, vec![Const { value }.into()]))
+        }
+
+        Instr::Binop(Binop { op }) => {
+            let rhs = constant(1)?;
+            match constant(2) {
+                Some(lhs) => {
+                    let value = eval_binop(*op, lhs, rhs)?;
+                    Some((i - 2, vec![Const { value }.into()]))
+                }
+                None if is_right_identity(*op, rhs) => Some((i - 1, vec![])),
+                None => None,
+            }
+        }
+
+        Instr::Select(_) => {
+            if condition()? {
+                return Some((i - 1, vec![Drop {}.into()]));
+            }
+            // The second operand is picked, and the first has to be dropped
+            // from under it. That's only possible when the second one is made
+            // by a single instruction that can move past the drop.
+            let second = i.checked_sub(2)?;
+            match &instrs[second].0 {
+                instr @ (Instr::Const(_)
+                | Instr::LocalGet(_)
+                | Instr::GlobalGet(_)
+                | Instr::RefNull(_)
+                | Instr::RefFunc(_)) => Some((second, vec![Drop {}.into(), instr.clone()])),
+                _ => None,
+            }
+        }
+
+        Instr::IfElse(IfElse {
+            consequent,
+            alternative,
+        }) => {
+            let seq = if condition()? {
+                *consequent
+            } else {
+                *alternative
+            };
+            Some((i - 1, vec![Block { seq }.into()]))
+        }
+
+        Instr::BrIf(BrIf { block }) => {
+            if condition()? {
+                Some((i - 1, vec![Br { block: *block }.into()]))
+            } else {
+                Some((i - 1, vec![]))
+            }
+        }
+
+        _ => None,
+    }
+}
+
+/// Is `x op rhs` always `x`?
+fn is_right_identity(op: BinaryOp, rhs: Value) -> bool {
+    use BinaryOp::*;
+    match rhs {
+        Value::I32(0) => matches!(
+            op,
+            I32Add | I32Sub | I32Or | I32Xor | I32Shl | I32ShrS | I32ShrU | I32Rotl | I32Rotr
+        ),
+        Value::I32(1) => matches!(op, I32Mul | I32DivS | I32DivU),
+        Value::I32(-1) => matches!(op, I32And),
+        Value::I64(0) => matches!(
+            op,
+            I64Add | I64Sub | I64Or | I64Xor | I64Shl | I64ShrS | I64ShrU | I64Rotl | I64Rotr
+        ),
+        Value::I64(1) => matches!(op, I64Mul | I64DivS | I64DivU),
+        Value::I64(-1) => matches!(op, I64And),
+        _ => false,
+    }
+}
+
+const F32_SIGN: u32 = 1 << 31;
+const F64_SIGN: u64 = 1 << 63;
+
+/// Evaluate a unary operator, unless it traps, produces a NaN, or isn't
+/// supported.
+fn eval_unop(op: UnaryOp, value: Value) -> Option<Value> {
+    use UnaryOp::*;
+    use Value::*;
+
+    let value = match (op, value) {
+        (I32Eqz, I32(x)) => I32((x == 0) as i32),
+        (I32Clz, I32(x)) => I32(x.leading_zeros() as i32),
+        (I32Ctz, I32(x)) => I32(x.trailing_zeros() as i32),
+        (I32Popcnt, I32(x)) => I32(x.count_ones() as i32),
+        (I64Eqz, I64(x)) => I32((x == 0) as i32),
+        (I64Clz, I64(x)) => I64(x.leading_zeros() as i64),
+        (I64Ctz, I64(x)) => I64(x.trailing_zeros() as i64),
+        (I64Popcnt, I64(x)) => I64(x.count_ones() as i64),
+
+        // These only touch the sign bit, even of NaNs.
+        (F32Abs, F32(x)) => return Some(F32(f32::from_bits(x.to_bits() & !F32_SIGN))),
+        (F32Neg, F32(x)) => return Some(F32(f32::from_bits(x.to_bits() ^ F32_SIGN))),
+        (F64Abs, F64(x)) => return Some(F64(f64::from_bits(x.to_bits() & !F64_SIGN))),
+        (F64Neg, F64(x)) => return Some(F64(f64::from_bits(x.to_bits() ^ F64_SIGN))),
+
+        (F32Ceil, F32(x)) => F32(x.ceil()),
+        (F32Floor, F32(x)) => F32(x.floor()),
+        (F32Trunc, F32(x)) => F32(x.trunc()),
+        (F32Nearest, F32(x)) => F32(x.round_ties_even()),
+        (F32Sqrt, F32(x)) => F32(x.sqrt()),
+        (F64Ceil, F64(x)) => F64(x.ceil()),
+        (F64Floor, F64(x)) => F64(x.floor()),
+        (F64Trunc, F64(x)) => F64(x.trunc()),
+        (F64Nearest, F64(x)) => F64(x.round_ties_even()),
+        (F64Sqrt, F64(x)) => F64(x.sqrt()),
+
+        (I32WrapI64, I64(x)) => I32(x as i32),
+        (I32TruncSF32, F32(x)) => I32(trunc(x as f64, -2147483648.0, 2147483648.0)? as i32),
+        (I32TruncUF32, F32(x)) => I32(trunc(x as f64, 0.0, 4294967296.0)? as u32 as i32),
+        (I32TruncSF64, F64(x)) => I32(trunc(x, -2147483648.0, 2147483648.0)? as i32),
+        (I32TruncUF64, F64(x)) => I32(trunc(x, 0.0, 4294967296.0)? as u32 as i32),
+        (I64ExtendSI32, I32(x)) => I64(x as i64),
+        (I64ExtendUI32, I32(x)) => I64(x as u32 as i64),
+        (I64TruncSF32, F32(x)) => {
+            I64(trunc(x as f64, -9223372036854775808.0, 9223372036854775808.0)? as i64)
+        }
+        (I64TruncUF32, F32(x)) => I64(trunc(x as f64, 0.0, 18446744073709551616.0)? as u64 as i64),
+        (I64TruncSF64, F64(x)) => {
+            I64(trunc(x, -9223372036854775808.0, 9223372036854775808.0)? as i64)
+        }
+        (I64TruncUF64, F64(x)) => I64(trunc(x, 0.0, 18446744073709551616.0)? as u64 as i64),
+
+        // Saturating truncation is exactly what `as` does.
+        (I32TruncSSatF32, F32(x)) => I32(x as i32),
+        (I32TruncUSatF32, F32(x)) => I32(x as u32 as i32),
+        (I32TruncSSatF64, F64(x)) => I32(x as i32),
+        (I32TruncUSatF64, F64(x)) => I32(x as u32 as i32),
+        (I64TruncSSatF32, F32(x)) => I64(x as i64),
+        (I64TruncUSatF32, F32(x)) => I64(x as u64 as i64),
+        (I64TruncSSatF64, F64(x)) => I64(x as i64),
+        (I64TruncUSatF64, F64(x)) => I64(x as u64 as i64),
+
+        (F32ConvertSI32, I32(x)) => F32(x as f32),
+        (F32ConvertUI32, I32(x)) => F32(x as u32 as f32),
+        (F32ConvertSI64, I64(x)) => F32(x as f32),
+        (F32ConvertUI64, I64(x)) => F32(x as u64 as f32),
+        (F32DemoteF64, F64(x)) => F32(x as f32),
+        (F64ConvertSI32, I32(x)) => F64(x as f64),
+        (F64ConvertUI32, I32(x)) => F64(x as u32 as f64),
+        (F64ConvertSI64, I64(x)) => F64(x as f64),
+        (F64ConvertUI64, I64(x)) => F64(x as u64 as f64),
+        (F64PromoteF32, F32(x)) => F64(x as f64),
+
+        // Reinterpreting keeps the bits of NaNs too.
+        (I32ReinterpretF32, F32(x)) => return Some(I32(x.to_bits() as i32)),
+        (I64ReinterpretF64, F64(x)) => return Some(I64(x.to_bits() as i64)),
+        (F32ReinterpretI32, I32(x)) => return Some(F32(f32::from_bits(x as u32))),
+        (F64ReinterpretI64, I64(x)) => return Some(F64(f64::from_bits(x as u64))),
+
+        (I32Extend8S, I32(x)) => I32(x as i8 as i32),
+        (I32Extend16S, I32(x)) => I32(x as i16 as i32),
+        (I64Extend8S, I64(x)) => I64(x as i8 as i64),
+        (I64Extend16S, I64(x)) => I64(x as i16 as i64),
+        (I64Extend32S, I64(x)) => I64(x as i32 as i64),
+
+        (V128Not, V128(x)) => V128(!x),
+
+        _ => return None,
+    };
+    if is_nan(value) {
+        return None;
+    }
+    Some(value)
+}
+
+/// The truncation of `x`, unless that is NaN or not in `min..max`, in which
+/// case the truncation traps.
+fn trunc(x: f64, min: f64, max: f64) -> Option<f64> {
+    let t = x.trunc();
+    if t.is_nan() || t < min || t >= max {
+        return None;
+    }
+    Some(t)
+}
+
+/// Evaluate a binary operator, unless it traps, produces a NaN, or isn't
+/// supported.
+fn eval_binop(op: BinaryOp, lhs: Value, rhs: Value) -> Option<Value> {
+    use BinaryOp::*;
+    use Value::*;
+
+    let value = match (op, lhs, rhs) {
+        (I32Eq, I32(a), I32(b)) => I32((a == b) as i32),
+        (I32Ne, I32(a), I32(b)) => I32((a != b) as i32),
+        (I32LtS, I32(a), I32(b)) => I32((a < b) as i32),
+        (I32LtU, I32(a), I32(b)) => I32(((a as u32) < (b as u32)) as i32),
+        (I32GtS, I32(a), I32(b)) => I32((a > b) as i32),
+        (I32GtU, I32(a), I32(b)) => I32((a as u32 > b as u32) as i32),
+        (I32LeS, I32(a), I32(b)) => I32((a <= b) as i32),
+        (I32LeU, I32(a), I32(b)) => I32((a as u32 <= b as u32) as i32),
+        (I32GeS, I32(a), I32(b)) => I32((a >= b) as i32),
+        (I32GeU, I32(a), I32(b)) => I32((a as u32 >= b as u32) as i32),
+
+        (I64Eq, I64(a), I64(b)) => I32((a == b) as i32),
+        (I64Ne, I64(a), I64(b)) => I32((a != b) as i32),
+        (I64LtS, I64(a), I64(b)) => I32((a < b) as i32),
+        (I64LtU, I64(a), I64(b)) => I32(((a as u64) < (b as u64)) as i32),
+        (I64GtS, I64(a), I64(b)) => I32((a > b) as i32),
+        (I64GtU, I64(a), I64(b)) => I32((a as u64 > b as u64) as i32),
+        (I64LeS, I64(a), I64(b)) => I32((a <= b) as i32),
+        (I64LeU, I64(a), I64(b)) => I32((a as u64 <= b as u64) as i32),
+        (I64GeS, I64(a), I64(b)) => I32((a >= b) as i32),
+        (I64GeU, I64(a), I64(b)) => I32((a as u64 >= b as u64) as i32),
+
+        (F32Eq, F32(a), F32(b)) => I32((a == b) as i32),
+        (F32Ne, F32(a), F32(b)) => I32((a != b) as i32),
+        (F32Lt, F32(a), F32(b)) => I32((a < b) as i32),
+        (F32Gt, F32(a), F32(b)) => I32((a > b) as i32),
+        (F32Le, F32(a), F32(b)) => I32((a <= b) as i32),
+        (F32Ge, F32(a), F32(b)) => I32((a >= b) as i32),
+
+        (F64Eq, F64(a), F64(b)) => I32((a == b) as i32),
+        (F64Ne, F64(a), F64(b)) => I32((a != b) as i32),
+        (F64Lt, F64(a), F64(b)) => I32((a < b) as i32),
+        (F64Gt, F64(a), F64(b)) => I32((a > b) as i32),
+        (F64Le, F64(a), F64(b)) => I32((a <= b) as i32),
+        (F64Ge, F64(a), F64(b)) => I32((a >= b) as i32),
+
+        (I32Add, I32(a), I32(b)) => I32(a.wrapping_add(b)),
+        (I32Sub, I32(a), I32(b)) => I32(a.wrapping_sub(b)),
+        (I32Mul, I32(a), I32(b)) => I32(a.wrapping_mul(b)),
+        (I32DivS, I32(a), I32(b)) => I32(a.checked_div(b)?),
+        (I32DivU, I32(a), I32(b)) => I32((a as u32).checked_div(b as u32)? as i32),
+        (I32RemS, I32(a), I32(b)) if b != 0 => I32(a.wrapping_rem(b)),
+        (I32RemU, I32(a), I32(b)) => I32((a as u32).checked_rem(b as u32)? as i32),
+        (I32And, I32(a), I32(b)) => I32(a & b),
+        (I32Or, I32(a), I32(b)) => I32(a | b),
+        (I32Xor, I32(a), I32(b)) => I32(a ^ b),
+        (I32Shl, I32(a), I32(b)) => I32(a.wrapping_shl(b as u32)),
+        (I32ShrS, I32(a), I32(b)) => I32(a.wrapping_shr(b as u32)),
+        (I32ShrU, I32(a), I32(b)) => I32((a as u32).wrapping_shr(b as u32) as i32),
+        (I32Rotl, I32(a), I32(b)) => I32(a.rotate_left(b as u32 % 32)),
+        (I32Rotr, I32(a), I32(b)) => I32(a.rotate_right(b as u32 % 32)),
+
+        (I64Add, I64(a), I64(b)) => I64(a.wrapping_add(b)),
+        (I64Sub, I64(a), I64(b)) => I64(a.wrapping_sub(b)),
+        (I64Mul, I64(a), I64(b)) => I64(a.wrapping_mul(b)),
+        (I64DivS, I64(a), I64(b)) => I64(a.checked_div(b)?),
+        (I64DivU, I64(a), I64(b)) => I64((a as u64).checked_div(b as u64)? as i64),
+        (I64RemS, I64(a), I64(b)) if b != 0 => I64(a.wrapping_rem(b)),
+        (I64RemU, I64(a), I64(b)) => I64((a as u64).checked_rem(b as u64)? as i64),
+        (I64And, I64(a), I64(b)) => I64(a & b),
+        (I64Or, I64(a), I64(b)) => I64(a | b),
+        (I64Xor, I64(a), I64(b)) => I64(a ^ b),
+        (I64Shl, I64(a), I64(b)) => I64(a.wrapping_shl(b as u32)),
+        (I64ShrS, I64(a), I64(b)) => I64(a.wrapping_shr(b as u32)),
+        (I64ShrU, I64(a), I64(b)) => I64((a as u64).wrapping_shr(b as u32) as i64),
+        (I64Rotl, I64(a), I64(b)) => I64(a.rotate_left((b as u64 % 64) as u32)),
+        (I64Rotr, I64(a), I64(b)) => I64(a.rotate_right((b as u64 % 64) as u32)),
+
+        (F32Add, F32(a), F32(b)) => F32(a + b),
+        (F32Sub, F32(a), F32(b)) => F32(a - b),
+        (F32Mul, F32(a), F32(b)) => F32(a * b),
+        (F32Div, F32(a), F32(b)) => F32(a / b),
+        (F32Min, F32(a), F32(b)) => F32(fmin(a as f64, b as f64)? as f32),
+        (F32Max, F32(a), F32(b)) => F32(fmax(a as f64, b as f64)? as f32),
+        (F32Copysign, F32(a), F32(b)) => {
+            let bits = (a.to_bits() & !F32_SIGN) | (b.to_bits() & F32_SIGN);
+            return Some(F32(f32::from_bits(bits)));
+        }
+
+        (F64Add, F64(a), F64(b)) => F64(a + b),
+        (F64Sub, F64(a), F64(b)) => F64(a - b),
+        (F64Mul, F64(a), F64(b)) => F64(a * b),
+        (F64Div, F64(a), F64(b)) => F64(a / b),
+        (F64Min, F64(a), F64(b)) => F64(fmin(a, b)?),
+        (F64Max, F64(a), F64(b)) => F64(fmax(a, b)?),
+        (F64Copysign, F64(a), F64(b)) => {
+            let bits = (a.to_bits() & !F64_SIGN) | (b.to_bits() & F64_SIGN);
+            return Some(F64(f64::from_bits(bits)));
+        }
+
+        (V128And, V128(a), V128(b)) => V128(a & b),
+        (V128Or, V128(a), V128(b)) => V128(a | b),
+        (V128Xor, V128(a), V128(b)) => V128(a ^ b),
+        (V128AndNot, V128(a), V128(b)) => V128(a & !b),
+
+        _ => return None,
+    };
+    if is_nan(value) {
+        return None;
+    }
+    Some(value)
+}
+
+/// The minimum of two floats, which wasm considers `-0` to be less than `+0`
+/// for. The result is NaN when either is, so that isn't folded.
+fn fmin(a: f64, b: f64) -> Option<f64> {
+    if a.is_nan() || b.is_nan() {
+        return None;
+    }
+    if a == b {
+        // Only differs for zeros of different signs.
+        return Some(f64::from_bits(a.to_bits() | b.to_bits()));
+    }
+    Some(if a < b { a } else { b })
+}
+
+/// The maximum of two floats, which wasm considers `+0` to be greater than
+/// `-0` for.
+fn fmax(a: f64, b: f64) -> Option<f64> {
+    if a.is_nan() || b.is_nan() {
+        return None;
+    }
+    if a == b {
+        return Some(f64::from_bits(a.to_bits() & b.to_bits()));
+    }
+    Some(if a > b { a } else { b })
+}
+
+fn is_nan(value: Value) -> bool {
+    match value {
+        Value::F32(x) => x.is_nan(),
+        Value::F64(x) => x.is_nan(),
+        _ => false,
+    }
+}
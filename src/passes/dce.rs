@@ -0,0 +1,203 @@
+//! Removes dead instructions from function bodies.
+//!
+//! Where `passes::gc` removes the items of a module that nothing uses, this
+//! pass cleans up within each local function, which is most useful after
+//! other transformations have left things behind:
+//!
+//! * instructions after an unconditional branch, `return`, `unreachable` or
+//!   `throw`, which can never run;
+//! * pure expressions whose result is dropped, as classified by
+//!   `Instr::is_pure`;
+//! * `block`s and `loop`s without any instructions, and `if`s whose arms have
+//!   no effects and never branch, when the `if` has no results or its single
+//!   result is dropped;
+//! * `local.set`s and `local.tee`s of locals that are never read.
+//!
+//! Anything that may have an effect, like a call, a store or an instruction
+//! that may trap, is kept, although its result may still be dropped.
+
+use crate::ir::*;
+use crate::map::IdHashSet;
+use crate::{LocalFunction, Module};
+
+/// Run dead code elimination over every local function of the module.
+pub fn run(module: &mut Module) {
+    for (_, func) in module.funcs.iter_local_mut() {
+        run_func(func);
+    }
+}
+
+/// Run dead code elimination over one function.
+pub(crate) fn run_func(func: &mut LocalFunction) {
+    // Removing one instruction can make others dead, so keep going until
+    // nothing changes anymore.
+    loop {
+        let mut visitor = Collect::default();
+        dfs_in_order(&mut visitor, func, func.entry_block());
+
+        // Nested sequences come after their parents, so going backwards
+        // cleans up a block before deciding whether it is empty.
+        let mut changed = false;
+        for &seq in visitor.seqs.iter().rev() {
+            let mut instrs = std::mem::take(&mut func.block_mut(seq).instrs);
+            changed |= clean_seq(func, &visitor.read, &mut instrs);
+            func.block_mut(seq).instrs = instrs;
+        }
+        if !changed {
+            break;
+        }
+    }
+}
+
+/// All the sequences of a function, in order, and the locals it reads.
+#[derive(Default)]
+struct Collect {
+    seqs: Vec<InstrSeqId>,
+    read: IdHashSet<Local>,
+}
+
+impl<'instr> Visitor<'instr> for Collect {
+    fn start_instr_seq(&mut self, seq: &'instr InstrSeq) {
+        self.seqs.push(seq.id());
+    }
+
+    fn visit_local_get(&mut self, instr: &LocalGet) {
+        self.read.insert(instr.local);
+    }
+}
+
+/// Remove the dead instructions of one sequence, and return whether anything
+/// changed.
+fn clean_seq(
+    func: &LocalFunction,
+    read: &IdHashSet<Local>,
+    instrs: &mut Vec<(Instr, InstrLocId)>,
+) -> bool {
+    let mut changed = false;
+
+    if let Some(end) = instrs
+        .iter()
+        .position(|(instr, _)| instr.following_instructions_are_unreachable())
+    {
+        changed |= end + 1 < instrs.len();
+        instrs.truncate(end + 1);
+    }
+
+    let mut i = 0;
+    while i < instrs.len() {
+        let loc = instrs[i].1;
+        let (start, replacement) = match &instrs[i].0 {
+            // The value that would have been stored still needs to be
+            // consumed, or passed along by a `local.tee`.
+            Instr::LocalSet(LocalSet { local }) if !read.contains(local) => {
+                (i, vec![Drop {}.into()])
+            }
+            Instr::LocalTee(LocalTee { local }) if !read.contains(local) => (i, vec![]),
+
+            Instr::Block(Block { seq }) | Instr::Loop(Loop { seq }) if is_empty(func, *seq) => {
+                (i, vec![])
+            }
+            // Only the condition is left to drop.
+            Instr::IfElse(IfElse {
+                consequent,
+                alternative,
+            }) if matches!(func.block(*consequent).ty, InstrSeqType::Simple(None))
+                && is_pure_seq(func, *consequent)
+                && is_pure_seq(func, *alternative) =>
+            {
+                (i, vec![Drop {}.into()])
+            }
+
+            Instr::Drop(_) if i > 0 => match &instrs[i - 1].0 {
+                Instr::LocalTee(LocalTee { local }) => {
+                    (i - 1, vec![LocalSet { local: *local }.into()])
+                }
+                Instr::IfElse(IfElse {
+                    consequent,
+                    alternative,
+                }) if matches!(func.block(*consequent).ty, InstrSeqType::Simple(Some(_)))
+                    && is_pure_seq(func, *consequent)
+                    && is_pure_seq(func, *alternative) =>
+                {
+                    (i - 1, vec![Drop {}.into()])
+                }
+                // Drop the operands of a pure instruction instead of its
+                // result, which in turn may remove what produced them.
+                instr if instr.is_pure() => match operands(instr) {
+                    Some(n) => (i - 1, vec![Drop {}.into(); n]),
+                    None => {
+                        i += 1;
+                        continue;
+                    }
+                },
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            },
+
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        changed = true;
+        instrs.splice(
+            start..i + 1,
+            replacement.into_iter().map(|instr| (instr, loc)),
+        );
+        // The new instructions may be dead as well.
+        i = start;
+    }
+
+    changed
+}
+
+/// Is `seq` a sequence without instructions that neither takes nor produces
+/// any values?
+fn is_empty(func: &LocalFunction, seq: InstrSeqId) -> bool {
+    let seq = func.block(seq);
+    seq.instrs.is_empty() && matches!(seq.ty, InstrSeqType::Simple(None))
+}
+
+/// Can `seq` run or not without changing what the function does?
+///
+/// This is the case when it only contains pure instructions, `drop`s, and
+/// blocks of those, so that it has no effects and never branches.
+fn is_pure_seq(func: &LocalFunction, seq: InstrSeqId) -> bool {
+    func.block(seq).instrs.iter().all(|(instr, _)| match instr {
+        Instr::Drop(_) => true,
+        Instr::Block(Block { seq }) | Instr::Loop(Loop { seq }) => is_pure_seq(func, *seq),
+        Instr::IfElse(IfElse {
+            consequent,
+            alternative,
+        }) => is_pure_seq(func, *consequent) && is_pure_seq(func, *alternative),
+        instr => instr.is_pure(),
+    })
+}
+
+/// The number of operands of a pure instruction that produces one value, if it
+/// doesn't depend on the types in the module.
+fn operands(instr: &Instr) -> Option<usize> {
+    match instr {
+        Instr::LocalGet(..)
+        | Instr::GlobalGet(..)
+        | Instr::Const(..)
+        | Instr::MemorySize(..)
+        | Instr::TableSize(..)
+        | Instr::RefNull(..)
+        | Instr::RefFunc(..) => Some(0),
+        Instr::Unop(..)
+        | Instr::RefIsNull(..)
+        | Instr::RefI31(..)
+        | Instr::RefTest(..)
+        | Instr::AnyConvertExtern(..)
+        | Instr::ExternConvertAny(..) => Some(1),
+        Instr::Binop(..) | Instr::RefEq(..) | Instr::I8x16Swizzle(..) | Instr::I8x16Shuffle(..) => {
+            Some(2)
+        }
+        Instr::TernOp(..) | Instr::Select(..) | Instr::V128Bitselect(..) => Some(3),
+        Instr::ArrayNewFixed(ArrayNewFixed { len, .. }) => Some(*len as usize),
+        _ => None,
+    }
+}
@@ -0,0 +1,306 @@
+//! Merges local functions that have identical bodies.
+//!
+//! Two functions are identical when they have the same type, their locals
+//! have the same types, and their bodies are the same instruction for
+//! instruction, once each function's locals are numbered in the order they
+//! are first used. Monomorphized generic code tends to contain many such
+//! functions.
+//!
+//! Every reference to a duplicate (calls, `ref.func`, element segments,
+//! exports, the start function and constant expressions) is redirected to one
+//! canonical copy, the first in the module, and the duplicates are deleted.
+//! Merging functions can make their callers identical as well, so this repeats
+//! until there is nothing left to merge.
+
+use crate::ir::*;
+use crate::map::{IdHashMap, IdHashSet};
+use crate::{
+    ElementItems, ExportItem, Function, FunctionId, GlobalKind, LocalFunction, Module, TypeId,
+    ValType,
+};
+use std::collections::HashMap;
+use std::mem::Discriminant;
+
+/// Merge the identical local functions of the module.
+pub fn run(module: &mut Module) {
+    loop {
+        let duplicates = find_duplicates(module);
+        if duplicates.is_empty() {
+            break;
+        }
+        log::debug!("merging {} duplicate functions", duplicates.len());
+        redirect(module, &duplicates);
+        for &duplicate in duplicates.keys() {
+            module.funcs.delete(duplicate);
+        }
+    }
+}
+
+/// Map each local function that is identical to an earlier one to that
+/// function.
+fn find_duplicates(module: &Module) -> IdHashMap<Function, FunctionId> {
+    let mut buckets: HashMap<Shape, Vec<(FunctionId, Body)>> = HashMap::new();
+    let mut duplicates = IdHashMap::default();
+    for (id, func) in module.funcs.iter_local() {
+        let body = Body::new(func);
+        let bucket = buckets.entry(Shape::new(module, func, &body)).or_default();
+        match bucket
+            .iter()
+            .find(|(other, other_body)| identical(module, *other, other_body, id, &body))
+        {
+            Some((canonical, _)) => {
+                duplicates.insert(id, *canonical);
+            }
+            None => bucket.push((id, body)),
+        }
+    }
+    duplicates
+}
+
+/// The locals and instruction sequences of a function, in the order they are
+/// first used.
+struct Body {
+    locals: Vec<LocalId>,
+    seqs: Vec<InstrSeqId>,
+}
+
+impl Body {
+    fn new(func: &LocalFunction) -> Body {
+        struct Collect<'a> {
+            body: Body,
+            seen: IdHashSet<Local>,
+            func: &'a LocalFunction,
+        }
+
+        impl<'instr> Visitor<'instr> for Collect<'_> {
+            fn start_instr_seq(&mut self, seq: &'instr InstrSeq) {
+                self.body.seqs.push(seq.id());
+            }
+
+            fn visit_local_id(&mut self, local: &LocalId) {
+                if self.seen.insert(*local) && !self.func.args.contains(local) {
+                    self.body.locals.push(*local);
+                }
+            }
+        }
+
+        let mut visitor = Collect {
+            body: Body {
+                locals: func.args.clone(),
+                seqs: Vec::new(),
+            },
+            seen: IdHashSet::default(),
+            func,
+        };
+        dfs_in_order(&mut visitor, func, func.entry_block());
+        visitor.body
+    }
+}
+
+/// A summary of a function that identical functions share, to only compare
+/// functions that are likely to be identical.
+#[derive(PartialEq, Eq, Hash)]
+struct Shape {
+    ty: TypeId,
+    locals: Vec<ValType>,
+    instrs: Vec<Discriminant<Instr>>,
+}
+
+impl Shape {
+    fn new(module: &Module, func: &LocalFunction, body: &Body) -> Shape {
+        Shape {
+            ty: func.ty(),
+            locals: body
+                .locals
+                .iter()
+                .map(|l| module.locals.get(*l).ty())
+                .collect(),
+            instrs: body
+                .seqs
+                .iter()
+                .flat_map(|seq| func.block(*seq).instrs.iter())
+                .map(|(instr, _)| std::mem::discriminant(instr))
+                .collect(),
+        }
+    }
+}
+
+/// Are the bodies of `a` and `b` the same, up to the numbering of their
+/// locals and instruction sequences? Their shapes are already known to be
+/// equal.
+fn identical(module: &Module, a: FunctionId, a_body: &Body, b: FunctionId, b_body: &Body) -> bool {
+    let a_func = module.funcs.get(a).kind.unwrap_local();
+    let b_func = module.funcs.get(b).kind.unwrap_local();
+    if a_body.seqs.len() != b_body.seqs.len() {
+        return false;
+    }
+    let locals = b_body
+        .locals
+        .iter()
+        .cloned()
+        .zip(a_body.locals.iter().cloned())
+        .collect::<IdHashMap<_, _>>();
+    let seqs = b_body
+        .seqs
+        .iter()
+        .cloned()
+        .zip(a_body.seqs.iter().cloned())
+        .collect::<IdHashMap<_, _>>();
+    let mut remap = Remap {
+        locals: &locals,
+        seqs: &seqs,
+    };
+
+    for (&a_seq, &b_seq) in a_body.seqs.iter().zip(b_body.seqs.iter()) {
+        let (a_seq, b_seq) = (a_func.block(a_seq), b_func.block(b_seq));
+        if a_seq.ty != b_seq.ty || a_seq.instrs.len() != b_seq.instrs.len() {
+            return false;
+        }
+        for ((a_instr, _), (b_instr, _)) in a_seq.instrs.iter().zip(b_seq.instrs.iter()) {
+            // Floats only show up in constants, where `==` would merge `0.0`
+            // with `-0.0` and never merge NaNs.
+            if let (Instr::Const(Const { value: a }), Instr::Const(Const { value: b })) =
+                (a_instr, b_instr)
+            {
+                if !same_value(a, b) {
+                    return false;
+                }
+                continue;
+            }
+            let mut b_instr = b_instr.clone();
+            b_instr.visit_mut(&mut remap);
+            remap.catches(&mut b_instr);
+            if *a_instr != b_instr {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Are two constants the same, down to the bits of floats?
+fn same_value(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::I32(a), Value::I32(b)) => a == b,
+        (Value::I64(a), Value::I64(b)) => a == b,
+        (Value::F32(a), Value::F32(b)) => a.to_bits() == b.to_bits(),
+        (Value::F64(a), Value::F64(b)) => a.to_bits() == b.to_bits(),
+        (Value::V128(a), Value::V128(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Renames the locals and sequences of one function to those of another.
+struct Remap<'a> {
+    locals: &'a IdHashMap<Local, LocalId>,
+    seqs: &'a IdHashMap<InstrSeq, InstrSeqId>,
+}
+
+impl Remap<'_> {
+    /// The sequences of `catch` clauses aren't visited.
+    fn catches(&self, instr: &mut Instr) {
+        match instr {
+            Instr::Try(Try { catches, .. }) => {
+                for catch in catches.iter_mut() {
+                    match catch {
+                        LegacyCatch::Catch { handler, .. } | LegacyCatch::CatchAll { handler } => {
+                            *handler = self.seqs[handler]
+                        }
+                        LegacyCatch::Delegate { .. } => {}
+                    }
+                }
+            }
+            Instr::TryTable(TryTable { catches, .. }) => {
+                for catch in catches.iter_mut() {
+                    match catch {
+                        TryTableCatch::Catch { label, .. }
+                        | TryTableCatch::CatchRef { label, .. }
+                        | TryTableCatch::CatchAll { label }
+                        | TryTableCatch::CatchAllRef { label } => *label = self.seqs[label],
+                    }
+                }
+            }
+            Instr::Resume(Resume { handlers, .. })
+            | Instr::ResumeThrow(ResumeThrow { handlers, .. })
+            | Instr::ResumeThrowRef(ResumeThrowRef { handlers, .. }) => {
+                for handler in handlers.iter_mut() {
+                    if let ResumeHandler::OnLabel { label, .. } = handler {
+                        *label = self.seqs[label];
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl VisitorMut for Remap<'_> {
+    // `Instr::visit_mut` can hand the same id to the visitor more than once,
+    // so ids that were already renamed are left alone.
+    fn visit_local_id_mut(&mut self, local: &mut LocalId) {
+        if let Some(new) = self.locals.get(local) {
+            *local = *new;
+        }
+    }
+
+    fn visit_instr_seq_id_mut(&mut self, seq: &mut InstrSeqId) {
+        if let Some(new) = self.seqs.get(seq) {
+            *seq = *new;
+        }
+    }
+}
+
+/// Replace every reference to a duplicate with its canonical function.
+fn redirect(module: &mut Module, duplicates: &IdHashMap<Function, FunctionId>) {
+    let canonical = |func: &mut FunctionId| {
+        if let Some(c) = duplicates.get(func) {
+            *func = *c;
+        }
+    };
+
+    for (_, func) in module.funcs.iter_local_mut() {
+        let entry = func.entry_block();
+        dfs_pre_order_mut(&mut Redirect { duplicates }, func, entry);
+    }
+    for export in module.exports.iter_mut() {
+        if let ExportItem::Function(f) = &mut export.item {
+            canonical(f);
+        }
+    }
+    if let Some(start) = &mut module.start {
+        canonical(start);
+    }
+    for elem in module.elements.iter_mut() {
+        match &mut elem.items {
+            ElementItems::Functions(funcs) => funcs.iter_mut().for_each(canonical),
+            ElementItems::Expressions(_, exprs) => {
+                for expr in exprs {
+                    expr.funcs_mut().for_each(canonical);
+                }
+            }
+        }
+    }
+    let globals = module.globals.iter().map(|g| g.id()).collect::<Vec<_>>();
+    for global in globals {
+        if let GlobalKind::Local(init) = &mut module.globals.get_mut(global).kind {
+            init.funcs_mut().for_each(canonical);
+        }
+    }
+    for table in module.tables.iter_mut() {
+        if let Some(init) = &mut table.init {
+            init.funcs_mut().for_each(canonical);
+        }
+    }
+}
+
+struct Redirect<'a> {
+    duplicates: &'a IdHashMap<Function, FunctionId>,
+}
+
+impl VisitorMut for Redirect<'_> {
+    fn visit_function_id_mut(&mut self, func: &mut FunctionId) {
+        if let Some(canonical) = self.duplicates.get(func) {
+            *func = *canonical;
+        }
+    }
+}
@@ -5,11 +5,112 @@
 
 use crate::map::IdHashSet;
 use crate::passes::used::Used;
-use crate::{ImportKind, Module};
+use crate::{Export, ImportKind, Module};
 use id_arena::Id;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Type alias for the predicate that decides which exports are roots.
+type ExportFilterFn = Box<dyn Fn(&Export) -> bool + Send + Sync + 'static>;
+
+/// Which items of a module the GC pass keeps even when nothing in the module
+/// references them.
+///
+/// By default every export and the start function are roots, which is what
+/// `run` uses. Exports that aren't roots are removed from the module, and so
+/// is the start function when it isn't one. Items that custom sections add
+/// through `CustomSection::add_gc_roots` are always kept.
+#[derive(Default)]
+pub struct RootPolicy {
+    exports: Option<ExportFilterFn>,
+    no_start: bool,
+}
+
+impl fmt::Debug for RootPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let RootPolicy {
+            ref exports,
+            no_start,
+        } = self;
+
+        f.debug_struct("RootPolicy")
+            .field("exports", &exports.as_ref().map(|_| ".."))
+            .field("no_start", no_start)
+            .finish()
+    }
+}
+
+impl RootPolicy {
+    /// Creates a new policy, which treats every export and the start function
+    /// as roots.
+    pub fn new() -> RootPolicy {
+        RootPolicy::default()
+    }
+
+    /// Only keep the exports with the given names.
+    ///
+    /// This replaces any previous `keep_exports` or `keep_exports_matching`.
+    pub fn keep_exports<I, S>(&mut self, names: I) -> &mut RootPolicy
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let names = names.into_iter().map(Into::into).collect::<HashSet<_>>();
+        self.keep_exports_matching(move |export| names.contains(&export.name))
+    }
+
+    /// Only keep the exports for which the given function returns `true`.
+    ///
+    /// This replaces any previous `keep_exports` or `keep_exports_matching`.
+    pub fn keep_exports_matching<F>(&mut self, f: F) -> &mut RootPolicy
+    where
+        F: Fn(&Export) -> bool + Send + Sync + 'static,
+    {
+        self.exports = Some(Box::new(f) as _);
+        self
+    }
+
+    /// Whether the start function is a root.
+    ///
+    /// When it isn't, the module no longer has a start function, and the
+    /// function itself is only kept if something else uses it.
+    pub fn start(&mut self, root: bool) -> &mut RootPolicy {
+        self.no_start = !root;
+        self
+    }
+
+    /// Remove the exports and start function that aren't roots.
+    fn prune(&self, m: &mut Module) {
+        if let Some(keep) = &self.exports {
+            let pruned = m
+                .exports
+                .iter()
+                .filter(|export| !keep(export))
+                .map(|export| export.id())
+                .collect::<Vec<_>>();
+            for id in pruned {
+                log::debug!("removing export {:?}", m.exports.get(id).name);
+                m.exports.delete(id);
+            }
+        }
+        if self.no_start {
+            m.start = None;
+        }
+    }
+}
 
 /// Run GC passes over the module specified.
 pub fn run(m: &mut Module) {
+    run_with_roots(m, &RootPolicy::default())
+}
+
+/// Run GC passes over the module specified, with the given roots.
+///
+/// This first removes the exports, and possibly the start function, that
+/// aren't roots according to `roots`, and then everything that is no longer
+/// used.
+pub fn run_with_roots(m: &mut Module, roots: &RootPolicy) {
+    roots.prune(m);
     let used = Used::new(m);
 
     let mut unused_imports = Vec::new();
@@ -0,0 +1,515 @@
+//! Inlines functions into the functions that call them.
+//!
+//! Inlining replaces a `call` of a local function with a copy of the callee's
+//! body, wrapped in a `block` that its `return`s branch out of. Which calls are
+//! inlined is decided by the policies of an `Inliner`:
+//!
+//! ```
+//! # fn foo(module: &mut walrus::Module) {
+//! use walrus::passes::inline::Inliner;
+//!
+//! Inliner::new()
+//!     .max_size(10)
+//!     .inline_single_caller(true)
+//!     .run(module);
+//!
+//! // Functions that were inlined into all of their callers aren't used
+//! // anymore, and the GC pass removes them.
+//! walrus::passes::gc::run(module);
+//! # }
+//! ```
+
+use crate::analysis::CallGraph;
+use crate::error::Result;
+use crate::ir::*;
+use crate::map::{IdHashMap, IdHashSet};
+use crate::passes::Pass;
+use crate::{
+    ElementItems, ExportItem, FunctionId, FunctionKind, GlobalKind, LocalFunction, Module,
+    ModuleLocals, ValType,
+};
+use std::fmt;
+
+/// Type alias for the `should_inline` callback function.
+type ShouldInlineFn = Box<dyn Fn(&Module, FunctionId, FunctionId) -> bool + Send + Sync + 'static>;
+
+/// Configuration for inlining functions into their callers.
+///
+/// A call is inlined when any of the enabled policies asks for it. By default
+/// none are, and nothing is inlined.
+///
+/// Only direct calls (`call` and `return_call`) of local functions are
+/// inlined, and never from one function into another that it is mutually
+/// recursive with. Callers are visited bottom-up in the call graph, so a
+/// function's own calls are inlined before it is inlined anywhere else.
+#[derive(Default)]
+pub struct Inliner {
+    max_size: Option<u64>,
+    single_caller: bool,
+    should_inline: Option<ShouldInlineFn>,
+}
+
+impl fmt::Debug for Inliner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Inliner {
+            max_size,
+            single_caller,
+            ref should_inline,
+        } = self;
+
+        f.debug_struct("Inliner")
+            .field("max_size", max_size)
+            .field("single_caller", single_caller)
+            .field("should_inline", &should_inline.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+impl Inliner {
+    /// Creates a new inliner, with every policy disabled.
+    pub fn new() -> Inliner {
+        Inliner::default()
+    }
+
+    /// Always inline functions with at most `size` instructions, as counted
+    /// by `LocalFunction::size`.
+    pub fn max_size(&mut self, size: u64) -> &mut Inliner {
+        self.max_size = Some(size);
+        self
+    }
+
+    /// Inline functions that are called from exactly one place, and that
+    /// aren't otherwise used: they aren't exported, the start function, or
+    /// referenced by an element segment or `ref.func`.
+    ///
+    /// Such functions aren't used anymore once inlined, so this doesn't make
+    /// the module any bigger after a GC pass.
+    pub fn inline_single_caller(&mut self, enable: bool) -> &mut Inliner {
+        self.single_caller = enable;
+        self
+    }
+
+    /// Inline a call whenever the given function returns `true`, given the
+    /// module, the caller and the callee.
+    ///
+    /// Note that only one `should_inline` function may be registered and
+    /// subsequent registrations will override the old ones.
+    pub fn should_inline<F>(&mut self, f: F) -> &mut Inliner
+    where
+        F: Fn(&Module, FunctionId, FunctionId) -> bool + Send + Sync + 'static,
+    {
+        self.should_inline = Some(Box::new(f) as _);
+        self
+    }
+
+    /// Inline calls throughout the given module.
+    ///
+    /// Inlined functions are left in the module, even when they aren't called
+    /// anymore; run `passes::gc` afterwards to remove them.
+    pub fn run(&self, module: &mut Module) {
+        let graph = CallGraph::new(module);
+        let mut uses = Uses::new(module);
+        let mut inlined = 0;
+
+        for scc in graph.sccs() {
+            for &caller in scc {
+                if !matches!(module.funcs.get(caller).kind, FunctionKind::Local(_)) {
+                    continue;
+                }
+                let func = module.funcs.get(caller).kind.unwrap_local();
+                let mut seqs = Vec::new();
+                let mut visitor = SeqsVisitor { seqs: &mut seqs };
+                dfs_in_order(&mut visitor, func, func.entry_block());
+
+                for seq in seqs {
+                    let mut index = 0;
+                    loop {
+                        let func = module.funcs.get(caller).kind.unwrap_local();
+                        let (callee, tail) = match func.block(seq).instrs.get(index) {
+                            None => break,
+                            Some((Instr::Call(Call { func }), _)) => (*func, false),
+                            Some((Instr::ReturnCall(ReturnCall { func }), _)) => (*func, true),
+                            Some(_) => {
+                                index += 1;
+                                continue;
+                            }
+                        };
+                        if scc.contains(&callee) || !self.wants(module, &uses, caller, callee) {
+                            index += 1;
+                            continue;
+                        }
+                        log::debug!("inlining {:?} into {:?}", callee, caller);
+                        uses.inlined(module, callee);
+                        index += inline_call(module, caller, seq, index, callee, tail);
+                        inlined += 1;
+                    }
+                }
+            }
+        }
+        log::debug!("inlined {} calls", inlined);
+    }
+
+    fn wants(&self, module: &Module, uses: &Uses, caller: FunctionId, callee: FunctionId) -> bool {
+        let func = match &module.funcs.get(callee).kind {
+            FunctionKind::Local(func) => func,
+            _ => return false,
+        };
+        if self.max_size.is_some_and(|max| func.size() <= max) {
+            return true;
+        }
+        if self.single_caller && uses.is_single_caller(callee) {
+            return true;
+        }
+        self.should_inline
+            .as_ref()
+            .is_some_and(|f| f(module, caller, callee))
+    }
+}
+
+impl Pass for Inliner {
+    fn name(&self) -> &str {
+        "inline"
+    }
+
+    fn run(&self, module: &mut Module) -> Result<()> {
+        Inliner::run(self, module);
+        Ok(())
+    }
+}
+
+/// How many times each function is called directly, and which functions are
+/// used in other ways.
+struct Uses {
+    call_sites: IdHashMap<crate::Function, usize>,
+    referenced: IdHashSet<crate::Function>,
+}
+
+impl Uses {
+    fn new(module: &Module) -> Uses {
+        let mut uses = Uses {
+            call_sites: IdHashMap::default(),
+            referenced: IdHashSet::default(),
+        };
+        for export in module.exports.iter() {
+            if let ExportItem::Function(f) = export.item {
+                uses.referenced.insert(f);
+            }
+        }
+        uses.referenced.extend(module.start);
+        for global in module.globals.iter() {
+            if let GlobalKind::Local(init) = &global.kind {
+                uses.referenced.extend(init.funcs());
+            }
+        }
+        for table in module.tables.iter() {
+            if let Some(init) = &table.init {
+                uses.referenced.extend(init.funcs());
+            }
+        }
+        for elem in module.elements.iter() {
+            match &elem.items {
+                ElementItems::Functions(funcs) => {
+                    uses.referenced.extend(funcs.iter().cloned());
+                }
+                ElementItems::Expressions(_, exprs) => {
+                    for expr in exprs {
+                        uses.referenced.extend(expr.funcs());
+                    }
+                }
+            }
+        }
+        for (_, func) in module.funcs.iter_local() {
+            uses.count(func);
+        }
+        uses
+    }
+
+    /// Count the uses within a function body.
+    fn count(&mut self, func: &LocalFunction) {
+        let mut visitor = UsesVisitor { uses: self };
+        dfs_in_order(&mut visitor, func, func.entry_block());
+    }
+
+    fn is_single_caller(&self, func: FunctionId) -> bool {
+        !self.referenced.contains(&func) && self.call_sites.get(&func) == Some(&1)
+    }
+
+    /// Update the counts for one call of `callee` being replaced by its body.
+    fn inlined(&mut self, module: &Module, callee: FunctionId) {
+        *self.call_sites.get_mut(&callee).unwrap() -= 1;
+        self.count(module.funcs.get(callee).kind.unwrap_local());
+    }
+}
+
+struct UsesVisitor<'a> {
+    uses: &'a mut Uses,
+}
+
+impl<'instr> Visitor<'instr> for UsesVisitor<'_> {
+    fn visit_instr(&mut self, instr: &'instr Instr, _: &'instr InstrLocId) {
+        match instr {
+            Instr::Call(Call { func }) | Instr::ReturnCall(ReturnCall { func }) => {
+                *self.uses.call_sites.entry(*func).or_insert(0) += 1;
+            }
+            Instr::RefFunc(RefFunc { func }) => {
+                self.uses.referenced.insert(*func);
+            }
+            _ => {}
+        }
+    }
+}
+
+struct SeqsVisitor<'a> {
+    seqs: &'a mut Vec<InstrSeqId>,
+}
+
+impl<'instr> Visitor<'instr> for SeqsVisitor<'_> {
+    fn start_instr_seq(&mut self, seq: &'instr InstrSeq) {
+        self.seqs.push(seq.id());
+    }
+}
+
+/// Replace the call of `callee` at `index` of `seq` in `caller` with the
+/// callee's body, and return how many instructions replaced it.
+fn inline_call(
+    module: &mut Module,
+    caller: FunctionId,
+    seq: InstrSeqId,
+    index: usize,
+    callee: FunctionId,
+    tail: bool,
+) -> usize {
+    let body = CalleeBody::new(module.funcs.get(callee).kind.unwrap_local());
+    // The type of a function's body is only for function entries, and can't
+    // be used by a block.
+    let results = module.types.results(module.funcs.get(callee).ty()).to_vec();
+    let block_ty = InstrSeqType::new(&mut module.types, &[], &results);
+    let caller_func = module.funcs.get_mut(caller).kind.unwrap_local_mut();
+    let caller_entry = caller_func.entry_block();
+    let loc = caller_func.block(seq).instrs[index].1;
+
+    // Give the callee's locals fresh ids in the caller.
+    let mut locals = IdHashMap::default();
+    for &arg in body.args.iter() {
+        locals.insert(arg, copy_local(&mut module.locals, arg));
+    }
+    let mut zeroed = Vec::new();
+    for &local in body.locals.iter() {
+        if locals.contains_key(&local) {
+            continue;
+        }
+        let new = copy_local(&mut module.locals, local);
+        locals.insert(local, new);
+        // The callee's locals start out with their default values in every
+        // call, but a copy of its body that runs more than once per call of
+        // the caller sees whatever the previous run left in them. At the top
+        // level of the caller's body, it runs once.
+        let ty = module.locals.get(new).ty();
+        if seq != caller_entry && ty.is_defaultable() {
+            zeroed.push((new, ty));
+        }
+    }
+
+    let mut seqs = IdHashMap::default();
+    for seq in body.seqs.iter() {
+        let ty = if seq.id == body.entry {
+            block_ty
+        } else {
+            seq.ty
+        };
+        let new = caller_func.builder_mut().dangling_instr_seq(ty).id();
+        seqs.insert(seq.id, new);
+    }
+    let entry = seqs[&body.entry];
+
+    let mut remap = Remap {
+        locals: &locals,
+        seqs: &seqs,
+    };
+    for seq in body.seqs {
+        let mut new_instrs = Vec::with_capacity(seq.instrs.len());
+        if seq.id == body.entry {
+            for (local, ty) in zeroed.iter() {
+                new_instrs.push((default_value(*ty), loc));
+                new_instrs.push((Instr::LocalSet(LocalSet { local: *local }), loc));
+            }
+        }
+        for (mut instr, instr_loc) in seq.instrs {
+            instr.visit_mut(&mut remap);
+            match &mut instr {
+                Instr::Try(Try { catches, .. }) => {
+                    for catch in catches.iter_mut() {
+                        match catch {
+                            LegacyCatch::Catch { handler, .. }
+                            | LegacyCatch::CatchAll { handler } => *handler = seqs[handler],
+                            LegacyCatch::Delegate { .. } => {}
+                        }
+                    }
+                }
+                Instr::TryTable(TryTable { catches, .. }) => {
+                    for catch in catches.iter_mut() {
+                        match catch {
+                            TryTableCatch::Catch { label, .. }
+                            | TryTableCatch::CatchRef { label, .. }
+                            | TryTableCatch::CatchAll { label }
+                            | TryTableCatch::CatchAllRef { label } => *label = seqs[label],
+                        }
+                    }
+                }
+                Instr::Resume(Resume { handlers, .. })
+                | Instr::ResumeThrow(ResumeThrow { handlers, .. })
+                | Instr::ResumeThrowRef(ResumeThrowRef { handlers, .. }) => {
+                    for handler in handlers.iter_mut() {
+                        if let ResumeHandler::OnLabel { label, .. } = handler {
+                            *label = seqs[label];
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            // A tail call's body can keep returning from the caller, but
+            // otherwise returns have to leave the inlined body instead.
+            let call = match instr {
+                _ if tail => None,
+                Instr::Return(_) => {
+                    instr = Br { block: entry }.into();
+                    None
+                }
+                Instr::ReturnCall(ReturnCall { func }) => Some(Call { func }.into()),
+                Instr::ReturnCallIndirect(ReturnCallIndirect { ty, table }) => {
+                    Some(CallIndirect { ty, table }.into())
+                }
+                Instr::ReturnCallRef(ReturnCallRef { ty }) => Some(CallRef { ty }.into()),
+                _ => None,
+            };
+            if let Some(call) = call {
+                new_instrs.push((call, instr_loc));
+                instr = Br { block: entry }.into();
+            }
+            new_instrs.push((instr, instr_loc));
+        }
+        let new = caller_func.block_mut(seqs[&seq.id]);
+        new.instrs = new_instrs;
+        new.end = seq.end;
+    }
+
+    // Arguments are on the stack in order, so the last one is set first.
+    let mut replacement = body
+        .args
+        .iter()
+        .rev()
+        .map(|arg| (Instr::LocalSet(LocalSet { local: locals[arg] }), loc))
+        .collect::<Vec<_>>();
+    replacement.push((Block { seq: entry }.into(), loc));
+    if tail {
+        replacement.push((Return {}.into(), loc));
+    }
+    let len = replacement.len();
+    caller_func
+        .block_mut(seq)
+        .instrs
+        .splice(index..index + 1, replacement);
+    len
+}
+
+/// A copy of a callee's body, made before the caller is modified.
+struct CalleeBody {
+    args: Vec<LocalId>,
+    locals: Vec<LocalId>,
+    entry: InstrSeqId,
+    seqs: Vec<SeqCopy>,
+}
+
+struct SeqCopy {
+    id: InstrSeqId,
+    ty: InstrSeqType,
+    instrs: Vec<(Instr, InstrLocId)>,
+    end: InstrLocId,
+}
+
+impl CalleeBody {
+    fn new(func: &LocalFunction) -> CalleeBody {
+        struct Collect {
+            locals: Vec<LocalId>,
+            seen: IdHashSet<crate::Local>,
+            seqs: Vec<SeqCopy>,
+        }
+
+        impl<'instr> Visitor<'instr> for Collect {
+            fn start_instr_seq(&mut self, seq: &'instr InstrSeq) {
+                self.seqs.push(SeqCopy {
+                    id: seq.id(),
+                    ty: seq.ty,
+                    instrs: seq.instrs.clone(),
+                    end: seq.end,
+                });
+            }
+
+            fn visit_local_id(&mut self, local: &LocalId) {
+                if self.seen.insert(*local) {
+                    self.locals.push(*local);
+                }
+            }
+        }
+
+        let mut visitor = Collect {
+            locals: Vec::new(),
+            seen: IdHashSet::default(),
+            seqs: Vec::new(),
+        };
+        dfs_in_order(&mut visitor, func, func.entry_block());
+        CalleeBody {
+            args: func.args.clone(),
+            locals: visitor.locals,
+            entry: func.entry_block(),
+            seqs: visitor.seqs,
+        }
+    }
+}
+
+/// Make a new local with the same type and name as `local`.
+fn copy_local(locals: &mut ModuleLocals, local: LocalId) -> LocalId {
+    let old = locals.get(local);
+    let (ty, name) = (old.ty(), old.name.clone());
+    let new = locals.add(ty);
+    locals.get_mut(new).name = name;
+    new
+}
+
+/// The instruction that pushes the default value of a defaultable type.
+fn default_value(ty: ValType) -> Instr {
+    let value = match ty {
+        ValType::I32 => Value::I32(0),
+        ValType::I64 => Value::I64(0),
+        ValType::F32 => Value::F32(0.0),
+        ValType::F64 => Value::F64(0.0),
+        ValType::V128 => Value::V128(0),
+        ValType::Ref(ty) => return RefNull { ty }.into(),
+    };
+    Const { value }.into()
+}
+
+/// Maps the callee's locals and sequences to their copies in the caller.
+///
+/// `Instr::visit_mut` can hand the same id to the visitor more than once, so
+/// ids that aren't in the maps are left alone. The copies are all new, so they
+/// are never in the maps themselves.
+struct Remap<'a> {
+    locals: &'a IdHashMap<crate::Local, LocalId>,
+    seqs: &'a IdHashMap<InstrSeq, InstrSeqId>,
+}
+
+impl VisitorMut for Remap<'_> {
+    fn visit_local_id_mut(&mut self, local: &mut LocalId) {
+        if let Some(new) = self.locals.get(local) {
+            *local = *new;
+        }
+    }
+
+    fn visit_instr_seq_id_mut(&mut self, seq: &mut InstrSeqId) {
+        if let Some(new) = self.seqs.get(seq) {
+            *seq = *new;
+        }
+    }
+}
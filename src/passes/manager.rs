@@ -0,0 +1,369 @@
+//! Running pipelines of passes over a module.
+
+use crate::error::Result;
+use crate::{
+    FunctionId, LocalFunction, Module, ModuleData, ModuleElements, ModuleExports, ModuleGlobals,
+    ModuleImports, ModuleLocals, ModuleMemories, ModuleTables, ModuleTags, ModuleTypes,
+};
+use anyhow::{bail, Context};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// A transformation of a whole module.
+pub trait Pass: Send + Sync {
+    /// The name of this pass, used in statistics and error messages.
+    fn name(&self) -> &str;
+
+    /// Run this pass over the given module.
+    fn run(&self, module: &mut Module) -> Result<()>;
+}
+
+/// A transformation of each local function on its own.
+///
+/// A function pass may change the function that it is given, but only read
+/// the rest of the module, so that with the `parallel` feature enabled it can
+/// run over many functions at once.
+pub trait FunctionPass: Send + Sync {
+    /// The name of this pass, used in statistics and error messages.
+    fn name(&self) -> &str;
+
+    /// Run this pass over the local function `id`.
+    fn run_function(
+        &self,
+        cx: &FunctionContext,
+        id: FunctionId,
+        func: &mut LocalFunction,
+    ) -> Result<()>;
+}
+
+/// The parts of a module besides its functions, which a `FunctionPass` can
+/// read.
+#[derive(Debug, Clone, Copy)]
+#[allow(missing_docs)]
+pub struct FunctionContext<'a> {
+    pub imports: &'a ModuleImports,
+    pub tables: &'a ModuleTables,
+    pub types: &'a ModuleTypes,
+    pub globals: &'a ModuleGlobals,
+    pub locals: &'a ModuleLocals,
+    pub exports: &'a ModuleExports,
+    pub memories: &'a ModuleMemories,
+    pub tags: &'a ModuleTags,
+    pub data: &'a ModuleData,
+    pub elements: &'a ModuleElements,
+}
+
+/// Type alias for the constructors of named module passes.
+type PassFactory = Box<dyn Fn() -> Box<dyn Pass> + Send + Sync + 'static>;
+
+/// Type alias for the constructors of named function passes.
+type FunctionPassFactory = Box<dyn Fn() -> Box<dyn FunctionPass> + Send + Sync + 'static>;
+
+enum Step {
+    Module(Box<dyn Pass>),
+    Function(Box<dyn FunctionPass>),
+}
+
+impl Step {
+    fn name(&self) -> &str {
+        match self {
+            Step::Module(pass) => pass.name(),
+            Step::Function(pass) => pass.name(),
+        }
+    }
+}
+
+enum Factory {
+    Module(PassFactory),
+    Function(FunctionPassFactory),
+}
+
+/// What happened while running one pass of a pipeline.
+#[derive(Debug, Clone)]
+pub struct PassStats {
+    /// The name of the pass.
+    pub name: String,
+    /// How long the pass took to run.
+    pub duration: Duration,
+    /// The number of instructions in the module's local functions before the
+    /// pass ran, as counted by `LocalFunction::size`.
+    pub size_before: u64,
+    /// The number of instructions in the module's local functions after the
+    /// pass ran.
+    pub size_after: u64,
+}
+
+impl PassStats {
+    /// How many instructions the pass added, or removed when negative.
+    pub fn size_delta(&self) -> i64 {
+        self.size_after as i64 - self.size_before as i64
+    }
+}
+
+/// Runs a pipeline of passes over a module.
+///
+/// Passes are added to the pipeline either directly or by name. The passes of
+/// this crate are registered under the names `gc`, `inline`, `dce`,
+/// `const-fold`, `coalesce-locals`, `dedup-funcs` and `pack-data`, and other
+/// crates can register their own with `register` and `register_function`.
+///
+/// The `inline` pass only inlines functions that are called from a single
+/// place, which doesn't make the module bigger once `gc` has run. An
+/// `Inliner` with other policies can be added with `add`, or registered under
+/// `inline` instead.
+///
+/// ```
+/// # fn foo(module: &mut walrus::Module) -> anyhow::Result<()> {
+/// use walrus::passes::PassManager;
+///
+/// let mut manager = PassManager::new();
+/// manager.pipeline(&["const-fold", "dce", "gc"])?.validate(true);
+/// for stats in manager.run(module)? {
+///     println!("{}: {:?}, {:+}", stats.name, stats.duration, stats.size_delta());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct PassManager {
+    registry: BTreeMap<String, Factory>,
+    pipeline: Vec<Step>,
+    validate: bool,
+}
+
+impl fmt::Debug for PassManager {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let PassManager {
+            registry,
+            pipeline,
+            validate,
+        } = self;
+
+        f.debug_struct("PassManager")
+            .field("registry", &registry.keys().collect::<Vec<_>>())
+            .field(
+                "pipeline",
+                &pipeline.iter().map(|step| step.name()).collect::<Vec<_>>(),
+            )
+            .field("validate", validate)
+            .finish()
+    }
+}
+
+impl Default for PassManager {
+    fn default() -> PassManager {
+        PassManager::new()
+    }
+}
+
+impl PassManager {
+    /// Creates a new pass manager with an empty pipeline, and the passes of
+    /// this crate registered.
+    pub fn new() -> PassManager {
+        let mut manager = PassManager {
+            registry: BTreeMap::new(),
+            pipeline: Vec::new(),
+            validate: false,
+        };
+        manager
+            .register("gc", || Box::new(ModulePassFn("gc", super::gc::run)))
+            .register("inline", || {
+                let mut inliner = super::inline::Inliner::new();
+                inliner.inline_single_caller(true);
+                Box::new(inliner)
+            })
+            .register("dedup-funcs", || {
+                Box::new(ModulePassFn("dedup-funcs", super::dedup_funcs::run))
+            })
+            .register("pack-data", || {
+                Box::new(ModulePassFn("pack-data", super::pack_data::run))
+            })
+            .register_function("dce", || {
+                Box::new(FunctionPassFn("dce", |_, func| super::dce::run_func(func)))
+            })
+            .register_function("const-fold", || {
+                Box::new(FunctionPassFn("const-fold", |cx, func| {
+                    super::const_fold::run_func(cx.globals, func)
+                }))
+            })
+            .register_function("coalesce-locals", || {
+                Box::new(FunctionPassFn("coalesce-locals", |cx, func| {
+                    super::coalesce_locals::run_func(cx.locals, func)
+                }))
+            });
+        manager
+    }
+
+    /// Register a module pass under the given name, replacing any pass that
+    /// was registered under it before.
+    pub fn register<F>(&mut self, name: &str, factory: F) -> &mut PassManager
+    where
+        F: Fn() -> Box<dyn Pass> + Send + Sync + 'static,
+    {
+        self.registry
+            .insert(name.to_string(), Factory::Module(Box::new(factory)));
+        self
+    }
+
+    /// Register a function pass under the given name, replacing any pass that
+    /// was registered under it before.
+    pub fn register_function<F>(&mut self, name: &str, factory: F) -> &mut PassManager
+    where
+        F: Fn() -> Box<dyn FunctionPass> + Send + Sync + 'static,
+    {
+        self.registry
+            .insert(name.to_string(), Factory::Function(Box::new(factory)));
+        self
+    }
+
+    /// The names of all registered passes, in order.
+    pub fn registered(&self) -> impl Iterator<Item = &str> {
+        self.registry.keys().map(|name| name.as_str())
+    }
+
+    /// Add a module pass to the end of the pipeline.
+    pub fn add<P>(&mut self, pass: P) -> &mut PassManager
+    where
+        P: Pass + 'static,
+    {
+        self.pipeline.push(Step::Module(Box::new(pass)));
+        self
+    }
+
+    /// Add a function pass to the end of the pipeline.
+    pub fn add_function<P>(&mut self, pass: P) -> &mut PassManager
+    where
+        P: FunctionPass + 'static,
+    {
+        self.pipeline.push(Step::Function(Box::new(pass)));
+        self
+    }
+
+    /// Add the pass registered under the given name to the end of the
+    /// pipeline.
+    pub fn add_named(&mut self, name: &str) -> Result<&mut PassManager> {
+        let step = match self.registry.get(name) {
+            Some(Factory::Module(factory)) => Step::Module(factory()),
+            Some(Factory::Function(factory)) => Step::Function(factory()),
+            None => bail!("no pass is registered under the name `{}`", name),
+        };
+        self.pipeline.push(step);
+        Ok(self)
+    }
+
+    /// Add the passes registered under the given names to the end of the
+    /// pipeline, in order.
+    pub fn pipeline(&mut self, names: &[&str]) -> Result<&mut PassManager> {
+        for name in names {
+            self.add_named(name)?;
+        }
+        Ok(self)
+    }
+
+    /// Validate the module after every pass, and fail with the name of the
+    /// pass that made it invalid.
+    ///
+    /// This is disabled by default.
+    pub fn validate(&mut self, validate: bool) -> &mut PassManager {
+        self.validate = validate;
+        self
+    }
+
+    /// Run the pipeline over the given module, and return statistics about
+    /// each pass.
+    pub fn run(&self, module: &mut Module) -> Result<Vec<PassStats>> {
+        let mut stats = Vec::with_capacity(self.pipeline.len());
+        let mut size = module_size(module);
+        for step in self.pipeline.iter() {
+            let name = step.name();
+            log::debug!("running pass `{}`", name);
+            let start = Instant::now();
+            match step {
+                Step::Module(pass) => pass.run(module),
+                Step::Function(pass) => run_function_pass(&**pass, module),
+            }
+            .with_context(|| format!("pass `{}` failed", name))?;
+            let duration = start.elapsed();
+
+            if self.validate {
+                module
+                    .validate()
+                    .with_context(|| format!("module is invalid after pass `{}`", name))?;
+            }
+            let size_after = module_size(module);
+            log::debug!(
+                "pass `{}` took {:?} and changed the size from {} to {}",
+                name,
+                duration,
+                size,
+                size_after
+            );
+            stats.push(PassStats {
+                name: name.to_string(),
+                duration,
+                size_before: size,
+                size_after,
+            });
+            size = size_after;
+        }
+        Ok(stats)
+    }
+}
+
+fn run_function_pass(pass: &dyn FunctionPass, module: &mut Module) -> Result<()> {
+    #[cfg(feature = "parallel")]
+    use rayon::iter::ParallelIterator;
+
+    let cx = FunctionContext {
+        imports: &module.imports,
+        tables: &module.tables,
+        types: &module.types,
+        globals: &module.globals,
+        locals: &module.locals,
+        exports: &module.exports,
+        memories: &module.memories,
+        tags: &module.tags,
+        data: &module.data,
+        elements: &module.elements,
+    };
+    let funcs = &mut module.funcs;
+    maybe_parallel!(funcs.(iter_local_mut | par_iter_local_mut))
+        .try_for_each(|(id, func)| pass.run_function(&cx, id, func))
+}
+
+fn module_size(module: &Module) -> u64 {
+    module.funcs.iter_local().map(|(_, func)| func.size()).sum()
+}
+
+/// One of this crate's module passes.
+struct ModulePassFn(&'static str, fn(&mut Module));
+
+impl Pass for ModulePassFn {
+    fn name(&self) -> &str {
+        self.0
+    }
+
+    fn run(&self, module: &mut Module) -> Result<()> {
+        (self.1)(module);
+        Ok(())
+    }
+}
+
+/// One of this crate's function passes.
+struct FunctionPassFn(&'static str, fn(&FunctionContext, &mut LocalFunction));
+
+impl FunctionPass for FunctionPassFn {
+    fn name(&self) -> &str {
+        self.0
+    }
+
+    fn run_function(
+        &self,
+        cx: &FunctionContext,
+        _: FunctionId,
+        func: &mut LocalFunction,
+    ) -> Result<()> {
+        (self.1)(cx, func);
+        Ok(())
+    }
+}
@@ -1,5 +1,13 @@
 //! Passes over whole modules or individual functions.
 
+pub mod coalesce_locals;
+pub mod const_fold;
+pub mod dce;
+pub mod dedup_funcs;
 pub mod gc;
+pub mod inline;
+mod manager;
+pub mod pack_data;
 mod used;
+pub use self::manager::{FunctionContext, FunctionPass, Pass, PassManager, PassStats};
 pub use self::used::Roots;
@@ -0,0 +1,184 @@
+//! Packs the active data segments of memories into fewer, smaller segments.
+//!
+//! The active segments of a memory are replaced by the bytes that they leave
+//! in memory once they are all applied, with each run of adjacent or
+//! overlapping segments turned into a single segment. Memories that the module
+//! defines itself start out zeroed, so for those, zeros at the start or the end
+//! of a segment are dropped, long runs of zeros split a segment in two, and
+//! segments separated by a few bytes are joined with the zeros in between.
+//!
+//! Only memories whose active segments all have constant offsets and fit in
+//! the memory's initial size are packed, so that no segment moves relative to
+//! one at an offset that is only known at instantiation, and instantiation
+//! still traps exactly when it did before. Memories with active segments that
+//! are used by `memory.init` or `data.drop` are left alone as well.
+//!
+//! Turning active segments into passive ones that a start function copies in
+//! with `memory.init` is out of scope: a passive segment takes up as many
+//! bytes as an active one, so this only adds the code to copy it, and it would
+//! make the data visible later during instantiation than it is now.
+
+use crate::ir::Value;
+use crate::map::IdHashSet;
+use crate::{ConstExpr, DataId, DataKind, MemoryId, Module};
+
+/// The length of the longest run of zeros that is kept inside of a segment,
+/// rather than splitting it, which is roughly what another segment costs.
+const MAX_ZERO_RUN: usize = 16;
+
+/// Pack the active data segments of every memory of the module.
+pub fn run(module: &mut Module) {
+    let mut used = IdHashSet::default();
+    for (_, func) in module.funcs.iter_local() {
+        used.extend(func.used_data_segments());
+    }
+    let memories = module.memories.iter().map(|m| m.id()).collect::<Vec<_>>();
+    for memory in memories {
+        pack(module, &used, memory);
+    }
+}
+
+fn pack(module: &mut Module, used: &IdHashSet<crate::Data>, memory: MemoryId) {
+    let mem = module.memories.get(memory);
+    let zeroed = mem.import.is_none();
+    let size = mem
+        .initial
+        .saturating_mul(1 << mem.page_size_log2.unwrap_or(16));
+
+    // The segments of this memory with their offsets, in the order they are
+    // applied.
+    let mut segments: Vec<(DataId, u64)> = Vec::new();
+    for data in module.data.iter() {
+        let offset = match &data.kind {
+            DataKind::Active { memory: m, .. } if *m != memory => continue,
+            DataKind::Passive => continue,
+            DataKind::Active { offset, .. } => offset,
+        };
+        let offset = match offset {
+            ConstExpr::Value(Value::I32(n)) => *n as u32 as u64,
+            ConstExpr::Value(Value::I64(n)) => *n as u64,
+            _ => return,
+        };
+        let in_bounds = offset
+            .checked_add(data.value.len() as u64)
+            .is_some_and(|end| end <= size);
+        if !in_bounds || used.contains(&data.id()) {
+            return;
+        }
+        segments.push((data.id(), offset));
+    }
+
+    let packed = packed_segments(module, &segments, zeroed);
+    let unchanged = packed.len() == segments.len()
+        && packed
+            .iter()
+            .zip(segments.iter())
+            .all(|((offset, value), (id, old_offset))| {
+                offset == old_offset && *value == module.data.get(*id).value
+            });
+    if unchanged {
+        return;
+    }
+    log::debug!(
+        "packing {} data segments of {:?} into {}",
+        segments.len(),
+        memory,
+        packed.len()
+    );
+
+    let memory64 = module.memories.get(memory).memory64;
+    for (id, _) in segments {
+        module.data.delete(id);
+        module.memories.get_mut(memory).data_segments.remove(&id);
+    }
+    for (offset, value) in packed {
+        let offset = if memory64 {
+            Value::I64(offset as i64)
+        } else {
+            Value::I32(offset as u32 as i32)
+        };
+        let kind = DataKind::Active {
+            memory,
+            offset: ConstExpr::Value(offset),
+        };
+        let id = module.data.add(kind, value);
+        module.memories.get_mut(memory).data_segments.insert(id);
+    }
+}
+
+/// The offsets and contents of the segments that leave the same bytes in
+/// memory as `segments`, sorted by offset.
+fn packed_segments(
+    module: &Module,
+    segments: &[(DataId, u64)],
+    zeroed: bool,
+) -> Vec<(u64, Vec<u8>)> {
+    // Find the ranges of memory that the segments cover, joining the ones
+    // that overlap or, in a zeroed memory, are close enough.
+    let gap = if zeroed { MAX_ZERO_RUN as u64 } else { 0 };
+    let mut ranges = segments
+        .iter()
+        .map(|(id, offset)| (*offset, offset + module.data.get(*id).value.len() as u64))
+        .filter(|(start, end)| start < end)
+        .collect::<Vec<_>>();
+    ranges.sort_unstable();
+    let mut joined: Vec<(u64, u64)> = Vec::new();
+    for (start, end) in ranges {
+        match joined.last_mut() {
+            Some((_, last_end)) if start <= last_end.saturating_add(gap) => {
+                *last_end = end.max(*last_end);
+            }
+            _ => joined.push((start, end)),
+        }
+    }
+
+    // Then apply the segments in order to see what ends up in each range.
+    let mut packed = joined
+        .iter()
+        .map(|(start, end)| (*start, vec![0; (end - start) as usize]))
+        .collect::<Vec<_>>();
+    for (id, offset) in segments {
+        let value = &module.data.get(*id).value;
+        if value.is_empty() {
+            continue;
+        }
+        let i = packed.partition_point(|(start, _)| start <= offset) - 1;
+        let (start, bytes) = &mut packed[i];
+        let at = (offset - *start) as usize;
+        bytes[at..at + value.len()].copy_from_slice(value);
+    }
+
+    if !zeroed {
+        return packed;
+    }
+    packed
+        .into_iter()
+        .flat_map(|(start, bytes)| split_zeros(start, &bytes))
+        .collect()
+}
+
+/// Split bytes at `offset` of a zeroed memory into the segments without long
+/// runs of zeros that write the same bytes.
+fn split_zeros(offset: u64, bytes: &[u8]) -> Vec<(u64, Vec<u8>)> {
+    let mut segments = Vec::new();
+    let mut i = 0;
+    while let Some(start) = bytes[i..].iter().position(|b| *b != 0).map(|n| i + n) {
+        // Extend the segment up to the next long run of zeros.
+        let mut end = start;
+        let mut zeros = 0;
+        for (j, b) in bytes.iter().enumerate().skip(start) {
+            if *b != 0 {
+                end = j + 1;
+                zeros = 0;
+            } else {
+                zeros += 1;
+                if zeros > MAX_ZERO_RUN {
+                    break;
+                }
+            }
+        }
+        segments.push((offset + start as u64, bytes[start..end].to_vec()));
+        i = end;
+    }
+    segments
+}
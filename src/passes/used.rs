@@ -1,9 +1,11 @@
+use crate::const_expr::ConstOp;
 use crate::ir::*;
 use crate::map::IdHashSet;
-use crate::{ConstExpr, Data, DataId, DataKind, Element, ExportItem, Function};
-use crate::{ElementId, ElementItems, ElementKind, Module, RefType, Tag, TagId, Type, TypeId};
+use crate::{ConstExpr, Data, DataId, DataKind, Element, ExportItem, Function, HeapType};
+use crate::{ElementId, ElementItems, ElementKind, Module, ModuleLocals, RefType, Tag, TagId};
 use crate::{FunctionId, FunctionKind, Global, GlobalId};
 use crate::{GlobalKind, Memory, MemoryId, Table, TableId};
+use crate::{Type, TypeId, TypeKind, ValType};
 
 /// Set of all root used items in a wasm module.
 #[derive(Debug, Default)]
@@ -15,6 +17,7 @@ pub struct Roots {
     tags: Vec<TagId>,
     datas: Vec<DataId>,
     elements: Vec<ElementId>,
+    types: Vec<TypeId>,
     used: Used,
 }
 
@@ -69,6 +72,68 @@ impl Roots {
         self
     }
 
+    /// Adds a new type to the set of roots
+    pub fn push_type(&mut self, ty: TypeId) -> &mut Roots {
+        if self.used.types.insert(ty) {
+            log::trace!("type is used: {:?}", ty);
+            self.types.push(ty);
+        }
+        self
+    }
+
+    fn push_val_type(&mut self, ty: ValType) -> &mut Roots {
+        if let ValType::Ref(r) = ty {
+            self.push_ref_type(r);
+        }
+        self
+    }
+
+    fn push_ref_type(&mut self, ty: RefType) -> &mut Roots {
+        if let HeapType::Concrete(t) = ty.heap_type {
+            self.push_type(t);
+        }
+        self
+    }
+
+    fn push_const_expr(&mut self, expr: &ConstExpr) -> &mut Roots {
+        match expr {
+            ConstExpr::Value(_) => {}
+            ConstExpr::Global(g) => {
+                self.push_global(*g);
+            }
+            ConstExpr::RefNull(ty) => {
+                self.push_ref_type(*ty);
+            }
+            ConstExpr::RefFunc(f) => {
+                self.push_func(*f);
+            }
+            ConstExpr::Extended(ops) => {
+                for op in ops {
+                    match op {
+                        ConstOp::GlobalGet(g) => {
+                            self.push_global(*g);
+                        }
+                        ConstOp::RefNull(ty) => {
+                            self.push_ref_type(*ty);
+                        }
+                        ConstOp::RefFunc(f) => {
+                            self.push_func(*f);
+                        }
+                        ConstOp::StructNew(ty)
+                        | ConstOp::StructNewDefault(ty)
+                        | ConstOp::ArrayNew(ty)
+                        | ConstOp::ArrayNewDefault(ty)
+                        | ConstOp::ArrayNewFixed { ty, .. } => {
+                            self.push_type(*ty);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        self
+    }
+
     fn push_data(&mut self, data: DataId) -> &mut Roots {
         if self.used.data.insert(data) {
             log::trace!("data is used: {:?}", data);
@@ -171,14 +236,18 @@ impl Used {
             || !stack.tags.is_empty()
             || !stack.datas.is_empty()
             || !stack.elements.is_empty()
+            || !stack.types.is_empty()
         {
             while let Some(f) = stack.funcs.pop() {
                 let func = module.funcs.get(f);
-                stack.used.types.insert(func.ty());
+                stack.push_type(func.ty());
 
                 match &func.kind {
                     FunctionKind::Local(func) => {
-                        let mut visitor = UsedVisitor { stack: &mut stack };
+                        let mut visitor = UsedVisitor {
+                            locals: &module.locals,
+                            stack: &mut stack,
+                        };
                         dfs_in_order(&mut visitor, func, func.entry_block());
                     }
                     FunctionKind::Import(_) => {}
@@ -187,42 +256,62 @@ impl Used {
             }
 
             while let Some(t) = stack.tables.pop() {
-                for elem in module.tables.get(t).elem_segments.iter() {
+                let table = module.tables.get(t);
+                stack.push_ref_type(table.element_ty);
+                if let Some(init) = &table.init {
+                    stack.push_const_expr(init);
+                }
+                for elem in table.elem_segments.iter() {
                     stack.push_element(*elem);
                 }
             }
 
             while let Some(t) = stack.globals.pop() {
-                match &module.globals.get(t).kind {
+                let global = module.globals.get(t);
+                stack.push_val_type(global.ty);
+                match &global.kind {
                     GlobalKind::Import(_) => {}
-                    GlobalKind::Local(ConstExpr::Global(global)) => {
-                        stack.push_global(*global);
-                    }
-                    GlobalKind::Local(ConstExpr::RefFunc(func)) => {
-                        stack.push_func(*func);
-                    }
-                    GlobalKind::Local(ConstExpr::Value(_))
-                    | GlobalKind::Local(ConstExpr::RefNull(_)) => {}
-                    GlobalKind::Local(ConstExpr::Extended(ops)) => {
-                        // Mark globals and functions referenced in extended const expressions
-                        for op in ops {
-                            match op {
-                                crate::const_expr::ConstOp::GlobalGet(g) => {
-                                    stack.push_global(*g);
-                                }
-                                crate::const_expr::ConstOp::RefFunc(f) => {
-                                    stack.push_func(*f);
-                                }
-                                _ => {}
-                            }
-                        }
+                    GlobalKind::Local(init) => {
+                        stack.push_const_expr(init);
                     }
                 }
             }
 
             while let Some(t) = stack.tags.pop() {
                 let tag = module.tags.get(t);
-                stack.used.types.insert(tag.ty);
+                stack.push_type(tag.ty);
+            }
+
+            while let Some(t) = stack.types.pop() {
+                let ty = module.types.get(t);
+                match ty.kind() {
+                    TypeKind::Function(f) => {
+                        for ty in f.params().iter().chain(f.results()) {
+                            stack.push_val_type(*ty);
+                        }
+                    }
+                    TypeKind::Struct(s) => {
+                        for field in s.fields.iter() {
+                            stack.push_val_type(field.element_type.unpacked());
+                        }
+                    }
+                    TypeKind::Array(a) => {
+                        stack.push_val_type(a.field.element_type.unpacked());
+                    }
+                    TypeKind::Cont(c) => {
+                        stack.push_type(c.func_type);
+                    }
+                }
+                if let Some(supertype) = ty.supertype() {
+                    stack.push_type(supertype);
+                }
+                // Recursion groups are only equivalent as a whole, so keep
+                // every type of a group that is used at all.
+                if let Some(group) = ty.rec_group() {
+                    for member in module.types.rec_group(group).types() {
+                        stack.push_type(*member);
+                    }
+                }
             }
 
             while let Some(t) = stack.memories.pop() {
@@ -249,19 +338,9 @@ impl Used {
                     });
                 }
                 if let ElementItems::Expressions(ref_type, items) = &e.items {
-                    // Check if it's a funcref type (nullable or not)
-                    if ref_type == &RefType::FUNCREF {
-                        for item in items {
-                            match item {
-                                ConstExpr::Global(g) => {
-                                    stack.push_global(*g);
-                                }
-                                ConstExpr::RefFunc(f) => {
-                                    stack.push_func(*f);
-                                }
-                                _ => {}
-                            }
-                        }
+                    stack.push_ref_type(*ref_type);
+                    for item in items {
+                        stack.push_const_expr(item);
                     }
                 }
                 if let ElementKind::Active { offset, table } = &e.kind {
@@ -291,6 +370,7 @@ impl Used {
 }
 
 struct UsedVisitor<'a> {
+    locals: &'a ModuleLocals,
     stack: &'a mut Roots,
 }
 
@@ -299,6 +379,10 @@ impl<'expr> Visitor<'expr> for UsedVisitor<'_> {
         self.stack.push_func(func);
     }
 
+    fn visit_local_id(&mut self, &local: &LocalId) {
+        self.stack.push_val_type(self.locals.get(local).ty());
+    }
+
     fn visit_memory_id(&mut self, &m: &MemoryId) {
         self.stack.push_memory(m);
     }
@@ -312,7 +396,7 @@ impl<'expr> Visitor<'expr> for UsedVisitor<'_> {
     }
 
     fn visit_type_id(&mut self, &t: &TypeId) {
-        self.stack.used.types.insert(t);
+        self.stack.push_type(t);
     }
 
     fn visit_data_id(&mut self, &d: &DataId) {
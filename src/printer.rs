@@ -0,0 +1,1873 @@
+//! Printing modules and functions in the WebAssembly text format.
+
+use crate::ir::*;
+use crate::map::{IdHashMap, IdHashSet};
+use crate::*;
+use id_arena::Id;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{self, Write};
+
+impl Module {
+    /// Render this module in the WebAssembly text format.
+    ///
+    /// This is a shorthand for `Printer::new(self).print_module()`; use a
+    /// `Printer` directly to annotate the output with walrus ids and
+    /// instruction locations, or to print a single function.
+    pub fn to_wat(&self) -> String {
+        Printer::new(self).print_module()
+    }
+}
+
+/// Renders a `Module`, or individual functions of one, in the WebAssembly
+/// text format.
+///
+/// Items are referred to by the names in the module's `name` section when
+/// they have one, and by their index otherwise. Indices are assigned in the
+/// order the items are printed, which is the order they would be emitted in
+/// except that local functions keep their order in the module, and every
+/// definition carries its index in a `(;N;)` comment.
+///
+/// Each `InstrSeq` is printed as a nested `block`, `loop`, `if`, `try_table`
+/// or `try`, with labels named after their nesting depth.
+///
+/// # Example
+///
+/// ```
+/// let mut module = walrus::Module::default();
+/// let mut builder = walrus::FunctionBuilder::new(&mut module.types, &[], &[]);
+/// builder.name("nop".to_string()).func_body().i32_const(1).drop();
+/// let nop = builder.finish(vec![], &mut module.funcs);
+///
+/// let wat = walrus::printer::Printer::new(&module)
+///     .annotate_ids(true)
+///     .print_function(nop);
+/// assert!(wat.starts_with("(func $nop (;0;) (;id 0;)"));
+/// ```
+pub struct Printer<'a> {
+    module: &'a Module,
+    names: Names,
+    annotate_ids: bool,
+    annotate_instr_locs: bool,
+}
+
+impl fmt::Debug for Printer<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Printer")
+            .field("annotate_ids", &self.annotate_ids)
+            .field("annotate_instr_locs", &self.annotate_instr_locs)
+            .finish()
+    }
+}
+
+impl<'a> Printer<'a> {
+    /// Create a printer for the given module.
+    pub fn new(module: &'a Module) -> Printer<'a> {
+        Printer {
+            module,
+            names: Names::new(module),
+            annotate_ids: false,
+            annotate_instr_locs: false,
+        }
+    }
+
+    /// Sets whether items, locals and instruction sequences are annotated
+    /// with the index of their walrus id, as an `(;id N;)` comment.
+    ///
+    /// By default this flag is `false`.
+    pub fn annotate_ids(&mut self, annotate: bool) -> &mut Printer<'a> {
+        self.annotate_ids = annotate;
+        self
+    }
+
+    /// Sets whether instructions are annotated with their `InstrLocId`, as an
+    /// `(;@offset;)` comment in hexadecimal. Instructions with the default
+    /// location, such as the ones added by passes, are not annotated.
+    ///
+    /// By default this flag is `false`.
+    pub fn annotate_instr_locs(&mut self, annotate: bool) -> &mut Printer<'a> {
+        self.annotate_instr_locs = annotate;
+        self
+    }
+
+    /// Render the whole module.
+    pub fn print_module(&self) -> String {
+        let module = self.module;
+        let mut out = String::from("(module");
+        if let Some(name) = &module.name {
+            out.push(' ');
+            out.push_str(&identifier(name));
+        }
+        out.push('\n');
+
+        let mut printed_groups = IdHashSet::default();
+        for ty in module.types.iter() {
+            if ty.is_for_function_entry() {
+                continue;
+            }
+            match ty.rec_group() {
+                Some(group) if module.types.rec_group(group).is_explicit() => {
+                    if !printed_groups.insert(group) {
+                        continue;
+                    }
+                    out.push_str("  (rec\n");
+                    for member in module.types.rec_group(group).types() {
+                        out.push_str("    ");
+                        self.type_definition(&mut out, module.types.get(*member));
+                        out.push('\n');
+                    }
+                    out.push_str("  )\n");
+                }
+                _ => {
+                    out.push_str("  ");
+                    self.type_definition(&mut out, ty);
+                    out.push('\n');
+                }
+            }
+        }
+
+        for import in module.imports.iter() {
+            let _ = write!(
+                out,
+                "  (import {} {} ",
+                string(import.module.as_bytes()),
+                string(import.name.as_bytes())
+            );
+            match import.kind {
+                ImportKind::Function(id) => self.function_signature(&mut out, id),
+                ImportKind::Table(id) => self.table_definition(&mut out, id),
+                ImportKind::Memory(id) => self.memory_definition(&mut out, id),
+                ImportKind::Global(id) => self.global_definition(&mut out, id),
+                ImportKind::Tag(id) => self.tag_definition(&mut out, id),
+            }
+            out.push_str(")\n");
+        }
+
+        for (id, func) in module.funcs.iter_local() {
+            self.local_function(&mut out, id, func, 1);
+        }
+
+        for table in module.tables.iter().filter(|t| t.import.is_none()) {
+            out.push_str("  ");
+            self.table_definition(&mut out, table.id());
+            out.push('\n');
+        }
+
+        for memory in module.memories.iter().filter(|m| m.import.is_none()) {
+            out.push_str("  ");
+            self.memory_definition(&mut out, memory.id());
+            out.push('\n');
+        }
+
+        for tag in module.tags.iter() {
+            if let TagKind::Local = tag.kind {
+                out.push_str("  ");
+                self.tag_definition(&mut out, tag.id);
+                out.push('\n');
+            }
+        }
+
+        for global in module.globals.iter() {
+            if let GlobalKind::Local(_) = global.kind {
+                out.push_str("  ");
+                self.global_definition(&mut out, global.id());
+                out.push('\n');
+            }
+        }
+
+        for export in module.exports.iter() {
+            let (kind, name) = match export.item {
+                ExportItem::Function(id) => ("func", self.names.funcs.reference(id)),
+                ExportItem::Table(id) => ("table", self.names.tables.reference(id)),
+                ExportItem::Memory(id) => ("memory", self.names.memories.reference(id)),
+                ExportItem::Global(id) => ("global", self.names.globals.reference(id)),
+                ExportItem::Tag(id) => ("tag", self.names.tags.reference(id)),
+            };
+            let _ = writeln!(
+                out,
+                "  (export {} ({} {}))",
+                string(export.name.as_bytes()),
+                kind,
+                name
+            );
+        }
+
+        if let Some(start) = module.start {
+            let _ = writeln!(out, "  (start {})", self.names.funcs.reference(start));
+        }
+
+        for element in module.elements.iter() {
+            out.push_str("  ");
+            self.element(&mut out, element);
+            out.push('\n');
+        }
+
+        for data in module.data.iter() {
+            out.push_str("  ");
+            self.data(&mut out, data);
+            out.push('\n');
+        }
+
+        out.push_str(")\n");
+        out
+    }
+
+    /// Render a single function of the module.
+    ///
+    /// Local functions are printed with their body, and imported functions
+    /// are printed as the import that brings them into the module. References
+    /// to other items use the same names and indices as `print_module`.
+    pub fn print_function(&self, id: FunctionId) -> String {
+        let mut out = String::new();
+        match &self.module.funcs.get(id).kind {
+            FunctionKind::Local(func) => self.local_function(&mut out, id, func, 0),
+            FunctionKind::Import(imported) => {
+                let import = self.module.imports.get(imported.import);
+                let _ = write!(
+                    out,
+                    "(import {} {} ",
+                    string(import.module.as_bytes()),
+                    string(import.name.as_bytes())
+                );
+                self.function_signature(&mut out, id);
+                out.push_str(")\n");
+            }
+            FunctionKind::Uninitialized(_) => unreachable!(),
+        }
+        out
+    }
+
+    fn type_definition(&self, out: &mut String, ty: &Type) {
+        out.push_str("(type ");
+        self.definition(out, &self.names.types, ty.id());
+        out.push(' ');
+
+        let sub = !ty.is_final() || ty.supertype().is_some();
+        if sub {
+            out.push_str("(sub ");
+            if ty.is_final() {
+                out.push_str("final ");
+            }
+            if let Some(supertype) = ty.supertype() {
+                out.push_str(&self.names.types.reference(supertype));
+                out.push(' ');
+            }
+        }
+
+        match ty.kind() {
+            TypeKind::Function(func) => {
+                out.push_str("(func");
+                self.params_results(out, func.params(), func.results());
+                out.push(')');
+            }
+            TypeKind::Struct(s) => {
+                out.push_str("(struct");
+                for field in s.fields.iter() {
+                    out.push_str(" (field ");
+                    out.push_str(&self.field_type(field));
+                    out.push(')');
+                }
+                out.push(')');
+            }
+            TypeKind::Array(a) => {
+                out.push_str("(array ");
+                out.push_str(&self.field_type(&a.field));
+                out.push(')');
+            }
+            TypeKind::Cont(c) => {
+                out.push_str("(cont ");
+                out.push_str(&self.names.types.reference(c.func_type));
+                out.push(')');
+            }
+        }
+
+        if sub {
+            out.push(')');
+        }
+        out.push(')');
+    }
+
+    /// Print a function without its body: its name, type and the names of its
+    /// parameters if it is a local function.
+    fn function_signature(&self, out: &mut String, id: FunctionId) {
+        let func = self.module.funcs.get(id);
+        let ty = func.ty();
+        out.push_str("(func ");
+        self.definition(out, &self.names.funcs, id);
+        let _ = write!(out, " (type {})", self.names.types.reference(ty));
+        self.params_results(
+            out,
+            self.module.types.params(ty),
+            self.module.types.results(ty),
+        );
+        out.push(')');
+    }
+
+    fn local_function(
+        &self,
+        out: &mut String,
+        id: FunctionId,
+        func: &LocalFunction,
+        indent: usize,
+    ) {
+        let mut locals = Namespace::default();
+        for arg in func.args.iter() {
+            locals.push(*arg, self.module.locals.get(*arg).name.as_deref());
+        }
+
+        // Order the rest of the locals the same way they are emitted: grouped
+        // by type, and by id within each group.
+        let args = func.args.iter().cloned().collect::<IdHashSet<_>>();
+        let mut used = func.used_locals().into_iter().collect::<Vec<_>>();
+        used.sort_unstable();
+        let mut ty_to_locals = BTreeMap::new();
+        for local in used {
+            if !args.contains(&local) {
+                let ty = self.module.locals.get(local).ty();
+                ty_to_locals.entry(ty).or_insert_with(Vec::new).push(local);
+            }
+        }
+        for local in ty_to_locals.values().flatten() {
+            locals.push(*local, self.module.locals.get(*local).name.as_deref());
+        }
+
+        let ty = func.ty();
+        push_indent(out, indent);
+        out.push_str("(func ");
+        self.definition(out, &self.names.funcs, id);
+        let _ = write!(out, " (type {})", self.names.types.reference(ty));
+        for arg in func.args.iter() {
+            out.push_str(" (param ");
+            self.definition(out, &locals, *arg);
+            let _ = write!(
+                out,
+                " {})",
+                self.val_type(self.module.locals.get(*arg).ty())
+            );
+        }
+        let results = self.module.types.results(ty);
+        if !results.is_empty() {
+            let _ = write!(out, " (result {})", self.val_types(results));
+        }
+        out.push('\n');
+
+        for local in ty_to_locals.values().flatten() {
+            push_indent(out, indent + 1);
+            out.push_str("(local ");
+            self.definition(out, &locals, *local);
+            let _ = writeln!(
+                out,
+                " {})",
+                self.val_type(self.module.locals.get(*local).ty())
+            );
+        }
+
+        let mut body = FunctionPrinter {
+            printer: self,
+            func,
+            locals,
+            labels: vec![func.entry_block()],
+            out,
+        };
+        body.instr_seq(func.entry_block(), indent + 1);
+        push_indent(out, indent);
+        out.push_str(")\n");
+    }
+
+    fn table_definition(&self, out: &mut String, id: TableId) {
+        let table = self.module.tables.get(id);
+        out.push_str("(table ");
+        self.definition(out, &self.names.tables, id);
+        if table.table64 {
+            out.push_str(" i64");
+        }
+        let _ = write!(out, " {}", table.initial);
+        if let Some(maximum) = table.maximum {
+            let _ = write!(out, " {}", maximum);
+        }
+        let _ = write!(out, " {}", self.ref_type(table.element_ty));
+        if let Some(init) = &table.init {
+            out.push(' ');
+            out.push_str(&self.folded_const_expr(init));
+        }
+        out.push(')');
+    }
+
+    fn memory_definition(&self, out: &mut String, id: MemoryId) {
+        let memory = self.module.memories.get(id);
+        out.push_str("(memory ");
+        self.definition(out, &self.names.memories, id);
+        if memory.memory64 {
+            out.push_str(" i64");
+        }
+        let _ = write!(out, " {}", memory.initial);
+        if let Some(maximum) = memory.maximum {
+            let _ = write!(out, " {}", maximum);
+        }
+        if memory.shared {
+            out.push_str(" shared");
+        }
+        if let Some(log2) = memory.page_size_log2 {
+            let _ = write!(out, " (pagesize {})", 1u64 << log2);
+        }
+        out.push(')');
+    }
+
+    fn global_definition(&self, out: &mut String, id: GlobalId) {
+        let global = self.module.globals.get(id);
+        out.push_str("(global ");
+        self.definition(out, &self.names.globals, id);
+        let ty = self.val_type(global.ty);
+        let _ = match (global.shared, global.mutable) {
+            (false, false) => write!(out, " {}", ty),
+            (false, true) => write!(out, " (mut {})", ty),
+            (true, false) => write!(out, " (shared {})", ty),
+            (true, true) => write!(out, " (shared mut {})", ty),
+        };
+        if let GlobalKind::Local(init) = &global.kind {
+            out.push(' ');
+            out.push_str(&self.folded_const_expr(init));
+        }
+        out.push(')');
+    }
+
+    fn tag_definition(&self, out: &mut String, id: TagId) {
+        let tag = self.module.tags.get(id);
+        out.push_str("(tag ");
+        self.definition(out, &self.names.tags, id);
+        let _ = write!(out, " (type {})", self.names.types.reference(tag.ty));
+        self.params_results(
+            out,
+            self.module.types.params(tag.ty),
+            self.module.types.results(tag.ty),
+        );
+        out.push(')');
+    }
+
+    fn element(&self, out: &mut String, element: &Element) {
+        out.push_str("(elem ");
+        self.definition(out, &self.names.elements, element.id());
+        match &element.kind {
+            ElementKind::Passive => {}
+            ElementKind::Declared => out.push_str(" declare"),
+            ElementKind::Active { table, offset } => {
+                let _ = write!(
+                    out,
+                    " (table {}) {}",
+                    self.names.tables.reference(*table),
+                    self.offset_expr(offset)
+                );
+            }
+        }
+        match &element.items {
+            ElementItems::Functions(funcs) => {
+                out.push_str(" func");
+                for func in funcs {
+                    out.push(' ');
+                    out.push_str(&self.names.funcs.reference(*func));
+                }
+            }
+            ElementItems::Expressions(ty, exprs) => {
+                out.push(' ');
+                out.push_str(&self.ref_type(*ty));
+                for expr in exprs {
+                    let _ = write!(out, " (item {})", self.folded_const_expr(expr));
+                }
+            }
+        }
+        out.push(')');
+    }
+
+    fn data(&self, out: &mut String, data: &Data) {
+        out.push_str("(data ");
+        self.definition(out, &self.names.data, data.id());
+        if let DataKind::Active { memory, offset } = &data.kind {
+            let _ = write!(
+                out,
+                " (memory {}) {}",
+                self.names.memories.reference(*memory),
+                self.offset_expr(offset)
+            );
+        }
+        out.push(' ');
+        out.push_str(&string(&data.value));
+        out.push(')');
+    }
+
+    /// Print the name and index of an item that is being defined, and its id
+    /// if requested.
+    fn definition<T>(&self, out: &mut String, namespace: &Namespace<T>, id: Id<T>) {
+        out.push_str(&namespace.definition(id));
+        if self.annotate_ids {
+            let _ = write!(out, " (;id {};)", id.index());
+        }
+    }
+
+    fn params_results(&self, out: &mut String, params: &[ValType], results: &[ValType]) {
+        if !params.is_empty() {
+            let _ = write!(out, " (param {})", self.val_types(params));
+        }
+        if !results.is_empty() {
+            let _ = write!(out, " (result {})", self.val_types(results));
+        }
+    }
+
+    fn val_types(&self, tys: &[ValType]) -> String {
+        tys.iter()
+            .map(|ty| self.val_type(*ty))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn val_type(&self, ty: ValType) -> String {
+        match ty {
+            ValType::Ref(ty) => self.ref_type(ty),
+            other => other.to_string(),
+        }
+    }
+
+    fn ref_type(&self, ty: RefType) -> String {
+        match ty.heap_type {
+            // Nullable abstract reference types have shorthands like
+            // `funcref`.
+            HeapType::Abstract(_) if ty.nullable => ty.to_string(),
+            heap_type => format!(
+                "(ref {}{})",
+                if ty.nullable { "null " } else { "" },
+                self.heap_type(heap_type)
+            ),
+        }
+    }
+
+    fn heap_type(&self, heap_type: HeapType) -> String {
+        match heap_type {
+            HeapType::Abstract(_) => heap_type.to_string(),
+            HeapType::Concrete(ty) => self.names.types.reference(ty),
+        }
+    }
+
+    fn field_type(&self, field: &FieldType) -> String {
+        let storage = match field.element_type {
+            StorageType::I8 => "i8".to_string(),
+            StorageType::I16 => "i16".to_string(),
+            StorageType::Val(ty) => self.val_type(ty),
+        };
+        if field.mutable {
+            format!("(mut {})", storage)
+        } else {
+            storage
+        }
+    }
+
+    /// The instructions of a constant expression, each as a folded
+    /// `(instr)`.
+    fn folded_const_expr(&self, expr: &ConstExpr) -> String {
+        self.const_expr(expr)
+            .iter()
+            .map(|op| format!("({})", op))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// A constant expression used as the offset of an active segment.
+    fn offset_expr(&self, expr: &ConstExpr) -> String {
+        match expr {
+            ConstExpr::Extended(_) => format!("(offset {})", self.folded_const_expr(expr)),
+            _ => self.folded_const_expr(expr),
+        }
+    }
+
+    fn const_expr(&self, expr: &ConstExpr) -> Vec<String> {
+        match expr {
+            ConstExpr::Value(value) => vec![value_const(value)],
+            ConstExpr::Global(g) => {
+                vec![format!("global.get {}", self.names.globals.reference(*g))]
+            }
+            ConstExpr::RefNull(ty) => vec![format!("ref.null {}", self.heap_type(ty.heap_type))],
+            ConstExpr::RefFunc(f) => vec![format!("ref.func {}", self.names.funcs.reference(*f))],
+            ConstExpr::Extended(ops) => ops
+                .iter()
+                .map(|op| match op {
+                    ConstOp::I32Const(v) => value_const(&Value::I32(*v)),
+                    ConstOp::I64Const(v) => value_const(&Value::I64(*v)),
+                    ConstOp::F32Const(v) => value_const(&Value::F32(*v)),
+                    ConstOp::F64Const(v) => value_const(&Value::F64(*v)),
+                    ConstOp::V128Const(v) => value_const(&Value::V128(*v)),
+                    ConstOp::GlobalGet(g) => {
+                        format!("global.get {}", self.names.globals.reference(*g))
+                    }
+                    ConstOp::RefNull(ty) => format!("ref.null {}", self.heap_type(ty.heap_type)),
+                    ConstOp::RefFunc(f) => format!("ref.func {}", self.names.funcs.reference(*f)),
+                    ConstOp::I32Add => "i32.add".to_string(),
+                    ConstOp::I32Sub => "i32.sub".to_string(),
+                    ConstOp::I32Mul => "i32.mul".to_string(),
+                    ConstOp::I64Add => "i64.add".to_string(),
+                    ConstOp::I64Sub => "i64.sub".to_string(),
+                    ConstOp::I64Mul => "i64.mul".to_string(),
+                    ConstOp::RefI31 => "ref.i31".to_string(),
+                    ConstOp::StructNew(ty) => {
+                        format!("struct.new {}", self.names.types.reference(*ty))
+                    }
+                    ConstOp::StructNewDefault(ty) => {
+                        format!("struct.new_default {}", self.names.types.reference(*ty))
+                    }
+                    ConstOp::ArrayNew(ty) => {
+                        format!("array.new {}", self.names.types.reference(*ty))
+                    }
+                    ConstOp::ArrayNewDefault(ty) => {
+                        format!("array.new_default {}", self.names.types.reference(*ty))
+                    }
+                    ConstOp::ArrayNewFixed { ty, len } => {
+                        format!(
+                            "array.new_fixed {} {}",
+                            self.names.types.reference(*ty),
+                            len
+                        )
+                    }
+                    ConstOp::AnyConvertExtern => "any.convert_extern".to_string(),
+                    ConstOp::ExternConvertAny => "extern.convert_any".to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The names and indices of every item in the module, per index space.
+struct Names {
+    types: Namespace<Type>,
+    funcs: Namespace<Function>,
+    tables: Namespace<Table>,
+    memories: Namespace<Memory>,
+    globals: Namespace<Global>,
+    tags: Namespace<Tag>,
+    elements: Namespace<Element>,
+    data: Namespace<Data>,
+}
+
+impl Names {
+    fn new(module: &Module) -> Names {
+        let mut names = Names {
+            types: Namespace::default(),
+            funcs: Namespace::default(),
+            tables: Namespace::default(),
+            memories: Namespace::default(),
+            globals: Namespace::default(),
+            tags: Namespace::default(),
+            elements: Namespace::default(),
+            data: Namespace::default(),
+        };
+
+        // Types are numbered like they are emitted: in the order they were
+        // added, with each recursion group kept together.
+        let mut seen_groups = IdHashSet::default();
+        for ty in module.types.iter() {
+            if ty.is_for_function_entry() {
+                continue;
+            }
+            match ty.rec_group() {
+                None => names.types.push(ty.id(), ty.name.as_deref()),
+                Some(group) => {
+                    if seen_groups.insert(group) {
+                        for member in module.types.rec_group(group).types() {
+                            let name = module.types.get(*member).name.as_deref();
+                            names.types.push(*member, name);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Imports come first in each index space.
+        for import in module.imports.iter() {
+            match import.kind {
+                ImportKind::Function(id) => {
+                    names.funcs.push(id, module.funcs.get(id).name.as_deref())
+                }
+                ImportKind::Table(id) => {
+                    names.tables.push(id, module.tables.get(id).name.as_deref())
+                }
+                ImportKind::Memory(id) => names
+                    .memories
+                    .push(id, module.memories.get(id).name.as_deref()),
+                ImportKind::Global(id) => names
+                    .globals
+                    .push(id, module.globals.get(id).name.as_deref()),
+                ImportKind::Tag(id) => names.tags.push(id, module.tags.get(id).name.as_deref()),
+            }
+        }
+
+        for (id, _) in module.funcs.iter_local() {
+            names.funcs.push(id, module.funcs.get(id).name.as_deref());
+        }
+        for table in module.tables.iter().filter(|t| t.import.is_none()) {
+            names.tables.push(table.id(), table.name.as_deref());
+        }
+        for memory in module.memories.iter().filter(|m| m.import.is_none()) {
+            names.memories.push(memory.id(), memory.name.as_deref());
+        }
+        for global in module.globals.iter() {
+            if let GlobalKind::Local(_) = global.kind {
+                names.globals.push(global.id(), global.name.as_deref());
+            }
+        }
+        for tag in module.tags.iter() {
+            if let TagKind::Local = tag.kind {
+                names.tags.push(tag.id, tag.name.as_deref());
+            }
+        }
+        for element in module.elements.iter() {
+            names.elements.push(element.id(), element.name.as_deref());
+        }
+        for data in module.data.iter() {
+            names.data.push(data.id(), data.name.as_deref());
+        }
+
+        names
+    }
+}
+
+/// The names and indices of the items in a single index space.
+struct Namespace<T> {
+    items: IdHashMap<T, (u32, Option<String>)>,
+    used_names: HashSet<String>,
+}
+
+impl<T> Default for Namespace<T> {
+    fn default() -> Namespace<T> {
+        Namespace {
+            items: IdHashMap::default(),
+            used_names: HashSet::new(),
+        }
+    }
+}
+
+impl<T> Namespace<T> {
+    /// Assign the next index to `id`.
+    ///
+    /// Names have to be unique within an index space, so a name that is
+    /// already taken gets a `#N` suffix.
+    fn push(&mut self, id: Id<T>, name: Option<&str>) {
+        let index = self.items.len() as u32;
+        let name = name.filter(|name| !name.is_empty()).map(|name| {
+            let mut unique = name.to_string();
+            let mut suffix = 1;
+            while !self.used_names.insert(unique.clone()) {
+                unique = format!("{}#{}", name, suffix);
+                suffix += 1;
+            }
+            identifier(&unique)
+        });
+        self.items.insert(id, (index, name));
+    }
+
+    fn get(&self, id: Id<T>) -> &(u32, Option<String>) {
+        self.items.get(&id).unwrap_or_else(|| {
+            panic!(
+                "attempt to print a reference to an item that is not in the module; id = {}",
+                id.index()
+            )
+        })
+    }
+
+    /// How a use of `id` refers to it.
+    fn reference(&self, id: Id<T>) -> String {
+        match self.get(id) {
+            (_, Some(name)) => name.clone(),
+            (index, None) => index.to_string(),
+        }
+    }
+
+    /// How the definition of `id` names it.
+    fn definition(&self, id: Id<T>) -> String {
+        match self.get(id) {
+            (index, Some(name)) => format!("{} (;{};)", name, index),
+            (index, None) => format!("(;{};)", index),
+        }
+    }
+}
+
+struct FunctionPrinter<'a, 'b> {
+    printer: &'b Printer<'a>,
+    func: &'b LocalFunction,
+    locals: Namespace<Local>,
+
+    // The instruction sequences enclosing the instruction being printed, with
+    // the function's entry block at the bottom. The label of a sequence is
+    // named after its position in this stack.
+    labels: Vec<InstrSeqId>,
+
+    out: &'b mut String,
+}
+
+impl FunctionPrinter<'_, '_> {
+    fn instr_seq(&mut self, seq: InstrSeqId, indent: usize) {
+        let func = self.func;
+        for (instr, loc) in func.block(seq).instrs.iter() {
+            push_indent(self.out, indent);
+            self.instr(instr, *loc, indent);
+        }
+    }
+
+    /// Print the `end` that closes the instruction sequence `seq`.
+    fn end(&mut self, seq: InstrSeqId, indent: usize) {
+        push_indent(self.out, indent);
+        self.out.push_str("end");
+        self.instr_loc(self.func.block(seq).end);
+        self.out.push('\n');
+    }
+
+    /// Print the header of a block-like instruction, `block $l1 (result i32)`
+    /// for example, without finishing the line.
+    fn block_start(&mut self, keyword: &str, seq: InstrSeqId) {
+        let _ = write!(self.out, "{} $l{}", keyword, self.labels.len());
+        if self.printer.annotate_ids {
+            let _ = write!(self.out, " (;id {};)", seq.index());
+        }
+        match self.func.block(seq).ty {
+            InstrSeqType::Simple(None) => {}
+            InstrSeqType::Simple(Some(ty)) => {
+                let _ = write!(self.out, " (result {})", self.printer.val_type(ty));
+            }
+            InstrSeqType::MultiValue(ty) => {
+                let _ = write!(
+                    self.out,
+                    " (type {})",
+                    self.printer.names.types.reference(ty)
+                );
+            }
+        }
+    }
+
+    fn instr_loc(&mut self, loc: InstrLocId) {
+        if self.printer.annotate_instr_locs && !loc.is_default() {
+            let _ = write!(self.out, " (;@{:x};)", loc.data());
+        }
+    }
+
+    fn label(&self, seq: InstrSeqId) -> String {
+        let position = self.labels.iter().rposition(|label| *label == seq).expect(
+            "attempt to branch to invalid block; bad transformation pass introduced bad branching?",
+        );
+        if position == 0 {
+            // The function body's label can't be named in the text format.
+            (self.labels.len() - 1).to_string()
+        } else {
+            format!("$l{}", position)
+        }
+    }
+
+    fn nested(&mut self, seq: InstrSeqId, indent: usize) {
+        self.labels.push(seq);
+        self.instr_seq(seq, indent + 1);
+        self.labels.pop();
+    }
+
+    fn instr(&mut self, instr: &Instr, loc: InstrLocId, indent: usize) {
+        let names = &self.printer.names;
+        let text = match instr {
+            Instr::Block(b) => {
+                self.block_start("block", b.seq);
+                self.instr_loc(loc);
+                self.out.push('\n');
+                self.nested(b.seq, indent);
+                self.end(b.seq, indent);
+                return;
+            }
+            Instr::Loop(l) => {
+                self.block_start("loop", l.seq);
+                self.instr_loc(loc);
+                self.out.push('\n');
+                self.nested(l.seq, indent);
+                self.end(l.seq, indent);
+                return;
+            }
+            Instr::IfElse(e) => {
+                self.block_start("if", e.consequent);
+                self.instr_loc(loc);
+                self.out.push('\n');
+                self.nested(e.consequent, indent);
+                let end = if self.func.block(e.alternative).is_empty() {
+                    e.consequent
+                } else {
+                    push_indent(self.out, indent);
+                    self.out.push_str("else");
+                    self.instr_loc(self.func.block(e.consequent).end);
+                    if self.printer.annotate_ids {
+                        let _ = write!(self.out, " (;id {};)", e.alternative.index());
+                    }
+                    self.out.push('\n');
+                    self.nested(e.alternative, indent);
+                    e.alternative
+                };
+                self.end(end, indent);
+                return;
+            }
+            Instr::TryTable(t) => {
+                // The catch clauses branch to labels outside of the
+                // `try_table`, so resolve them before entering it.
+                let catches = t
+                    .catches
+                    .iter()
+                    .map(|catch| match catch {
+                        TryTableCatch::Catch { tag, label } => format!(
+                            " (catch {} {})",
+                            names.tags.reference(*tag),
+                            self.label(*label)
+                        ),
+                        TryTableCatch::CatchRef { tag, label } => format!(
+                            " (catch_ref {} {})",
+                            names.tags.reference(*tag),
+                            self.label(*label)
+                        ),
+                        TryTableCatch::CatchAll { label } => {
+                            format!(" (catch_all {})", self.label(*label))
+                        }
+                        TryTableCatch::CatchAllRef { label } => {
+                            format!(" (catch_all_ref {})", self.label(*label))
+                        }
+                    })
+                    .collect::<String>();
+                self.block_start("try_table", t.seq);
+                self.out.push_str(&catches);
+                self.instr_loc(loc);
+                self.out.push('\n');
+                self.nested(t.seq, indent);
+                self.end(t.seq, indent);
+                return;
+            }
+            Instr::Try(t) => {
+                self.block_start("try", t.seq);
+                self.instr_loc(loc);
+                self.out.push('\n');
+                self.nested(t.seq, indent);
+                let mut last = t.seq;
+                for catch in t.catches.iter() {
+                    push_indent(self.out, indent);
+                    let handler = match catch {
+                        LegacyCatch::Catch { tag, handler } => {
+                            let _ = write!(self.out, "catch {}", names.tags.reference(*tag));
+                            *handler
+                        }
+                        LegacyCatch::CatchAll { handler } => {
+                            self.out.push_str("catch_all");
+                            *handler
+                        }
+                        LegacyCatch::Delegate { relative_depth } => {
+                            let _ = writeln!(self.out, "delegate {}", relative_depth);
+                            return;
+                        }
+                    };
+                    if self.printer.annotate_ids {
+                        let _ = write!(self.out, " (;id {};)", handler.index());
+                    }
+                    self.out.push('\n');
+                    // A handler shares the label of its `try`.
+                    self.nested(handler, indent);
+                    last = handler;
+                }
+                self.end(last, indent);
+                return;
+            }
+
+            Instr::Call(c) => format!("call {}", names.funcs.reference(c.func)),
+            Instr::CallIndirect(c) => format!(
+                "call_indirect{} (type {})",
+                self.table_operand(c.table),
+                names.types.reference(c.ty)
+            ),
+            Instr::ReturnCall(c) => format!("return_call {}", names.funcs.reference(c.func)),
+            Instr::ReturnCallIndirect(c) => format!(
+                "return_call_indirect{} (type {})",
+                self.table_operand(c.table),
+                names.types.reference(c.ty)
+            ),
+            Instr::CallRef(c) => format!("call_ref {}", names.types.reference(c.ty)),
+            Instr::ReturnCallRef(c) => {
+                format!("return_call_ref {}", names.types.reference(c.ty))
+            }
+            Instr::LocalGet(l) => format!("local.get {}", self.locals.reference(l.local)),
+            Instr::LocalSet(l) => format!("local.set {}", self.locals.reference(l.local)),
+            Instr::LocalTee(l) => format!("local.tee {}", self.locals.reference(l.local)),
+            Instr::GlobalGet(g) => format!("global.get {}", names.globals.reference(g.global)),
+            Instr::GlobalSet(g) => format!("global.set {}", names.globals.reference(g.global)),
+            Instr::Const(c) => value_const(&c.value),
+            Instr::TernOp(t) => ternop_name(&t.op).to_string(),
+            Instr::Binop(b) => lane_op(binop_name(&b.op)),
+            Instr::Unop(u) => lane_op(unop_name(&u.op)),
+            Instr::Select(s) => match s.ty {
+                Some(ty) => format!("select (result {})", self.printer.val_type(ty)),
+                None => "select".to_string(),
+            },
+            Instr::Unreachable(_) => "unreachable".to_string(),
+            Instr::Br(b) => format!("br {}", self.label(b.block)),
+            Instr::BrIf(b) => format!("br_if {}", self.label(b.block)),
+            Instr::BrTable(b) => {
+                let mut text = "br_table".to_string();
+                for block in b.blocks.iter().chain(Some(&b.default)) {
+                    text.push(' ');
+                    text.push_str(&self.label(*block));
+                }
+                text
+            }
+            Instr::Drop(_) => "drop".to_string(),
+            Instr::Return(_) => "return".to_string(),
+
+            Instr::MemorySize(m) => format!("memory.size{}", self.memory_operand(m.memory)),
+            Instr::MemoryGrow(m) => format!("memory.grow{}", self.memory_operand(m.memory)),
+            Instr::MemoryInit(m) => format!(
+                "memory.init{} {}",
+                self.memory_operand(m.memory),
+                names.data.reference(m.data)
+            ),
+            Instr::DataDrop(d) => format!("data.drop {}", names.data.reference(d.data)),
+            Instr::MemoryCopy(m) => {
+                if names.memories.get(m.dst).0 == 0 && names.memories.get(m.src).0 == 0 {
+                    "memory.copy".to_string()
+                } else {
+                    format!(
+                        "memory.copy {} {}",
+                        names.memories.reference(m.dst),
+                        names.memories.reference(m.src)
+                    )
+                }
+            }
+            Instr::MemoryFill(m) => format!("memory.fill{}", self.memory_operand(m.memory)),
+            Instr::Load(l) => format!(
+                "{}{}",
+                load_name(&l.kind),
+                self.memarg(l.memory, &l.arg, l.kind.width())
+            ),
+            Instr::Store(s) => format!(
+                "{}{}",
+                store_name(&s.kind),
+                self.memarg(s.memory, &s.arg, s.kind.width())
+            ),
+            Instr::AtomicRmw(a) => {
+                let op = match a.op {
+                    AtomicOp::Add => "add",
+                    AtomicOp::Sub => "sub",
+                    AtomicOp::And => "and",
+                    AtomicOp::Or => "or",
+                    AtomicOp::Xor => "xor",
+                    AtomicOp::Xchg => "xchg",
+                };
+                format!(
+                    "{}{}",
+                    atomic_rmw_name(&a.width, op),
+                    self.memarg(a.memory, &a.arg, a.width.bytes())
+                )
+            }
+            Instr::Cmpxchg(c) => format!(
+                "{}{}",
+                atomic_rmw_name(&c.width, "cmpxchg"),
+                self.memarg(c.memory, &c.arg, c.width.bytes())
+            ),
+            Instr::AtomicNotify(a) => {
+                format!("memory.atomic.notify{}", self.memarg(a.memory, &a.arg, 4))
+            }
+            Instr::AtomicWait(a) => {
+                let (name, width) = if a.sixty_four {
+                    ("memory.atomic.wait64", 8)
+                } else {
+                    ("memory.atomic.wait32", 4)
+                };
+                format!("{}{}", name, self.memarg(a.memory, &a.arg, width))
+            }
+            Instr::AtomicFence(_) => "atomic.fence".to_string(),
+
+            Instr::TableGet(t) => format!("table.get {}", names.tables.reference(t.table)),
+            Instr::TableSet(t) => format!("table.set {}", names.tables.reference(t.table)),
+            Instr::TableGrow(t) => format!("table.grow {}", names.tables.reference(t.table)),
+            Instr::TableSize(t) => format!("table.size {}", names.tables.reference(t.table)),
+            Instr::TableFill(t) => format!("table.fill {}", names.tables.reference(t.table)),
+            Instr::TableInit(t) => format!(
+                "table.init {} {}",
+                names.tables.reference(t.table),
+                names.elements.reference(t.elem)
+            ),
+            Instr::ElemDrop(e) => format!("elem.drop {}", names.elements.reference(e.elem)),
+            Instr::TableCopy(t) => format!(
+                "table.copy {} {}",
+                names.tables.reference(t.dst),
+                names.tables.reference(t.src)
+            ),
+
+            Instr::RefNull(r) => format!("ref.null {}", self.printer.heap_type(r.ty.heap_type)),
+            Instr::RefIsNull(_) => "ref.is_null".to_string(),
+            Instr::RefFunc(r) => format!("ref.func {}", names.funcs.reference(r.func)),
+            Instr::RefAsNonNull(_) => "ref.as_non_null".to_string(),
+            Instr::BrOnNull(b) => format!("br_on_null {}", self.label(b.block)),
+            Instr::BrOnNonNull(b) => format!("br_on_non_null {}", self.label(b.block)),
+            Instr::RefI31(_) => "ref.i31".to_string(),
+            Instr::I31GetS(_) => "i31.get_s".to_string(),
+            Instr::I31GetU(_) => "i31.get_u".to_string(),
+            Instr::RefTest(r) => format!("ref.test {}", self.cast_type(r.nullable, r.heap_type)),
+            Instr::RefCast(r) => format!("ref.cast {}", self.cast_type(r.nullable, r.heap_type)),
+            Instr::BrOnCast(b) => format!(
+                "br_on_cast {} {} {}",
+                self.label(b.block),
+                self.cast_type(b.from_nullable, b.from_heap_type),
+                self.cast_type(b.to_nullable, b.to_heap_type)
+            ),
+            Instr::BrOnCastFail(b) => format!(
+                "br_on_cast_fail {} {} {}",
+                self.label(b.block),
+                self.cast_type(b.from_nullable, b.from_heap_type),
+                self.cast_type(b.to_nullable, b.to_heap_type)
+            ),
+            Instr::AnyConvertExtern(_) => "any.convert_extern".to_string(),
+            Instr::ExternConvertAny(_) => "extern.convert_any".to_string(),
+            Instr::RefEq(_) => "ref.eq".to_string(),
+
+            Instr::StructNew(s) => format!("struct.new {}", names.types.reference(s.ty)),
+            Instr::StructNewDefault(s) => {
+                format!("struct.new_default {}", names.types.reference(s.ty))
+            }
+            Instr::StructGet(s) => {
+                format!("struct.get {} {}", names.types.reference(s.ty), s.field)
+            }
+            Instr::StructGetS(s) => {
+                format!("struct.get_s {} {}", names.types.reference(s.ty), s.field)
+            }
+            Instr::StructGetU(s) => {
+                format!("struct.get_u {} {}", names.types.reference(s.ty), s.field)
+            }
+            Instr::StructSet(s) => {
+                format!("struct.set {} {}", names.types.reference(s.ty), s.field)
+            }
+            Instr::ArrayNew(a) => format!("array.new {}", names.types.reference(a.ty)),
+            Instr::ArrayNewDefault(a) => {
+                format!("array.new_default {}", names.types.reference(a.ty))
+            }
+            Instr::ArrayNewFixed(a) => {
+                format!("array.new_fixed {} {}", names.types.reference(a.ty), a.len)
+            }
+            Instr::ArrayNewData(a) => format!(
+                "array.new_data {} {}",
+                names.types.reference(a.ty),
+                names.data.reference(a.data)
+            ),
+            Instr::ArrayNewElem(a) => format!(
+                "array.new_elem {} {}",
+                names.types.reference(a.ty),
+                names.elements.reference(a.elem)
+            ),
+            Instr::ArrayGet(a) => format!("array.get {}", names.types.reference(a.ty)),
+            Instr::ArrayGetS(a) => format!("array.get_s {}", names.types.reference(a.ty)),
+            Instr::ArrayGetU(a) => format!("array.get_u {}", names.types.reference(a.ty)),
+            Instr::ArraySet(a) => format!("array.set {}", names.types.reference(a.ty)),
+            Instr::ArrayLen(_) => "array.len".to_string(),
+            Instr::ArrayFill(a) => format!("array.fill {}", names.types.reference(a.ty)),
+            Instr::ArrayCopy(a) => format!(
+                "array.copy {} {}",
+                names.types.reference(a.dst),
+                names.types.reference(a.src)
+            ),
+            Instr::ArrayInitData(a) => format!(
+                "array.init_data {} {}",
+                names.types.reference(a.ty),
+                names.data.reference(a.data)
+            ),
+            Instr::ArrayInitElem(a) => format!(
+                "array.init_elem {} {}",
+                names.types.reference(a.ty),
+                names.elements.reference(a.elem)
+            ),
+
+            Instr::V128Bitselect(_) => "v128.bitselect".to_string(),
+            Instr::I8x16Swizzle(_) => "i8x16.swizzle".to_string(),
+            Instr::I8x16Shuffle(s) => {
+                let mut text = "i8x16.shuffle".to_string();
+                for index in s.indices.iter() {
+                    let _ = write!(text, " {}", index);
+                }
+                text
+            }
+            Instr::LoadSimd(l) => {
+                let (name, width, lane) = load_simd_name(&l.kind);
+                let mut text = format!("{}{}", name, self.memarg(l.memory, &l.arg, width));
+                if let Some(lane) = lane {
+                    let _ = write!(text, " {}", lane);
+                }
+                text
+            }
+
+            Instr::Throw(t) => format!("throw {}", names.tags.reference(t.tag)),
+            Instr::ThrowRef(_) => "throw_ref".to_string(),
+            Instr::Rethrow(r) => format!("rethrow {}", r.relative_depth),
+
+            Instr::ContNew(c) => format!("cont.new {}", names.types.reference(c.ty)),
+            Instr::ContBind(c) => format!(
+                "cont.bind {} {}",
+                names.types.reference(c.from),
+                names.types.reference(c.to)
+            ),
+            Instr::Suspend(s) => format!("suspend {}", names.tags.reference(s.tag)),
+            Instr::Resume(r) => format!(
+                "resume {}{}",
+                names.types.reference(r.ty),
+                self.resume_handlers(&r.handlers)
+            ),
+            Instr::ResumeThrow(r) => format!(
+                "resume_throw {} {}{}",
+                names.types.reference(r.ty),
+                names.tags.reference(r.tag),
+                self.resume_handlers(&r.handlers)
+            ),
+            Instr::ResumeThrowRef(r) => format!(
+                "resume_throw_ref {}{}",
+                names.types.reference(r.ty),
+                self.resume_handlers(&r.handlers)
+            ),
+            Instr::Switch(s) => format!(
+                "switch {} {}",
+                names.types.reference(s.ty),
+                names.tags.reference(s.tag)
+            ),
+            Instr::I64Add128(_) => "i64.add128".to_string(),
+            Instr::I64Sub128(_) => "i64.sub128".to_string(),
+            Instr::I64MulWideS(_) => "i64.mul_wide_s".to_string(),
+            Instr::I64MulWideU(_) => "i64.mul_wide_u".to_string(),
+        };
+        self.out.push_str(&text);
+        self.instr_loc(loc);
+        self.out.push('\n');
+    }
+
+    /// The `(on ...)` clauses of a `resume` instruction.
+    fn resume_handlers(&self, handlers: &[ResumeHandler]) -> String {
+        let names = &self.printer.names;
+        handlers
+            .iter()
+            .map(|handler| match handler {
+                ResumeHandler::OnLabel { tag, label } => format!(
+                    " (on {} {})",
+                    names.tags.reference(*tag),
+                    self.label(*label)
+                ),
+                ResumeHandler::OnSwitch { tag } => {
+                    format!(" (on {} switch)", names.tags.reference(*tag))
+                }
+            })
+            .collect()
+    }
+
+    /// The optional table operand of `call_indirect`, which defaults to the
+    /// first table.
+    fn table_operand(&self, table: TableId) -> String {
+        let names = &self.printer.names;
+        if names.tables.get(table).0 == 0 {
+            String::new()
+        } else {
+            format!(" {}", names.tables.reference(table))
+        }
+    }
+
+    /// The optional memory operand of memory instructions, which defaults to
+    /// the first memory.
+    fn memory_operand(&self, memory: MemoryId) -> String {
+        let names = &self.printer.names;
+        if names.memories.get(memory).0 == 0 {
+            String::new()
+        } else {
+            format!(" {}", names.memories.reference(memory))
+        }
+    }
+
+    /// The operands of a memory access, where `natural` is the natural
+    /// alignment of the access in bytes.
+    fn memarg(&self, memory: MemoryId, arg: &MemArg, natural: u32) -> String {
+        let mut text = self.memory_operand(memory);
+        if arg.offset != 0 {
+            let _ = write!(text, " offset={}", arg.offset);
+        }
+        if arg.align != natural {
+            let _ = write!(text, " align={}", arg.align);
+        }
+        text
+    }
+
+    fn cast_type(&self, nullable: bool, heap_type: HeapType) -> String {
+        format!(
+            "(ref {}{})",
+            if nullable { "null " } else { "" },
+            self.printer.heap_type(heap_type)
+        )
+    }
+}
+
+fn push_indent(out: &mut String, indent: usize) {
+    for _ in 0..indent {
+        out.push_str("  ");
+    }
+}
+
+/// The `$name` identifier for a name, which is quoted if it contains
+/// characters that plain identifiers can't.
+fn identifier(name: &str) -> String {
+    let plain = name
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-./:<=>?@\\^_`|~".contains(&b));
+    if plain {
+        format!("${}", name)
+    } else {
+        format!("${}", string(name.as_bytes()))
+    }
+}
+
+/// A string literal for the given bytes.
+fn string(bytes: &[u8]) -> String {
+    let mut s = String::from("\"");
+    for b in bytes {
+        match b {
+            b'"' => s.push_str("\\\""),
+            b'\\' => s.push_str("\\\\"),
+            0x20..=0x7e => s.push(*b as char),
+            _ => {
+                let _ = write!(s, "\\{:02x}", b);
+            }
+        }
+    }
+    s.push('"');
+    s
+}
+
+fn value_const(value: &Value) -> String {
+    match value {
+        Value::I32(v) => format!("i32.const {}", v),
+        Value::I64(v) => format!("i64.const {}", v),
+        Value::F32(v) => format!("f32.const {}", f32_literal(*v)),
+        Value::F64(v) => format!("f64.const {}", f64_literal(*v)),
+        Value::V128(v) => format!(
+            "v128.const i32x4 {:#010x} {:#010x} {:#010x} {:#010x}",
+            *v as u32,
+            (*v >> 32) as u32,
+            (*v >> 64) as u32,
+            (*v >> 96) as u32
+        ),
+    }
+}
+
+fn f32_literal(v: f32) -> String {
+    let bits = v.to_bits();
+    let sign = if bits >> 31 == 1 { "-" } else { "" };
+    if v.is_nan() {
+        let payload = bits & 0x7f_ffff;
+        if payload == 0x40_0000 {
+            format!("{}nan", sign)
+        } else {
+            format!("{}nan:{:#x}", sign, payload)
+        }
+    } else if v.is_infinite() {
+        format!("{}inf", sign)
+    } else {
+        // `Display` prints the shortest decimal that parses back to `v`.
+        v.to_string()
+    }
+}
+
+fn f64_literal(v: f64) -> String {
+    let bits = v.to_bits();
+    let sign = if bits >> 63 == 1 { "-" } else { "" };
+    if v.is_nan() {
+        let payload = bits & 0xf_ffff_ffff_ffff;
+        if payload == 0x8_0000_0000_0000 {
+            format!("{}nan", sign)
+        } else {
+            format!("{}nan:{:#x}", sign, payload)
+        }
+    } else if v.is_infinite() {
+        format!("{}inf", sign)
+    } else {
+        v.to_string()
+    }
+}
+
+fn lane_op((name, lane): (&str, Option<u8>)) -> String {
+    match lane {
+        Some(lane) => format!("{} {}", name, lane),
+        None => name.to_string(),
+    }
+}
+
+fn load_name(kind: &LoadKind) -> &'static str {
+    use ExtendedLoad::*;
+    match kind {
+        LoadKind::I32 { atomic: false } => "i32.load",
+        LoadKind::I32 { atomic: true } => "i32.atomic.load",
+        LoadKind::I64 { atomic: false } => "i64.load",
+        LoadKind::I64 { atomic: true } => "i64.atomic.load",
+        LoadKind::F32 => "f32.load",
+        LoadKind::F64 => "f64.load",
+        LoadKind::V128 => "v128.load",
+        LoadKind::I32_8 { kind: SignExtend } => "i32.load8_s",
+        LoadKind::I32_8 { kind: ZeroExtend } => "i32.load8_u",
+        LoadKind::I32_8 {
+            kind: ZeroExtendAtomic,
+        } => "i32.atomic.load8_u",
+        LoadKind::I32_16 { kind: SignExtend } => "i32.load16_s",
+        LoadKind::I32_16 { kind: ZeroExtend } => "i32.load16_u",
+        LoadKind::I32_16 {
+            kind: ZeroExtendAtomic,
+        } => "i32.atomic.load16_u",
+        LoadKind::I64_8 { kind: SignExtend } => "i64.load8_s",
+        LoadKind::I64_8 { kind: ZeroExtend } => "i64.load8_u",
+        LoadKind::I64_8 {
+            kind: ZeroExtendAtomic,
+        } => "i64.atomic.load8_u",
+        LoadKind::I64_16 { kind: SignExtend } => "i64.load16_s",
+        LoadKind::I64_16 { kind: ZeroExtend } => "i64.load16_u",
+        LoadKind::I64_16 {
+            kind: ZeroExtendAtomic,
+        } => "i64.atomic.load16_u",
+        LoadKind::I64_32 { kind: SignExtend } => "i64.load32_s",
+        LoadKind::I64_32 { kind: ZeroExtend } => "i64.load32_u",
+        LoadKind::I64_32 {
+            kind: ZeroExtendAtomic,
+        } => "i64.atomic.load32_u",
+    }
+}
+
+fn store_name(kind: &StoreKind) -> &'static str {
+    match kind {
+        StoreKind::I32 { atomic: false } => "i32.store",
+        StoreKind::I32 { atomic: true } => "i32.atomic.store",
+        StoreKind::I64 { atomic: false } => "i64.store",
+        StoreKind::I64 { atomic: true } => "i64.atomic.store",
+        StoreKind::F32 => "f32.store",
+        StoreKind::F64 => "f64.store",
+        StoreKind::V128 => "v128.store",
+        StoreKind::I32_8 { atomic: false } => "i32.store8",
+        StoreKind::I32_8 { atomic: true } => "i32.atomic.store8",
+        StoreKind::I32_16 { atomic: false } => "i32.store16",
+        StoreKind::I32_16 { atomic: true } => "i32.atomic.store16",
+        StoreKind::I64_8 { atomic: false } => "i64.store8",
+        StoreKind::I64_8 { atomic: true } => "i64.atomic.store8",
+        StoreKind::I64_16 { atomic: false } => "i64.store16",
+        StoreKind::I64_16 { atomic: true } => "i64.atomic.store16",
+        StoreKind::I64_32 { atomic: false } => "i64.store32",
+        StoreKind::I64_32 { atomic: true } => "i64.atomic.store32",
+    }
+}
+
+fn load_simd_name(kind: &LoadSimdKind) -> (&'static str, u32, Option<u8>) {
+    use LoadSimdKind::*;
+    match *kind {
+        Splat8 => ("v128.load8_splat", 1, None),
+        Splat16 => ("v128.load16_splat", 2, None),
+        Splat32 => ("v128.load32_splat", 4, None),
+        Splat64 => ("v128.load64_splat", 8, None),
+        V128Load8x8S => ("v128.load8x8_s", 8, None),
+        V128Load8x8U => ("v128.load8x8_u", 8, None),
+        V128Load16x4S => ("v128.load16x4_s", 8, None),
+        V128Load16x4U => ("v128.load16x4_u", 8, None),
+        V128Load32x2S => ("v128.load32x2_s", 8, None),
+        V128Load32x2U => ("v128.load32x2_u", 8, None),
+        V128Load32Zero => ("v128.load32_zero", 4, None),
+        V128Load64Zero => ("v128.load64_zero", 8, None),
+        V128Load8Lane(lane) => ("v128.load8_lane", 1, Some(lane)),
+        V128Load16Lane(lane) => ("v128.load16_lane", 2, Some(lane)),
+        V128Load32Lane(lane) => ("v128.load32_lane", 4, Some(lane)),
+        V128Load64Lane(lane) => ("v128.load64_lane", 8, Some(lane)),
+        V128Store8Lane(lane) => ("v128.store8_lane", 1, Some(lane)),
+        V128Store16Lane(lane) => ("v128.store16_lane", 2, Some(lane)),
+        V128Store32Lane(lane) => ("v128.store32_lane", 4, Some(lane)),
+        V128Store64Lane(lane) => ("v128.store64_lane", 8, Some(lane)),
+    }
+}
+
+fn atomic_rmw_name(width: &AtomicWidth, op: &str) -> String {
+    let (prefix, suffix) = match width {
+        AtomicWidth::I32 => ("i32.atomic.rmw", ""),
+        AtomicWidth::I32_8 => ("i32.atomic.rmw8", "_u"),
+        AtomicWidth::I32_16 => ("i32.atomic.rmw16", "_u"),
+        AtomicWidth::I64 => ("i64.atomic.rmw", ""),
+        AtomicWidth::I64_8 => ("i64.atomic.rmw8", "_u"),
+        AtomicWidth::I64_16 => ("i64.atomic.rmw16", "_u"),
+        AtomicWidth::I64_32 => ("i64.atomic.rmw32", "_u"),
+    };
+    format!("{}.{}{}", prefix, op, suffix)
+}
+
+fn ternop_name(op: &TernaryOp) -> &'static str {
+    use TernaryOp::*;
+    match *op {
+        F32x4RelaxedMadd => "f32x4.relaxed_madd",
+        F32x4RelaxedNmadd => "f32x4.relaxed_nmadd",
+        F64x2RelaxedMadd => "f64x2.relaxed_madd",
+        F64x2RelaxedNmadd => "f64x2.relaxed_nmadd",
+        I8x16RelaxedLaneselect => "i8x16.relaxed_laneselect",
+        I16x8RelaxedLaneselect => "i16x8.relaxed_laneselect",
+        I32x4RelaxedLaneselect => "i32x4.relaxed_laneselect",
+        I64x2RelaxedLaneselect => "i64x2.relaxed_laneselect",
+        I32x4RelaxedDotI8x16I7x16AddS => "i32x4.relaxed_dot_i8x16_i7x16_add_s",
+    }
+}
+
+fn binop_name(op: &BinaryOp) -> (&'static str, Option<u8>) {
+    use BinaryOp::*;
+    match *op {
+        I32Eq => ("i32.eq", None),
+        I32Ne => ("i32.ne", None),
+        I32LtS => ("i32.lt_s", None),
+        I32LtU => ("i32.lt_u", None),
+        I32GtS => ("i32.gt_s", None),
+        I32GtU => ("i32.gt_u", None),
+        I32LeS => ("i32.le_s", None),
+        I32LeU => ("i32.le_u", None),
+        I32GeS => ("i32.ge_s", None),
+        I32GeU => ("i32.ge_u", None),
+        I64Eq => ("i64.eq", None),
+        I64Ne => ("i64.ne", None),
+        I64LtS => ("i64.lt_s", None),
+        I64LtU => ("i64.lt_u", None),
+        I64GtS => ("i64.gt_s", None),
+        I64GtU => ("i64.gt_u", None),
+        I64LeS => ("i64.le_s", None),
+        I64LeU => ("i64.le_u", None),
+        I64GeS => ("i64.ge_s", None),
+        I64GeU => ("i64.ge_u", None),
+        F32Eq => ("f32.eq", None),
+        F32Ne => ("f32.ne", None),
+        F32Lt => ("f32.lt", None),
+        F32Gt => ("f32.gt", None),
+        F32Le => ("f32.le", None),
+        F32Ge => ("f32.ge", None),
+        F64Eq => ("f64.eq", None),
+        F64Ne => ("f64.ne", None),
+        F64Lt => ("f64.lt", None),
+        F64Gt => ("f64.gt", None),
+        F64Le => ("f64.le", None),
+        F64Ge => ("f64.ge", None),
+        I32Add => ("i32.add", None),
+        I32Sub => ("i32.sub", None),
+        I32Mul => ("i32.mul", None),
+        I32DivS => ("i32.div_s", None),
+        I32DivU => ("i32.div_u", None),
+        I32RemS => ("i32.rem_s", None),
+        I32RemU => ("i32.rem_u", None),
+        I32And => ("i32.and", None),
+        I32Or => ("i32.or", None),
+        I32Xor => ("i32.xor", None),
+        I32Shl => ("i32.shl", None),
+        I32ShrS => ("i32.shr_s", None),
+        I32ShrU => ("i32.shr_u", None),
+        I32Rotl => ("i32.rotl", None),
+        I32Rotr => ("i32.rotr", None),
+        I64Add => ("i64.add", None),
+        I64Sub => ("i64.sub", None),
+        I64Mul => ("i64.mul", None),
+        I64DivS => ("i64.div_s", None),
+        I64DivU => ("i64.div_u", None),
+        I64RemS => ("i64.rem_s", None),
+        I64RemU => ("i64.rem_u", None),
+        I64And => ("i64.and", None),
+        I64Or => ("i64.or", None),
+        I64Xor => ("i64.xor", None),
+        I64Shl => ("i64.shl", None),
+        I64ShrS => ("i64.shr_s", None),
+        I64ShrU => ("i64.shr_u", None),
+        I64Rotl => ("i64.rotl", None),
+        I64Rotr => ("i64.rotr", None),
+        F32Add => ("f32.add", None),
+        F32Sub => ("f32.sub", None),
+        F32Mul => ("f32.mul", None),
+        F32Div => ("f32.div", None),
+        F32Min => ("f32.min", None),
+        F32Max => ("f32.max", None),
+        F32Copysign => ("f32.copysign", None),
+        F64Add => ("f64.add", None),
+        F64Sub => ("f64.sub", None),
+        F64Mul => ("f64.mul", None),
+        F64Div => ("f64.div", None),
+        F64Min => ("f64.min", None),
+        F64Max => ("f64.max", None),
+        F64Copysign => ("f64.copysign", None),
+        I8x16ReplaceLane { idx } => ("i8x16.replace_lane", Some(idx)),
+        I16x8ReplaceLane { idx } => ("i16x8.replace_lane", Some(idx)),
+        I32x4ReplaceLane { idx } => ("i32x4.replace_lane", Some(idx)),
+        I64x2ReplaceLane { idx } => ("i64x2.replace_lane", Some(idx)),
+        F32x4ReplaceLane { idx } => ("f32x4.replace_lane", Some(idx)),
+        F64x2ReplaceLane { idx } => ("f64x2.replace_lane", Some(idx)),
+        I8x16Eq => ("i8x16.eq", None),
+        I8x16Ne => ("i8x16.ne", None),
+        I8x16LtS => ("i8x16.lt_s", None),
+        I8x16LtU => ("i8x16.lt_u", None),
+        I8x16GtS => ("i8x16.gt_s", None),
+        I8x16GtU => ("i8x16.gt_u", None),
+        I8x16LeS => ("i8x16.le_s", None),
+        I8x16LeU => ("i8x16.le_u", None),
+        I8x16GeS => ("i8x16.ge_s", None),
+        I8x16GeU => ("i8x16.ge_u", None),
+        I16x8Eq => ("i16x8.eq", None),
+        I16x8Ne => ("i16x8.ne", None),
+        I16x8LtS => ("i16x8.lt_s", None),
+        I16x8LtU => ("i16x8.lt_u", None),
+        I16x8GtS => ("i16x8.gt_s", None),
+        I16x8GtU => ("i16x8.gt_u", None),
+        I16x8LeS => ("i16x8.le_s", None),
+        I16x8LeU => ("i16x8.le_u", None),
+        I16x8GeS => ("i16x8.ge_s", None),
+        I16x8GeU => ("i16x8.ge_u", None),
+        I32x4Eq => ("i32x4.eq", None),
+        I32x4Ne => ("i32x4.ne", None),
+        I32x4LtS => ("i32x4.lt_s", None),
+        I32x4LtU => ("i32x4.lt_u", None),
+        I32x4GtS => ("i32x4.gt_s", None),
+        I32x4GtU => ("i32x4.gt_u", None),
+        I32x4LeS => ("i32x4.le_s", None),
+        I32x4LeU => ("i32x4.le_u", None),
+        I32x4GeS => ("i32x4.ge_s", None),
+        I32x4GeU => ("i32x4.ge_u", None),
+        I64x2Eq => ("i64x2.eq", None),
+        I64x2Ne => ("i64x2.ne", None),
+        I64x2LtS => ("i64x2.lt_s", None),
+        I64x2GtS => ("i64x2.gt_s", None),
+        I64x2LeS => ("i64x2.le_s", None),
+        I64x2GeS => ("i64x2.ge_s", None),
+        F32x4Eq => ("f32x4.eq", None),
+        F32x4Ne => ("f32x4.ne", None),
+        F32x4Lt => ("f32x4.lt", None),
+        F32x4Gt => ("f32x4.gt", None),
+        F32x4Le => ("f32x4.le", None),
+        F32x4Ge => ("f32x4.ge", None),
+        F64x2Eq => ("f64x2.eq", None),
+        F64x2Ne => ("f64x2.ne", None),
+        F64x2Lt => ("f64x2.lt", None),
+        F64x2Gt => ("f64x2.gt", None),
+        F64x2Le => ("f64x2.le", None),
+        F64x2Ge => ("f64x2.ge", None),
+        V128And => ("v128.and", None),
+        V128AndNot => ("v128.andnot", None),
+        V128Or => ("v128.or", None),
+        V128Xor => ("v128.xor", None),
+        I8x16NarrowI16x8S => ("i8x16.narrow_i16x8_s", None),
+        I8x16NarrowI16x8U => ("i8x16.narrow_i16x8_u", None),
+        I8x16Shl => ("i8x16.shl", None),
+        I8x16ShrS => ("i8x16.shr_s", None),
+        I8x16ShrU => ("i8x16.shr_u", None),
+        I8x16Add => ("i8x16.add", None),
+        I8x16AddSatS => ("i8x16.add_sat_s", None),
+        I8x16AddSatU => ("i8x16.add_sat_u", None),
+        I8x16Sub => ("i8x16.sub", None),
+        I8x16SubSatS => ("i8x16.sub_sat_s", None),
+        I8x16SubSatU => ("i8x16.sub_sat_u", None),
+        I8x16MinS => ("i8x16.min_s", None),
+        I8x16MinU => ("i8x16.min_u", None),
+        I8x16MaxS => ("i8x16.max_s", None),
+        I8x16MaxU => ("i8x16.max_u", None),
+        I8x16AvgrU => ("i8x16.avgr_u", None),
+        I16x8NarrowI32x4S => ("i16x8.narrow_i32x4_s", None),
+        I16x8NarrowI32x4U => ("i16x8.narrow_i32x4_u", None),
+        I16x8Shl => ("i16x8.shl", None),
+        I16x8ShrS => ("i16x8.shr_s", None),
+        I16x8ShrU => ("i16x8.shr_u", None),
+        I16x8Add => ("i16x8.add", None),
+        I16x8AddSatS => ("i16x8.add_sat_s", None),
+        I16x8AddSatU => ("i16x8.add_sat_u", None),
+        I16x8Sub => ("i16x8.sub", None),
+        I16x8SubSatS => ("i16x8.sub_sat_s", None),
+        I16x8SubSatU => ("i16x8.sub_sat_u", None),
+        I16x8Mul => ("i16x8.mul", None),
+        I16x8MinS => ("i16x8.min_s", None),
+        I16x8MinU => ("i16x8.min_u", None),
+        I16x8MaxS => ("i16x8.max_s", None),
+        I16x8MaxU => ("i16x8.max_u", None),
+        I16x8AvgrU => ("i16x8.avgr_u", None),
+        I32x4Shl => ("i32x4.shl", None),
+        I32x4ShrS => ("i32x4.shr_s", None),
+        I32x4ShrU => ("i32x4.shr_u", None),
+        I32x4Add => ("i32x4.add", None),
+        I32x4Sub => ("i32x4.sub", None),
+        I32x4Mul => ("i32x4.mul", None),
+        I32x4MinS => ("i32x4.min_s", None),
+        I32x4MinU => ("i32x4.min_u", None),
+        I32x4MaxS => ("i32x4.max_s", None),
+        I32x4MaxU => ("i32x4.max_u", None),
+        I64x2Shl => ("i64x2.shl", None),
+        I64x2ShrS => ("i64x2.shr_s", None),
+        I64x2ShrU => ("i64x2.shr_u", None),
+        I64x2Add => ("i64x2.add", None),
+        I64x2Sub => ("i64x2.sub", None),
+        I64x2Mul => ("i64x2.mul", None),
+        F32x4Add => ("f32x4.add", None),
+        F32x4Sub => ("f32x4.sub", None),
+        F32x4Mul => ("f32x4.mul", None),
+        F32x4Div => ("f32x4.div", None),
+        F32x4Min => ("f32x4.min", None),
+        F32x4Max => ("f32x4.max", None),
+        F32x4PMin => ("f32x4.pmin", None),
+        F32x4PMax => ("f32x4.pmax", None),
+        F64x2Add => ("f64x2.add", None),
+        F64x2Sub => ("f64x2.sub", None),
+        F64x2Mul => ("f64x2.mul", None),
+        F64x2Div => ("f64x2.div", None),
+        F64x2Min => ("f64x2.min", None),
+        F64x2Max => ("f64x2.max", None),
+        F64x2PMin => ("f64x2.pmin", None),
+        F64x2PMax => ("f64x2.pmax", None),
+        I32x4DotI16x8S => ("i32x4.dot_i16x8_s", None),
+        I16x8Q15MulrSatS => ("i16x8.q15mulr_sat_s", None),
+        I16x8ExtMulLowI8x16S => ("i16x8.extmul_low_i8x16_s", None),
+        I16x8ExtMulHighI8x16S => ("i16x8.extmul_high_i8x16_s", None),
+        I16x8ExtMulLowI8x16U => ("i16x8.extmul_low_i8x16_u", None),
+        I16x8ExtMulHighI8x16U => ("i16x8.extmul_high_i8x16_u", None),
+        I32x4ExtMulLowI16x8S => ("i32x4.extmul_low_i16x8_s", None),
+        I32x4ExtMulHighI16x8S => ("i32x4.extmul_high_i16x8_s", None),
+        I32x4ExtMulLowI16x8U => ("i32x4.extmul_low_i16x8_u", None),
+        I32x4ExtMulHighI16x8U => ("i32x4.extmul_high_i16x8_u", None),
+        I64x2ExtMulLowI32x4S => ("i64x2.extmul_low_i32x4_s", None),
+        I64x2ExtMulHighI32x4S => ("i64x2.extmul_high_i32x4_s", None),
+        I64x2ExtMulLowI32x4U => ("i64x2.extmul_low_i32x4_u", None),
+        I64x2ExtMulHighI32x4U => ("i64x2.extmul_high_i32x4_u", None),
+        I8x16RelaxedSwizzle => ("i8x16.relaxed_swizzle", None),
+        F32x4RelaxedMin => ("f32x4.relaxed_min", None),
+        F32x4RelaxedMax => ("f32x4.relaxed_max", None),
+        F64x2RelaxedMin => ("f64x2.relaxed_min", None),
+        F64x2RelaxedMax => ("f64x2.relaxed_max", None),
+        I16x8RelaxedQ15mulrS => ("i16x8.relaxed_q15mulr_s", None),
+        I16x8RelaxedDotI8x16I7x16S => ("i16x8.relaxed_dot_i8x16_i7x16_s", None),
+    }
+}
+
+fn unop_name(op: &UnaryOp) -> (&'static str, Option<u8>) {
+    use UnaryOp::*;
+    match *op {
+        I32Eqz => ("i32.eqz", None),
+        I32Clz => ("i32.clz", None),
+        I32Ctz => ("i32.ctz", None),
+        I32Popcnt => ("i32.popcnt", None),
+        I64Eqz => ("i64.eqz", None),
+        I64Clz => ("i64.clz", None),
+        I64Ctz => ("i64.ctz", None),
+        I64Popcnt => ("i64.popcnt", None),
+        F32Abs => ("f32.abs", None),
+        F32Neg => ("f32.neg", None),
+        F32Ceil => ("f32.ceil", None),
+        F32Floor => ("f32.floor", None),
+        F32Trunc => ("f32.trunc", None),
+        F32Nearest => ("f32.nearest", None),
+        F32Sqrt => ("f32.sqrt", None),
+        F64Abs => ("f64.abs", None),
+        F64Neg => ("f64.neg", None),
+        F64Ceil => ("f64.ceil", None),
+        F64Floor => ("f64.floor", None),
+        F64Trunc => ("f64.trunc", None),
+        F64Nearest => ("f64.nearest", None),
+        F64Sqrt => ("f64.sqrt", None),
+        I32WrapI64 => ("i32.wrap_i64", None),
+        I32TruncSF32 => ("i32.trunc_f32_s", None),
+        I32TruncUF32 => ("i32.trunc_f32_u", None),
+        I32TruncSF64 => ("i32.trunc_f64_s", None),
+        I32TruncUF64 => ("i32.trunc_f64_u", None),
+        I64ExtendSI32 => ("i64.extend_i32_s", None),
+        I64ExtendUI32 => ("i64.extend_i32_u", None),
+        I64TruncSF32 => ("i64.trunc_f32_s", None),
+        I64TruncUF32 => ("i64.trunc_f32_u", None),
+        I64TruncSF64 => ("i64.trunc_f64_s", None),
+        I64TruncUF64 => ("i64.trunc_f64_u", None),
+        F32ConvertSI32 => ("f32.convert_i32_s", None),
+        F32ConvertUI32 => ("f32.convert_i32_u", None),
+        F32ConvertSI64 => ("f32.convert_i64_s", None),
+        F32ConvertUI64 => ("f32.convert_i64_u", None),
+        F32DemoteF64 => ("f32.demote_f64", None),
+        F64ConvertSI32 => ("f64.convert_i32_s", None),
+        F64ConvertUI32 => ("f64.convert_i32_u", None),
+        F64ConvertSI64 => ("f64.convert_i64_s", None),
+        F64ConvertUI64 => ("f64.convert_i64_u", None),
+        F64PromoteF32 => ("f64.promote_f32", None),
+        I32ReinterpretF32 => ("i32.reinterpret_f32", None),
+        I64ReinterpretF64 => ("i64.reinterpret_f64", None),
+        F32ReinterpretI32 => ("f32.reinterpret_i32", None),
+        F64ReinterpretI64 => ("f64.reinterpret_i64", None),
+        I32Extend8S => ("i32.extend8_s", None),
+        I32Extend16S => ("i32.extend16_s", None),
+        I64Extend8S => ("i64.extend8_s", None),
+        I64Extend16S => ("i64.extend16_s", None),
+        I64Extend32S => ("i64.extend32_s", None),
+        I8x16Splat => ("i8x16.splat", None),
+        I16x8Splat => ("i16x8.splat", None),
+        I32x4Splat => ("i32x4.splat", None),
+        I64x2Splat => ("i64x2.splat", None),
+        F32x4Splat => ("f32x4.splat", None),
+        F64x2Splat => ("f64x2.splat", None),
+        I8x16ExtractLaneS { idx } => ("i8x16.extract_lane_s", Some(idx)),
+        I8x16ExtractLaneU { idx } => ("i8x16.extract_lane_u", Some(idx)),
+        I16x8ExtractLaneS { idx } => ("i16x8.extract_lane_s", Some(idx)),
+        I16x8ExtractLaneU { idx } => ("i16x8.extract_lane_u", Some(idx)),
+        I32x4ExtractLane { idx } => ("i32x4.extract_lane", Some(idx)),
+        I64x2ExtractLane { idx } => ("i64x2.extract_lane", Some(idx)),
+        F32x4ExtractLane { idx } => ("f32x4.extract_lane", Some(idx)),
+        F64x2ExtractLane { idx } => ("f64x2.extract_lane", Some(idx)),
+        V128Not => ("v128.not", None),
+        V128AnyTrue => ("v128.any_true", None),
+        I8x16Abs => ("i8x16.abs", None),
+        I8x16Popcnt => ("i8x16.popcnt", None),
+        I8x16Neg => ("i8x16.neg", None),
+        I8x16AllTrue => ("i8x16.all_true", None),
+        I8x16Bitmask => ("i8x16.bitmask", None),
+        I16x8Abs => ("i16x8.abs", None),
+        I16x8Neg => ("i16x8.neg", None),
+        I16x8AllTrue => ("i16x8.all_true", None),
+        I16x8Bitmask => ("i16x8.bitmask", None),
+        I16x8WidenLowI8x16S => ("i16x8.extend_low_i8x16_s", None),
+        I16x8WidenHighI8x16S => ("i16x8.extend_high_i8x16_s", None),
+        I16x8WidenLowI8x16U => ("i16x8.extend_low_i8x16_u", None),
+        I16x8WidenHighI8x16U => ("i16x8.extend_high_i8x16_u", None),
+        I32x4Abs => ("i32x4.abs", None),
+        I32x4Neg => ("i32x4.neg", None),
+        I32x4AllTrue => ("i32x4.all_true", None),
+        I32x4Bitmask => ("i32x4.bitmask", None),
+        I32x4WidenLowI16x8S => ("i32x4.extend_low_i16x8_s", None),
+        I32x4WidenHighI16x8S => ("i32x4.extend_high_i16x8_s", None),
+        I32x4WidenLowI16x8U => ("i32x4.extend_low_i16x8_u", None),
+        I32x4WidenHighI16x8U => ("i32x4.extend_high_i16x8_u", None),
+        I64x2Abs => ("i64x2.abs", None),
+        I64x2Neg => ("i64x2.neg", None),
+        I64x2AllTrue => ("i64x2.all_true", None),
+        I64x2Bitmask => ("i64x2.bitmask", None),
+        F32x4Abs => ("f32x4.abs", None),
+        F32x4Neg => ("f32x4.neg", None),
+        F32x4Sqrt => ("f32x4.sqrt", None),
+        F32x4Ceil => ("f32x4.ceil", None),
+        F32x4Floor => ("f32x4.floor", None),
+        F32x4Trunc => ("f32x4.trunc", None),
+        F32x4Nearest => ("f32x4.nearest", None),
+        F64x2Abs => ("f64x2.abs", None),
+        F64x2Neg => ("f64x2.neg", None),
+        F64x2Sqrt => ("f64x2.sqrt", None),
+        F64x2Ceil => ("f64x2.ceil", None),
+        F64x2Floor => ("f64x2.floor", None),
+        F64x2Trunc => ("f64x2.trunc", None),
+        F64x2Nearest => ("f64x2.nearest", None),
+        I32x4TruncSatF32x4S => ("i32x4.trunc_sat_f32x4_s", None),
+        I32x4TruncSatF32x4U => ("i32x4.trunc_sat_f32x4_u", None),
+        F32x4ConvertI32x4S => ("f32x4.convert_i32x4_s", None),
+        F32x4ConvertI32x4U => ("f32x4.convert_i32x4_u", None),
+        I32TruncSSatF32 => ("i32.trunc_sat_f32_s", None),
+        I32TruncUSatF32 => ("i32.trunc_sat_f32_u", None),
+        I32TruncSSatF64 => ("i32.trunc_sat_f64_s", None),
+        I32TruncUSatF64 => ("i32.trunc_sat_f64_u", None),
+        I64TruncSSatF32 => ("i64.trunc_sat_f32_s", None),
+        I64TruncUSatF32 => ("i64.trunc_sat_f32_u", None),
+        I64TruncSSatF64 => ("i64.trunc_sat_f64_s", None),
+        I64TruncUSatF64 => ("i64.trunc_sat_f64_u", None),
+        I16x8ExtAddPairwiseI8x16S => ("i16x8.extadd_pairwise_i8x16_s", None),
+        I16x8ExtAddPairwiseI8x16U => ("i16x8.extadd_pairwise_i8x16_u", None),
+        I32x4ExtAddPairwiseI16x8S => ("i32x4.extadd_pairwise_i16x8_s", None),
+        I32x4ExtAddPairwiseI16x8U => ("i32x4.extadd_pairwise_i16x8_u", None),
+        I64x2ExtendLowI32x4S => ("i64x2.extend_low_i32x4_s", None),
+        I64x2ExtendHighI32x4S => ("i64x2.extend_high_i32x4_s", None),
+        I64x2ExtendLowI32x4U => ("i64x2.extend_low_i32x4_u", None),
+        I64x2ExtendHighI32x4U => ("i64x2.extend_high_i32x4_u", None),
+        I32x4TruncSatF64x2SZero => ("i32x4.trunc_sat_f64x2_s_zero", None),
+        I32x4TruncSatF64x2UZero => ("i32x4.trunc_sat_f64x2_u_zero", None),
+        F64x2ConvertLowI32x4S => ("f64x2.convert_low_i32x4_s", None),
+        F64x2ConvertLowI32x4U => ("f64x2.convert_low_i32x4_u", None),
+        F32x4DemoteF64x2Zero => ("f32x4.demote_f64x2_zero", None),
+        F64x2PromoteLowF32x4 => ("f64x2.promote_low_f32x4", None),
+        I32x4RelaxedTruncF32x4S => ("i32x4.relaxed_trunc_f32x4_s", None),
+        I32x4RelaxedTruncF32x4U => ("i32x4.relaxed_trunc_f32x4_u", None),
+        I32x4RelaxedTruncF64x2SZero => ("i32x4.relaxed_trunc_f64x2_s_zero", None),
+        I32x4RelaxedTruncF64x2UZero => ("i32x4.relaxed_trunc_f64x2_u_zero", None),
+    }
+}
@@ -1,6 +1,8 @@
 //! WebAssembly function and value types.
 
-use crate::error::Result;
+use crate::emit::IdsToIndices;
+use crate::error::{Proposal, Result, Unlocated};
+use crate::parse::IndicesToIds;
 use crate::tombstone_arena::Tombstone;
 use anyhow::bail;
 use id_arena::Id;
@@ -12,12 +14,80 @@ use std::hash;
 /// An identifier for types.
 pub type TypeId = Id<Type>;
 
-/// A function type.
+/// An identifier for recursive type groups.
+pub type RecGroupId = Id<RecGroup>;
+
+/// A recursive type group, ie a `(rec ...)` in the text format.
+///
+/// The types in a group may refer to each other, and are only equivalent to
+/// the types of another group if the whole groups are. Types that are not in
+/// an explicit group form an implicit group of their own, which is only
+/// recorded as a `RecGroup` when the type refers to itself.
+#[derive(Debug, Clone)]
+pub struct RecGroup {
+    id: RecGroupId,
+    pub(crate) types: Vec<TypeId>,
+    pub(crate) explicit: bool,
+}
+
+impl RecGroup {
+    pub(crate) fn new(id: RecGroupId) -> RecGroup {
+        RecGroup {
+            id,
+            types: Vec::new(),
+            explicit: true,
+        }
+    }
+
+    /// Get the id of this recursive type group.
+    #[inline]
+    pub fn id(&self) -> RecGroupId {
+        self.id
+    }
+
+    /// Get the types in this group, in order.
+    #[inline]
+    pub fn types(&self) -> &[TypeId] {
+        &self.types
+    }
+
+    /// Was this group written as a `(rec ...)`?
+    ///
+    /// This is `false` for the implicit group of a self-referential type that
+    /// was parsed outside of an explicit group, which is emitted without the
+    /// `rec` again.
+    #[inline]
+    pub fn is_explicit(&self) -> bool {
+        self.explicit
+    }
+}
+
+/// The definition of a type along with its declared subtyping information.
+///
+/// This is used to create new types with `ModuleTypes::add_sub_type` and
+/// `ModuleTypes::add_rec_group`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SubType {
+    /// The composite type being defined.
+    pub kind: TypeKind,
+    /// The declared supertype, if any.
+    pub supertype: Option<TypeId>,
+    /// Whether this type may be used as a supertype of other types.
+    pub is_final: bool,
+}
+
+/// A type defined in the type section: a function, struct, array or
+/// continuation type.
 #[derive(Debug, Clone)]
 pub struct Type {
     id: TypeId,
-    params: Box<[ValType]>,
-    results: Box<[ValType]>,
+    kind: TypeKind,
+    supertype: Option<TypeId>,
+    is_final: bool,
+
+    // The recursion group this type belongs to, along with its position
+    // within that group.
+    rec_group: Option<(RecGroupId, usize)>,
 
     // Whether or not this type is for a multi-value function entry block, and
     // therefore is for internal use only and shouldn't be emitted when we
@@ -26,17 +96,173 @@ pub struct Type {
 
     /// An optional name for debugging.
     ///
-    /// This is not really used by anything currently, but a theoretical WAT to
-    /// walrus parser could keep track of the original name in the WAT.
+    /// This comes from the `name` section, or from the type's identifier when
+    /// the module is parsed from the text format with `ModuleConfig::parse_wat`.
     pub name: Option<String>,
 }
 
+/// The different kinds of composite types that a `Type` can define.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TypeKind {
+    /// A function type.
+    Function(FunctionType),
+    /// A struct type from the GC proposal.
+    Struct(StructType),
+    /// An array type from the GC proposal.
+    Array(ArrayType),
+    /// A continuation type from the stack switching proposal.
+    Cont(ContType),
+}
+
+/// The signature of a function: its parameters and results.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FunctionType {
+    params: Box<[ValType]>,
+    results: Box<[ValType]>,
+}
+
+impl FunctionType {
+    /// Construct a new function type with the given parameters and results.
+    pub fn new(params: Box<[ValType]>, results: Box<[ValType]>) -> FunctionType {
+        FunctionType { params, results }
+    }
+
+    /// Get the parameters of this function type.
+    #[inline]
+    pub fn params(&self) -> &[ValType] {
+        &self.params
+    }
+
+    /// Get the results of this function type.
+    #[inline]
+    pub fn results(&self) -> &[ValType] {
+        &self.results
+    }
+}
+
+/// A struct type: a fixed sequence of fields.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StructType {
+    /// The fields of this struct, in declaration order.
+    pub fields: Box<[FieldType]>,
+}
+
+/// An array type: a dynamically sized sequence of a single field type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ArrayType {
+    /// The type of each element of this array.
+    pub field: FieldType,
+}
+
+/// A continuation type: a suspended computation, which takes the parameters
+/// of a function type when it is resumed, and returns its results when it
+/// finishes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ContType {
+    /// The function type of this continuation.
+    pub func_type: TypeId,
+}
+
+/// The type of a struct field or array element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FieldType {
+    /// What is stored in this field.
+    pub element_type: StorageType,
+    /// Whether this field can be written to after allocation.
+    pub mutable: bool,
+}
+
+/// The storage type of a struct field or array element.
+///
+/// Fields may use the packed `i8` and `i16` types in addition to the regular
+/// value types; packed values are extended to `i32` when read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum StorageType {
+    /// A packed 8-bit integer.
+    I8,
+    /// A packed 16-bit integer.
+    I16,
+    /// An unpacked value type.
+    Val(ValType),
+}
+
+impl FieldType {
+    pub(crate) fn parse(input: &wasmparser::FieldType, ids: &IndicesToIds) -> Result<FieldType> {
+        Ok(FieldType {
+            element_type: match input.element_type {
+                wasmparser::StorageType::I8 => StorageType::I8,
+                wasmparser::StorageType::I16 => StorageType::I16,
+                wasmparser::StorageType::Val(ty) => StorageType::Val(ValType::parse(&ty, ids)?),
+            },
+            mutable: input.mutable,
+        })
+    }
+
+    pub(crate) fn to_wasmencoder_field_type(
+        self,
+        indices: &IdsToIndices,
+    ) -> wasm_encoder::FieldType {
+        wasm_encoder::FieldType {
+            element_type: self.element_type.to_wasmencoder_storage_type(indices),
+            mutable: self.mutable,
+        }
+    }
+}
+
+impl StorageType {
+    /// Returns the type of values produced when reading this storage type,
+    /// which is `i32` for the packed types.
+    pub fn unpacked(self) -> ValType {
+        match self {
+            StorageType::I8 | StorageType::I16 => ValType::I32,
+            StorageType::Val(ty) => ty,
+        }
+    }
+
+    pub(crate) fn to_wasmencoder_storage_type(
+        self,
+        indices: &IdsToIndices,
+    ) -> wasm_encoder::StorageType {
+        match self {
+            StorageType::I8 => wasm_encoder::StorageType::I8,
+            StorageType::I16 => wasm_encoder::StorageType::I16,
+            StorageType::Val(ty) => wasm_encoder::StorageType::Val(ty.to_wasmencoder_type(indices)),
+        }
+    }
+}
+
+impl fmt::Display for StorageType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorageType::I8 => write!(f, "i8"),
+            StorageType::I16 => write!(f, "i16"),
+            StorageType::Val(ty) => write!(f, "{}", ty),
+        }
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.mutable {
+            write!(f, "(mut {})", self.element_type)
+        } else {
+            write!(f, "{}", self.element_type)
+        }
+    }
+}
+
 impl PartialEq for Type {
     #[inline]
     fn eq(&self, rhs: &Type) -> bool {
-        // NB: do not compare id or name.
-        self.params == rhs.params
-            && self.results == rhs.results
+        // NB: do not compare id or name. Types in recursion groups
+        // are identified by their position in the group alone, so they are
+        // never merged with any other type.
+        if self.rec_group.is_some() || rhs.rec_group.is_some() {
+            return self.rec_group == rhs.rec_group;
+        }
+        self.kind == rhs.kind
+            && self.supertype == rhs.supertype
+            && self.is_final == rhs.is_final
             && self.is_for_function_entry == rhs.is_for_function_entry
     }
 }
@@ -51,9 +277,16 @@ impl PartialOrd for Type {
 
 impl Ord for Type {
     fn cmp(&self, rhs: &Type) -> Ordering {
-        self.params()
-            .cmp(rhs.params())
-            .then_with(|| self.results().cmp(rhs.results()))
+        // Keep this consistent with `PartialEq`.
+        match (self.rec_group, rhs.rec_group) {
+            (None, None) => self
+                .kind
+                .cmp(&rhs.kind)
+                .then_with(|| self.supertype.cmp(&rhs.supertype))
+                .then_with(|| self.is_final.cmp(&rhs.is_final))
+                .then_with(|| self.is_for_function_entry.cmp(&rhs.is_for_function_entry)),
+            (a, b) => a.cmp(&b),
+        }
     }
 }
 
@@ -61,16 +294,23 @@ impl hash::Hash for Type {
     #[inline]
     fn hash<H: hash::Hasher>(&self, h: &mut H) {
         // Do not hash id or name.
-        self.params.hash(h);
-        self.results.hash(h);
+        if let Some(group) = self.rec_group {
+            group.hash(h);
+            return;
+        }
+        self.kind.hash(h);
+        self.supertype.hash(h);
+        self.is_final.hash(h);
         self.is_for_function_entry.hash(h);
     }
 }
 
 impl Tombstone for Type {
     fn on_delete(&mut self) {
-        self.params = Box::new([]);
-        self.results = Box::new([]);
+        self.kind = TypeKind::Function(FunctionType {
+            params: Box::new([]),
+            results: Box::new([]),
+        });
     }
 }
 
@@ -78,25 +318,56 @@ impl Type {
     /// Construct a new function type.
     #[inline]
     pub(crate) fn new(id: TypeId, params: Box<[ValType]>, results: Box<[ValType]>) -> Type {
+        Type::with_kind(id, TypeKind::Function(FunctionType { params, results }))
+    }
+
+    /// Construct a new type of the given kind.
+    #[inline]
+    pub(crate) fn with_kind(id: TypeId, kind: TypeKind) -> Type {
         Type {
             id,
-            params,
-            results,
+            kind,
+            supertype: None,
+            is_final: true,
+            rec_group: None,
             is_for_function_entry: false,
             name: None,
         }
     }
 
+    /// Construct a new type from a sub type definition.
+    #[inline]
+    pub(crate) fn from_sub_type(
+        id: TypeId,
+        sub: SubType,
+        rec_group: Option<(RecGroupId, usize)>,
+    ) -> Type {
+        Type {
+            supertype: sub.supertype,
+            is_final: sub.is_final,
+            rec_group,
+            ..Type::with_kind(id, sub.kind)
+        }
+    }
+
+    /// Replace the definition of a type in a recursion group.
+    ///
+    /// This doesn't affect the type's hash since group members are only
+    /// identified by their position.
+    pub(crate) fn define_in_rec_group(&mut self, sub: SubType) {
+        debug_assert!(self.rec_group.is_some());
+        self.kind = sub.kind;
+        self.supertype = sub.supertype;
+        self.is_final = sub.is_final;
+    }
+
     /// Construct a new type for function entry blocks.
     #[inline]
     pub(crate) fn for_function_entry(id: TypeId, results: Box<[ValType]>) -> Type {
         let params = vec![].into();
         Type {
-            id,
-            params,
-            results,
             is_for_function_entry: true,
-            name: None,
+            ..Type::new(id, params, results)
         }
     }
 
@@ -106,16 +377,101 @@ impl Type {
         self.id
     }
 
+    /// Get the kind of composite type this is.
+    #[inline]
+    pub fn kind(&self) -> &TypeKind {
+        &self.kind
+    }
+
+    /// Get the declared supertype of this type, if any.
+    #[inline]
+    pub fn supertype(&self) -> Option<TypeId> {
+        self.supertype
+    }
+
+    /// Is this type final, ie not allowed to be the supertype of other types?
+    #[inline]
+    pub fn is_final(&self) -> bool {
+        self.is_final
+    }
+
+    /// Get the recursion group this type is defined in, if any.
+    ///
+    /// This is `None` for a type in an implicit group of its own that
+    /// doesn't refer to itself.
+    #[inline]
+    pub fn rec_group(&self) -> Option<RecGroupId> {
+        self.rec_group.map(|(group, _)| group)
+    }
+
+    /// Is this a function type?
+    #[inline]
+    pub fn is_function(&self) -> bool {
+        matches!(self.kind, TypeKind::Function(_))
+    }
+
+    /// Get the function signature of this type, if it is a function type.
+    #[inline]
+    pub fn as_function(&self) -> Option<&FunctionType> {
+        match &self.kind {
+            TypeKind::Function(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    /// Get the struct definition of this type, if it is a struct type.
+    #[inline]
+    pub fn as_struct(&self) -> Option<&StructType> {
+        match &self.kind {
+            TypeKind::Struct(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Get the array definition of this type, if it is an array type.
+    #[inline]
+    pub fn as_array(&self) -> Option<&ArrayType> {
+        match &self.kind {
+            TypeKind::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Get the definition of this type, if it is a continuation type.
+    #[inline]
+    pub fn as_cont(&self) -> Option<&ContType> {
+        match &self.kind {
+            TypeKind::Cont(c) => Some(c),
+            _ => None,
+        }
+    }
+
     /// Get the parameters to this function type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is not a function type. Code that looks at every type
+    /// of a module should use `as_function` instead.
     #[inline]
     pub fn params(&self) -> &[ValType] {
-        &self.params
+        self.unwrap_function().params()
     }
 
     /// Get the results of this function type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is not a function type.
     #[inline]
     pub fn results(&self) -> &[ValType] {
-        &self.results
+        self.unwrap_function().results()
+    }
+
+    fn unwrap_function(&self) -> &FunctionType {
+        match &self.kind {
+            TypeKind::Function(f) => f,
+            _ => panic!("type {:?} is not a function type", self.id),
+        }
     }
 
     pub(crate) fn is_for_function_entry(&self) -> bool {
@@ -148,23 +504,43 @@ pub enum ValType {
 pub enum HeapType {
     /// Abstract heap type (abstract types like func, extern, any, etc.)
     Abstract(AbstractHeapType),
-    /// Concrete (indexed) heap type - currently not supported
-    Concrete(u32),
+    /// Concrete heap type, referring to a type defined in this module.
+    Concrete(TypeId),
 }
 
 impl HeapType {
     /// Convert to wasm_encoder HeapType.
-    pub fn to_wasmencoder_heap_type(self) -> wasm_encoder::HeapType {
+    pub fn to_wasmencoder_heap_type(self, indices: &IdsToIndices) -> wasm_encoder::HeapType {
         match self {
             HeapType::Abstract(ab_heap_type) => wasm_encoder::HeapType::Abstract {
                 shared: false,
                 ty: ab_heap_type.into(),
             },
-            HeapType::Concrete(_) => todo!("concrete heap types not yet supported"),
+            HeapType::Concrete(ty) => wasm_encoder::HeapType::Concrete(indices.get_type_index(ty)),
+        }
+    }
+
+    /// Parse a heap type, resolving a concrete type index with `ids`.
+    ///
+    /// The type section registers the ids of a recursion group's types before
+    /// parsing their definitions, so a type may refer to itself and to the
+    /// other types of its group.
+    pub(crate) fn parse(heap_type: wasmparser::HeapType, ids: &IndicesToIds) -> Result<HeapType> {
+        match heap_type {
+            wasmparser::HeapType::Concrete(idx) => match idx.as_module_index() {
+                Some(idx) => Ok(HeapType::Concrete(ids.get_type(idx)?)),
+                None => bail!("unexpected rec-group-relative type index in heap type"),
+            },
+            wasmparser::HeapType::Abstract { shared: true, .. } => {
+                Err(Unlocated(Proposal::SharedEverythingThreads).into())
+            }
+            other => other.try_into(),
         }
     }
 }
 
+/// Converts abstract heap types only; concrete heap types refer to the type
+/// section and have to be resolved while parsing a module.
 impl TryFrom<wasmparser::HeapType> for HeapType {
     type Error = anyhow::Error;
 
@@ -173,9 +549,10 @@ impl TryFrom<wasmparser::HeapType> for HeapType {
             wasmparser::HeapType::Abstract { shared: _, ty } => {
                 Ok(HeapType::Abstract(ty.try_into()?))
             }
-            wasmparser::HeapType::Concrete(_) | wasmparser::HeapType::Exact(_) => {
-                bail!("concrete (indexed) heap types are not yet supported")
+            wasmparser::HeapType::Concrete(_) => {
+                bail!("concrete heap types can only be converted while parsing a module")
             }
+            wasmparser::HeapType::Exact(_) => Err(Unlocated(Proposal::CustomDescriptors).into()),
         }
     }
 }
@@ -199,9 +576,11 @@ impl fmt::Display for HeapType {
                     AbstractHeapType::I31 => "i31",
                     AbstractHeapType::Exn => "exn",
                     AbstractHeapType::NoExn => "noexn",
+                    AbstractHeapType::Cont => "cont",
+                    AbstractHeapType::NoCont => "nocont",
                 }
             ),
-            HeapType::Concrete(id) => write!(f, "{id}"),
+            HeapType::Concrete(id) => write!(f, "{}", id.index()),
         }
     }
 }
@@ -234,6 +613,10 @@ pub enum AbstractHeapType {
     Exn,
     /// The abstract `noexn` heap type (bottom type for exception refs).
     NoExn,
+    /// The abstract `cont` heap type (any continuation).
+    Cont,
+    /// The abstract `nocont` heap type (bottom type for continuation refs).
+    NoCont,
 }
 
 #[allow(clippy::from_over_into)]
@@ -252,6 +635,8 @@ impl Into<wasm_encoder::AbstractHeapType> for AbstractHeapType {
             AbstractHeapType::I31 => wasm_encoder::AbstractHeapType::I31,
             AbstractHeapType::Exn => wasm_encoder::AbstractHeapType::Exn,
             AbstractHeapType::NoExn => wasm_encoder::AbstractHeapType::NoExn,
+            AbstractHeapType::Cont => wasm_encoder::AbstractHeapType::Cont,
+            AbstractHeapType::NoCont => wasm_encoder::AbstractHeapType::NoCont,
         }
     }
 }
@@ -275,9 +660,8 @@ impl TryFrom<wasmparser::AbstractHeapType> for AbstractHeapType {
             wasmparser::AbstractHeapType::I31 => AbstractHeapType::I31,
             wasmparser::AbstractHeapType::Exn => AbstractHeapType::Exn,
             wasmparser::AbstractHeapType::NoExn => AbstractHeapType::NoExn,
-            wasmparser::AbstractHeapType::Cont | wasmparser::AbstractHeapType::NoCont => {
-                bail!("Stack switching proposal is not supported")
-            }
+            wasmparser::AbstractHeapType::Cont => AbstractHeapType::Cont,
+            wasmparser::AbstractHeapType::NoCont => AbstractHeapType::NoCont,
         })
     }
 }
@@ -343,6 +727,12 @@ impl RefType {
         heap_type: HeapType::Abstract(AbstractHeapType::Exn),
     };
 
+    /// Alias for the `contref` type in WebAssembly.
+    pub const CONTREF: RefType = RefType {
+        nullable: true,
+        heap_type: HeapType::Abstract(AbstractHeapType::Cont),
+    };
+
     /// Alias for the `nullref` type in WebAssembly.
     pub const NULLREF: RefType = RefType {
         nullable: true,
@@ -361,27 +751,35 @@ impl RefType {
         heap_type: HeapType::Abstract(AbstractHeapType::NoFunc),
     };
 
+    /// Alias for the `nullcontref` type in WebAssembly.
+    pub const NULLCONTREF: RefType = RefType {
+        nullable: true,
+        heap_type: HeapType::Abstract(AbstractHeapType::NoCont),
+    };
+
     /// Returns whether this reference type is nullable.
     pub fn is_nullable(&self) -> bool {
         self.nullable
     }
 
     /// Convert to wasm_encoder RefType.
-    pub fn to_wasmencoder_ref_type(self) -> wasm_encoder::RefType {
+    pub fn to_wasmencoder_ref_type(self, indices: &IdsToIndices) -> wasm_encoder::RefType {
         wasm_encoder::RefType {
             nullable: self.nullable,
-            heap_type: self.heap_type.to_wasmencoder_heap_type(),
+            heap_type: self.heap_type.to_wasmencoder_heap_type(indices),
         }
     }
-}
 
-#[allow(clippy::from_over_into)]
-impl Into<wasm_encoder::RefType> for RefType {
-    fn into(self) -> wasm_encoder::RefType {
-        self.to_wasmencoder_ref_type()
+    pub(crate) fn parse(ref_type: wasmparser::RefType, ids: &IndicesToIds) -> Result<RefType> {
+        Ok(RefType {
+            nullable: ref_type.is_nullable(),
+            heap_type: HeapType::parse(ref_type.heap_type(), ids)?,
+        })
     }
 }
 
+/// Converts reference types to abstract heap types only, see the conversion
+/// for `HeapType`.
 impl TryFrom<wasmparser::RefType> for RefType {
     type Error = anyhow::Error;
 
@@ -410,7 +808,9 @@ impl fmt::Display for RefType {
                 HeapType::Abstract(AbstractHeapType::NoExtern) => write!(f, "nullexternref"),
                 HeapType::Abstract(AbstractHeapType::NoFunc) => write!(f, "nullfuncref"),
                 HeapType::Abstract(AbstractHeapType::NoExn) => write!(f, "nullexnref"),
-                HeapType::Concrete(idx) => write!(f, "(ref null {idx})"),
+                HeapType::Abstract(AbstractHeapType::Cont) => write!(f, "contref"),
+                HeapType::Abstract(AbstractHeapType::NoCont) => write!(f, "nullcontref"),
+                HeapType::Concrete(id) => write!(f, "(ref null {})", id.index()),
             }
         } else {
             write!(f, "(ref {})", self.heap_type)
@@ -419,13 +819,25 @@ impl fmt::Display for RefType {
 }
 
 impl ValType {
-    pub(crate) fn from_wasmparser_type(ty: wasmparser::ValType) -> Result<Box<[ValType]>> {
-        let v = vec![ValType::parse(&ty)?];
+    /// Does this type have a default value, which locals of this type start
+    /// out with?
+    pub(crate) fn is_defaultable(&self) -> bool {
+        match self {
+            ValType::Ref(r) => r.nullable,
+            _ => true,
+        }
+    }
+
+    pub(crate) fn from_wasmparser_type(
+        ty: wasmparser::ValType,
+        ids: &IndicesToIds,
+    ) -> Result<Box<[ValType]>> {
+        let v = vec![ValType::parse(&ty, ids)?];
         Ok(v.into_boxed_slice())
     }
 
     #[allow(clippy::wrong_self_convention)]
-    pub(crate) fn to_wasmencoder_type(&self) -> wasm_encoder::ValType {
+    pub(crate) fn to_wasmencoder_type(&self, indices: &IdsToIndices) -> wasm_encoder::ValType {
         match self {
             ValType::I32 => wasm_encoder::ValType::I32,
             ValType::I64 => wasm_encoder::ValType::I64,
@@ -433,25 +845,19 @@ impl ValType {
             ValType::F64 => wasm_encoder::ValType::F64,
             ValType::V128 => wasm_encoder::ValType::V128,
             ValType::Ref(ref_type) => {
-                wasm_encoder::ValType::Ref(ref_type.to_wasmencoder_ref_type())
+                wasm_encoder::ValType::Ref(ref_type.to_wasmencoder_ref_type(indices))
             }
         }
     }
 
-    pub(crate) fn parse(input: &wasmparser::ValType) -> Result<ValType> {
+    pub(crate) fn parse(input: &wasmparser::ValType, ids: &IndicesToIds) -> Result<ValType> {
         match input {
             wasmparser::ValType::I32 => Ok(ValType::I32),
             wasmparser::ValType::I64 => Ok(ValType::I64),
             wasmparser::ValType::F32 => Ok(ValType::F32),
             wasmparser::ValType::F64 => Ok(ValType::F64),
             wasmparser::ValType::V128 => Ok(ValType::V128),
-            wasmparser::ValType::Ref(wasmparser::RefType::CONT)
-            | wasmparser::ValType::Ref(wasmparser::RefType::CONTREF)
-            | wasmparser::ValType::Ref(wasmparser::RefType::NULLCONTREF)
-            | wasmparser::ValType::Ref(wasmparser::RefType::NOCONT) => {
-                bail!("The stack switching proposal is not supported")
-            }
-            wasmparser::ValType::Ref(ref_type) => Ok(ValType::Ref((*ref_type).try_into()?)),
+            wasmparser::ValType::Ref(ref_type) => Ok(ValType::Ref(RefType::parse(*ref_type, ids)?)),
         }
     }
 }
@@ -0,0 +1,2276 @@
+//! Checking that the IR of a module is well-formed and well-typed.
+//!
+//! This works on the `Instr` tree directly rather than on emitted bytes, so
+//! that errors point at the `FunctionId`, `InstrSeqId` and index of the
+//! offending instruction, which is where a transformation would go wrong.
+
+use crate::analysis::OperandStack;
+use crate::error::{InstrPosition, Result};
+use crate::ir::*;
+use crate::map::{IdHashMap, IdHashSet};
+use crate::{
+    AbstractHeapType, ArrayType, ConstExpr, ConstOp, DataId, DataKind, ElementId, ElementItems,
+    ElementKind, ExportItem, FieldType, FunctionId, FunctionType, GlobalId, GlobalKind, HeapType,
+    LocalFunction, MemoryId, Module, RefType, StorageType, StructType, TableId, TagId, TypeId,
+    TypeKind, ValType,
+};
+use anyhow::{anyhow, bail};
+
+impl Module {
+    /// Check that the IR of this module is well-formed and well-typed.
+    ///
+    /// This checks, among other things, that
+    ///
+    /// * every instruction sequence leaves the types it declares on the
+    ///   operand stack, and that every instruction finds the types it expects
+    ///   there,
+    /// * branches only target instruction sequences that enclose them,
+    /// * locals used by a function aren't the parameters of another function,
+    /// * none of the functions, tables, memories, globals, etc... that are
+    ///   referenced have been deleted, and
+    /// * tables, memories and globals are used according to their types, for
+    ///   example that `global.set` only writes to mutable globals.
+    ///
+    /// This is useful to find the mistakes of a transformation where they
+    /// are made, rather than when the emitted wasm is eventually validated.
+    /// When an instruction is at fault, the returned error has an
+    /// `InstrPosition` context pointing at it.
+    pub fn validate(&self) -> Result<()> {
+        let mut params = IdHashMap::default();
+        for (id, func) in self.funcs.iter_local() {
+            for arg in func.args.iter() {
+                if let Some(other) = params.insert(*arg, id) {
+                    bail!(
+                        "local {} is a parameter of both function {} and function {}",
+                        arg.index(),
+                        other.index(),
+                        id.index()
+                    );
+                }
+            }
+        }
+
+        self.validate_items()?;
+        for (id, func) in self.funcs.iter_local() {
+            FunctionChecker::new(self, id, func, &params).run()?;
+        }
+        Ok(())
+    }
+
+    /// Check only that none of the items this module refers to have been
+    /// deleted, and that branches target instruction sequences that enclose
+    /// them, without checking types.
+    ///
+    /// This is what emitting the module relies on, and is much cheaper than
+    /// `validate`.
+    pub(crate) fn validate_ids(&self) -> Result<()> {
+        self.validate_items()?;
+        for (id, func) in self.funcs.iter_local() {
+            IdChecker::new(self, id, func).run()?;
+        }
+        Ok(())
+    }
+
+    /// Check the module's items outside of function bodies.
+    fn validate_items(&self) -> Result<()> {
+        for func in self.funcs.iter() {
+            let ty = func.ty();
+            if !self.types.contains(ty) || !self.types.get(ty).is_function() {
+                bail!(
+                    "function {} has type {}, which is not a function type",
+                    func.id().index(),
+                    ty.index()
+                );
+            }
+        }
+
+        for ty in self.types.iter() {
+            if let Some(cont) = ty.as_cont() {
+                let func_type = cont.func_type;
+                if !self.types.contains(func_type) || !self.types.get(func_type).is_function() {
+                    bail!(
+                        "continuation type {} is of type {}, which is not a function type",
+                        ty.id().index(),
+                        func_type.index()
+                    );
+                }
+            }
+        }
+
+        for global in self.globals.iter() {
+            if let GlobalKind::Local(init) = &global.kind {
+                self.validate_const_expr(init)?;
+            }
+        }
+
+        for table in self.tables.iter() {
+            if let Some(init) = &table.init {
+                self.validate_const_expr(init)?;
+            }
+        }
+
+        for tag in self.tags.iter() {
+            let ty = tag.ty();
+            if !self.types.contains(ty) || !self.types.get(ty).is_function() {
+                bail!(
+                    "tag {} has type {}, which is not a function type",
+                    tag.id().index(),
+                    ty.index()
+                );
+            }
+        }
+
+        for export in self.exports.iter() {
+            let exists = match export.item {
+                ExportItem::Function(id) => self.funcs.contains(id),
+                ExportItem::Table(id) => self.tables.contains(id),
+                ExportItem::Memory(id) => self.memories.contains(id),
+                ExportItem::Global(id) => self.globals.contains(id),
+                ExportItem::Tag(id) => self.tags.contains(id),
+            };
+            if !exists {
+                bail!(
+                    "export `{}` refers to an item that was deleted",
+                    export.name
+                );
+            }
+        }
+
+        if let Some(start) = self.start {
+            if !self.funcs.contains(start) {
+                bail!("the start function {} was deleted", start.index());
+            }
+            let ty = self.types.get(self.funcs.get(start).ty());
+            if !ty.params().is_empty() || !ty.results().is_empty() {
+                bail!(
+                    "the start function {} must not take parameters or return results",
+                    start.index()
+                );
+            }
+        }
+
+        for elem in self.elements.iter() {
+            if let ElementKind::Active { table, offset } = &elem.kind {
+                if !self.tables.contains(*table) {
+                    bail!(
+                        "element segment {} initializes table {}, which was deleted",
+                        elem.id().index(),
+                        table.index()
+                    );
+                }
+                self.validate_const_expr(offset)?;
+            }
+            match &elem.items {
+                ElementItems::Functions(funcs) => {
+                    if let Some(func) = funcs.iter().find(|f| !self.funcs.contains(**f)) {
+                        bail!(
+                            "element segment {} refers to function {}, which was deleted",
+                            elem.id().index(),
+                            func.index()
+                        );
+                    }
+                }
+                ElementItems::Expressions(_, exprs) => {
+                    for expr in exprs {
+                        self.validate_const_expr(expr)?;
+                    }
+                }
+            }
+        }
+
+        for data in self.data.iter() {
+            if let DataKind::Active { memory, offset } = &data.kind {
+                if !self.memories.contains(*memory) {
+                    bail!(
+                        "data segment {} initializes memory {}, which was deleted",
+                        data.id().index(),
+                        memory.index()
+                    );
+                }
+                self.validate_const_expr(offset)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that the ids a constant expression refers to haven't been
+    /// deleted.
+    fn validate_const_expr(&self, expr: &ConstExpr) -> Result<()> {
+        let check_global = |global: GlobalId| {
+            if self.globals.contains(global) {
+                Ok(())
+            } else {
+                Err(anyhow!(
+                    "constant expression refers to global {}, which was deleted",
+                    global.index()
+                ))
+            }
+        };
+        let check_func = |func: FunctionId| {
+            if self.funcs.contains(func) {
+                Ok(())
+            } else {
+                Err(anyhow!(
+                    "constant expression refers to function {}, which was deleted",
+                    func.index()
+                ))
+            }
+        };
+        let check_type = |ty: TypeId| {
+            if self.types.contains(ty) {
+                Ok(())
+            } else {
+                Err(anyhow!(
+                    "constant expression refers to type {}, which was deleted",
+                    ty.index()
+                ))
+            }
+        };
+        match expr {
+            ConstExpr::Value(_) | ConstExpr::RefNull(_) => Ok(()),
+            ConstExpr::Global(global) => check_global(*global),
+            ConstExpr::RefFunc(func) => check_func(*func),
+            ConstExpr::Extended(ops) => {
+                for op in ops {
+                    match op {
+                        ConstOp::GlobalGet(global) => check_global(*global)?,
+                        ConstOp::RefFunc(func) => check_func(*func)?,
+                        ConstOp::StructNew(ty)
+                        | ConstOp::StructNewDefault(ty)
+                        | ConstOp::ArrayNew(ty)
+                        | ConstOp::ArrayNewDefault(ty)
+                        | ConstOp::ArrayNewFixed { ty, .. } => check_type(*ty)?,
+                        _ => {}
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A control frame: an instruction sequence that is being checked, along with
+/// the sequences that come after it in the same instruction, such as the
+/// alternative of an `if`.
+struct Frame<'a> {
+    seq: &'a InstrSeq,
+    kind: BlockKind,
+    params: Vec<ValType>,
+    results: Vec<ValType>,
+    /// The height of the operand stack when this frame was entered.
+    height: usize,
+    /// The number of initialized locals when this frame was entered.
+    inits: usize,
+    /// Whether the rest of this frame is unreachable, in which case the
+    /// operand stack is polymorphic.
+    unreachable: bool,
+    /// The index of the next instruction to check.
+    next: usize,
+    /// The instruction sequences to check after this one, in reverse order.
+    rest: Vec<Arm<'a>>,
+}
+
+/// An instruction sequence that an instruction runs, along with the kind of
+/// block it is and the values it starts with on the operand stack.
+struct Arm<'a> {
+    seq: &'a InstrSeq,
+    kind: BlockKind,
+    params: Vec<ValType>,
+}
+
+struct FunctionChecker<'a> {
+    module: &'a Module,
+    id: FunctionId,
+    func: &'a LocalFunction,
+    /// The function each parameter local belongs to.
+    params: &'a IdHashMap<Local, FunctionId>,
+
+    /// The operand stack. `None` is a value of unknown type, which is popped
+    /// from the polymorphic stack of unreachable code.
+    operands: Vec<Option<ValType>>,
+    frames: Vec<Frame<'a>>,
+    /// The instruction sequences that have been used so far.
+    seqs: IdHashSet<InstrSeq>,
+
+    /// Non-defaultable locals that have been set, in the order they were set.
+    inits: Vec<LocalId>,
+    initialized: IdHashSet<Local>,
+
+    /// The position of the instruction being checked.
+    seq: InstrSeqId,
+    index: usize,
+
+    /// If requested, the operand stack before each instruction and at the end
+    /// of each instruction sequence.
+    stacks: Option<IdHashMap<InstrSeq, Vec<OperandStack>>>,
+}
+
+/// Compute the operand stack before each instruction of a local function,
+/// and at the end of each of its instruction sequences, checking that the
+/// function is well-typed along the way.
+pub(crate) fn operand_stacks(
+    module: &Module,
+    id: FunctionId,
+    func: &LocalFunction,
+) -> Result<IdHashMap<InstrSeq, Vec<OperandStack>>> {
+    let params = IdHashMap::default();
+    let mut checker = FunctionChecker::new(module, id, func, &params);
+    checker.stacks = Some(IdHashMap::default());
+    checker.run()?;
+    Ok(checker.stacks.unwrap())
+}
+
+impl<'a> FunctionChecker<'a> {
+    fn new(
+        module: &'a Module,
+        id: FunctionId,
+        func: &'a LocalFunction,
+        params: &'a IdHashMap<Local, FunctionId>,
+    ) -> FunctionChecker<'a> {
+        FunctionChecker {
+            module,
+            id,
+            func,
+            params,
+            operands: Vec::new(),
+            frames: Vec::new(),
+            seqs: IdHashSet::default(),
+            inits: Vec::new(),
+            initialized: func.args.iter().cloned().collect(),
+            seq: func.entry_block(),
+            index: 0,
+            stacks: None,
+        }
+    }
+
+    fn run(&mut self) -> Result<()> {
+        let ty = self.func.ty();
+        let ty = match self.module.types.contains(ty) {
+            true => self.module.types.get(ty).as_function(),
+            false => None,
+        };
+        let ty = match ty {
+            Some(ty) => ty,
+            None => bail!(
+                "function {} has type {}, which is not a function type",
+                self.id.index(),
+                self.func.ty().index()
+            ),
+        };
+        if ty.params().len() != self.func.args.len()
+            || ty
+                .params()
+                .iter()
+                .zip(self.func.args.iter())
+                .any(|(ty, arg)| {
+                    !self.module.locals.contains(*arg) || self.module.locals.get(*arg).ty() != *ty
+                })
+        {
+            bail!(
+                "the parameters of function {} don't match its type",
+                self.id.index()
+            );
+        }
+
+        let entry = self.instr_seq(self.func.entry_block())?;
+        let results = ty.results().to_vec();
+        self.push_frame(
+            vec![Arm {
+                seq: entry,
+                kind: BlockKind::FunctionEntry,
+                params: Vec::new(),
+            }],
+            results,
+        );
+
+        while let Some(frame) = self.frames.last_mut() {
+            let seq = frame.seq;
+            self.seq = seq.id();
+            if let Some(stacks) = &mut self.stacks {
+                stacks.entry(seq.id()).or_default().push(OperandStack {
+                    values: self.operands.clone(),
+                    base: frame.height,
+                    unreachable: frame.unreachable,
+                });
+            }
+            if frame.next < seq.len() {
+                self.index = frame.next;
+                frame.next += 1;
+                self.instr(&seq.instrs[self.index].0)?;
+            } else {
+                self.index = seq.len();
+                self.end_frame()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Create an error located at the instruction being checked.
+    fn error(&self, msg: String) -> anyhow::Error {
+        anyhow!(msg).context(InstrPosition {
+            func: self.id,
+            seq: self.seq,
+            index: self.index,
+        })
+    }
+
+    fn type_mismatch(&self, expected: impl std::fmt::Display, actual: ValType) -> anyhow::Error {
+        self.error(format!(
+            "type mismatch: expected {}, found {}",
+            expected, actual
+        ))
+    }
+
+    fn push(&mut self, ty: ValType) {
+        self.operands.push(Some(ty));
+    }
+
+    fn push_all(&mut self, tys: &[ValType]) {
+        self.operands.extend(tys.iter().map(|ty| Some(*ty)));
+    }
+
+    /// Pop an operand of any type.
+    fn pop_operand(&mut self, expected: Option<ValType>) -> Result<Option<ValType>> {
+        let frame = self.frames.last().unwrap();
+        if self.operands.len() > frame.height {
+            return Ok(self.operands.pop().unwrap());
+        }
+        if frame.unreachable {
+            return Ok(None);
+        }
+        Err(self.error(match expected {
+            Some(ty) => format!(
+                "type mismatch: expected {}, but nothing is on the stack",
+                ty
+            ),
+            None => "type mismatch: expected a value, but nothing is on the stack".to_string(),
+        }))
+    }
+
+    /// Pop an operand that must be a subtype of `expected`.
+    fn pop(&mut self, expected: ValType) -> Result<Option<ValType>> {
+        let actual = self.pop_operand(Some(expected))?;
+        if let Some(actual) = actual {
+            if !self.module.types.is_subtype(actual, expected) {
+                return Err(self.type_mismatch(expected, actual));
+            }
+        }
+        Ok(actual)
+    }
+
+    fn pop_all(&mut self, tys: &[ValType]) -> Result<()> {
+        for ty in tys.iter().rev() {
+            self.pop(*ty)?;
+        }
+        Ok(())
+    }
+
+    /// Pop an operand of any reference type.
+    fn pop_ref(&mut self) -> Result<Option<RefType>> {
+        match self.pop_operand(None)? {
+            None => Ok(None),
+            Some(ValType::Ref(r)) => Ok(Some(r)),
+            Some(ty) => Err(self.type_mismatch("a reference", ty)),
+        }
+    }
+
+    /// Check that the top of the operand stack matches `tys`, without
+    /// changing it.
+    fn peek_all(&mut self, tys: &[ValType]) -> Result<()> {
+        let mut popped = Vec::with_capacity(tys.len());
+        for ty in tys.iter().rev() {
+            popped.push(self.pop(*ty)?);
+        }
+        self.operands.extend(popped.into_iter().rev());
+        Ok(())
+    }
+
+    /// Mark the rest of the current frame as unreachable.
+    fn unreachable(&mut self) {
+        let frame = self.frames.last_mut().unwrap();
+        self.operands.truncate(frame.height);
+        frame.unreachable = true;
+    }
+
+    /// Start checking the given instruction sequences, one after the other,
+    /// each of which leaves `results` on the operand stack.
+    fn push_frame(&mut self, mut arms: Vec<Arm<'a>>, results: Vec<ValType>) {
+        arms.reverse();
+        let arm = arms.pop().unwrap();
+        self.enter(arm, arms, results);
+    }
+
+    /// Start checking `arm`, followed by the arms of `rest`, which are in
+    /// reverse order.
+    fn enter(&mut self, arm: Arm<'a>, rest: Vec<Arm<'a>>, results: Vec<ValType>) {
+        let height = self.operands.len();
+        self.push_all(&arm.params);
+        self.frames.push(Frame {
+            seq: arm.seq,
+            kind: arm.kind,
+            params: arm.params,
+            results,
+            height,
+            inits: self.inits.len(),
+            unreachable: false,
+            next: 0,
+            rest,
+        });
+    }
+
+    fn end_frame(&mut self) -> Result<()> {
+        let results = self.frames.last().unwrap().results.clone();
+        self.pop_all(&results)?;
+        let frame = self.frames.pop().unwrap();
+        if self.operands.len() != frame.height {
+            let extra = self.operands.len() - frame.height;
+            self.frames.push(frame);
+            return Err(self.error(format!(
+                "type mismatch: {} more value(s) than expected on the stack at the end of \
+                 the instruction sequence",
+                extra
+            )));
+        }
+
+        for local in self.inits.drain(frame.inits..) {
+            self.initialized.remove(&local);
+        }
+
+        let mut rest = frame.rest;
+        if let Some(arm) = rest.pop() {
+            self.enter(arm, rest, frame.results);
+        } else if !self.frames.is_empty() {
+            self.push_all(&frame.results);
+        }
+        Ok(())
+    }
+
+    /// Get an instruction sequence of this function that isn't already used
+    /// somewhere else.
+    fn instr_seq(&mut self, id: InstrSeqId) -> Result<&'a InstrSeq> {
+        let seq = match self.func.builder().arena.get(id) {
+            Some(seq) => seq,
+            None => {
+                return Err(self.error(format!(
+                    "instruction sequence {} does not belong to this function",
+                    id.index()
+                )))
+            }
+        };
+        if !self.seqs.insert(id) {
+            return Err(self.error(format!(
+                "instruction sequence {} is used more than once",
+                id.index()
+            )));
+        }
+        Ok(seq)
+    }
+
+    /// The types of values that a branch to `block` takes.
+    fn label_types(&self, block: InstrSeqId) -> Result<Vec<ValType>> {
+        match self.frames.iter().rev().find(|f| f.seq.id() == block) {
+            Some(frame) if frame.kind == BlockKind::Loop => Ok(frame.params.clone()),
+            Some(frame) => Ok(frame.results.clone()),
+            None => Err(self.error(format!(
+                "branch to instruction sequence {}, which does not enclose it",
+                block.index()
+            ))),
+        }
+    }
+
+    /// The parameter and result types of an instruction sequence.
+    fn seq_types(&self, seq: &InstrSeq) -> Result<(Vec<ValType>, Vec<ValType>)> {
+        match seq.ty {
+            InstrSeqType::Simple(None) => Ok((Vec::new(), Vec::new())),
+            InstrSeqType::Simple(Some(ty)) => {
+                self.val_type(ty)?;
+                Ok((Vec::new(), vec![ty]))
+            }
+            InstrSeqType::MultiValue(ty) => {
+                let ty = self.func_type(ty)?;
+                Ok((ty.params().to_vec(), ty.results().to_vec()))
+            }
+        }
+    }
+
+    fn type_id(&self, ty: TypeId) -> Result<&'a TypeKind> {
+        if !self.module.types.contains(ty) {
+            return Err(self.error(format!("type {} was deleted", ty.index())));
+        }
+        Ok(self.module.types.get(ty).kind())
+    }
+
+    fn func_type(&self, ty: TypeId) -> Result<&'a FunctionType> {
+        match self.type_id(ty)? {
+            TypeKind::Function(f) => Ok(f),
+            _ => Err(self.error(format!("type {} is not a function type", ty.index()))),
+        }
+    }
+
+    fn struct_type(&self, ty: TypeId) -> Result<&'a StructType> {
+        match self.type_id(ty)? {
+            TypeKind::Struct(s) => Ok(s),
+            _ => Err(self.error(format!("type {} is not a struct type", ty.index()))),
+        }
+    }
+
+    fn array_type(&self, ty: TypeId) -> Result<&'a ArrayType> {
+        match self.type_id(ty)? {
+            TypeKind::Array(a) => Ok(a),
+            _ => Err(self.error(format!("type {} is not an array type", ty.index()))),
+        }
+    }
+
+    fn struct_field(&self, ty: TypeId, field: u32) -> Result<(StorageType, bool)> {
+        match self.struct_type(ty)?.fields.get(field as usize) {
+            Some(f) => Ok((f.element_type, f.mutable)),
+            None => Err(self.error(format!("struct type {} has no field {}", ty.index(), field))),
+        }
+    }
+
+    fn heap_type(&self, ty: HeapType) -> Result<()> {
+        if let HeapType::Concrete(ty) = ty {
+            self.type_id(ty)?;
+        }
+        Ok(())
+    }
+
+    fn val_type(&self, ty: ValType) -> Result<()> {
+        match ty {
+            ValType::Ref(r) => self.heap_type(r.heap_type),
+            _ => Ok(()),
+        }
+    }
+
+    /// The abstract type at the top of the hierarchy that `ty` belongs to.
+    fn top_type(&self, ty: HeapType) -> AbstractHeapType {
+        use AbstractHeapType::*;
+        match ty {
+            HeapType::Abstract(Func | NoFunc) => Func,
+            HeapType::Abstract(Extern | NoExtern) => Extern,
+            HeapType::Abstract(Exn | NoExn) => Exn,
+            HeapType::Abstract(Cont | NoCont) => Cont,
+            HeapType::Abstract(_) => Any,
+            HeapType::Concrete(ty) => match self.module.types.get(ty).kind() {
+                TypeKind::Function(_) => Func,
+                TypeKind::Struct(_) | TypeKind::Array(_) => Any,
+                TypeKind::Cont(_) => Cont,
+            },
+        }
+    }
+
+    fn local(&self, local: LocalId) -> Result<ValType> {
+        if !self.module.locals.contains(local) {
+            return Err(self.error(format!("local {} does not exist", local.index())));
+        }
+        if let Some(func) = self.params.get(&local) {
+            if *func != self.id {
+                return Err(self.error(format!(
+                    "local {} is a parameter of function {}",
+                    local.index(),
+                    func.index()
+                )));
+            }
+        }
+        let ty = self.module.locals.get(local).ty();
+        self.val_type(ty)?;
+        Ok(ty)
+    }
+
+    fn set_local(&mut self, local: LocalId) -> Result<ValType> {
+        let ty = self.local(local)?;
+        if !ty.is_defaultable() && self.initialized.insert(local) {
+            self.inits.push(local);
+        }
+        Ok(ty)
+    }
+
+    fn function(&self, func: FunctionId) -> Result<&'a FunctionType> {
+        if !self.module.funcs.contains(func) {
+            return Err(self.error(format!("function {} was deleted", func.index())));
+        }
+        self.func_type(self.module.funcs.get(func).ty())
+    }
+
+    /// The index type of a memory.
+    fn memory(&self, memory: MemoryId) -> Result<ValType> {
+        if !self.module.memories.contains(memory) {
+            return Err(self.error(format!("memory {} was deleted", memory.index())));
+        }
+        Ok(if self.module.memories.get(memory).memory64 {
+            ValType::I64
+        } else {
+            ValType::I32
+        })
+    }
+
+    /// The index type and element type of a table.
+    fn table(&self, table: TableId) -> Result<(ValType, RefType)> {
+        if !self.module.tables.contains(table) {
+            return Err(self.error(format!("table {} was deleted", table.index())));
+        }
+        let table = self.module.tables.get(table);
+        let index = if table.table64 {
+            ValType::I64
+        } else {
+            ValType::I32
+        };
+        Ok((index, table.element_ty))
+    }
+
+    fn data(&self, data: DataId) -> Result<()> {
+        if !self.module.data.contains(data) {
+            return Err(self.error(format!("data segment {} was deleted", data.index())));
+        }
+        Ok(())
+    }
+
+    /// The type of an element segment's items.
+    fn elem(&self, elem: ElementId) -> Result<RefType> {
+        if !self.module.elements.contains(elem) {
+            return Err(self.error(format!("element segment {} was deleted", elem.index())));
+        }
+        Ok(match &self.module.elements.get(elem).items {
+            ElementItems::Functions(_) => RefType::FUNCREF,
+            ElementItems::Expressions(ty, _) => *ty,
+        })
+    }
+
+    /// The types of the values that a tag carries.
+    fn tag(&self, tag: TagId) -> Result<&'a [ValType]> {
+        Ok(self.tag_type(tag)?.params())
+    }
+
+    /// The function type of a tag, whose results are only used by tags that
+    /// suspend continuations.
+    fn tag_type(&self, tag: TagId) -> Result<&'a FunctionType> {
+        if !self.module.tags.contains(tag) {
+            return Err(self.error(format!("tag {} was deleted", tag.index())));
+        }
+        self.func_type(self.module.tags.get(tag).ty())
+    }
+
+    /// The function type of the continuation type `ty`.
+    fn cont_type(&self, ty: TypeId) -> Result<&'a FunctionType> {
+        match self.type_id(ty)? {
+            TypeKind::Cont(c) => self.func_type(c.func_type),
+            _ => Err(self.error(format!("type {} is not a continuation type", ty.index()))),
+        }
+    }
+
+    /// The function type of the continuation that a reference of type `r`
+    /// refers to.
+    fn cont_ref_type(&self, r: RefType) -> Result<&'a FunctionType> {
+        match r.heap_type {
+            HeapType::Concrete(ty) => self.cont_type(ty),
+            _ => Err(self.error(format!(
+                "type mismatch: expected a continuation reference, found {}",
+                r
+            ))),
+        }
+    }
+
+    /// Is every type in `sub` a subtype of the type at the same position in
+    /// `sup`?
+    fn types_match(&self, sub: &[ValType], sup: &[ValType]) -> bool {
+        sub.len() == sup.len()
+            && sub
+                .iter()
+                .zip(sup)
+                .all(|(a, b)| self.module.types.is_subtype(*a, *b))
+    }
+
+    /// Check the handlers of a `resume` of a continuation that returns
+    /// `results`.
+    fn resume_handlers(&self, handlers: &[ResumeHandler], results: &[ValType]) -> Result<()> {
+        for handler in handlers {
+            match handler {
+                ResumeHandler::OnLabel { tag, label } => {
+                    // The label takes the tag's values and the continuation
+                    // of the suspended computation, which takes the tag's
+                    // results and returns the resumed continuation's results.
+                    let tag_ty = self.tag_type(*tag)?;
+                    let (params, cont) = self.label_with_ref(*label)?;
+                    let cont = self.cont_ref_type(cont)?;
+                    if !self.types_match(tag_ty.params(), &params)
+                        || !self.types_match(cont.params(), tag_ty.results())
+                        || !self.types_match(results, cont.results())
+                    {
+                        return Err(self.error(format!(
+                            "type mismatch: instruction sequence {} can't handle \
+                             a suspension to tag {}",
+                            label.index(),
+                            tag.index()
+                        )));
+                    }
+                }
+                ResumeHandler::OnSwitch { tag } => {
+                    if !self.tag_type(*tag)?.params().is_empty() {
+                        return Err(self.error(format!(
+                            "tag {} of a switch handler must not carry any values",
+                            tag.index()
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn call(&mut self, ty: &FunctionType) -> Result<()> {
+        self.pop_all(ty.params())?;
+        self.push_all(ty.results());
+        Ok(())
+    }
+
+    fn return_call(&mut self, ty: &FunctionType) -> Result<()> {
+        self.pop_all(ty.params())?;
+        let results = self.module.types.results(self.func.ty());
+        if ty.results().len() != results.len()
+            || ty
+                .results()
+                .iter()
+                .zip(results)
+                .any(|(a, b)| !self.module.types.is_subtype(*a, *b))
+        {
+            return Err(self.error(
+                "type mismatch: the callee's results don't match the caller's".to_string(),
+            ));
+        }
+        self.unreachable();
+        Ok(())
+    }
+
+    /// Check that a branch can take values of types `tys` to `block`.
+    fn branch_with(&self, tys: &[ValType], block: InstrSeqId) -> Result<()> {
+        let label = self.label_types(block)?;
+        if tys.len() != label.len()
+            || tys
+                .iter()
+                .zip(label.iter())
+                .any(|(a, b)| !self.module.types.is_subtype(*a, *b))
+        {
+            return Err(self.error(format!(
+                "type mismatch: instruction sequence {} doesn't take the values being \
+                 branched to it",
+                block.index()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Split the types a branch to `block` takes into everything but the last
+    /// one, and the last one, which must be a reference.
+    fn label_with_ref(&self, block: InstrSeqId) -> Result<(Vec<ValType>, RefType)> {
+        let mut label = self.label_types(block)?;
+        match label.pop() {
+            Some(ValType::Ref(r)) => Ok((label, r)),
+            _ => Err(self.error(format!(
+                "type mismatch: instruction sequence {} must take a reference as its last value",
+                block.index()
+            ))),
+        }
+    }
+
+    fn storage_type_matches(&self, sub: StorageType, sup: StorageType) -> bool {
+        match (sub, sup) {
+            (StorageType::Val(sub), StorageType::Val(sup)) => {
+                self.module.types.is_subtype(sub, sup)
+            }
+            (sub, sup) => sub == sup,
+        }
+    }
+
+    fn instr(&mut self, instr: &'a Instr) -> Result<()> {
+        use ValType::*;
+
+        match instr {
+            Instr::Block(Block { seq }) | Instr::Loop(Loop { seq }) => {
+                let kind = match instr {
+                    Instr::Block(_) => BlockKind::Block,
+                    _ => BlockKind::Loop,
+                };
+                let seq = self.instr_seq(*seq)?;
+                let (params, results) = self.seq_types(seq)?;
+                self.pop_all(&params)?;
+                self.push_frame(vec![Arm { seq, kind, params }], results);
+            }
+            Instr::IfElse(IfElse {
+                consequent,
+                alternative,
+            }) => {
+                self.pop(I32)?;
+                let consequent = self.instr_seq(*consequent)?;
+                let alternative = self.instr_seq(*alternative)?;
+                if consequent.ty != alternative.ty {
+                    return Err(self.error(
+                        "the consequent and alternative of an `if` have different types"
+                            .to_string(),
+                    ));
+                }
+                let (params, results) = self.seq_types(consequent)?;
+                self.pop_all(&params)?;
+                let arms = vec![
+                    Arm {
+                        seq: consequent,
+                        kind: BlockKind::If,
+                        params: params.clone(),
+                    },
+                    Arm {
+                        seq: alternative,
+                        kind: BlockKind::Else,
+                        params,
+                    },
+                ];
+                self.push_frame(arms, results);
+            }
+            Instr::TryTable(TryTable { seq, catches }) => {
+                for catch in catches {
+                    match catch {
+                        TryTableCatch::Catch { tag, label } => {
+                            let tys = self.tag(*tag)?;
+                            self.branch_with(tys, *label)?;
+                        }
+                        TryTableCatch::CatchRef { tag, label } => {
+                            let mut tys = self.tag(*tag)?.to_vec();
+                            tys.push(Ref(EXN));
+                            self.branch_with(&tys, *label)?;
+                        }
+                        TryTableCatch::CatchAll { label } => self.branch_with(&[], *label)?,
+                        TryTableCatch::CatchAllRef { label } => {
+                            self.branch_with(&[Ref(EXN)], *label)?
+                        }
+                    }
+                }
+                let seq = self.instr_seq(*seq)?;
+                let (params, results) = self.seq_types(seq)?;
+                self.pop_all(&params)?;
+                let kind = BlockKind::TryTable;
+                self.push_frame(vec![Arm { seq, kind, params }], results);
+            }
+            Instr::Try(Try { seq, catches }) => {
+                let seq = self.instr_seq(*seq)?;
+                let (params, results) = self.seq_types(seq)?;
+                let kind = BlockKind::Try;
+                let mut arms = vec![Arm {
+                    seq,
+                    kind,
+                    params: params.clone(),
+                }];
+                for (i, catch) in catches.iter().enumerate() {
+                    match catch {
+                        LegacyCatch::Catch { tag, handler } => {
+                            let params = self.tag(*tag)?.to_vec();
+                            let seq = self.instr_seq(*handler)?;
+                            let kind = BlockKind::Catch;
+                            arms.push(Arm { seq, kind, params });
+                        }
+                        LegacyCatch::CatchAll { handler } if i == catches.len() - 1 => {
+                            let seq = self.instr_seq(*handler)?;
+                            let kind = BlockKind::CatchAll;
+                            let params = Vec::new();
+                            arms.push(Arm { seq, kind, params });
+                        }
+                        LegacyCatch::Delegate { relative_depth } if catches.len() == 1 => {
+                            if *relative_depth as usize >= self.frames.len() {
+                                return Err(self.error(format!(
+                                    "`delegate` to nonexistent depth {}",
+                                    relative_depth
+                                )));
+                            }
+                        }
+                        LegacyCatch::CatchAll { .. } => {
+                            return Err(self.error(
+                                "`catch_all` must be the last handler of a `try`".to_string(),
+                            ))
+                        }
+                        LegacyCatch::Delegate { .. } => {
+                            return Err(self.error(
+                                "`delegate` can't be combined with other handlers".to_string(),
+                            ))
+                        }
+                    }
+                }
+                self.pop_all(&params)?;
+                self.push_frame(arms, results);
+            }
+
+            Instr::Br(Br { block }) => {
+                let label = self.label_types(*block)?;
+                self.pop_all(&label)?;
+                self.unreachable();
+            }
+            Instr::BrIf(BrIf { block }) => {
+                self.pop(I32)?;
+                let label = self.label_types(*block)?;
+                self.pop_all(&label)?;
+                self.push_all(&label);
+            }
+            Instr::BrTable(BrTable { blocks, default }) => {
+                self.pop(I32)?;
+                let label = self.label_types(*default)?;
+                for block in blocks.iter() {
+                    let tys = self.label_types(*block)?;
+                    if tys.len() != label.len() {
+                        return Err(self.error(format!(
+                            "type mismatch: instruction sequences {} and {} of a `br_table` \
+                             take different numbers of values",
+                            block.index(),
+                            default.index()
+                        )));
+                    }
+                    self.peek_all(&tys)?;
+                }
+                self.pop_all(&label)?;
+                self.unreachable();
+            }
+            Instr::BrOnNull(BrOnNull { block }) => {
+                let r = self.pop_ref()?;
+                let label = self.label_types(*block)?;
+                self.peek_all(&label)?;
+                self.operands.push(r.map(|r| Ref(non_null(r))));
+            }
+            Instr::BrOnNonNull(BrOnNonNull { block }) => {
+                let r = self.pop_ref()?;
+                let (label, last) = self.label_with_ref(*block)?;
+                if let Some(r) = r {
+                    if !self.module.types.is_ref_subtype(non_null(r), last) {
+                        return Err(self.type_mismatch(Ref(last), Ref(non_null(r))));
+                    }
+                }
+                self.peek_all(&label)?;
+            }
+            Instr::BrOnCast(BrOnCast {
+                block,
+                from_nullable,
+                from_heap_type,
+                to_nullable,
+                to_heap_type,
+            })
+            | Instr::BrOnCastFail(BrOnCastFail {
+                block,
+                from_nullable,
+                from_heap_type,
+                to_nullable,
+                to_heap_type,
+            }) => {
+                self.heap_type(*from_heap_type)?;
+                self.heap_type(*to_heap_type)?;
+                let from = RefType {
+                    nullable: *from_nullable,
+                    heap_type: *from_heap_type,
+                };
+                let to = RefType {
+                    nullable: *to_nullable,
+                    heap_type: *to_heap_type,
+                };
+                if !self.module.types.is_ref_subtype(to, from) {
+                    return Err(self.type_mismatch(Ref(from), Ref(to)));
+                }
+                // The type of the reference if the cast fails.
+                let diff = RefType {
+                    nullable: from.nullable && !to.nullable,
+                    heap_type: from.heap_type,
+                };
+                let (branched, fallthrough) = match instr {
+                    Instr::BrOnCast(_) => (to, diff),
+                    _ => (diff, to),
+                };
+                self.pop(Ref(from))?;
+                let (label, last) = self.label_with_ref(*block)?;
+                if !self.module.types.is_ref_subtype(branched, last) {
+                    return Err(self.type_mismatch(Ref(last), Ref(branched)));
+                }
+                self.peek_all(&label)?;
+                self.push(Ref(fallthrough));
+            }
+            Instr::Return(Return {}) => {
+                let results = self.module.types.results(self.func.ty());
+                self.pop_all(results)?;
+                self.unreachable();
+            }
+            Instr::Unreachable(Unreachable {}) => self.unreachable(),
+
+            Instr::Call(Call { func }) => {
+                let ty = self.function(*func)?;
+                self.call(ty)?;
+            }
+            Instr::ReturnCall(ReturnCall { func }) => {
+                let ty = self.function(*func)?;
+                self.return_call(ty)?;
+            }
+            Instr::CallIndirect(CallIndirect { ty, table })
+            | Instr::ReturnCallIndirect(ReturnCallIndirect { ty, table }) => {
+                let (index, elem) = self.table(*table)?;
+                if !self.module.types.is_ref_subtype(elem, RefType::FUNCREF) {
+                    return Err(self.error(format!(
+                        "table {} of type {} can't be used for indirect calls",
+                        table.index(),
+                        elem
+                    )));
+                }
+                let ty = self.func_type(*ty)?;
+                self.pop(index)?;
+                match instr {
+                    Instr::CallIndirect(_) => self.call(ty)?,
+                    _ => self.return_call(ty)?,
+                }
+            }
+            Instr::CallRef(CallRef { ty }) | Instr::ReturnCallRef(ReturnCallRef { ty }) => {
+                let func = self.func_type(*ty)?;
+                self.pop(Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(*ty),
+                }))?;
+                match instr {
+                    Instr::CallRef(_) => self.call(func)?,
+                    _ => self.return_call(func)?,
+                }
+            }
+
+            Instr::LocalGet(LocalGet { local }) => {
+                let ty = self.local(*local)?;
+                if !ty.is_defaultable() && !self.initialized.contains(local) {
+                    return Err(self.error(format!(
+                        "local {} of type {} is read before it is set",
+                        local.index(),
+                        ty
+                    )));
+                }
+                self.push(ty);
+            }
+            Instr::LocalSet(LocalSet { local }) => {
+                let ty = self.set_local(*local)?;
+                self.pop(ty)?;
+            }
+            Instr::LocalTee(LocalTee { local }) => {
+                let ty = self.set_local(*local)?;
+                self.pop(ty)?;
+                self.push(ty);
+            }
+            Instr::GlobalGet(GlobalGet { global }) | Instr::GlobalSet(GlobalSet { global }) => {
+                if !self.module.globals.contains(*global) {
+                    return Err(self.error(format!("global {} was deleted", global.index())));
+                }
+                let g = self.module.globals.get(*global);
+                if let Instr::GlobalGet(_) = instr {
+                    self.push(g.ty);
+                } else if g.mutable {
+                    self.pop(g.ty)?;
+                } else {
+                    return Err(self.error(format!(
+                        "global {} is immutable and can't be set",
+                        global.index()
+                    )));
+                }
+            }
+
+            Instr::Const(Const { value }) => self.push(match value {
+                Value::I32(_) => I32,
+                Value::I64(_) => I64,
+                Value::F32(_) => F32,
+                Value::F64(_) => F64,
+                Value::V128(_) => V128,
+            }),
+            Instr::Unop(Unop { op }) => {
+                let (input, output) = unop_types(*op);
+                self.pop(input)?;
+                self.push(output);
+            }
+            Instr::Binop(Binop { op }) => {
+                let (lhs, rhs, output) = binop_types(*op);
+                self.pop(rhs)?;
+                self.pop(lhs)?;
+                self.push(output);
+            }
+            Instr::TernOp(TernOp { .. }) | Instr::V128Bitselect(V128Bitselect {}) => {
+                self.pop_all(&[V128, V128, V128])?;
+                self.push(V128);
+            }
+            Instr::I8x16Swizzle(I8x16Swizzle {}) | Instr::I8x16Shuffle(I8x16Shuffle { .. }) => {
+                self.pop_all(&[V128, V128])?;
+                self.push(V128);
+            }
+            Instr::Drop(Drop {}) => {
+                self.pop_operand(None)?;
+            }
+            Instr::Select(Select { ty: Some(ty) }) => {
+                self.val_type(*ty)?;
+                self.pop(I32)?;
+                self.pop_all(&[*ty, *ty])?;
+                self.push(*ty);
+            }
+            Instr::Select(Select { ty: None }) => {
+                self.pop(I32)?;
+                let a = self.pop_operand(None)?;
+                let b = self.pop_operand(None)?;
+                for ty in a.iter().chain(b.iter()) {
+                    if let Ref(_) = ty {
+                        return Err(self.type_mismatch("a numeric or vector value", *ty));
+                    }
+                }
+                if let (Some(a), Some(b)) = (a, b) {
+                    if a != b {
+                        return Err(self.type_mismatch(b, a));
+                    }
+                }
+                self.operands.push(a.or(b));
+            }
+
+            Instr::MemorySize(MemorySize { memory }) => {
+                let index = self.memory(*memory)?;
+                self.push(index);
+            }
+            Instr::MemoryGrow(MemoryGrow { memory }) => {
+                let index = self.memory(*memory)?;
+                self.pop(index)?;
+                self.push(index);
+            }
+            Instr::MemoryInit(MemoryInit { memory, data }) => {
+                let index = self.memory(*memory)?;
+                self.data(*data)?;
+                self.pop_all(&[index, I32, I32])?;
+            }
+            Instr::DataDrop(DataDrop { data }) => self.data(*data)?,
+            Instr::MemoryCopy(MemoryCopy { src, dst }) => {
+                let src = self.memory(*src)?;
+                let dst = self.memory(*dst)?;
+                self.pop_all(&[dst, src, min_index(src, dst)])?;
+            }
+            Instr::MemoryFill(MemoryFill { memory }) => {
+                let index = self.memory(*memory)?;
+                self.pop_all(&[index, I32, index])?;
+            }
+            Instr::Load(Load { memory, kind, .. }) => {
+                let index = self.memory(*memory)?;
+                self.pop(index)?;
+                self.push(match kind {
+                    LoadKind::I32 { .. } | LoadKind::I32_8 { .. } | LoadKind::I32_16 { .. } => I32,
+                    LoadKind::I64 { .. }
+                    | LoadKind::I64_8 { .. }
+                    | LoadKind::I64_16 { .. }
+                    | LoadKind::I64_32 { .. } => I64,
+                    LoadKind::F32 => F32,
+                    LoadKind::F64 => F64,
+                    LoadKind::V128 => V128,
+                });
+            }
+            Instr::Store(Store { memory, kind, .. }) => {
+                let index = self.memory(*memory)?;
+                let value = match kind {
+                    StoreKind::I32 { .. } | StoreKind::I32_8 { .. } | StoreKind::I32_16 { .. } => {
+                        I32
+                    }
+                    StoreKind::I64 { .. }
+                    | StoreKind::I64_8 { .. }
+                    | StoreKind::I64_16 { .. }
+                    | StoreKind::I64_32 { .. } => I64,
+                    StoreKind::F32 => F32,
+                    StoreKind::F64 => F64,
+                    StoreKind::V128 => V128,
+                };
+                self.pop_all(&[index, value])?;
+            }
+            Instr::LoadSimd(LoadSimd { memory, kind, .. }) => {
+                let index = self.memory(*memory)?;
+                match kind {
+                    LoadSimdKind::V128Load8Lane(_)
+                    | LoadSimdKind::V128Load16Lane(_)
+                    | LoadSimdKind::V128Load32Lane(_)
+                    | LoadSimdKind::V128Load64Lane(_) => {
+                        self.pop_all(&[index, V128])?;
+                        self.push(V128);
+                    }
+                    LoadSimdKind::V128Store8Lane(_)
+                    | LoadSimdKind::V128Store16Lane(_)
+                    | LoadSimdKind::V128Store32Lane(_)
+                    | LoadSimdKind::V128Store64Lane(_) => {
+                        self.pop_all(&[index, V128])?;
+                    }
+                    _ => {
+                        self.pop(index)?;
+                        self.push(V128);
+                    }
+                }
+            }
+            Instr::AtomicRmw(AtomicRmw { memory, width, .. })
+            | Instr::Cmpxchg(Cmpxchg { memory, width, .. }) => {
+                let index = self.memory(*memory)?;
+                let value = match width {
+                    AtomicWidth::I32 | AtomicWidth::I32_8 | AtomicWidth::I32_16 => I32,
+                    _ => I64,
+                };
+                if let Instr::Cmpxchg(_) = instr {
+                    self.pop(value)?;
+                }
+                self.pop_all(&[index, value])?;
+                self.push(value);
+            }
+            Instr::AtomicNotify(AtomicNotify { memory, .. }) => {
+                let index = self.memory(*memory)?;
+                self.pop_all(&[index, I32])?;
+                self.push(I32);
+            }
+            Instr::AtomicWait(AtomicWait {
+                memory, sixty_four, ..
+            }) => {
+                let index = self.memory(*memory)?;
+                let expected = if *sixty_four { I64 } else { I32 };
+                self.pop_all(&[index, expected, I64])?;
+                self.push(I32);
+            }
+            Instr::AtomicFence(AtomicFence {}) => {}
+
+            Instr::TableGet(TableGet { table }) => {
+                let (index, elem) = self.table(*table)?;
+                self.pop(index)?;
+                self.push(Ref(elem));
+            }
+            Instr::TableSet(TableSet { table }) => {
+                let (index, elem) = self.table(*table)?;
+                self.pop_all(&[index, Ref(elem)])?;
+            }
+            Instr::TableGrow(TableGrow { table }) => {
+                let (index, elem) = self.table(*table)?;
+                self.pop_all(&[Ref(elem), index])?;
+                self.push(index);
+            }
+            Instr::TableSize(TableSize { table }) => {
+                let (index, _) = self.table(*table)?;
+                self.push(index);
+            }
+            Instr::TableFill(TableFill { table }) => {
+                let (index, elem) = self.table(*table)?;
+                self.pop_all(&[index, Ref(elem), index])?;
+            }
+            Instr::TableInit(TableInit { table, elem }) => {
+                let (index, ty) = self.table(*table)?;
+                let elem_ty = self.elem(*elem)?;
+                if !self.module.types.is_ref_subtype(elem_ty, ty) {
+                    return Err(self.type_mismatch(Ref(ty), Ref(elem_ty)));
+                }
+                self.pop_all(&[index, I32, I32])?;
+            }
+            Instr::ElemDrop(ElemDrop { elem }) => {
+                self.elem(*elem)?;
+            }
+            Instr::TableCopy(TableCopy { src, dst }) => {
+                let (src_index, src_ty) = self.table(*src)?;
+                let (dst_index, dst_ty) = self.table(*dst)?;
+                if !self.module.types.is_ref_subtype(src_ty, dst_ty) {
+                    return Err(self.type_mismatch(Ref(dst_ty), Ref(src_ty)));
+                }
+                self.pop_all(&[dst_index, src_index, min_index(src_index, dst_index)])?;
+            }
+
+            Instr::RefNull(RefNull { ty }) => {
+                self.heap_type(ty.heap_type)?;
+                self.push(Ref(RefType {
+                    nullable: true,
+                    heap_type: ty.heap_type,
+                }));
+            }
+            Instr::RefIsNull(RefIsNull {}) => {
+                self.pop_ref()?;
+                self.push(I32);
+            }
+            Instr::RefFunc(RefFunc { func }) => {
+                self.function(*func)?;
+                self.push(Ref(RefType {
+                    nullable: false,
+                    heap_type: HeapType::Concrete(self.module.funcs.get(*func).ty()),
+                }));
+            }
+            Instr::RefAsNonNull(RefAsNonNull {}) => {
+                let r = self.pop_ref()?;
+                self.operands.push(r.map(|r| Ref(non_null(r))));
+            }
+            Instr::RefEq(RefEq {}) => {
+                self.pop_all(&[Ref(RefType::EQREF), Ref(RefType::EQREF)])?;
+                self.push(I32);
+            }
+            Instr::RefI31(RefI31 {}) => {
+                self.pop(I32)?;
+                self.push(Ref(non_null(RefType::I31REF)));
+            }
+            Instr::I31GetS(I31GetS {}) | Instr::I31GetU(I31GetU {}) => {
+                self.pop(Ref(RefType::I31REF))?;
+                self.push(I32);
+            }
+            Instr::RefTest(RefTest {
+                nullable,
+                heap_type,
+            })
+            | Instr::RefCast(RefCast {
+                nullable,
+                heap_type,
+            }) => {
+                self.heap_type(*heap_type)?;
+                if let Some(r) = self.pop_ref()? {
+                    if self.top_type(r.heap_type) != self.top_type(*heap_type) {
+                        return Err(self.type_mismatch(
+                            format!("a reference to a subtype of {}", heap_type),
+                            Ref(r),
+                        ));
+                    }
+                }
+                self.push(match instr {
+                    Instr::RefTest(_) => I32,
+                    _ => Ref(RefType {
+                        nullable: *nullable,
+                        heap_type: *heap_type,
+                    }),
+                });
+            }
+            Instr::AnyConvertExtern(AnyConvertExtern {})
+            | Instr::ExternConvertAny(ExternConvertAny {}) => {
+                let (from, to) = match instr {
+                    Instr::AnyConvertExtern(_) => (RefType::EXTERNREF, AbstractHeapType::Any),
+                    _ => (RefType::ANYREF, AbstractHeapType::Extern),
+                };
+                let nullable = match self.pop(Ref(from))? {
+                    Some(Ref(r)) => r.nullable,
+                    _ => false,
+                };
+                self.push(Ref(RefType {
+                    nullable,
+                    heap_type: HeapType::Abstract(to),
+                }));
+            }
+
+            Instr::StructNew(StructNew { ty }) => {
+                let fields = self.struct_type(*ty)?.fields.iter();
+                let tys = fields
+                    .map(|f| f.element_type.unpacked())
+                    .collect::<Vec<_>>();
+                self.pop_all(&tys)?;
+                self.push(Ref(new_ref(*ty)));
+            }
+            Instr::StructNewDefault(StructNewDefault { ty }) => {
+                let s = self.struct_type(*ty)?;
+                if let Some(f) = s
+                    .fields
+                    .iter()
+                    .find(|f| !f.element_type.unpacked().is_defaultable())
+                {
+                    return Err(self.error(format!(
+                        "struct type {} has a field of type {}, which has no default value",
+                        ty.index(),
+                        f.element_type
+                    )));
+                }
+                self.push(Ref(new_ref(*ty)));
+            }
+            Instr::StructGet(StructGet { ty, field })
+            | Instr::StructGetS(StructGetS { ty, field })
+            | Instr::StructGetU(StructGetU { ty, field }) => {
+                let (storage, _) = self.struct_field(*ty, *field)?;
+                self.check_packed(instr, storage)?;
+                self.pop(Ref(nullable_ref(*ty)))?;
+                self.push(storage.unpacked());
+            }
+            Instr::StructSet(StructSet { ty, field }) => {
+                let (storage, mutable) = self.struct_field(*ty, *field)?;
+                if !mutable {
+                    return Err(self.error(format!(
+                        "field {} of struct type {} is immutable",
+                        field,
+                        ty.index()
+                    )));
+                }
+                self.pop_all(&[Ref(nullable_ref(*ty)), storage.unpacked()])?;
+            }
+
+            Instr::ArrayNew(ArrayNew { ty }) => {
+                let elem = self.array_type(*ty)?.field.element_type.unpacked();
+                self.pop_all(&[elem, I32])?;
+                self.push(Ref(new_ref(*ty)));
+            }
+            Instr::ArrayNewDefault(ArrayNewDefault { ty }) => {
+                let elem = self.array_type(*ty)?.field.element_type.unpacked();
+                if !elem.is_defaultable() {
+                    return Err(self.error(format!(
+                        "array type {} has elements of type {}, which has no default value",
+                        ty.index(),
+                        elem
+                    )));
+                }
+                self.pop(I32)?;
+                self.push(Ref(new_ref(*ty)));
+            }
+            Instr::ArrayNewFixed(ArrayNewFixed { ty, len }) => {
+                let elem = self.array_type(*ty)?.field.element_type.unpacked();
+                for _ in 0..*len {
+                    self.pop(elem)?;
+                }
+                self.push(Ref(new_ref(*ty)));
+            }
+            Instr::ArrayNewData(ArrayNewData { ty, data }) => {
+                self.array_of_numbers(*ty)?;
+                self.data(*data)?;
+                self.pop_all(&[I32, I32])?;
+                self.push(Ref(new_ref(*ty)));
+            }
+            Instr::ArrayNewElem(ArrayNewElem { ty, elem }) => {
+                self.array_of_elems(*ty, *elem)?;
+                self.pop_all(&[I32, I32])?;
+                self.push(Ref(new_ref(*ty)));
+            }
+            Instr::ArrayGet(ArrayGet { ty })
+            | Instr::ArrayGetS(ArrayGetS { ty })
+            | Instr::ArrayGetU(ArrayGetU { ty }) => {
+                let storage = self.array_type(*ty)?.field.element_type;
+                self.check_packed(instr, storage)?;
+                self.pop_all(&[Ref(nullable_ref(*ty)), I32])?;
+                self.push(storage.unpacked());
+            }
+            Instr::ArraySet(ArraySet { ty }) => {
+                let elem = self.mutable_array(*ty)?.element_type.unpacked();
+                self.pop_all(&[Ref(nullable_ref(*ty)), I32, elem])?;
+            }
+            Instr::ArrayLen(ArrayLen {}) => {
+                self.pop(Ref(RefType::ARRAYREF))?;
+                self.push(I32);
+            }
+            Instr::ArrayFill(ArrayFill { ty }) => {
+                let elem = self.mutable_array(*ty)?.element_type.unpacked();
+                self.pop_all(&[Ref(nullable_ref(*ty)), I32, elem, I32])?;
+            }
+            Instr::ArrayCopy(ArrayCopy { dst, src }) => {
+                let dst_elem = self.mutable_array(*dst)?.element_type;
+                let src_elem = self.array_type(*src)?.field.element_type;
+                if !self.storage_type_matches(src_elem, dst_elem) {
+                    return Err(self.error(format!(
+                        "type mismatch: can't copy elements of type {} into an array of {}",
+                        src_elem, dst_elem
+                    )));
+                }
+                self.pop_all(&[
+                    Ref(nullable_ref(*dst)),
+                    I32,
+                    Ref(nullable_ref(*src)),
+                    I32,
+                    I32,
+                ])?;
+            }
+            Instr::ArrayInitData(ArrayInitData { ty, data }) => {
+                self.mutable_array(*ty)?;
+                self.array_of_numbers(*ty)?;
+                self.data(*data)?;
+                self.pop_all(&[Ref(nullable_ref(*ty)), I32, I32, I32])?;
+            }
+            Instr::ArrayInitElem(ArrayInitElem { ty, elem }) => {
+                self.mutable_array(*ty)?;
+                self.array_of_elems(*ty, *elem)?;
+                self.pop_all(&[Ref(nullable_ref(*ty)), I32, I32, I32])?;
+            }
+
+            Instr::Throw(Throw { tag }) => {
+                let tys = self.tag(*tag)?;
+                self.pop_all(tys)?;
+                self.unreachable();
+            }
+            Instr::ThrowRef(ThrowRef {}) => {
+                self.pop(Ref(RefType::EXNREF))?;
+                self.unreachable();
+            }
+            Instr::Rethrow(Rethrow { relative_depth }) => {
+                let depth = *relative_depth as usize;
+                let is_catch = depth < self.frames.len()
+                    && matches!(
+                        self.frames[self.frames.len() - 1 - depth].kind,
+                        BlockKind::Catch | BlockKind::CatchAll
+                    );
+                if !is_catch {
+                    return Err(self.error(format!(
+                        "`rethrow` depth {} doesn't refer to a catch handler",
+                        relative_depth
+                    )));
+                }
+                self.unreachable();
+            }
+
+            Instr::ContNew(ContNew { ty }) => {
+                let func = match self.type_id(*ty)? {
+                    TypeKind::Cont(c) => c.func_type,
+                    _ => {
+                        return Err(
+                            self.error(format!("type {} is not a continuation type", ty.index()))
+                        )
+                    }
+                };
+                self.pop(Ref(nullable_ref(func)))?;
+                self.push(Ref(new_ref(*ty)));
+            }
+            Instr::ContBind(ContBind { from, to }) => {
+                let from_ty = self.cont_type(*from)?;
+                let to_ty = self.cont_type(*to)?;
+                // The bound arguments are a prefix of the original
+                // continuation's parameters.
+                let bound = from_ty
+                    .params()
+                    .len()
+                    .checked_sub(to_ty.params().len())
+                    .filter(|&n| {
+                        self.types_match(to_ty.params(), &from_ty.params()[n..])
+                            && self.types_match(from_ty.results(), to_ty.results())
+                    });
+                let bound = match bound {
+                    Some(n) => n,
+                    None => {
+                        return Err(self.error(format!(
+                            "type mismatch: can't bind continuation type {} to {}",
+                            from.index(),
+                            to.index()
+                        )))
+                    }
+                };
+                self.pop(Ref(nullable_ref(*from)))?;
+                self.pop_all(&from_ty.params()[..bound])?;
+                self.push(Ref(new_ref(*to)));
+            }
+            Instr::Suspend(Suspend { tag }) => {
+                let ty = self.tag_type(*tag)?;
+                self.call(ty)?;
+            }
+            Instr::Resume(Resume { ty, handlers }) => {
+                let cont = self.cont_type(*ty)?;
+                self.resume_handlers(handlers, cont.results())?;
+                self.pop(Ref(nullable_ref(*ty)))?;
+                self.call(cont)?;
+            }
+            Instr::ResumeThrow(ResumeThrow { ty, tag, handlers }) => {
+                let cont = self.cont_type(*ty)?;
+                self.resume_handlers(handlers, cont.results())?;
+                let tag_ty = self.tag_type(*tag)?;
+                if !tag_ty.results().is_empty() {
+                    return Err(self.error(format!(
+                        "tag {} can't be thrown because it has results",
+                        tag.index()
+                    )));
+                }
+                self.pop(Ref(nullable_ref(*ty)))?;
+                self.pop_all(tag_ty.params())?;
+                self.push_all(cont.results());
+            }
+            Instr::ResumeThrowRef(ResumeThrowRef { ty, handlers }) => {
+                let cont = self.cont_type(*ty)?;
+                self.resume_handlers(handlers, cont.results())?;
+                self.pop_all(&[Ref(RefType::EXNREF), Ref(nullable_ref(*ty))])?;
+                self.push_all(cont.results());
+            }
+            Instr::Switch(Switch { ty, tag }) => {
+                // The continuation being switched to takes the arguments and
+                // a continuation of the current computation.
+                let cont = self.cont_type(*ty)?;
+                let tag_ty = self.tag_type(*tag)?;
+                if !tag_ty.params().is_empty() {
+                    return Err(self.error(format!(
+                        "tag {} of a switch must not carry any values",
+                        tag.index()
+                    )));
+                }
+                let (args, current) = match cont.params().split_last() {
+                    Some((ValType::Ref(r), args)) => (args, self.cont_ref_type(*r)?),
+                    _ => {
+                        return Err(self.error(format!(
+                            "type mismatch: continuation type {} must take a continuation \
+                             as its last parameter",
+                            ty.index()
+                        )))
+                    }
+                };
+                if !self.types_match(cont.results(), tag_ty.results())
+                    || !self.types_match(tag_ty.results(), current.results())
+                {
+                    return Err(self.error(format!(
+                        "type mismatch: can't switch to continuation type {} with tag {}",
+                        ty.index(),
+                        tag.index()
+                    )));
+                }
+                self.pop(Ref(nullable_ref(*ty)))?;
+                self.pop_all(args)?;
+                self.push_all(current.params());
+            }
+
+            Instr::I64Add128(I64Add128 {}) | Instr::I64Sub128(I64Sub128 {}) => {
+                self.pop_all(&[I64, I64, I64, I64])?;
+                self.push_all(&[I64, I64]);
+            }
+            Instr::I64MulWideS(I64MulWideS {}) | Instr::I64MulWideU(I64MulWideU {}) => {
+                self.pop_all(&[I64, I64])?;
+                self.push_all(&[I64, I64]);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that `struct.get` and `array.get` are used on unpacked fields,
+    /// and their `_s` and `_u` variants on packed fields.
+    fn check_packed(&self, instr: &Instr, storage: StorageType) -> Result<()> {
+        let packed = !matches!(storage, StorageType::Val(_));
+        let wants_packed = !matches!(instr, Instr::StructGet(_) | Instr::ArrayGet(_));
+        if packed != wants_packed {
+            return Err(self.error(format!(
+                "type mismatch: can't read a field of type {} with this instruction",
+                storage
+            )));
+        }
+        Ok(())
+    }
+
+    fn mutable_array(&self, ty: TypeId) -> Result<FieldType> {
+        let field = self.array_type(ty)?.field;
+        if !field.mutable {
+            return Err(self.error(format!(
+                "the elements of array type {} are immutable",
+                ty.index()
+            )));
+        }
+        Ok(field)
+    }
+
+    /// Check that an array can be initialized from a data segment.
+    fn array_of_numbers(&self, ty: TypeId) -> Result<()> {
+        match self.array_type(ty)?.field.element_type {
+            StorageType::Val(ValType::Ref(_)) => Err(self.error(format!(
+                "array type {} has reference elements, which can't come from a data segment",
+                ty.index()
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that an array can be initialized from an element segment.
+    fn array_of_elems(&self, ty: TypeId, elem: ElementId) -> Result<()> {
+        let elem_ty = ValType::Ref(self.elem(elem)?);
+        let storage = self.array_type(ty)?.field.element_type;
+        if !self.storage_type_matches(StorageType::Val(elem_ty), storage) {
+            return Err(self.type_mismatch(storage, elem_ty));
+        }
+        Ok(())
+    }
+}
+
+/// Checks that the items a local function refers to haven't been deleted, and
+/// that its branches target instruction sequences that enclose them.
+///
+/// Unlike `FunctionChecker`, this doesn't look at the operand stack at all.
+struct IdChecker<'a> {
+    module: &'a Module,
+    id: FunctionId,
+    func: &'a LocalFunction,
+    /// The instruction sequences that enclose the instruction being checked,
+    /// with the index of their next instruction and the sequences to check
+    /// after them in the same instruction, in reverse order.
+    frames: Vec<(InstrSeqId, usize, Vec<InstrSeqId>)>,
+    /// The position of the instruction being checked.
+    seq: InstrSeqId,
+    index: usize,
+    /// The first problem found by the `Visitor` methods.
+    error: Option<String>,
+}
+
+impl<'a> IdChecker<'a> {
+    fn new(module: &'a Module, id: FunctionId, func: &'a LocalFunction) -> IdChecker<'a> {
+        let entry = func.entry_block();
+        IdChecker {
+            module,
+            id,
+            func,
+            frames: Vec::new(),
+            seq: entry,
+            index: 0,
+            error: None,
+        }
+    }
+
+    fn run(&mut self) -> Result<()> {
+        self.enter(self.func.entry_block(), Vec::new())?;
+        while let Some((seq, next, rest)) = self.frames.last_mut() {
+            let seq = *seq;
+            self.seq = seq;
+            let instrs = &self.func.block(seq).instrs;
+            if *next == instrs.len() {
+                let rest = std::mem::take(rest);
+                self.frames.pop();
+                if let Some((&arm, rest)) = rest.split_last() {
+                    self.enter(arm, rest.to_vec())?;
+                }
+                continue;
+            }
+            self.index = *next;
+            *next += 1;
+            let instr = &instrs[self.index].0;
+            instr.visit(self);
+            self.branch_targets(instr);
+            if let Some(msg) = self.error.take() {
+                return Err(self.error(msg));
+            }
+            let mut arms = match instr {
+                Instr::Block(Block { seq }) | Instr::Loop(Loop { seq }) => vec![*seq],
+                Instr::IfElse(IfElse {
+                    consequent,
+                    alternative,
+                }) => vec![*consequent, *alternative],
+                Instr::TryTable(TryTable { seq, .. }) => vec![*seq],
+                Instr::Try(Try { seq, catches }) => std::iter::once(*seq)
+                    .chain(catches.iter().filter_map(|catch| match catch {
+                        LegacyCatch::Catch { handler, .. } | LegacyCatch::CatchAll { handler } => {
+                            Some(*handler)
+                        }
+                        LegacyCatch::Delegate { .. } => None,
+                    }))
+                    .collect(),
+                _ => continue,
+            };
+            arms.reverse();
+            let first = arms.pop().unwrap();
+            self.enter(first, arms)?;
+        }
+        Ok(())
+    }
+
+    /// Start checking an instruction sequence of this function.
+    fn enter(&mut self, id: InstrSeqId, rest: Vec<InstrSeqId>) -> Result<()> {
+        let seq = match self.func.builder().arena.get(id) {
+            Some(seq) => seq,
+            None => {
+                return Err(self.error(format!(
+                    "instruction sequence {} does not belong to this function",
+                    id.index()
+                )))
+            }
+        };
+        seq.visit(self);
+        if let Some(msg) = self.error.take() {
+            return Err(self.error(msg));
+        }
+        self.frames.push((id, 0, rest));
+        Ok(())
+    }
+
+    /// Check the labels that an instruction branches to, along with the tags
+    /// of its handlers, which aren't visited by `Visit::visit`.
+    fn branch_targets(&mut self, instr: &Instr) {
+        let mut labels = Vec::new();
+        match instr {
+            Instr::Br(Br { block })
+            | Instr::BrIf(BrIf { block })
+            | Instr::BrOnNull(BrOnNull { block })
+            | Instr::BrOnNonNull(BrOnNonNull { block })
+            | Instr::BrOnCast(BrOnCast { block, .. })
+            | Instr::BrOnCastFail(BrOnCastFail { block, .. }) => labels.push(*block),
+            Instr::BrTable(BrTable { blocks, default }) => {
+                labels.extend(blocks.iter().copied());
+                labels.push(*default);
+            }
+            Instr::TryTable(TryTable { catches, .. }) => {
+                for catch in catches {
+                    catch.visit(self);
+                    match catch {
+                        TryTableCatch::Catch { label, .. }
+                        | TryTableCatch::CatchRef { label, .. }
+                        | TryTableCatch::CatchAll { label }
+                        | TryTableCatch::CatchAllRef { label } => labels.push(*label),
+                    }
+                }
+            }
+            Instr::Try(Try { catches, .. }) => {
+                for catch in catches {
+                    catch.visit(self);
+                }
+            }
+            Instr::Resume(Resume { handlers, .. })
+            | Instr::ResumeThrow(ResumeThrow { handlers, .. })
+            | Instr::ResumeThrowRef(ResumeThrowRef { handlers, .. }) => {
+                for handler in handlers {
+                    handler.visit(self);
+                    if let ResumeHandler::OnLabel { label, .. } = handler {
+                        labels.push(*label);
+                    }
+                }
+            }
+            _ => {}
+        }
+        for label in labels {
+            if !self.frames.iter().any(|(seq, ..)| *seq == label) {
+                self.fail(format!(
+                    "branch to instruction sequence {}, which does not enclose it",
+                    label.index()
+                ));
+            }
+        }
+    }
+
+    /// Record a problem found while visiting an instruction, keeping the
+    /// first one.
+    fn fail(&mut self, msg: String) {
+        self.error.get_or_insert(msg);
+    }
+
+    /// Create an error located at the instruction being checked.
+    fn error(&self, msg: String) -> anyhow::Error {
+        anyhow!(msg).context(InstrPosition {
+            func: self.id,
+            seq: self.seq,
+            index: self.index,
+        })
+    }
+}
+
+impl<'instr> Visitor<'instr> for IdChecker<'_> {
+    fn visit_local_id(&mut self, local: &LocalId) {
+        if !self.module.locals.contains(*local) {
+            self.fail(format!("local {} does not exist", local.index()));
+        } else {
+            let ty = self.module.locals.get(*local).ty();
+            self.visit_val_type(&ty);
+        }
+    }
+
+    fn visit_memory_id(&mut self, memory: &MemoryId) {
+        if !self.module.memories.contains(*memory) {
+            self.fail(format!("memory {} was deleted", memory.index()));
+        }
+    }
+
+    fn visit_table_id(&mut self, table: &TableId) {
+        if !self.module.tables.contains(*table) {
+            self.fail(format!("table {} was deleted", table.index()));
+        }
+    }
+
+    fn visit_global_id(&mut self, global: &GlobalId) {
+        if !self.module.globals.contains(*global) {
+            self.fail(format!("global {} was deleted", global.index()));
+        }
+    }
+
+    fn visit_function_id(&mut self, func: &FunctionId) {
+        if !self.module.funcs.contains(*func) {
+            self.fail(format!("function {} was deleted", func.index()));
+        }
+    }
+
+    fn visit_data_id(&mut self, data: &DataId) {
+        if !self.module.data.contains(*data) {
+            self.fail(format!("data segment {} was deleted", data.index()));
+        }
+    }
+
+    fn visit_type_id(&mut self, ty: &TypeId) {
+        if !self.module.types.contains(*ty) {
+            self.fail(format!("type {} was deleted", ty.index()));
+        }
+    }
+
+    fn visit_element_id(&mut self, elem: &ElementId) {
+        if !self.module.elements.contains(*elem) {
+            self.fail(format!("element segment {} was deleted", elem.index()));
+        }
+    }
+
+    fn visit_tag_id(&mut self, tag: &TagId) {
+        if !self.module.tags.contains(*tag) {
+            self.fail(format!("tag {} was deleted", tag.index()));
+        }
+    }
+}
+
+const EXN: RefType = RefType {
+    nullable: false,
+    heap_type: HeapType::Abstract(AbstractHeapType::Exn),
+};
+
+fn non_null(r: RefType) -> RefType {
+    RefType {
+        nullable: false,
+        ..r
+    }
+}
+
+/// The type of a newly allocated struct or array.
+fn new_ref(ty: TypeId) -> RefType {
+    RefType {
+        nullable: false,
+        heap_type: HeapType::Concrete(ty),
+    }
+}
+
+fn nullable_ref(ty: TypeId) -> RefType {
+    RefType {
+        nullable: true,
+        heap_type: HeapType::Concrete(ty),
+    }
+}
+
+/// The type of the length operand of an instruction that copies between two
+/// memories or tables.
+fn min_index(a: ValType, b: ValType) -> ValType {
+    if a == ValType::I64 && b == ValType::I64 {
+        ValType::I64
+    } else {
+        ValType::I32
+    }
+}
+
+/// The operand and result types of a unary operator.
+fn unop_types(op: UnaryOp) -> (ValType, ValType) {
+    use crate::ir::UnaryOp::*;
+    use ValType::*;
+
+    match op {
+        I32Eqz | I32Clz | I32Ctz | I32Popcnt | I32Extend8S | I32Extend16S => (I32, I32),
+        I64Eqz => (I64, I32),
+        I64Clz | I64Ctz | I64Popcnt | I64Extend8S | I64Extend16S | I64Extend32S => (I64, I64),
+        F32Abs | F32Neg | F32Ceil | F32Floor | F32Trunc | F32Nearest | F32Sqrt => (F32, F32),
+        F64Abs | F64Neg | F64Ceil | F64Floor | F64Trunc | F64Nearest | F64Sqrt => (F64, F64),
+
+        I32WrapI64 => (I64, I32),
+        I32TruncSF32 | I32TruncUF32 | I32TruncSSatF32 | I32TruncUSatF32 | I32ReinterpretF32 => {
+            (F32, I32)
+        }
+        I32TruncSF64 | I32TruncUF64 | I32TruncSSatF64 | I32TruncUSatF64 => (F64, I32),
+        I64ExtendSI32 | I64ExtendUI32 => (I32, I64),
+        I64TruncSF32 | I64TruncUF32 | I64TruncSSatF32 | I64TruncUSatF32 => (F32, I64),
+        I64TruncSF64 | I64TruncUF64 | I64TruncSSatF64 | I64TruncUSatF64 | I64ReinterpretF64 => {
+            (F64, I64)
+        }
+        F32ConvertSI32 | F32ConvertUI32 | F32ReinterpretI32 => (I32, F32),
+        F32ConvertSI64 | F32ConvertUI64 => (I64, F32),
+        F32DemoteF64 => (F64, F32),
+        F64ConvertSI32 | F64ConvertUI32 => (I32, F64),
+        F64ConvertSI64 | F64ConvertUI64 | F64ReinterpretI64 => (I64, F64),
+        F64PromoteF32 => (F32, F64),
+
+        I8x16Splat | I16x8Splat | I32x4Splat => (I32, V128),
+        I64x2Splat => (I64, V128),
+        F32x4Splat => (F32, V128),
+        F64x2Splat => (F64, V128),
+        I8x16ExtractLaneS { .. }
+        | I8x16ExtractLaneU { .. }
+        | I16x8ExtractLaneS { .. }
+        | I16x8ExtractLaneU { .. }
+        | I32x4ExtractLane { .. } => (V128, I32),
+        I64x2ExtractLane { .. } => (V128, I64),
+        F32x4ExtractLane { .. } => (V128, F32),
+        F64x2ExtractLane { .. } => (V128, F64),
+        V128AnyTrue | I8x16AllTrue | I8x16Bitmask | I16x8AllTrue | I16x8Bitmask | I32x4AllTrue
+        | I32x4Bitmask | I64x2AllTrue | I64x2Bitmask => (V128, I32),
+
+        V128Not
+        | I8x16Abs
+        | I8x16Popcnt
+        | I8x16Neg
+        | I16x8Abs
+        | I16x8Neg
+        | I32x4Abs
+        | I32x4Neg
+        | I64x2Abs
+        | I64x2Neg
+        | F32x4Abs
+        | F32x4Neg
+        | F32x4Sqrt
+        | F32x4Ceil
+        | F32x4Floor
+        | F32x4Trunc
+        | F32x4Nearest
+        | F64x2Abs
+        | F64x2Neg
+        | F64x2Sqrt
+        | F64x2Ceil
+        | F64x2Floor
+        | F64x2Trunc
+        | F64x2Nearest
+        | I16x8ExtAddPairwiseI8x16S
+        | I16x8ExtAddPairwiseI8x16U
+        | I32x4ExtAddPairwiseI16x8S
+        | I32x4ExtAddPairwiseI16x8U
+        | I64x2ExtendLowI32x4S
+        | I64x2ExtendHighI32x4S
+        | I64x2ExtendLowI32x4U
+        | I64x2ExtendHighI32x4U
+        | I32x4TruncSatF64x2SZero
+        | I32x4TruncSatF64x2UZero
+        | F64x2ConvertLowI32x4S
+        | F64x2ConvertLowI32x4U
+        | F32x4DemoteF64x2Zero
+        | F64x2PromoteLowF32x4
+        | I32x4TruncSatF32x4S
+        | I32x4TruncSatF32x4U
+        | F32x4ConvertI32x4S
+        | F32x4ConvertI32x4U
+        | I16x8WidenLowI8x16S
+        | I16x8WidenLowI8x16U
+        | I16x8WidenHighI8x16S
+        | I16x8WidenHighI8x16U
+        | I32x4WidenLowI16x8S
+        | I32x4WidenLowI16x8U
+        | I32x4WidenHighI16x8S
+        | I32x4WidenHighI16x8U
+        | I32x4RelaxedTruncF32x4S
+        | I32x4RelaxedTruncF32x4U
+        | I32x4RelaxedTruncF64x2SZero
+        | I32x4RelaxedTruncF64x2UZero => (V128, V128),
+    }
+}
+
+/// The operand and result types of a binary operator.
+fn binop_types(op: BinaryOp) -> (ValType, ValType, ValType) {
+    use crate::ir::BinaryOp::*;
+    use ValType::*;
+
+    match op {
+        I32Eq | I32Ne | I32LtS | I32LtU | I32GtS | I32GtU | I32LeS | I32LeU | I32GeS | I32GeU
+        | I32Add | I32Sub | I32Mul | I32DivS | I32DivU | I32RemS | I32RemU | I32And | I32Or
+        | I32Xor | I32Shl | I32ShrS | I32ShrU | I32Rotl | I32Rotr => (I32, I32, I32),
+        I64Eq | I64Ne | I64LtS | I64LtU | I64GtS | I64GtU | I64LeS | I64LeU | I64GeS | I64GeU => {
+            (I64, I64, I32)
+        }
+        I64Add | I64Sub | I64Mul | I64DivS | I64DivU | I64RemS | I64RemU | I64And | I64Or
+        | I64Xor | I64Shl | I64ShrS | I64ShrU | I64Rotl | I64Rotr => (I64, I64, I64),
+        F32Eq | F32Ne | F32Lt | F32Gt | F32Le | F32Ge => (F32, F32, I32),
+        F32Add | F32Sub | F32Mul | F32Div | F32Min | F32Max | F32Copysign => (F32, F32, F32),
+        F64Eq | F64Ne | F64Lt | F64Gt | F64Le | F64Ge => (F64, F64, I32),
+        F64Add | F64Sub | F64Mul | F64Div | F64Min | F64Max | F64Copysign => (F64, F64, F64),
+
+        I8x16ReplaceLane { .. } | I16x8ReplaceLane { .. } | I32x4ReplaceLane { .. } => {
+            (V128, I32, V128)
+        }
+        I64x2ReplaceLane { .. } => (V128, I64, V128),
+        F32x4ReplaceLane { .. } => (V128, F32, V128),
+        F64x2ReplaceLane { .. } => (V128, F64, V128),
+        I8x16Shl | I8x16ShrS | I8x16ShrU | I16x8Shl | I16x8ShrS | I16x8ShrU | I32x4Shl
+        | I32x4ShrS | I32x4ShrU | I64x2Shl | I64x2ShrS | I64x2ShrU => (V128, I32, V128),
+
+        I8x16Eq
+        | I8x16Ne
+        | I8x16LtS
+        | I8x16LtU
+        | I8x16GtS
+        | I8x16GtU
+        | I8x16LeS
+        | I8x16LeU
+        | I8x16GeS
+        | I8x16GeU
+        | I16x8Eq
+        | I16x8Ne
+        | I16x8LtS
+        | I16x8LtU
+        | I16x8GtS
+        | I16x8GtU
+        | I16x8LeS
+        | I16x8LeU
+        | I16x8GeS
+        | I16x8GeU
+        | I32x4Eq
+        | I32x4Ne
+        | I32x4LtS
+        | I32x4LtU
+        | I32x4GtS
+        | I32x4GtU
+        | I32x4LeS
+        | I32x4LeU
+        | I32x4GeS
+        | I32x4GeU
+        | I64x2Eq
+        | I64x2Ne
+        | I64x2LtS
+        | I64x2GtS
+        | I64x2LeS
+        | I64x2GeS
+        | F32x4Eq
+        | F32x4Ne
+        | F32x4Lt
+        | F32x4Gt
+        | F32x4Le
+        | F32x4Ge
+        | F64x2Eq
+        | F64x2Ne
+        | F64x2Lt
+        | F64x2Gt
+        | F64x2Le
+        | F64x2Ge
+        | V128And
+        | V128Or
+        | V128Xor
+        | V128AndNot
+        | I8x16Add
+        | I8x16AddSatS
+        | I8x16AddSatU
+        | I8x16Sub
+        | I8x16SubSatS
+        | I8x16SubSatU
+        | I16x8Add
+        | I16x8AddSatS
+        | I16x8AddSatU
+        | I16x8Sub
+        | I16x8SubSatS
+        | I16x8SubSatU
+        | I16x8Mul
+        | I32x4Add
+        | I32x4Sub
+        | I32x4Mul
+        | I64x2Add
+        | I64x2Sub
+        | I64x2Mul
+        | F32x4Add
+        | F32x4Sub
+        | F32x4Mul
+        | F32x4Div
+        | F32x4Min
+        | F32x4Max
+        | F32x4PMin
+        | F32x4PMax
+        | F64x2Add
+        | F64x2Sub
+        | F64x2Mul
+        | F64x2Div
+        | F64x2Min
+        | F64x2Max
+        | F64x2PMin
+        | F64x2PMax
+        | I8x16NarrowI16x8S
+        | I8x16NarrowI16x8U
+        | I16x8NarrowI32x4S
+        | I16x8NarrowI32x4U
+        | I8x16AvgrU
+        | I16x8AvgrU
+        | I8x16MinS
+        | I8x16MinU
+        | I8x16MaxS
+        | I8x16MaxU
+        | I16x8MinS
+        | I16x8MinU
+        | I16x8MaxS
+        | I16x8MaxU
+        | I32x4MinS
+        | I32x4MinU
+        | I32x4MaxS
+        | I32x4MaxU
+        | I32x4DotI16x8S
+        | I16x8Q15MulrSatS
+        | I16x8ExtMulLowI8x16S
+        | I16x8ExtMulHighI8x16S
+        | I16x8ExtMulLowI8x16U
+        | I16x8ExtMulHighI8x16U
+        | I32x4ExtMulLowI16x8S
+        | I32x4ExtMulHighI16x8S
+        | I32x4ExtMulLowI16x8U
+        | I32x4ExtMulHighI16x8U
+        | I64x2ExtMulLowI32x4S
+        | I64x2ExtMulHighI32x4S
+        | I64x2ExtMulLowI32x4U
+        | I64x2ExtMulHighI32x4U
+        | I8x16RelaxedSwizzle
+        | F32x4RelaxedMin
+        | F32x4RelaxedMax
+        | F64x2RelaxedMin
+        | F64x2RelaxedMax
+        | I16x8RelaxedQ15mulrS
+        | I16x8RelaxedDotI8x16I7x16S => (V128, V128, V128),
+    }
+}